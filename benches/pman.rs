@@ -0,0 +1,144 @@
+//! Parsing, packing and `ZL` decompression, on the reference archive and on synthetic ones.
+//!
+//! `cargo bench --bench pman`; add `-- --save-baseline before` (and `--baseline before`, after a
+//! change) to compare two revisions.
+
+#![warn(clippy::pedantic)]
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rashen::format::pman::{self, PmanFile, PmanFileData};
+use std::hint::black_box;
+
+const INPUT: &[u8] = include_bytes!("../.res/packfile.dat");
+
+/// An archive with `count` entries of `size` bytes each; their data doesn't compress well.
+#[allow(clippy::cast_possible_truncation)]
+fn synthetic(count: usize, size: usize) -> Vec<u8> {
+    let mut pman = PmanFile::new(INPUT).expect("the reference archive is valid.");
+    let files = pman.files_mut();
+    files.clear();
+
+    let mut state = 0x2545_F491_u32;
+    for _ in 0..count {
+        let mut file = PmanFileData::default();
+        file.set_bytes(
+            (0..size)
+                .map(|_| {
+                    // xorshift32
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as u8
+                })
+                .collect::<Vec<_>>(),
+        );
+        files.push(file);
+    }
+
+    pman.into_bytes()
+        .expect("the archive fits on `u32` offsets.")
+}
+
+/// The reference archive, and synthetic ones with many small entries, or a few large ones.
+fn archives() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("reference", INPUT.to_vec()),
+        ("16384x256B", synthetic(16384, 256)),
+        ("256x64KiB", synthetic(256, 64 * 1024)),
+    ]
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    for (name, bytes) in archives() {
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &bytes, |b, bytes| {
+            b.iter(|| PmanFile::new(black_box(bytes)));
+        });
+    }
+}
+
+fn into_bytes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("into_bytes");
+
+    for (name, bytes) in archives() {
+        let pman = PmanFile::new(&bytes).expect("the archive is valid.");
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &pman, |b, pman| {
+            b.iter_batched(|| pman.clone(), PmanFile::into_bytes, BatchSize::LargeInput);
+        });
+    }
+}
+
+fn decompress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decompress");
+
+    let pman = PmanFile::new(INPUT).expect("the reference archive is valid.");
+    let chunks: Vec<_> = pman
+        .files()
+        .iter()
+        .map(PmanFileData::bytes)
+        .filter(|bytes| bytes.starts_with(b"ZL"))
+        .collect();
+    let size: usize = chunks.iter().map(|bytes| bytes.len()).sum();
+    group.throughput(Throughput::Bytes(size as u64));
+    group.bench_function("reference", |b| {
+        b.iter(|| {
+            for chunk in &chunks {
+                _ = black_box(pman::decompress(chunk));
+            }
+        });
+    });
+
+    // data larger than a `ZL` chunk can hold, so it is split into many of them.
+    let data: Vec<_> = INPUT.iter().copied().cycle().take(0x180_0000).collect();
+    let compressed = pman::compress(&data).expect("the data can be compressed.");
+    group.throughput(Throughput::Bytes(compressed.len() as u64));
+    group.bench_function("24MiB", |b| {
+        b.iter(|| pman::decompress(black_box(&compressed)));
+    });
+}
+
+/// Compresses every `ZL` entry of the reference archive again, and packs it; run it with (and
+/// without) `--features rayon` to compare.
+fn repack(c: &mut Criterion) {
+    let mut group = c.benchmark_group("repack");
+    group.sample_size(10);
+
+    let pman = PmanFile::new(INPUT).expect("the reference archive is valid.");
+    let entries: Vec<_> = pman
+        .files()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, file)| Some((index, file.to_zlib().ok()??)))
+        .collect();
+    let data: Vec<_> = entries.iter().map(|(_, data)| data).collect();
+    let repack = |compressed: Vec<Vec<u8>>| {
+        let mut pman = pman.clone();
+        for ((index, _), bytes) in entries.iter().zip(compressed) {
+            pman.files_mut()[*index].set_bytes(bytes);
+        }
+
+        pman.into_bytes()
+    };
+
+    group.throughput(Throughput::Bytes(INPUT.len() as u64));
+    group.bench_function("serial", |b| {
+        b.iter(|| {
+            let compressed = data.iter().map(|data| pman::compress(data));
+            repack(
+                compressed
+                    .collect::<Result<_, _>>()
+                    .expect("the data can be compressed."),
+            )
+        });
+    });
+    group.bench_function("compress_all", |b| {
+        b.iter(|| repack(pman::compress_all(&data).expect("the data can be compressed.")));
+    });
+}
+
+criterion_group!(benches, parse, into_bytes, decompress, repack);
+criterion_main!(benches);
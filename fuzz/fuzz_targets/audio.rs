@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::format::audio::{AudioClip, SoundArchive};
+
+fuzz_target!(|data: &[u8]| {
+    _ = SoundArchive::new(data);
+    _ = AudioClip::new(data);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::format::{chunk::Chunk, detect};
+
+fuzz_target!(|data: &[u8]| {
+    _ = Chunk::walk(data);
+    _ = detect::detect(data);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::format::model::Model;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(model) = Model::new(data) {
+        // animations index into the frames of the model.
+        for animation in &model.animations {
+            _ = animation.frame_range();
+            _ = animation.keyframes(&model).count();
+        }
+    }
+});
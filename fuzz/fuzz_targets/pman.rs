@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::format::pman::PmanFile;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(pman) = PmanFile::new_lenient(data) {
+        for file in pman.files() {
+            _ = file.to_zlib();
+            _ = file.detect_type();
+        }
+        // an archive that can be written back can be parsed again; a copyright notice with invalid
+        // UTF-8 might not fit anymore.
+        if let Ok(bytes) = pman.clone().into_bytes() {
+            assert!(PmanFile::new_lenient(&bytes).is_ok());
+        }
+    }
+    if let Ok(pman) = PmanFile::new_partial(data) {
+        _ = pman.is_complete();
+    }
+});
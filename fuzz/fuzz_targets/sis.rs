@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::import::install;
+
+fuzz_target!(|data: &[u8]| {
+    _ = install::from_sis(data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::format::skybox::Skybox;
+
+fuzz_target!(|data: &[u8]| {
+    _ = Skybox::new(data);
+});
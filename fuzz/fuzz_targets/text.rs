@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::format::text::TextBank;
+
+fuzz_target!(|data: &[u8]| {
+    _ = TextBank::new(data);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::format::texture::{Texture, TextureTable};
+
+fuzz_target!(|data: &[u8]| {
+    _ = Texture::new(data);
+    _ = TextureTable::new(data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::format::waypoint::WaypointGraph;
+
+fuzz_target!(|data: &[u8]| {
+    _ = WaypointGraph::new(data);
+});
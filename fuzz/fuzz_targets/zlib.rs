@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rashen::format::pman;
+
+fuzz_target!(|data: &[u8]| {
+    _ = pman::decompress_chunk(data);
+    if let Ok(Some(zlib)) = pman::decompress(data) {
+        // compressing it again gives the same data back.
+        let compressed = pman::compress(&zlib).expect("the data can be compressed.");
+        assert_eq!(pman::decompress(&compressed).ok().flatten(), Some(zlib));
+    }
+});
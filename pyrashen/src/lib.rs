@@ -0,0 +1,531 @@
+//! Python bindings of `rashen`; built into the `pyrashen` module with `maturin`.
+//!
+//! Entries are copied out of the archive when they are accessed, so a `PmanFile` can be changed
+//! while they are alive. Data is returned as `bytes`.
+
+#![warn(clippy::pedantic)]
+// `#[pymethods]` take their arguments by value.
+#![allow(clippy::needless_pass_by_value)]
+
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyIndexError},
+    prelude::*,
+    types::PyBytes,
+};
+use rashen::{
+    error::Error,
+    format::{
+        audio::{AudioClip, SoundArchive},
+        detect,
+        model::Model,
+        palette::{self, Palette},
+        pman::{self, Compression, PmanFile, PmanFileData},
+        skybox::Skybox,
+        text::TextBank,
+        texture::Texture,
+    },
+};
+use std::{fs, path::PathBuf};
+
+create_exception!(
+    pyrashen,
+    RashenError,
+    PyException,
+    "An archive, or an asset, is not valid."
+);
+
+fn error(err: Error) -> PyErr {
+    RashenError::new_err(err.to_string())
+}
+
+fn rgba_bytes<'py>(py: Python<'py>, pixels: &[[u8; 4]]) -> Bound<'py, PyBytes> {
+    PyBytes::new(py, pixels.as_flattened())
+}
+
+/// A `packfile.dat` archive.
+#[pyclass(name = "PmanFile", module = "pyrashen")]
+struct PyPmanFile(PmanFile);
+
+#[pymethods]
+impl PyPmanFile {
+    #[new]
+    fn new(bytes: &[u8]) -> PyResult<Self> {
+        PmanFile::new(bytes).map(Self).map_err(error)
+    }
+
+    /// Reads, and parses, the archive at `path`.
+    #[staticmethod]
+    fn open(path: PathBuf) -> PyResult<Self> {
+        let bytes = fs::read(path).map_err(|err| error(err.into()))?;
+
+        Self::new(&bytes)
+    }
+
+    #[getter]
+    fn copyright(&self) -> &str {
+        self.0.copyright()
+    }
+
+    #[getter]
+    fn version(&self) -> String {
+        self.0.version().to_string()
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.files().len()
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<PyEntry> {
+        let len = self.0.files().len();
+        // negative indices count from the end, like on a list.
+        let index = if index < 0 {
+            index.checked_add_unsigned(len)
+        } else {
+            Some(index)
+        }
+        .and_then(|index| usize::try_from(index).ok())
+        .filter(|&index| index < len)
+        .ok_or_else(|| PyIndexError::new_err("entry index out of range"))?;
+
+        Ok(PyEntry {
+            index,
+            file: self.0.files()[index].clone(),
+        })
+    }
+
+    /// Replaces the data of the entry at `index`; with `compress`, it is `ZL` compressed first.
+    #[pyo3(signature = (index, data, compress = false))]
+    fn replace(&mut self, index: usize, data: &[u8], compress: bool) -> PyResult<()> {
+        let data = if compress {
+            pman::compress(data).map_err(error)?
+        } else {
+            data.to_vec()
+        };
+        let file = self
+            .0
+            .files_mut()
+            .get_mut(index)
+            .ok_or_else(|| PyIndexError::new_err("entry index out of range"))?;
+        file.set_bytes(data);
+
+        Ok(())
+    }
+
+    /// Packs the archive.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let bytes = self.0.clone().into_bytes().map_err(error)?;
+
+        Ok(PyBytes::new(py, &bytes))
+    }
+}
+
+/// An entry of a `PmanFile`.
+#[pyclass(name = "Entry", module = "pyrashen", frozen)]
+struct PyEntry {
+    #[pyo3(get)]
+    index: usize,
+    file: PmanFileData,
+}
+
+#[pymethods]
+impl PyEntry {
+    /// The raw `type` column of the entry table.
+    #[getter]
+    fn entry_type(&self) -> u32 {
+        self.file.entry_type()
+    }
+
+    #[getter]
+    fn compressed(&self) -> bool {
+        matches!(self.file.compression(), Compression::Zlib { .. })
+    }
+
+    /// The data of the entry, as it is stored on the archive.
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.file.bytes())
+    }
+
+    /// The decompressed data of the entry; or its data, if it is not compressed.
+    fn decompress<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let zlib = self.file.to_zlib().map_err(error)?;
+
+        Ok(PyBytes::new(
+            py,
+            zlib.as_deref().unwrap_or(self.file.bytes()),
+        ))
+    }
+
+    /// What the entry most likely is (e.g. `"texture"`); `None` if nothing matches.
+    fn detect(&self) -> Option<String> {
+        self.file
+            .detect_type()
+            .map(|file_type| format!("{file_type:?}").to_lowercase())
+    }
+
+    fn __len__(&self) -> usize {
+        self.file.bytes().len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Entry {} ({:#X} bytes)>",
+            self.index,
+            self.file.bytes().len()
+        )
+    }
+}
+
+/// `ZL` compresses `data`.
+#[pyfunction]
+fn compress<'py>(py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+    let bytes = pman::compress(data).map_err(error)?;
+
+    Ok(PyBytes::new(py, &bytes))
+}
+
+/// Decompresses a `ZL` chunk; `None` if `data` is not one.
+#[pyfunction]
+fn decompress<'py>(py: Python<'py>, data: &[u8]) -> PyResult<Option<Bound<'py, PyBytes>>> {
+    let bytes = pman::decompress(data).map_err(error)?;
+
+    Ok(bytes.map(|bytes| PyBytes::new(py, &bytes)))
+}
+
+/// Every type that `data` could be, as `(type, confidence)` pairs; from the most to the least
+/// likely.
+#[pyfunction(name = "detect")]
+fn detect_type(data: &[u8]) -> Vec<(String, String)> {
+    detect::detect(data)
+        .into_iter()
+        .map(|guess| {
+            (
+                format!("{:?}", guess.file_type).to_lowercase(),
+                format!("{:?}", guess.confidence).to_lowercase(),
+            )
+        })
+        .collect()
+}
+
+/// A table of 256 `0x0RGB` colors.
+#[pyclass(name = "Palette", module = "pyrashen", frozen)]
+struct PyPalette(Palette);
+
+#[pymethods]
+impl PyPalette {
+    #[new]
+    fn new(bytes: &[u8]) -> PyResult<Self> {
+        Palette::new(bytes).map(Self).map_err(error)
+    }
+
+    #[getter]
+    fn colors(&self) -> Vec<u16> {
+        self.0.colors.to_vec()
+    }
+
+    /// Every color, as RGBA bytes.
+    fn to_rgba<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let colors = self.0.colors.map(palette::rgb444_to_rgba);
+
+        rgba_bytes(py, &colors)
+    }
+}
+
+/// A standalone texture; indices into a `Palette`.
+#[pyclass(name = "Texture", module = "pyrashen", frozen)]
+struct PyTexture(Texture);
+
+#[pymethods]
+impl PyTexture {
+    #[new]
+    fn new(bytes: &[u8]) -> PyResult<Self> {
+        Texture::new(bytes).map(Self).map_err(error)
+    }
+
+    #[getter]
+    fn width(&self) -> u32 {
+        self.0.width
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.0.height
+    }
+
+    #[getter]
+    fn pixels<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.pixels)
+    }
+
+    /// The pixels, as RGBA bytes, row by row.
+    fn to_rgba<'py>(&self, py: Python<'py>, palette: &PyPalette) -> Bound<'py, PyBytes> {
+        rgba_bytes(py, &self.0.to_rgba(&palette.0))
+    }
+}
+
+/// A skybox; an indexed panorama, with its own palette.
+#[pyclass(name = "Skybox", module = "pyrashen", frozen)]
+struct PySkybox(Skybox);
+
+#[pymethods]
+impl PySkybox {
+    #[new]
+    fn new(bytes: &[u8]) -> PyResult<Self> {
+        Skybox::new(bytes).map(Self).map_err(error)
+    }
+
+    #[getter]
+    fn width(&self) -> u32 {
+        self.0.width
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.0.height
+    }
+
+    #[getter]
+    fn palette(&self) -> PyPalette {
+        PyPalette(self.0.palette.clone())
+    }
+
+    #[getter]
+    fn pixels<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.pixels)
+    }
+
+    /// The pixels, as RGBA bytes, row by row.
+    fn to_rgba<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        rgba_bytes(py, &self.0.to_rgba())
+    }
+}
+
+/// A language text bank.
+#[pyclass(name = "TextBank", module = "pyrashen", frozen)]
+struct PyTextBank(TextBank);
+
+#[pymethods]
+impl PyTextBank {
+    #[new]
+    fn new(bytes: &[u8]) -> PyResult<Self> {
+        TextBank::new(bytes).map(Self).map_err(error)
+    }
+
+    /// Every string, as `(id, string)` pairs.
+    #[getter]
+    fn entries(&self) -> Vec<(u32, String)> {
+        self.0.entries.clone()
+    }
+
+    /// The string with `id`, if any.
+    fn get(&self, id: u32) -> Option<&str> {
+        self.0.get(id)
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.entries.len()
+    }
+}
+
+/// A model; a textured mesh, animated by whole frames.
+#[pyclass(name = "Model", module = "pyrashen", frozen)]
+struct PyModel(Model);
+
+#[pymethods]
+impl PyModel {
+    #[new]
+    fn new(bytes: &[u8]) -> PyResult<Self> {
+        Model::new(bytes).map(Self).map_err(error)
+    }
+
+    #[getter]
+    fn vertex_count(&self) -> usize {
+        self.0.vertex_count()
+    }
+
+    #[getter]
+    fn frame_count(&self) -> usize {
+        self.0.frames.len()
+    }
+
+    /// The vertex indices of every triangle.
+    #[getter]
+    fn faces(&self) -> Vec<[u16; 3]> {
+        self.0.faces.iter().map(|face| face.vertices).collect()
+    }
+
+    /// The texture coordinates of every triangle, in texels.
+    #[getter]
+    fn uvs(&self) -> Vec<[[u16; 2]; 3]> {
+        self.0.faces.iter().map(|face| face.uvs).collect()
+    }
+
+    /// The frames of every animation, as indices.
+    #[getter]
+    fn animations(&self) -> Vec<Vec<u32>> {
+        self.0
+            .animations
+            .iter()
+            .map(|animation| animation.frames.clone())
+            .collect()
+    }
+
+    #[getter]
+    fn texture_size(&self) -> (u32, u32) {
+        (self.0.texture_width, self.0.texture_height)
+    }
+
+    /// The texture, as indices into a palette.
+    #[getter]
+    fn texture<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.texture)
+    }
+
+    /// The position of every vertex on `frame`.
+    fn positions(&self, frame: usize) -> PyResult<Vec<[f32; 3]>> {
+        self.0
+            .frames
+            .get(frame)
+            .map(rashen::format::model::Frame::positions)
+            .ok_or_else(|| PyIndexError::new_err("frame index out of range"))
+    }
+}
+
+/// The `TSND` entry; every song and sound effect of the game.
+#[pyclass(name = "SoundArchive", module = "pyrashen", frozen)]
+struct PySoundArchive(SoundArchive);
+
+#[pymethods]
+impl PySoundArchive {
+    #[new]
+    fn new(bytes: &[u8]) -> PyResult<Self> {
+        SoundArchive::new(bytes).map(Self).map_err(error)
+    }
+
+    /// The entries of every bank, as they are stored.
+    #[getter]
+    fn banks<'py>(&self, py: Python<'py>) -> Vec<Vec<Bound<'py, PyBytes>>> {
+        self.0
+            .banks
+            .iter()
+            .map(|bank| bank.iter().map(|entry| PyBytes::new(py, entry)).collect())
+            .collect()
+    }
+
+    /// The instrument at `index`, decompressed and parsed.
+    fn clip(&self, index: usize) -> PyResult<PyAudioClip> {
+        let bytes = self.0.banks[1]
+            .get(index)
+            .ok_or_else(|| PyIndexError::new_err("clip index out of range"))?;
+        let zlib = pman::decompress(bytes).map_err(error)?;
+
+        PyAudioClip::new(zlib.as_deref().unwrap_or(bytes))
+    }
+}
+
+/// An instrument of the `SoundArchive`; mono 8-bit PCM samples.
+#[pyclass(name = "AudioClip", module = "pyrashen", frozen)]
+struct PyAudioClip(AudioClip);
+
+#[pymethods]
+impl PyAudioClip {
+    #[new]
+    fn new(bytes: &[u8]) -> PyResult<Self> {
+        AudioClip::new(bytes).map(Self).map_err(error)
+    }
+
+    #[getter]
+    fn sample_rate(&self) -> u32 {
+        self.0.sample_rate()
+    }
+
+    #[getter]
+    fn is_looped(&self) -> bool {
+        self.0.is_looped()
+    }
+
+    /// Where the loop starts and ends, in half samples.
+    #[getter]
+    fn loop_range(&self) -> (u32, u32) {
+        (self.0.loop_start, self.0.loop_end)
+    }
+
+    /// The signed samples, as bytes.
+    #[getter]
+    fn samples<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let samples: Vec<_> = self.0.samples.iter().map(|s| s.cast_unsigned()).collect();
+
+        PyBytes::new(py, &samples)
+    }
+}
+
+#[pymodule]
+fn pyrashen(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("RashenError", m.py().get_type::<RashenError>())?;
+    m.add_class::<PyPmanFile>()?;
+    m.add_class::<PyEntry>()?;
+    m.add_class::<PyPalette>()?;
+    m.add_class::<PyTexture>()?;
+    m.add_class::<PySkybox>()?;
+    m.add_class::<PyTextBank>()?;
+    m.add_class::<PyModel>()?;
+    m.add_class::<PySoundArchive>()?;
+    m.add_class::<PyAudioClip>()?;
+    m.add_function(wrap_pyfunction!(compress, m)?)?;
+    m.add_function(wrap_pyfunction!(decompress, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_type, m)?)?;
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    fn module_test() -> PyResult<()> {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "pyrashen")?;
+            pyrashen(&module)?;
+            let globals = PyDict::new(py);
+            globals.set_item("pyrashen", module)?;
+            globals.set_item("INPUT", PyBytes::new(py, INPUT))?;
+
+            py.run(
+                cr#"
+pman = pyrashen.PmanFile(INPUT)
+assert len(pman) == 158
+assert pman[-1].index == 157
+
+german = pman[156]
+assert german.compressed and german.detect() == "text"
+bank = pyrashen.TextBank(german.decompress())
+assert len(bank) > 0 and bank.get(0) is not None
+
+palette = pyrashen.Palette(pman[66].decompress())
+texture = pyrashen.Texture(pman[72].decompress())
+assert len(texture.to_rgba(palette)) == texture.width * texture.height * 4
+
+clip = pyrashen.SoundArchive(pman[151].decompress()).clip(0)
+assert clip.sample_rate > 0 and len(clip.samples) > 0
+
+pman.replace(72, bytes(len(pman[72])))
+assert pyrashen.PmanFile(pman.to_bytes())[72].data == bytes(len(pman[72]))
+
+try:
+    pyrashen.PmanFile(INPUT[:16])
+    assert False
+except pyrashen.RashenError:
+    pass
+"#,
+                Some(&globals),
+                None,
+            )
+        })
+    }
+}
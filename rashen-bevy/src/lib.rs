@@ -0,0 +1,345 @@
+//! Bevy asset loaders for the formats of the game; see [`RashenPlugin`].
+//!
+//! A whole archive is loaded as a [`Packfile`], whose textures, skyboxes and models are labeled
+//! sub-assets; e.g. `packfile.dat#Image72` or `packfile.dat#Mesh10`. Single entries (e.g. the
+//! files of an extraction, or of a mount) are loaded by their extension; `texture`, `skybox` and
+//! `model`. They can be `ZL` compressed, or not.
+
+#![warn(clippy::pedantic)]
+
+use bevy_app::{App, Plugin};
+use bevy_asset::{
+    io::Reader, Asset, AssetApp, AssetLoader, Handle, LoadContext, ReadAssetBytesError,
+    RenderAssetUsages,
+};
+use bevy_image::Image;
+use bevy_mesh::{Mesh, PrimitiveTopology};
+use bevy_reflect::TypePath;
+use rashen::format::{
+    detect::FileType,
+    model::Model,
+    palette::Palette,
+    pman::{self, PmanFile},
+    skybox::Skybox,
+    texture::Texture,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// Registers the [`Packfile`] asset, and every loader of this crate.
+///
+/// It needs the `Image` and `Mesh` assets; e.g. from the `DefaultPlugins`.
+pub struct RashenPlugin;
+
+impl Plugin for RashenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Packfile>()
+            .register_asset_loader(PackfileLoader)
+            .register_asset_loader(TextureLoader)
+            .register_asset_loader(SkyboxLoader)
+            .register_asset_loader(ModelLoader);
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Rashen(#[from] rashen::error::Error),
+    #[error("the palette of the texture can't be read; {0}")]
+    Palette(#[from] ReadAssetBytesError),
+    #[error("a standalone texture needs the `palette` setting")]
+    MissingPalette,
+}
+
+/// A `packfile.dat`, and the handles of the entries that were loaded out of it.
+#[derive(Asset, TypePath, Debug)]
+pub struct Packfile {
+    pub pman: PmanFile,
+    /// The textures (as their palette indices; see [`texture_indices_to_image`]) and skyboxes, by
+    /// entry; labeled `Image{index}`.
+    pub images: BTreeMap<usize, Handle<Image>>,
+    /// The models, on their first frame, by entry; labeled `Mesh{index}`.
+    pub meshes: BTreeMap<usize, Handle<Mesh>>,
+}
+
+/// The data of an entry; decompressed, if it is `ZL` compressed.
+fn entry_data(bytes: Vec<u8>) -> Result<Vec<u8>, LoadError> {
+    Ok(pman::decompress(&bytes)?.unwrap_or(bytes))
+}
+
+async fn read_all(reader: &mut dyn Reader) -> Result<Vec<u8>, LoadError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+
+    Ok(bytes)
+}
+
+fn rgba_image(width: u32, height: u32, pixels: &[[u8; 4]]) -> Image {
+    Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        pixels.as_flattened().to_vec(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// Converts `texture` into an RGBA image, with the colors of `palette`.
+#[must_use]
+pub fn texture_to_image(texture: &Texture, palette: &Palette) -> Image {
+    rgba_image(texture.width, texture.height, &texture.to_rgba(palette))
+}
+
+/// Converts `texture` into a single channel image of its palette indices; the palette of each
+/// texture entry is not known yet, so it is up to the shader (or a [`TextureLoader`]) to apply one.
+#[must_use]
+pub fn texture_indices_to_image(texture: &Texture) -> Image {
+    Image::new(
+        Extent3d {
+            width: texture.width,
+            height: texture.height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        texture.pixels.clone(),
+        TextureFormat::R8Unorm,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// Converts `skybox` into an RGBA image, with the colors of its own palette.
+#[must_use]
+pub fn skybox_to_image(skybox: &Skybox) -> Image {
+    rgba_image(skybox.width, skybox.height, &skybox.to_rgba())
+}
+
+/// Converts a frame of `model` into a mesh, with flat normals; the first frame, if there is no
+/// frame at `frame`.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn model_to_mesh(model: &Model, frame: usize) -> Mesh {
+    let positions = model
+        .frames
+        .get(frame)
+        .or(model.frames.first())
+        .map(rashen::format::model::Frame::positions)
+        .unwrap_or_default();
+    let (width, height) = (
+        model.texture_width.max(1) as f32,
+        model.texture_height.max(1) as f32,
+    );
+
+    // texture coordinates belong to the corners of the faces, so vertices can't be shared.
+    let corners: Vec<_> = model
+        .faces
+        .iter()
+        .flat_map(|face| face.vertices)
+        .map(|v| positions.get(usize::from(v)).copied().unwrap_or_default())
+        .collect();
+    let uvs: Vec<_> = model
+        .faces
+        .iter()
+        .flat_map(|face| face.uvs)
+        .map(|[u, v]| [f32::from(u) / width, f32::from(v) / height])
+        .collect();
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, corners)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_computed_flat_normals()
+}
+
+/// Loads `dat` (and `pman`) files as [`Packfile`]s.
+#[derive(TypePath)]
+pub struct PackfileLoader;
+
+impl AssetLoader for PackfileLoader {
+    type Asset = Packfile;
+    type Settings = ();
+    type Error = LoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        (): &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Packfile, LoadError> {
+        let pman = PmanFile::new(&read_all(reader).await?)?;
+        let mut images = BTreeMap::new();
+        let mut meshes = BTreeMap::new();
+
+        for (index, file) in pman.files().iter().enumerate() {
+            let label = || format!("Image{index}");
+
+            match file.detect_type() {
+                Some(FileType::Texture) => {
+                    let texture = Texture::new(&entry_data(file.bytes().to_vec())?)?;
+                    let image = texture_indices_to_image(&texture);
+                    images.insert(index, load_context.add_labeled_asset(label(), image));
+                }
+                Some(FileType::Skybox) => {
+                    let skybox = Skybox::new(&entry_data(file.bytes().to_vec())?)?;
+                    let image = skybox_to_image(&skybox);
+                    images.insert(index, load_context.add_labeled_asset(label(), image));
+                }
+                Some(FileType::Model) => {
+                    let model = Model::new(&entry_data(file.bytes().to_vec())?)?;
+                    let mesh = load_context
+                        .add_labeled_asset(format!("Mesh{index}"), model_to_mesh(&model, 0));
+                    meshes.insert(index, mesh);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Packfile {
+            pman,
+            images,
+            meshes,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dat", "pman"]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TextureSettings {
+    /// The asset path of the palette entry of the texture; e.g. `entries/00D1C720.palette`.
+    pub palette: Option<String>,
+}
+
+/// Loads `texture` files as [`Image`]s; their palette is given by [`TextureSettings`].
+#[derive(TypePath)]
+pub struct TextureLoader;
+
+impl AssetLoader for TextureLoader {
+    type Asset = Image;
+    type Settings = TextureSettings;
+    type Error = LoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &TextureSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Image, LoadError> {
+        let path = settings
+            .palette
+            .as_deref()
+            .ok_or(LoadError::MissingPalette)?;
+        let palette = load_context.read_asset_bytes(path.to_owned()).await?;
+        let palette = Palette::new(&entry_data(palette)?)?;
+        let texture = Texture::new(&entry_data(read_all(reader).await?)?)?;
+
+        Ok(texture_to_image(&texture, &palette))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["texture"]
+    }
+}
+
+/// Loads `skybox` files as [`Image`]s.
+#[derive(TypePath)]
+pub struct SkyboxLoader;
+
+impl AssetLoader for SkyboxLoader {
+    type Asset = Image;
+    type Settings = ();
+    type Error = LoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        (): &(),
+        _: &mut LoadContext<'_>,
+    ) -> Result<Image, LoadError> {
+        let skybox = Skybox::new(&entry_data(read_all(reader).await?)?)?;
+
+        Ok(skybox_to_image(&skybox))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["skybox"]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ModelSettings {
+    /// The frame that the mesh is posed with.
+    pub frame: usize,
+}
+
+/// Loads `model` files as [`Mesh`]es; posed with the frame of [`ModelSettings`].
+#[derive(TypePath)]
+pub struct ModelLoader;
+
+impl AssetLoader for ModelLoader {
+    type Asset = Mesh;
+    type Settings = ModelSettings;
+    type Error = LoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &ModelSettings,
+        _: &mut LoadContext<'_>,
+    ) -> Result<Mesh, LoadError> {
+        let model = Model::new(&entry_data(read_all(reader).await?)?)?;
+
+        Ok(model_to_mesh(&model, settings.frame))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["model"]
+    }
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    fn convert_test() -> Result<(), LoadError> {
+        let pman = PmanFile::new(INPUT)?;
+        let data = |index: usize| entry_data(pman[index].bytes().to_vec());
+
+        let texture = Texture::new(&data(72)?)?;
+        let palette = Palette::new(&data(66)?)?;
+        let image = texture_to_image(&texture, &palette);
+        assert_eq!(
+            (image.width(), image.height()),
+            (texture.width, texture.height)
+        );
+        let image = texture_indices_to_image(&texture);
+        assert_eq!(image.data.as_ref(), Some(&texture.pixels));
+
+        let skybox = Skybox::new(&data(60)?)?;
+        let image = skybox_to_image(&skybox);
+        assert_eq!(
+            image.data.as_ref().map(Vec::len),
+            Some(skybox.pixels.len() * 4)
+        );
+
+        let model = Model::new(&data(10)?)?;
+        let mesh = model_to_mesh(&model, usize::MAX);
+        assert_eq!(mesh.count_vertices(), model.faces.len() * 3);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+
+        Ok(())
+    }
+}
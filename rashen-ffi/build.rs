@@ -0,0 +1,15 @@
+use std::{env, path::PathBuf};
+
+fn main() {
+    let dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let config = cbindgen::Config::from_file(dir.join("cbindgen.toml")).unwrap();
+
+    println!("cargo::rerun-if-changed=src/lib.rs");
+    println!("cargo::rerun-if-changed=cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_crate(&dir)
+        .with_config(config)
+        .generate()
+        .expect("the C header can be generated.")
+        .write_to_file(dir.join("include/rashen.h"));
+}
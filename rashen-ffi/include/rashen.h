@@ -0,0 +1,142 @@
+#ifndef RASHEN_H
+#define RASHEN_H
+
+/* Generated by cbindgen from `rashen-ffi/src/lib.rs`; don't edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// What the data of an entry most likely is; see `rashen::format::detect`.
+typedef enum RashenFileType {
+  RASHEN_FILE_TYPE_UNKNOWN,
+  RASHEN_FILE_TYPE_PACKFILE,
+  RASHEN_FILE_TYPE_ZLIB,
+  RASHEN_FILE_TYPE_COLLISION,
+  RASHEN_FILE_TYPE_WAYPOINT,
+  RASHEN_FILE_TYPE_SOUND,
+  RASHEN_FILE_TYPE_TEXT,
+  RASHEN_FILE_TYPE_PALETTE,
+  RASHEN_FILE_TYPE_TEXTURE,
+  RASHEN_FILE_TYPE_SKYBOX,
+  RASHEN_FILE_TYPE_MODEL,
+  RASHEN_FILE_TYPE_NOISE,
+} RashenFileType;
+
+// An opened `packfile.dat`.
+typedef struct RashenArchive RashenArchive;
+
+// A row of the entry table of an archive.
+typedef struct RashenEntry {
+  // The size of the entry, as it is stored on the archive.
+  size_t size;
+  // The raw `type` column of the entry table.
+  uint32_t entry_type;
+  // Whether the entry is `ZL` compressed.
+  bool compressed;
+  enum RashenFileType file_type;
+} RashenEntry;
+
+// The data of an entry, or of a whole archive.
+typedef struct RashenBuffer {
+  uint8_t *data;
+  size_t len;
+} RashenBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The message of the last error of this thread, or null if there wasn't one.
+//
+// The string is owned by the library, and it is valid until the next call that fails.
+const char *rashen_last_error(void);
+
+// Opens the archive at `path`; returns null if it can't be read or parsed.
+//
+// # Safety
+//
+// `path` must be a valid, nul terminated, string.
+struct RashenArchive *rashen_archive_open(const char *path);
+
+// Parses an archive from the `len` bytes at `data`; returns null if they are not valid.
+//
+// # Safety
+//
+// `data` must point to `len` readable bytes.
+struct RashenArchive *rashen_archive_open_bytes(const uint8_t *data, size_t len);
+
+// Releases an archive; null is ignored.
+//
+// # Safety
+//
+// `archive` must be returned by `rashen_archive_open*`, and it can't be used afterwards.
+void rashen_archive_free(struct RashenArchive *archive);
+
+// The number of entries of `archive`.
+//
+// # Safety
+//
+// `archive` must be a valid archive.
+size_t rashen_archive_len(const struct RashenArchive *archive);
+
+// Writes the row of the entry at `index` into `entry`.
+//
+// # Safety
+//
+// `archive` must be a valid archive, and `entry` must be writable.
+bool rashen_archive_entry(const struct RashenArchive *archive,
+                          size_t index,
+                          struct RashenEntry *entry);
+
+// Copies the data of the entry at `index` into `buffer`; with `decompress`, `ZL` entries are
+// decompressed first.
+//
+// # Safety
+//
+// `archive` must be a valid archive, and `buffer` must be writable.
+bool rashen_archive_extract(const struct RashenArchive *archive,
+                            size_t index,
+                            bool decompress,
+                            struct RashenBuffer *buffer);
+
+// Replaces the data of the entry at `index` with the `len` bytes at `data`; with `compress`,
+// they are `ZL` compressed first.
+//
+// # Safety
+//
+// `archive` must be a valid archive, and `data` must point to `len` readable bytes.
+bool rashen_archive_replace(struct RashenArchive *archive,
+                            size_t index,
+                            const uint8_t *data,
+                            size_t len,
+                            bool compress);
+
+// Packs `archive` into `buffer`.
+//
+// # Safety
+//
+// `archive` must be a valid archive, and `buffer` must be writable.
+bool rashen_archive_pack(const struct RashenArchive *archive, struct RashenBuffer *buffer);
+
+// Packs `archive`, and writes it to `path`.
+//
+// # Safety
+//
+// `archive` must be a valid archive, and `path` a valid, nul terminated, string.
+bool rashen_archive_save(const struct RashenArchive *archive, const char *path);
+
+// Releases the data of `buffer`, and empties it; an empty buffer is ignored.
+//
+// # Safety
+//
+// `buffer` must be filled by this library, or be empty.
+void rashen_buffer_free(struct RashenBuffer *buffer);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RASHEN_H */
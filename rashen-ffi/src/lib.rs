@@ -0,0 +1,462 @@
+//! A C ABI for `rashen`, for tools that can't link against a Rust crate; the header is generated
+//! into `include/rashen.h`.
+//!
+//! Functions that can fail return `false` (or a null pointer), and leave a message that
+//! [`rashen_last_error`] returns. Buffers that are returned to the caller are owned by it, and
+//! they are released with [`rashen_buffer_free`].
+
+#![warn(clippy::pedantic)]
+
+use rashen::format::{
+    detect::FileType,
+    pman::{self, Compression, PmanFile},
+};
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    fs,
+    path::Path,
+    ptr, slice,
+};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: &str) {
+    // an inner nul can't be represented; the message is cut there.
+    let message = message.split('\0').next().unwrap_or_default();
+
+    LAST_ERROR.with(|error| *error.borrow_mut() = CString::new(message).ok());
+}
+
+/// Stores the error of `result`, if any; see [`rashen_last_error`].
+fn check<T, E: ToString>(result: Result<T, E>) -> Option<T> {
+    result.map_err(|err| set_error(&err.to_string())).ok()
+}
+
+/// An opened `packfile.dat`.
+pub struct RashenArchive(PmanFile);
+
+/// The data of an entry, or of a whole archive.
+#[repr(C)]
+pub struct RashenBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl RashenBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let bytes = Box::leak(bytes.into_boxed_slice());
+
+        Self {
+            data: bytes.as_mut_ptr(),
+            len: bytes.len(),
+        }
+    }
+}
+
+/// What the data of an entry most likely is; see `rashen::format::detect`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RashenFileType {
+    Unknown,
+    Packfile,
+    Zlib,
+    Collision,
+    Waypoint,
+    Sound,
+    Text,
+    Palette,
+    Texture,
+    Skybox,
+    Model,
+    Noise,
+}
+
+impl From<Option<FileType>> for RashenFileType {
+    fn from(file_type: Option<FileType>) -> Self {
+        match file_type {
+            None => Self::Unknown,
+            Some(FileType::Packfile) => Self::Packfile,
+            Some(FileType::Zlib) => Self::Zlib,
+            Some(FileType::Collision) => Self::Collision,
+            Some(FileType::Waypoint) => Self::Waypoint,
+            Some(FileType::Sound) => Self::Sound,
+            Some(FileType::Text) => Self::Text,
+            Some(FileType::Palette) => Self::Palette,
+            Some(FileType::Texture) => Self::Texture,
+            Some(FileType::Skybox) => Self::Skybox,
+            Some(FileType::Model) => Self::Model,
+            Some(FileType::Noise) => Self::Noise,
+        }
+    }
+}
+
+/// A row of the entry table of an archive.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RashenEntry {
+    /// The size of the entry, as it is stored on the archive.
+    pub size: usize,
+    /// The raw `type` column of the entry table.
+    pub entry_type: u32,
+    /// Whether the entry is `ZL` compressed.
+    pub compressed: bool,
+    pub file_type: RashenFileType,
+}
+
+/// The message of the last error of this thread, or null if there wasn't one.
+///
+/// The string is owned by the library, and it is valid until the next call that fails.
+#[no_mangle]
+pub extern "C" fn rashen_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Opens the archive at `path`; returns null if it can't be read or parsed.
+///
+/// # Safety
+///
+/// `path` must be a valid, nul terminated, string.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_archive_open(path: *const c_char) -> *mut RashenArchive {
+    if path.is_null() {
+        set_error("`path` is null");
+        return ptr::null_mut();
+    }
+    let Some(path) = check(unsafe { CStr::from_ptr(path) }.to_str()) else {
+        return ptr::null_mut();
+    };
+
+    check(fs::read(path))
+        .and_then(|bytes| check(PmanFile::new(&bytes)))
+        .map_or(ptr::null_mut(), |pman| {
+            Box::into_raw(Box::new(RashenArchive(pman)))
+        })
+}
+
+/// Parses an archive from the `len` bytes at `data`; returns null if they are not valid.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_archive_open_bytes(
+    data: *const u8,
+    len: usize,
+) -> *mut RashenArchive {
+    let Some(bytes) = (unsafe { bytes(data, len) }) else {
+        return ptr::null_mut();
+    };
+
+    check(PmanFile::new(bytes)).map_or(ptr::null_mut(), |pman| {
+        Box::into_raw(Box::new(RashenArchive(pman)))
+    })
+}
+
+/// Releases an archive; null is ignored.
+///
+/// # Safety
+///
+/// `archive` must be returned by `rashen_archive_open*`, and it can't be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_archive_free(archive: *mut RashenArchive) {
+    if !archive.is_null() {
+        drop(unsafe { Box::from_raw(archive) });
+    }
+}
+
+/// The number of entries of `archive`.
+///
+/// # Safety
+///
+/// `archive` must be a valid archive.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_archive_len(archive: *const RashenArchive) -> usize {
+    unsafe { archive.as_ref() }.map_or(0, |archive| archive.0.files().len())
+}
+
+/// Writes the row of the entry at `index` into `entry`.
+///
+/// # Safety
+///
+/// `archive` must be a valid archive, and `entry` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_archive_entry(
+    archive: *const RashenArchive,
+    index: usize,
+    entry: *mut RashenEntry,
+) -> bool {
+    let Some(file) = (unsafe { file(archive, index) }) else {
+        return false;
+    };
+    if entry.is_null() {
+        set_error("`entry` is null");
+        return false;
+    }
+
+    let row = RashenEntry {
+        size: file.bytes().len(),
+        entry_type: file.entry_type(),
+        compressed: matches!(file.compression(), Compression::Zlib { .. }),
+        file_type: file.detect_type().into(),
+    };
+    unsafe { entry.write(row) };
+
+    true
+}
+
+/// Copies the data of the entry at `index` into `buffer`; with `decompress`, `ZL` entries are
+/// decompressed first.
+///
+/// # Safety
+///
+/// `archive` must be a valid archive, and `buffer` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_archive_extract(
+    archive: *const RashenArchive,
+    index: usize,
+    decompress: bool,
+    buffer: *mut RashenBuffer,
+) -> bool {
+    let Some(file) = (unsafe { file(archive, index) }) else {
+        return false;
+    };
+    let bytes = if decompress {
+        let Some(zlib) = check(file.to_zlib()) else {
+            return false;
+        };
+        zlib.unwrap_or_else(|| file.bytes().to_vec())
+    } else {
+        file.bytes().to_vec()
+    };
+
+    unsafe { write_buffer(buffer, bytes) }
+}
+
+/// Replaces the data of the entry at `index` with the `len` bytes at `data`; with `compress`,
+/// they are `ZL` compressed first.
+///
+/// # Safety
+///
+/// `archive` must be a valid archive, and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_archive_replace(
+    archive: *mut RashenArchive,
+    index: usize,
+    data: *const u8,
+    len: usize,
+    compress: bool,
+) -> bool {
+    let Some(archive) = (unsafe { archive.as_mut() }) else {
+        set_error("`archive` is null");
+        return false;
+    };
+    let Some(bytes) = (unsafe { self::bytes(data, len) }) else {
+        return false;
+    };
+    let count = archive.0.files().len();
+    let Some(file) = archive.0.files_mut().get_mut(index) else {
+        set_error(&format!(
+            "there is no entry {index}; the archive has {count} of them"
+        ));
+        return false;
+    };
+
+    let bytes = if compress {
+        let Some(bytes) = check(pman::compress(bytes)) else {
+            return false;
+        };
+        bytes
+    } else {
+        bytes.to_vec()
+    };
+    file.set_bytes(bytes);
+
+    true
+}
+
+/// Packs `archive` into `buffer`.
+///
+/// # Safety
+///
+/// `archive` must be a valid archive, and `buffer` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_archive_pack(
+    archive: *const RashenArchive,
+    buffer: *mut RashenBuffer,
+) -> bool {
+    let Some(archive) = (unsafe { archive.as_ref() }) else {
+        set_error("`archive` is null");
+        return false;
+    };
+
+    check(archive.0.clone().into_bytes())
+        .is_some_and(|bytes| unsafe { write_buffer(buffer, bytes) })
+}
+
+/// Packs `archive`, and writes it to `path`.
+///
+/// # Safety
+///
+/// `archive` must be a valid archive, and `path` a valid, nul terminated, string.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_archive_save(
+    archive: *const RashenArchive,
+    path: *const c_char,
+) -> bool {
+    let Some(archive) = (unsafe { archive.as_ref() }) else {
+        set_error("`archive` is null");
+        return false;
+    };
+    if path.is_null() {
+        set_error("`path` is null");
+        return false;
+    }
+    let Some(path) = check(unsafe { CStr::from_ptr(path) }.to_str()) else {
+        return false;
+    };
+
+    check(archive.0.clone().into_bytes())
+        .and_then(|bytes| check(fs::write(Path::new(path), bytes)))
+        .is_some()
+}
+
+/// Releases the data of `buffer`, and empties it; an empty buffer is ignored.
+///
+/// # Safety
+///
+/// `buffer` must be filled by this library, or be empty.
+#[no_mangle]
+pub unsafe extern "C" fn rashen_buffer_free(buffer: *mut RashenBuffer) {
+    let Some(buffer) = (unsafe { buffer.as_mut() }) else {
+        return;
+    };
+
+    if !buffer.data.is_null() {
+        let bytes = ptr::slice_from_raw_parts_mut(buffer.data, buffer.len);
+        drop(unsafe { Box::from_raw(bytes) });
+    }
+    buffer.data = ptr::null_mut();
+    buffer.len = 0;
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (data.is_null(), len) {
+        (_, 0) => Some(&[]),
+        (true, _) => {
+            set_error("`data` is null");
+            None
+        }
+        (false, _) => Some(unsafe { slice::from_raw_parts(data, len) }),
+    }
+}
+
+unsafe fn file<'a>(archive: *const RashenArchive, index: usize) -> Option<&'a pman::PmanFileData> {
+    let Some(archive) = (unsafe { archive.as_ref() }) else {
+        set_error("`archive` is null");
+        return None;
+    };
+    let files = archive.0.files();
+
+    files.get(index).or_else(|| {
+        set_error(&format!(
+            "there is no entry {index}; the archive has {} of them",
+            files.len()
+        ));
+        None
+    })
+}
+
+unsafe fn write_buffer(buffer: *mut RashenBuffer, bytes: Vec<u8>) -> bool {
+    if buffer.is_null() {
+        set_error("`buffer` is null");
+        return false;
+    }
+    unsafe { buffer.write(RashenBuffer::new(bytes)) };
+
+    true
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(rashen_last_error()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn archive_test() {
+        unsafe {
+            let archive = rashen_archive_open_bytes(INPUT.as_ptr(), INPUT.len());
+            assert!(!archive.is_null());
+            assert_eq!(rashen_archive_len(archive), 158);
+
+            let mut entry = RashenEntry {
+                size: 0,
+                entry_type: 0,
+                compressed: false,
+                file_type: RashenFileType::Unknown,
+            };
+            assert!(rashen_archive_entry(archive, 156, &raw mut entry));
+            assert!(entry.compressed);
+            assert_eq!(entry.file_type, RashenFileType::Text);
+
+            let mut buffer = RashenBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert!(rashen_archive_extract(archive, 156, true, &raw mut buffer));
+            let german = slice::from_raw_parts(buffer.data, buffer.len).to_vec();
+            rashen_buffer_free(&raw mut buffer);
+            assert!(buffer.data.is_null());
+
+            assert!(rashen_archive_replace(
+                archive,
+                72,
+                german.as_ptr(),
+                german.len(),
+                true
+            ));
+            assert!(rashen_archive_pack(archive, &raw mut buffer));
+            rashen_archive_free(archive);
+
+            let pman = PmanFile::new(slice::from_raw_parts(buffer.data, buffer.len)).unwrap();
+            assert_eq!(pman[72].to_zlib().unwrap(), Some(german));
+            rashen_buffer_free(&raw mut buffer);
+        }
+    }
+
+    #[test]
+    fn archive_error_test() {
+        unsafe {
+            let archive = rashen_archive_open_bytes(INPUT.as_ptr(), 16);
+            assert!(archive.is_null());
+            assert!(!last_error().is_empty());
+
+            let archive = rashen_archive_open_bytes(INPUT.as_ptr(), INPUT.len());
+            let mut buffer = RashenBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert!(!rashen_archive_extract(
+                archive,
+                1000,
+                false,
+                &raw mut buffer
+            ));
+            assert_eq!(
+                last_error(),
+                "there is no entry 1000; the archive has 158 of them"
+            );
+            assert!(buffer.data.is_null());
+            rashen_archive_free(archive);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! A Node.js API of `rashen`, for mod managers (e.g. Electron ones) that embed it; built with
+//! `napi build --platform --release` on `rashen-node/`.
+//!
+//! Bytes cross the boundary as `Buffer`s, and errors are thrown as `Error`s.
+
+#![warn(clippy::pedantic)]
+// `#[napi]` functions take their arguments by value.
+#![allow(clippy::needless_pass_by_value)]
+
+use napi::{bindgen_prelude::Buffer, Error, Result};
+use napi_derive::napi;
+use rashen::{
+    format::{
+        detect,
+        pman::{self, Compression, PackOptions, PmanFile},
+    },
+    import::install,
+};
+use std::fs;
+
+fn error(err: impl ToString) -> Error {
+    Error::from_reason(err.to_string())
+}
+
+/// A `packfile.dat`, and the sizes that its entries had when it was opened.
+#[napi]
+pub struct Archive {
+    pman: PmanFile,
+    original: PackOptions,
+}
+
+/// A row of the entry table of an [`Archive`].
+#[napi(object)]
+pub struct EntryInfo {
+    pub index: u32,
+    /// The size of the entry, as it is stored on the archive.
+    pub size: u32,
+    pub entry_type: u32,
+    pub compressed: bool,
+    /// What the entry most likely is (e.g. `"texture"`); `undefined` if nothing matches.
+    pub file_type: Option<String>,
+}
+
+/// A limit that a packed archive exceeds; see [`Archive::pack`].
+#[napi(object)]
+pub struct Violation {
+    pub message: String,
+    /// The entry that grew past its original size; `undefined` if it is the whole archive.
+    pub index: Option<u32>,
+}
+
+/// A packed archive, and the limits that it exceeds.
+#[napi(object)]
+pub struct Packed {
+    pub bytes: Buffer,
+    pub violations: Vec<Violation>,
+}
+
+#[napi]
+impl Archive {
+    /// Parses an archive.
+    ///
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid archive.
+    #[napi(constructor)]
+    pub fn new(bytes: Buffer) -> Result<Self> {
+        let pman = PmanFile::new(&bytes).map_err(error)?;
+
+        Ok(Self {
+            original: PackOptions::with_original_sizes(&pman),
+            pman,
+        })
+    }
+
+    /// Reads an archive from `path`; the archive itself, or the install folder, the `.sis`
+    /// installer or the card image of the game.
+    ///
+    /// # Errors
+    ///
+    /// If `path` can't be read, or if there is no valid archive on it.
+    #[napi(factory)]
+    pub fn open(path: String) -> Result<Self> {
+        let (bytes, _) = install::locate(path).map_err(error)?;
+
+        Self::new(bytes.into())
+    }
+
+    /// The number of entries.
+    #[napi(getter)]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn length(&self) -> u32 {
+        // the entry table counts them with an `u32`.
+        self.pman.files().len() as u32
+    }
+
+    #[napi(getter)]
+    #[must_use]
+    pub fn copyright(&self) -> String {
+        self.pman.copyright().to_owned()
+    }
+
+    /// Every row of the entry table.
+    #[napi]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn entries(&self) -> Vec<EntryInfo> {
+        self.pman
+            .files()
+            .iter()
+            .enumerate()
+            .map(|(index, file)| EntryInfo {
+                index: index as u32,
+                size: file.bytes().len() as u32,
+                entry_type: file.entry_type(),
+                compressed: matches!(file.compression(), Compression::Zlib { .. }),
+                file_type: file
+                    .detect_type()
+                    .map(|file_type| file_type.extension().to_owned()),
+            })
+            .collect()
+    }
+
+    fn file(&self, index: u32) -> Result<&pman::PmanFileData> {
+        self.pman.files().get(index as usize).ok_or_else(|| {
+            error(format!(
+                "there is no entry {index}; the archive has {} of them",
+                self.length()
+            ))
+        })
+    }
+
+    /// The data of the entry at `index`; `ZL` entries are decompressed, unless `decompress` is
+    /// `false`.
+    ///
+    /// # Errors
+    ///
+    /// If there is no entry at `index`, or if it can't be decompressed.
+    #[napi]
+    pub fn extract(&self, index: u32, decompress: Option<bool>) -> Result<Buffer> {
+        let file = self.file(index)?;
+
+        if decompress.unwrap_or(true) {
+            if let Some(zlib) = file.to_zlib().map_err(error)? {
+                return Ok(zlib.into());
+            }
+        }
+
+        Ok(file.bytes().to_vec().into())
+    }
+
+    /// Replaces the data of the entry at `index`; it is `ZL` compressed first if `compress` is
+    /// `true`, or if it is not given and the entry was compressed.
+    ///
+    /// # Errors
+    ///
+    /// If there is no entry at `index`, or if `bytes` can't be compressed.
+    #[napi]
+    pub fn replace(&mut self, index: u32, bytes: Buffer, compress: Option<bool>) -> Result<()> {
+        let compressed = matches!(self.file(index)?.compression(), Compression::Zlib { .. });
+        let bytes = if compress.unwrap_or(compressed) {
+            pman::compress(&bytes).map_err(error)?
+        } else {
+            bytes.to_vec()
+        };
+        self.pman.files_mut()[index as usize].set_bytes(bytes);
+
+        Ok(())
+    }
+
+    /// Packs the archive, with every replaced entry; the entries (and the archive) that grew past
+    /// their size on the opened archive are returned as `violations`. With `strict`, it throws on
+    /// them instead.
+    ///
+    /// # Errors
+    ///
+    /// If an entry is too big for the entry table; or, with `strict`, if a limit is exceeded.
+    #[napi]
+    pub fn pack(&self, strict: Option<bool>) -> Result<Packed> {
+        let options = PackOptions {
+            strict: strict.unwrap_or(false),
+            ..self.original.clone()
+        };
+        let (bytes, violations) = self.pman.clone().into_bytes_with(&options).map_err(error)?;
+        #[allow(clippy::cast_possible_truncation)]
+        let violations = violations
+            .into_iter()
+            .map(|violation| Violation {
+                message: violation.to_string(),
+                index: match violation {
+                    pman::BudgetViolation::Entry { index, .. } => Some(index as u32),
+                    pman::BudgetViolation::Archive { .. } => None,
+                },
+            })
+            .collect();
+
+        Ok(Packed {
+            bytes: bytes.into(),
+            violations,
+        })
+    }
+
+    /// Packs the archive into `path`; see [`Self::pack`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::pack`]; or if `path` can't be written.
+    #[napi]
+    pub fn save(&self, path: String, strict: Option<bool>) -> Result<Vec<Violation>> {
+        let packed = self.pack(strict)?;
+        fs::write(path, &packed.bytes[..]).map_err(error)?;
+
+        Ok(packed.violations)
+    }
+}
+
+/// `ZL` compresses `data`.
+///
+/// # Errors
+///
+/// If the zlib stream can't be written.
+#[napi]
+pub fn compress(data: Buffer) -> Result<Buffer> {
+    Ok(pman::compress(&data).map_err(error)?.into())
+}
+
+/// Decompresses a `ZL` chunk; `null` if `data` is not one.
+///
+/// # Errors
+///
+/// If `data` is a `ZL` chunk, but it can't be decompressed.
+#[napi]
+pub fn decompress(data: Buffer) -> Result<Option<Buffer>> {
+    Ok(pman::decompress(&data).map_err(error)?.map(Into::into))
+}
+
+/// Every type that `data` could be (e.g. `"texture"`); from the most to the least likely.
+#[napi]
+#[must_use]
+pub fn detect(data: Buffer) -> Vec<String> {
+    detect::detect(&data)
+        .into_iter()
+        .map(|guess| guess.file_type.extension().to_owned())
+        .collect()
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    fn archive_test() -> Result<()> {
+        let mut archive = Archive::new(INPUT.to_vec().into())?;
+        let entries = archive.entries();
+        assert_eq!(entries.len(), 158);
+        assert_eq!(entries[156].file_type.as_deref(), Some("text"));
+        assert!(entries[156].compressed);
+
+        assert!(archive.pack(None)?.violations.is_empty());
+
+        let text = archive.extract(156, None)?;
+        assert_eq!(detect(text.to_vec().into())[0], "text");
+
+        // it is compressed back, like the original one.
+        archive.replace(156, text, None)?;
+        assert!(archive.entries()[156].compressed);
+
+        archive.replace(156, vec![0; 0x10_0000].into(), Some(false))?;
+        let packed = archive.pack(None)?;
+        assert_eq!(packed.violations[0].index, Some(156));
+        assert!(archive.pack(Some(true)).is_err());
+        assert!(archive.extract(158, None).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+//! A JavaScript API of `rashen`, for browsers; built with `wasm-pack build rashen-wasm`.
+//!
+//! Bytes cross the boundary as `Uint8Array`s, and errors are thrown as `Error`s.
+
+#![warn(clippy::pedantic)]
+// `#[wasm_bindgen]` methods take their arguments by value.
+#![allow(clippy::needless_pass_by_value)]
+
+use rashen::format::pman::{self, Compression, ParseLimits, PmanFile};
+use wasm_bindgen::prelude::*;
+
+/// A `packfile.dat` that was dropped on the page.
+#[wasm_bindgen]
+pub struct Archive {
+    pman: PmanFile,
+}
+
+/// A row of the entry table of an [`Archive`].
+#[wasm_bindgen(getter_with_clone)]
+pub struct EntryInfo {
+    pub index: usize,
+    /// The size of the entry, as it is stored on the archive.
+    pub size: usize,
+    #[wasm_bindgen(js_name = "entryType")]
+    pub entry_type: u32,
+    pub compressed: bool,
+    /// What the entry most likely is (e.g. `"texture"`); `undefined` if nothing matches.
+    #[wasm_bindgen(js_name = "fileType")]
+    pub file_type: Option<String>,
+}
+
+fn no_entry(index: usize, len: usize) -> JsError {
+    JsError::new(&format!(
+        "there is no entry {index}; the archive has {len} of them"
+    ))
+}
+
+#[wasm_bindgen]
+impl Archive {
+    /// Parses an archive; it was dropped by whoever is on the page, so it is checked against the
+    /// default [`ParseLimits`].
+    ///
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid archive, or if it goes past the limits.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<Archive, JsError> {
+        Ok(Self {
+            pman: PmanFile::from_reader_with_limits(bytes, &ParseLimits::default())?,
+        })
+    }
+
+    /// The number of entries.
+    #[wasm_bindgen(getter)]
+    #[must_use]
+    pub fn length(&self) -> usize {
+        self.pman.files().len()
+    }
+
+    #[wasm_bindgen(getter)]
+    #[must_use]
+    pub fn copyright(&self) -> String {
+        self.pman.copyright().to_owned()
+    }
+
+    /// Every row of the entry table.
+    #[must_use]
+    pub fn entries(&self) -> Vec<EntryInfo> {
+        self.pman
+            .files()
+            .iter()
+            .enumerate()
+            .map(|(index, file)| EntryInfo {
+                index,
+                size: file.bytes().len(),
+                entry_type: file.entry_type(),
+                compressed: matches!(file.compression(), Compression::Zlib { .. }),
+                file_type: file
+                    .detect_type()
+                    .map(|file_type| format!("{file_type:?}").to_lowercase()),
+            })
+            .collect()
+    }
+
+    /// The data of the entry at `index`; with `decompress`, `ZL` entries are decompressed first.
+    ///
+    /// # Errors
+    ///
+    /// If there is no entry at `index`, or if it can't be decompressed.
+    pub fn extract(&self, index: usize, decompress: bool) -> Result<Vec<u8>, JsError> {
+        let file = self
+            .pman
+            .files()
+            .get(index)
+            .ok_or_else(|| no_entry(index, self.length()))?;
+
+        if decompress {
+            if let Some(zlib) = file.to_zlib()? {
+                return Ok(zlib);
+            }
+        }
+
+        Ok(file.bytes().to_vec())
+    }
+
+    /// Replaces the data of the entry at `index`; with `compress`, it is `ZL` compressed first.
+    ///
+    /// # Errors
+    ///
+    /// If there is no entry at `index`, or if `bytes` can't be compressed.
+    pub fn replace(&mut self, index: usize, bytes: &[u8], compress: bool) -> Result<(), JsError> {
+        let len = self.length();
+        let bytes = if compress {
+            pman::compress(bytes)?
+        } else {
+            bytes.to_vec()
+        };
+        let file = self
+            .pman
+            .files_mut()
+            .get_mut(index)
+            .ok_or_else(|| no_entry(index, len))?;
+        file.set_bytes(bytes);
+
+        Ok(())
+    }
+
+    /// Packs the archive, with every replaced entry.
+    ///
+    /// # Errors
+    ///
+    /// If an entry is too big for the entry table.
+    pub fn repack(&self) -> Result<Vec<u8>, JsError> {
+        Ok(self.pman.clone().into_bytes()?)
+    }
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    // `JsError`s can only be made on `wasm32`, so only the successful paths are tested here.
+    #[test]
+    fn archive_test() {
+        let mut archive = Archive::new(INPUT).unwrap();
+        let entries = archive.entries();
+        assert_eq!(entries.len(), archive.length());
+        assert_eq!(entries[156].file_type.as_deref(), Some("text"));
+        assert!(entries[156].compressed);
+
+        let german = archive.extract(156, true).unwrap();
+        assert!(german.len() > entries[156].size);
+
+        archive.replace(72, &german, true).unwrap();
+        let archive = Archive::new(&archive.repack().unwrap()).unwrap();
+        assert_eq!(archive.extract(72, true).unwrap(), german);
+    }
+}
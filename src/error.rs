@@ -0,0 +1,742 @@
+use crate::format::pman::BudgetViolation;
+use miette::{
+    Diagnostic, LabeledSpan, MietteError, MietteSpanContents, SourceCode, SourceSpan, SpanContents,
+};
+use nom::{error::ErrorKind as NomErrorKind, Offset};
+use nom_supreme::error::{BaseErrorKind, Expectation, GenericErrorTree, StackContext};
+use serde_json::{json, Value};
+use std::{error::Error as StdError, fmt::Write, io};
+use thiserror::Error;
+
+/// The label attached to a parser with [`nom_supreme::ParserExt::context`].
+pub(crate) type NomContext = &'static str;
+
+type NomExternalError = Box<dyn StdError + Send + Sync + 'static>;
+
+/// The error produced by every parser on [`crate::format`].
+pub(crate) type NomError<'a> =
+    GenericErrorTree<&'a [u8], &'static [u8], NomContext, NomExternalError>;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum Error {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidFormat(Box<InvalidFormatError>),
+    #[error(
+        "the archive is truncated; its entries need {expected:#X} bytes, but there are only \
+         {actual:#X}, and entry {first_missing_entry} is the first one that doesn't fit"
+    )]
+    #[diagnostic(
+        code(rashen::truncated),
+        help("the entries that fit can still be read with `PmanFile::new_lenient`.")
+    )]
+    Truncated {
+        expected: usize,
+        actual: usize,
+        first_missing_entry: usize,
+    },
+    #[error(
+        "entry {index} starts at {offset:#X}, but the data before it ends at {previous_end:#X}; \
+         they overlap"
+    )]
+    #[diagnostic(
+        code(rashen::entry_overlap),
+        help("`PmanFile::new_lenient` reads every entry from its own offset, overlap or not.")
+    )]
+    EntryOverlap {
+        index: usize,
+        offset: usize,
+        previous_end: usize,
+    },
+    #[error(
+        "entry {index} starts at {offset:#X}, before the previous entry at {previous_offset:#X}; \
+         the entries are out of order"
+    )]
+    #[diagnostic(
+        code(rashen::entry_out_of_order),
+        help("`PmanFile::new_lenient` reads every entry from its own offset, in any order.")
+    )]
+    EntryOutOfOrder {
+        index: usize,
+        offset: usize,
+        previous_offset: usize,
+    },
+    #[error("invalid `ZL` data")]
+    #[diagnostic(code(rashen::invalid_zlib))]
+    InvalidZlib(#[source] io::Error),
+    #[error("the `ZL` header says {expected:#X} bytes, but the data decompresses to {actual:#X}")]
+    #[diagnostic(code(rashen::zlib_size_mismatch))]
+    ZlibSizeMismatch { expected: usize, actual: usize },
+    #[error(
+        "the `ZL` data decompresses to more than {limit:#X} bytes, the most that {what} can take"
+    )]
+    #[diagnostic(
+        code(rashen::decompression_limit),
+        help("the limits can be raised with `DecompressLimits`, if the archive is trusted.")
+    )]
+    DecompressionLimit { limit: usize, what: &'static str },
+    #[error("{what} goes past the limit of {limit:#X}")]
+    #[diagnostic(
+        code(rashen::parse_limit),
+        help("the limits can be raised with `ParseLimits`, if the archive is trusted.")
+    )]
+    ParseLimit { limit: usize, what: &'static str },
+    #[error("the copyright notice is {len} bytes long, but it can't be longer than {max} bytes")]
+    #[diagnostic(
+        code(rashen::copyright_too_long),
+        help("do note that `length` != `# of chars`; non-ascii characters take more than 1 byte.")
+    )]
+    CopyrightTooLong { len: usize, max: usize },
+    #[error("the archive is {size:#X} bytes long, but offsets can't go past `u32::MAX`")]
+    #[diagnostic(code(rashen::archive_too_large))]
+    ArchiveTooLarge { size: usize },
+    #[error("the name `{name}` is {} bytes long, but it can't be longer than {max} bytes", .name.len())]
+    #[diagnostic(code(rashen::name_too_long))]
+    NameTooLong { name: String, max: usize },
+    #[error("there are {len} {what}, but their count can't go past `u32::MAX`")]
+    #[diagnostic(code(rashen::too_many_items))]
+    TooManyItems { what: &'static str, len: usize },
+    #[error("string {index} of the text bank can't be encoded; {reason}")]
+    #[diagnostic(code(rashen::invalid_text))]
+    InvalidText { index: usize, reason: String },
+    #[error("a {width}x{height} image needs {} pixels, but there are {len}", *.width as usize * *.height as usize)]
+    #[diagnostic(code(rashen::invalid_image_size))]
+    InvalidImageSize { width: u32, height: u32, len: usize },
+    #[error("the model can't be encoded; {reason}")]
+    #[diagnostic(code(rashen::invalid_model))]
+    InvalidModel { reason: String },
+    #[error("the font can't be read or written; {reason}")]
+    #[diagnostic(code(rashen::invalid_font))]
+    InvalidFont { reason: String },
+    #[error("the {format} file can't be imported; {reason}")]
+    #[diagnostic(code(rashen::invalid_import))]
+    InvalidImport {
+        format: &'static str,
+        reason: String,
+    },
+    #[error("the archive doesn't fit on its size budget; {}", join_violations(.violations))]
+    #[diagnostic(
+        code(rashen::over_budget),
+        help(
+            "the game might not load entries (or archives) that are larger than the original ones."
+        )
+    )]
+    OverBudget { violations: Vec<BudgetViolation> },
+    #[error(transparent)]
+    #[diagnostic(code(rashen::io))]
+    Io(#[from] io::Error),
+}
+
+fn join_violations(violations: &[BudgetViolation]) -> String {
+    let violations: Vec<_> = violations.iter().map(ToString::to_string).collect();
+
+    violations.join("; ")
+}
+
+impl Error {
+    /// What kind of failure this is, so it can be matched without looking at its message.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::InvalidFormat(err) => err.kind(),
+            Self::Truncated { .. } => ErrorKind::Truncated,
+            Self::EntryOverlap { .. } => ErrorKind::EntryOverlap,
+            Self::EntryOutOfOrder { .. } => ErrorKind::EntryOutOfOrder,
+            Self::InvalidZlib(_) => ErrorKind::InvalidZlib,
+            Self::ZlibSizeMismatch { .. } => ErrorKind::ZlibSizeMismatch,
+            Self::DecompressionLimit { .. } => ErrorKind::DecompressionLimit,
+            Self::ParseLimit { .. } => ErrorKind::ParseLimit,
+            Self::CopyrightTooLong { .. } => ErrorKind::CopyrightTooLong,
+            Self::ArchiveTooLarge { .. } => ErrorKind::ArchiveTooLarge,
+            Self::NameTooLong { .. } => ErrorKind::NameTooLong,
+            Self::TooManyItems { .. } => ErrorKind::TooManyItems,
+            Self::InvalidText { .. } => ErrorKind::InvalidText,
+            Self::InvalidImageSize { .. } => ErrorKind::InvalidImageSize,
+            Self::InvalidModel { .. } => ErrorKind::InvalidModel,
+            Self::InvalidFont { .. } => ErrorKind::InvalidFont,
+            Self::InvalidImport { .. } => ErrorKind::InvalidImport,
+            Self::OverBudget { .. } => ErrorKind::OverBudget,
+            Self::Io(_) => ErrorKind::Io,
+        }
+    }
+
+    /// A machine readable report of this error, for tools that can't parse the rendered one.
+    ///
+    /// Unlike the rendered report, every offset is relative to the start of the parsed file.
+    ///
+    /// ```json
+    /// {
+    ///   "kind": "BadMagic",
+    ///   "code": "rashen::invalid_format",
+    ///   "message": "invalid magic string at 0x0; expected `PMAN`",
+    ///   "help": "this doesn't look like a PMAN archive; ...",
+    ///   "offset": 0,
+    ///   "len": 4,
+    ///   "contexts": [{ "context": "magic string", "offset": 0 }, ...],
+    ///   "labels": [{ "label": "expected `PMAN`", "offset": 0, "len": 4, "primary": true }]
+    /// }
+    /// ```
+    #[must_use]
+    pub fn to_json(&self) -> Value {
+        let (offset, len, contexts, labels) = match self {
+            Self::InvalidFormat(err) => (
+                Some(err.offset),
+                Some(err.len),
+                err.contexts
+                    .iter()
+                    .map(|(offset, context)| json!({ "context": context, "offset": offset }))
+                    .collect(),
+                err.archive_labels()
+                    .map(|(label, offset, len, primary)| {
+                        json!({ "label": label, "offset": offset, "len": len, "primary": primary })
+                    })
+                    .collect(),
+            ),
+            _ => (None, None, vec![], vec![]),
+        };
+
+        json!({
+            "kind": format!("{:?}", self.kind()),
+            "code": self.code().map(|code| code.to_string()),
+            "message": self.to_string(),
+            "help": self.help().map(|help| help.to_string()),
+            "offset": offset,
+            "len": len,
+            "contexts": contexts,
+            "labels": labels,
+        })
+    }
+}
+
+impl From<InvalidFormatError> for Error {
+    fn from(err: InvalidFormatError) -> Self {
+        Self::InvalidFormat(Box::new(err))
+    }
+}
+
+/// A stable classification of an [`Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The file doesn't start with its magic string; `PMAN` for an archive.
+    BadMagic,
+    /// The file is too short to hold the header.
+    TruncatedHeader,
+    /// The copyright notice is not null terminated.
+    InvalidCopyright,
+    /// The file is too short to hold the entry table.
+    TruncatedEntryTable,
+    /// A row of the entry table has unexpected values.
+    InvalidEntryTable,
+    /// A file entry points past the end of the file.
+    EntryOutOfBounds,
+    /// There are bytes left after the last file.
+    TrailingBytes,
+    /// The file ends before the data of its entries does.
+    Truncated,
+    /// The data of an entry starts before the end of the previous one (or of the entry table).
+    EntryOverlap,
+    /// An entry starts before the previous one.
+    EntryOutOfOrder,
+    /// Malformed data that doesn't fit any other kind.
+    InvalidFormat,
+    /// The `ZL` header or its zlib stream is corrupted.
+    InvalidZlib,
+    /// A `ZL` stream doesn't decompress to the size its header says.
+    ZlibSizeMismatch,
+    /// `ZL` data decompresses to more than a limit of its
+    /// [`DecompressLimits`](crate::format::pman::DecompressLimits).
+    DecompressionLimit,
+    /// An archive goes past a limit of its [`ParseLimits`](crate::format::pman::ParseLimits).
+    ParseLimit,
+    /// The copyright notice doesn't fit on the header.
+    CopyrightTooLong,
+    /// The archive doesn't fit on the `u32` offsets of the entry table.
+    ArchiveTooLarge,
+    /// A name doesn't fit on its fixed size field.
+    NameTooLong,
+    /// A list has more items than what its `u32` count can hold.
+    TooManyItems,
+    /// A string of a text bank can't be encoded.
+    InvalidText,
+    /// The pixels of an image don't match its dimensions.
+    InvalidImageSize,
+    /// The frames of a model don't match its vertices or faces.
+    InvalidModel,
+    /// The sprites of a font are missing, or a glyph doesn't fit in its dimensions.
+    InvalidFont,
+    /// A file of another tool doesn't have what is needed to build an entry out of it.
+    InvalidImport,
+    /// A rebuilt archive exceeds a limit of its [`PackOptions`](crate::format::pman::PackOptions).
+    OverBudget,
+    Io,
+}
+
+impl ErrorKind {
+    /// A suggestion on how to fix a `packfile.dat` format error of this kind.
+    fn help(self) -> &'static str {
+        match self {
+            Self::BadMagic => {
+                "this doesn't look like a PMAN archive; did you pass the `.app` or the `.sis` \
+                 instead of the `packfile.dat`?"
+            }
+            Self::TruncatedHeader | Self::TruncatedEntryTable | Self::EntryOutOfBounds => {
+                "the file may be truncated; make sure it was copied completely."
+            }
+            Self::InvalidCopyright => {
+                "the copyright notice must be null terminated, and fit on the 64 bytes header."
+            }
+            Self::InvalidEntryTable => {
+                "every row of the entry table, and the table itself, must end with a zeroed `u32`."
+            }
+            Self::EntryOverlap => {
+                "the data of every entry must start after the end of the previous one; \
+                 `PmanFile::new_lenient` reads every entry from its own offset, overlap or not."
+            }
+            Self::EntryOutOfOrder => {
+                "the entries must be sorted by their offsets; `PmanFile::new_lenient` reads every \
+                 entry from its own offset, in any order."
+            }
+            Self::TrailingBytes => {
+                "if the extra bytes are expected, use `PmanFile::new_lenient` to keep them."
+            }
+            _ => {
+                "make sure that the file is the `packfile.dat` of the version `1.0.6` of the game."
+            }
+        }
+    }
+}
+
+/// A failure that `nom` can't describe by itself; e.g. an entry that points past the end of the
+/// file.
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub(crate) struct Violation {
+    kind: ErrorKind,
+    message: String,
+    /// How many bytes, starting from where the parser failed, are affected.
+    len: usize,
+    help: Option<String>,
+}
+
+impl Violation {
+    pub(crate) fn new<S>(kind: ErrorKind, message: S, len: usize) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            kind,
+            message: message.into(),
+            len,
+            help: None,
+        }
+    }
+
+    /// Replaces the generic help of [`Self::kind`] with a more specific one.
+    pub(crate) fn with_help<S>(mut self, help: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("invalid {} at {offset:#X}; {reason}", .contexts.first().map_or("input", |(_, c)| c))]
+#[diagnostic(code(rashen::invalid_format))]
+pub struct InvalidFormatError {
+    kind: ErrorKind,
+    offset: usize,
+    len: usize,
+    reason: String,
+    #[help]
+    help: Option<String>,
+    contexts: Vec<(usize, NomContext)>,
+    #[source_code]
+    dump: HexDump,
+    #[label(collection)]
+    labels: Vec<LabeledSpan>,
+}
+
+impl InvalidFormatError {
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The offset of the byte where the parser failed.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The bytes of the original input that caused the failure.
+    #[must_use]
+    pub fn span(&self) -> SourceSpan {
+        (self.offset, self.len).into()
+    }
+
+    /// What went wrong at [`Self::offset`].
+    #[must_use]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// The sections that were being parsed when the parser failed, and the offset where each of
+    /// them starts; from innermost to outermost.
+    #[must_use]
+    pub fn contexts(&self) -> &[(usize, NomContext)] {
+        &self.contexts
+    }
+
+    /// The same labels shown on the rendered report, but with offsets of the original input
+    /// instead of the hex dump: `(label, offset, len, primary)`.
+    fn archive_labels(&self) -> impl Iterator<Item = (String, usize, usize, bool)> + '_ {
+        std::iter::once((self.reason.clone(), self.offset, self.len, true)).chain(
+            inner_contexts(&self.contexts)
+                .iter()
+                .filter(|(start, _)| *start != self.offset)
+                .map(|(start, context)| (format!("{context} starts here"), *start, 1, false)),
+        )
+    }
+}
+
+/// Every context, but the outermost; it wraps the whole input, so there is no point on labeling
+/// it.
+fn inner_contexts<T>(contexts: &[T]) -> &[T] {
+    contexts.split_last().map_or(&[][..], |(_, inner)| inner)
+}
+
+/// Flattens `err` into its deepest location and the kind of the failure, while pushing every
+/// context found on the way into `contexts` (innermost first).
+fn flatten<'a>(
+    err: NomError<'a>,
+    contexts: &mut Vec<(&'a [u8], NomContext)>,
+) -> (&'a [u8], BaseErrorKind<&'static [u8], NomExternalError>) {
+    match err {
+        GenericErrorTree::Base { location, kind } => (location, kind),
+        GenericErrorTree::Stack {
+            base,
+            contexts: stack,
+        } => {
+            let base = flatten(*base, contexts);
+
+            contexts.extend(
+                stack
+                    .into_iter()
+                    .filter_map(|(location, context)| match context {
+                        StackContext::Context(context) => Some((location, context)),
+                        StackContext::Kind(_) => None,
+                    }),
+            );
+
+            base
+        }
+        // The branch that made the most progress is usually the one that matters.
+        GenericErrorTree::Alt(alts) => alts
+            .into_iter()
+            .map(|alt| {
+                let mut alt_contexts = vec![];
+                let base = flatten(alt, &mut alt_contexts);
+
+                (base, alt_contexts)
+            })
+            .min_by_key(|((location, _), _)| location.len())
+            .map(|(base, alt_contexts)| {
+                contexts.extend(alt_contexts);
+
+                base
+            })
+            .expect("`Alt` has at least two branches."),
+    }
+}
+
+/// A human readable reason of the failure, and how many bytes it affects; unless the parser
+/// already described it with a [`Violation`].
+fn describe(
+    kind: BaseErrorKind<&'static [u8], NomExternalError>,
+) -> std::result::Result<Violation, (String, usize)> {
+    let (reason, len) = match kind {
+        BaseErrorKind::Expected(Expectation::Tag(tag)) => (
+            format!("expected `{}`", String::from_utf8_lossy(tag)),
+            tag.len(),
+        ),
+        BaseErrorKind::Expected(Expectation::Char(c)) => (format!("expected {c:?}"), 1),
+        BaseErrorKind::Expected(Expectation::Eof) => ("expected the end of the data".into(), 1),
+        BaseErrorKind::Expected(expectation) => (format!("expected {expectation}"), 1),
+        BaseErrorKind::Kind(NomErrorKind::Eof) => ("unexpected end of file".into(), 1),
+        BaseErrorKind::Kind(NomErrorKind::Verify) => ("unexpected value".into(), 4),
+        BaseErrorKind::Kind(kind) => (kind.description().to_lowercase(), 1),
+        BaseErrorKind::External(err) => match err.downcast::<Violation>() {
+            Ok(violation) => return Ok(*violation),
+            Err(err) => (err.to_string(), 1),
+        },
+    };
+
+    Err((reason, len))
+}
+
+/// Tags every failure of `err` with `kind`, unless it was already described with a [`Violation`]
+/// (the innermost kind wins).
+pub(crate) fn with_kind(err: NomError<'_>, kind: ErrorKind) -> NomError<'_> {
+    match err {
+        GenericErrorTree::Base {
+            location,
+            kind: base,
+        } => {
+            let violation =
+                describe(base).unwrap_or_else(|(reason, len)| Violation::new(kind, reason, len));
+
+            GenericErrorTree::Base {
+                location,
+                kind: BaseErrorKind::External(Box::new(violation)),
+            }
+        }
+        GenericErrorTree::Stack { base, contexts } => GenericErrorTree::Stack {
+            base: Box::new(with_kind(*base, kind)),
+            contexts,
+        },
+        GenericErrorTree::Alt(alts) => {
+            GenericErrorTree::Alt(alts.into_iter().map(|alt| with_kind(alt, kind)).collect())
+        }
+    }
+}
+
+impl From<NomError<'_>> for InvalidFormatError {
+    fn from(err: NomError<'_>) -> Self {
+        let mut contexts = vec![];
+        let (location, kind) = flatten(err, &mut contexts);
+        let (kind, reason, len, help) = match describe(kind) {
+            Ok(violation) => (
+                violation.kind,
+                violation.message,
+                violation.len,
+                violation.help,
+            ),
+            Err((reason, len)) => (ErrorKind::InvalidFormat, reason, len, None),
+        };
+        let root = contexts.last().map(|(_, context)| *context);
+        // the hints of `ErrorKind` only make sense for the archive itself.
+        let help = help.or_else(|| (root == Some("packfile")).then(|| kind.help().into()));
+        // The outermost context wraps the whole input, so every other location is a sub-slice of
+        // it.
+        let input = contexts.last().map_or(location, |(input, _)| input);
+        let offset = input.offset(location);
+        let contexts = contexts
+            .into_iter()
+            .map(|(location, context)| (input.offset(location), context))
+            .collect::<Vec<_>>();
+
+        let dump = HexDump::new(root.unwrap_or("input"), input, offset);
+        let labels = std::iter::once(LabeledSpan::new_primary_with_span(
+            Some(reason.clone()),
+            dump.span(offset, len),
+        ))
+        .chain(
+            inner_contexts(&contexts)
+                .iter()
+                .filter(|(start, _)| *start != offset && dump.contains(*start))
+                .map(|(start, context)| {
+                    LabeledSpan::new_with_span(
+                        Some(format!("{context} starts here")),
+                        dump.span(*start, 1),
+                    )
+                }),
+        )
+        .collect();
+
+        Self {
+            kind,
+            offset,
+            len,
+            reason,
+            help,
+            contexts,
+            dump,
+            labels,
+        }
+    }
+}
+
+const HEX_DUMP_LINE_WIDTH: usize = 16;
+/// How many lines are shown before and after the line of the offending byte.
+const HEX_DUMP_CONTEXT_LINES: usize = 4;
+/// `offset` + 2 spaces.
+const HEX_DUMP_HEX_START: usize = 10;
+/// `hex` + 2 spaces + `|ascii|` + newline.
+const HEX_DUMP_LINE_SIZE: usize = HEX_DUMP_HEX_START + HEX_DUMP_LINE_WIDTH * 3 + 1 + 1 + 18 + 1;
+
+/// A window of binary data rendered as a hex + ASCII dump, so [`miette`] can point at the exact
+/// bytes where something went wrong.
+///
+/// ```text
+/// 00000000  50 4D 41 4E 9E 00 00 00  43 6F 70 79 72 69 67 68  |PMAN....Copyrigh|
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HexDump {
+    /// What the original input is; e.g. `packfile`.
+    name: NomContext,
+    /// The offset of the first byte of `bytes` on the original input.
+    start: usize,
+    bytes: Vec<u8>,
+    text: String,
+}
+
+impl HexDump {
+    /// Creates a dump of the lines of `input` around `offset`.
+    fn new(name: NomContext, input: &[u8], offset: usize) -> Self {
+        let line = offset / HEX_DUMP_LINE_WIDTH;
+        let start = line.saturating_sub(HEX_DUMP_CONTEXT_LINES) * HEX_DUMP_LINE_WIDTH;
+        let end = ((line + HEX_DUMP_CONTEXT_LINES + 1) * HEX_DUMP_LINE_WIDTH).min(input.len());
+        let bytes = input.get(start..end).unwrap_or_default().to_vec();
+
+        let mut text = String::with_capacity(bytes.len().div_ceil(16) * HEX_DUMP_LINE_SIZE);
+        bytes
+            .chunks(HEX_DUMP_LINE_WIDTH)
+            .enumerate()
+            .for_each(|(i, chunk)| {
+                // `fmt::Write` for `String` never fails.
+                _ = write!(text, "{:08X}  ", start + i * HEX_DUMP_LINE_WIDTH);
+                (0..HEX_DUMP_LINE_WIDTH).for_each(|i| {
+                    match chunk.get(i) {
+                        Some(byte) => _ = write!(text, "{byte:02X} "),
+                        None => text.push_str("   "),
+                    }
+                    if i == HEX_DUMP_LINE_WIDTH / 2 - 1 {
+                        text.push(' ');
+                    }
+                });
+                text.push_str(" |");
+                text.extend(chunk.iter().map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                }));
+                text.push_str("|\n");
+            });
+
+        Self {
+            name,
+            start,
+            bytes,
+            text,
+        }
+    }
+
+    /// The offset of `offset` (relative to the original input) on the rendered text.
+    fn text_offset(&self, offset: usize) -> usize {
+        let relative = offset - self.start;
+        let (line, column) = (
+            relative / HEX_DUMP_LINE_WIDTH,
+            relative % HEX_DUMP_LINE_WIDTH,
+        );
+        let gap = usize::from(column >= HEX_DUMP_LINE_WIDTH / 2);
+
+        line * HEX_DUMP_LINE_SIZE + HEX_DUMP_HEX_START + column * 3 + gap
+    }
+
+    /// Whether the byte at `offset` of the original input is part of the dumped window.
+    fn contains(&self, offset: usize) -> bool {
+        (self.start..self.start + self.bytes.len()).contains(&offset)
+    }
+
+    /// Converts a range of bytes of the original input into a span of the rendered text.
+    ///
+    /// Bytes outside of the dumped window are clamped to its bounds.
+    fn span(&self, offset: usize, len: usize) -> SourceSpan {
+        let Some(last) = (self.start + self.bytes.len()).checked_sub(1) else {
+            return (0, 0).into();
+        };
+        let first = offset.clamp(self.start, last);
+        let end = (offset + len.max(1) - 1).clamp(first, last);
+        let start = self.text_offset(first);
+
+        (start, self.text_offset(end) + 2 - start).into()
+    }
+}
+
+impl SourceCode for HexDump {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> std::result::Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let contents = self
+            .text
+            .read_span(span, context_lines_before, context_lines_after)?;
+
+        Ok(Box::new(MietteSpanContents::new_named(
+            self.name.into(),
+            contents.data(),
+            *contents.span(),
+            contents.line(),
+            contents.column(),
+            contents.line_count(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_to_json_test() {
+        let input = [&b"SIS\0"[..], &[0; 60]].concat();
+        let err = crate::format::pman::PmanFile::new(&input).unwrap_err();
+        let json = err.to_json();
+
+        assert_eq!(json["kind"], "BadMagic");
+        assert_eq!(json["code"], "rashen::invalid_format");
+        assert_eq!(
+            (json["offset"].as_u64(), json["len"].as_u64()),
+            (Some(0), Some(4))
+        );
+        assert_eq!(json["labels"][0]["label"], "expected `PMAN`");
+        assert!(json["help"].as_str().unwrap().contains("PMAN archive"));
+
+        let json = Error::CopyrightTooLong { len: 60, max: 55 }.to_json();
+
+        assert_eq!(json["kind"], "CopyrightTooLong");
+        assert!(json["offset"].is_null());
+    }
+
+    #[test]
+    fn hex_dump_test() {
+        let input = (0..=u8::MAX).collect::<Vec<_>>();
+        let dump = HexDump::new("input", &input, 0x41);
+        let lines = dump.text.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 9);
+        assert_eq!(
+            lines[4],
+            "00000040  40 41 42 43 44 45 46 47  48 49 4A 4B 4C 4D 4E 4F  |@ABCDEFGHIJKLMNO|"
+        );
+        assert!(dump.text.lines().all(|l| l.len() + 1 == HEX_DUMP_LINE_SIZE));
+
+        let span = dump.span(0x41, 1);
+        assert_eq!(&dump.text[span.offset()..][..span.len()], "41");
+
+        let span = dump.span(0x47, 2);
+        assert_eq!(&dump.text[span.offset()..][..span.len()], "47  48");
+
+        let span = dump.span(0x8E, 0x100);
+        assert_eq!(&dump.text[span.offset()..][..span.len()], "8E 8F");
+    }
+
+    #[test]
+    fn hex_dump_partial_line_test() {
+        let dump = HexDump::new("input", b"PMAN", 4);
+
+        assert_eq!(
+            dump.text,
+            "00000000  50 4D 41 4E                                       |PMAN|\n"
+        );
+    }
+}
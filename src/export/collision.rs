@@ -0,0 +1,117 @@
+//! JSON export for collision meshes, that keeps what every face is; e.g. so a level editor can tell
+//! the floors of a level apart from its walls, or its water from its clip brushes.
+//!
+//! ```json
+//! {
+//!   "materials": [{
+//!     "name": "textures/wad2tga_level1/1_grl02h",
+//!     "flags": 1,
+//!     "contents": "solid"
+//!   }],
+//!   "models": [{
+//!     "brushes": [{
+//!       "material": 0,
+//!       "contents": "solid",
+//!       "faces": [{
+//!         "surface": "floor",
+//!         "normal": [0.0, 0.0, 1.0],
+//!         "vertices": [[0.0, 0.0, 0.0], ...]
+//!       }]
+//!     }]
+//!   }]
+//! }
+//! ```
+
+use crate::format::collision::{CollisionMesh, Contents, Material, Surface};
+use serde_json::{json, Value};
+
+/// Converts `mesh` into JSON; brushes are grouped by model, and the first model is the level
+/// itself.
+#[must_use]
+pub fn collision_to_json(mesh: &CollisionMesh) -> Value {
+    let materials: Vec<_> = mesh
+        .materials
+        .iter()
+        .map(|material| {
+            json!({
+                "name": material.name,
+                "flags": material.flags,
+                "contents": material.contents().name(),
+            })
+        })
+        .collect();
+    let models: Vec<_> = mesh
+        .models
+        .iter()
+        .map(|model| {
+            let first = model.first_brush as usize;
+            let brushes: Vec<_> = (first..first.saturating_add(model.brush_count as usize))
+                .filter_map(|brush| Some((brush, mesh.brushes.get(brush)?)))
+                .map(|(index, brush)| {
+                    let contents = mesh
+                        .material_of_brush(index)
+                        .map_or(Contents::Unknown, Material::contents);
+                    let faces: Vec<_> = mesh
+                        .brush_faces_by_plane(index)
+                        .into_iter()
+                        .map(|(plane, vertices)| {
+                            let normal = plane.normal_f32();
+
+                            json!({
+                                "surface": Surface::of(normal).name(),
+                                "normal": normal,
+                                "vertices": vertices,
+                            })
+                        })
+                        .collect();
+
+                    json!({
+                        "material": brush.material,
+                        "contents": contents.name(),
+                        "faces": faces,
+                    })
+                })
+                .collect();
+
+            json!({ "brushes": brushes })
+        })
+        .collect();
+
+    json!({ "materials": materials, "models": models })
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+    use crate::format::pman::PmanFile;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    fn collision_json_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = pman[77].to_zlib()?.expect("`COLL` entries are compressed.");
+        let mesh = CollisionMesh::new(&bytes)?;
+        let json = collision_to_json(&mesh);
+
+        assert_eq!(json["materials"][0]["contents"], "solid");
+        assert_eq!(json["models"].as_array().map(Vec::len), Some(25));
+
+        let brushes = json["models"][0]["brushes"].as_array().expect("a list");
+        let faces = |surface: &str| {
+            brushes
+                .iter()
+                .flat_map(|brush| brush["faces"].as_array().into_iter().flatten())
+                .filter(|face| face["surface"] == surface)
+                .count()
+        };
+        assert!(faces("floor") > 0);
+        assert!(faces("wall") > faces("floor"));
+        assert!(faces("ceiling") > 0);
+
+        let water = brushes.iter().filter(|brush| brush["contents"] == "water");
+        assert!(water.count() > 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,416 @@
+//! Binary glTF 2.0 (`.glb`) export for models and collision meshes.
+
+use crate::format::{
+    collision::CollisionMesh,
+    model::{Frame, Model},
+};
+use serde_json::{json, Value};
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_VERSION: u32 = 2;
+const JSON_CHUNK: &[u8; 4] = b"JSON";
+const BIN_CHUNK: &[u8; 4] = b"BIN\0";
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+/// Rotates the Z up space of the game into the Y up space of glTF.
+const Z_UP_TO_Y_UP: [f32; 4] = [
+    -std::f32::consts::FRAC_1_SQRT_2,
+    0.0,
+    0.0,
+    std::f32::consts::FRAC_1_SQRT_2,
+];
+/// The keyframes played every second.
+// TODO(Unavailable): find out the rate that the game uses.
+const FRAME_RATE: f32 = 10.0;
+
+/// The buffer views and the accessors of a `.glb`, and the binary chunk that they point into.
+#[derive(Default)]
+struct Buffers {
+    bin: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Buffers {
+    /// Adds `data` as a new buffer view, and returns its index.
+    fn push_view(&mut self, data: impl IntoIterator<Item = [u8; 4]>, target: Option<u32>) -> usize {
+        let offset = self.bin.len();
+        self.bin.extend(data.into_iter().flatten());
+
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": self.bin.len() - offset,
+        });
+        if let Some(target) = target {
+            view["target"] = target.into();
+        }
+
+        self.views.push(view);
+        self.views.len() - 1
+    }
+
+    /// Adds a `VEC3` float accessor, and returns its index.
+    fn push_vec3(&mut self, data: &[[f32; 3]]) -> usize {
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for v in data {
+            for i in 0..3 {
+                min[i] = min[i].min(v[i]);
+                max[i] = max[i].max(v[i]);
+            }
+        }
+        if data.is_empty() {
+            (min, max) = ([0.0; 3], [0.0; 3]);
+        }
+
+        let bytes = data.iter().flatten().map(|x| x.to_le_bytes());
+        let view = self.push_view(bytes, Some(ARRAY_BUFFER));
+
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": data.len(),
+            "type": "VEC3",
+            "min": min,
+            "max": max,
+        }))
+    }
+
+    /// Adds a `VEC2` float accessor, and returns its index.
+    fn push_vec2(&mut self, data: &[[f32; 2]]) -> usize {
+        let bytes = data.iter().flatten().map(|x| x.to_le_bytes());
+        let view = self.push_view(bytes, Some(ARRAY_BUFFER));
+
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": data.len(),
+            "type": "VEC2",
+        }))
+    }
+
+    /// Adds a `SCALAR` float accessor, and returns its index; `bounds` adds its `min` and `max`.
+    fn push_scalars(&mut self, data: &[f32], bounds: bool) -> usize {
+        let view = self.push_view(data.iter().map(|x| x.to_le_bytes()), None);
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": data.len(),
+            "type": "SCALAR",
+        });
+        if bounds {
+            let min = data.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = data.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+            accessor["min"] = json!([min]);
+            accessor["max"] = json!([max]);
+        }
+
+        self.push_accessor(accessor)
+    }
+
+    /// Adds an `u32` index accessor, and returns its index.
+    fn push_indices(&mut self, data: &[u32]) -> usize {
+        let bytes = data.iter().map(|x| x.to_le_bytes());
+        let view = self.push_view(bytes, Some(ELEMENT_ARRAY_BUFFER));
+
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": data.len(),
+            "type": "SCALAR",
+        }))
+    }
+
+    fn push_accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Bundles `gltf` (without its buffers) and the buffers into a `.glb` file.
+    fn into_glb(self, mut gltf: Value) -> Vec<u8> {
+        gltf["asset"] = json!({ "version": "2.0", "generator": "rashen" });
+        gltf["buffers"] = json!([{ "byteLength": self.bin.len() }]);
+        gltf["bufferViews"] = self.views.into();
+        gltf["accessors"] = self.accessors.into();
+
+        let mut json = gltf.to_string().into_bytes();
+        let mut bin = self.bin;
+        // chunks are 4 bytes aligned; the JSON one with spaces, and the binary one with zeros.
+        json.resize(json.len().next_multiple_of(4), b' ');
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let chunk_len = |chunk: &[u8]| u32::try_from(chunk.len()).unwrap_or(u32::MAX);
+        let total = 12 + 8 + json.len() + 8 + bin.len();
+        let mut glb = Vec::with_capacity(total);
+
+        glb.extend_from_slice(GLB_MAGIC);
+        glb.extend_from_slice(&GLB_VERSION.to_le_bytes());
+        glb.extend_from_slice(&u32::try_from(total).unwrap_or(u32::MAX).to_le_bytes());
+        glb.extend_from_slice(&chunk_len(&json).to_le_bytes());
+        glb.extend_from_slice(JSON_CHUNK);
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&chunk_len(&bin).to_le_bytes());
+        glb.extend_from_slice(BIN_CHUNK);
+        glb.extend_from_slice(&bin);
+
+        glb
+    }
+}
+
+/// Converts `model` into a `.glb` file.
+///
+/// The mesh has the first frame as its base pose, and every frame as a morph target (see
+/// [`Model::morph_targets`]); animations switch between the targets with `STEP` interpolation.
+/// The texture is not included, since there is no way to decode it yet; see [`Model::texture`].
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn model_to_glb(model: &Model, name: &str) -> Vec<u8> {
+    let mut buffers = Buffers::default();
+    let (width, height) = (
+        model.texture_width.max(1) as f32,
+        model.texture_height.max(1) as f32,
+    );
+
+    // texture coordinates belong to the corners of the faces, so vertices can't be shared.
+    let corners = |positions: &[[f32; 3]]| -> Vec<[f32; 3]> {
+        model
+            .faces
+            .iter()
+            .flat_map(|face| face.vertices)
+            .map(|v| positions.get(usize::from(v)).copied().unwrap_or_default())
+            .collect()
+    };
+    let base = corners(
+        &model
+            .frames
+            .first()
+            .map(Frame::positions)
+            .unwrap_or_default(),
+    );
+    let uvs: Vec<_> = model
+        .faces
+        .iter()
+        .flat_map(|face| face.uvs)
+        .map(|[u, v]| [f32::from(u) / width, f32::from(v) / height])
+        .collect();
+
+    let position = buffers.push_vec3(&base);
+    let texcoord = buffers.push_vec2(&uvs);
+    let targets: Vec<_> = model
+        .morph_targets()
+        .iter()
+        .map(|delta| json!({ "POSITION": buffers.push_vec3(&corners(delta)) }))
+        .collect();
+
+    let mut weights = vec![0.0; targets.len()];
+    if let Some(first) = weights.first_mut() {
+        *first = 1.0;
+    }
+
+    let animations: Vec<_> = model
+        .animations
+        .iter()
+        .enumerate()
+        .filter(|(_, animation)| !animation.frames.is_empty())
+        .map(|(index, animation)| {
+            let times: Vec<_> = (0..animation.frames.len())
+                .map(|i| i as f32 / FRAME_RATE)
+                .collect();
+            let output: Vec<_> = animation.morph_weights(targets.len()).flatten().collect();
+            let input = buffers.push_scalars(&times, true);
+            let output = buffers.push_scalars(&output, false);
+
+            json!({
+                "name": format!("animation_{index}"),
+                "samplers": [{ "input": input, "output": output, "interpolation": "STEP" }],
+                "channels": [{ "sampler": 0, "target": { "node": 0, "path": "weights" } }],
+            })
+        })
+        .collect();
+
+    let mut primitive = json!({
+        "attributes": { "POSITION": position, "TEXCOORD_0": texcoord },
+        "material": 0,
+    });
+    if !targets.is_empty() {
+        primitive["targets"] = targets.into();
+    }
+
+    let mut gltf = json!({
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": name, "mesh": 0, "rotation": Z_UP_TO_Y_UP }],
+        "meshes": [{ "name": name, "primitives": [primitive], "weights": weights }],
+        "materials": [{ "name": "texture", "doubleSided": true }],
+    });
+    if !animations.is_empty() {
+        gltf["animations"] = animations.into();
+    }
+
+    buffers.into_glb(gltf)
+}
+
+/// Converts the brushes of `mesh` into a `.glb` file; there is one node for every model of the
+/// mesh, and one primitive for every material of a model.
+#[must_use]
+pub fn collision_to_glb(mesh: &CollisionMesh, name: &str) -> Vec<u8> {
+    let mut buffers = Buffers::default();
+    let mut nodes = Vec::new();
+    let mut meshes = Vec::new();
+
+    for (index, model) in mesh.models.iter().enumerate() {
+        // the triangles of every material, as a fan of the polygons of the faces.
+        let mut positions = Vec::new();
+        let mut by_material: Vec<(u32, Vec<u32>)> = Vec::new();
+
+        let first = model.first_brush as usize;
+        for brush in first..first.saturating_add(model.brush_count as usize) {
+            let Some(material) = mesh.brushes.get(brush).map(|b| b.material) else {
+                continue;
+            };
+            let slot = by_material
+                .iter()
+                .position(|(m, _)| *m == material)
+                .unwrap_or_else(|| {
+                    by_material.push((material, Vec::new()));
+                    by_material.len() - 1
+                });
+            let indices = &mut by_material[slot].1;
+
+            for polygon in mesh.brush_faces(brush) {
+                let start = u32::try_from(positions.len()).unwrap_or(u32::MAX);
+
+                for corner in 1..polygon.len() - 1 {
+                    let corner = u32::try_from(corner).unwrap_or(u32::MAX);
+
+                    indices.extend_from_slice(&[start, start + corner, start + corner + 1]);
+                }
+                positions.extend(polygon);
+            }
+        }
+
+        if positions.is_empty() {
+            continue;
+        }
+
+        let position = buffers.push_vec3(&positions);
+        let primitives: Vec<_> = by_material
+            .iter()
+            .filter(|(_, indices)| !indices.is_empty())
+            .map(|(material, indices)| {
+                json!({
+                    "attributes": { "POSITION": position },
+                    "indices": buffers.push_indices(indices),
+                    "material": material,
+                })
+            })
+            .collect();
+
+        nodes.push(json!({ "name": format!("model_{index}"), "mesh": meshes.len() }));
+        meshes.push(json!({ "name": format!("model_{index}"), "primitives": primitives }));
+    }
+
+    let materials: Vec<_> = mesh
+        .materials
+        .iter()
+        .map(|material| {
+            json!({
+                "name": material.name,
+                "extras": { "flags": material.flags, "contents": material.contents().name() },
+            })
+        })
+        .collect();
+    let children: Vec<_> = (1..=nodes.len()).collect();
+    nodes.insert(
+        0,
+        json!({ "name": name, "children": children, "rotation": Z_UP_TO_Y_UP }),
+    );
+
+    let mut gltf = json!({
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": nodes,
+    });
+    if !meshes.is_empty() {
+        gltf["meshes"] = meshes.into();
+    }
+    if !materials.is_empty() {
+        gltf["materials"] = materials.into();
+    }
+
+    buffers.into_glb(gltf)
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+    use crate::format::pman::PmanFile;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    /// Splits a `.glb` file into its JSON and its binary chunk.
+    fn parse_glb(glb: &[u8]) -> eyre::Result<(Value, &[u8])> {
+        let u32_at = |offset: usize| -> eyre::Result<usize> {
+            Ok(u32::from_le_bytes(glb[offset..offset + 4].try_into()?) as usize)
+        };
+
+        assert_eq!(&glb[..4], GLB_MAGIC);
+        assert_eq!(u32_at(8)?, glb.len());
+
+        let json_len = u32_at(12)?;
+        let json = serde_json::from_slice(&glb[20..20 + json_len])?;
+        let bin_len = u32_at(20 + json_len)?;
+        let bin = &glb[28 + json_len..];
+
+        assert_eq!(bin.len(), bin_len);
+
+        Ok((json, bin))
+    }
+
+    #[test]
+    fn model_to_glb_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = pman[10].to_zlib()?.expect("models are compressed.");
+        let model = Model::new(&bytes)?;
+        let glb = model_to_glb(&model, "model");
+        let (gltf, bin) = parse_glb(&glb)?;
+
+        let primitive = &gltf["meshes"][0]["primitives"][0];
+        let position = &gltf["accessors"][usize::try_from(
+            primitive["attributes"]["POSITION"]
+                .as_u64()
+                .unwrap_or_default(),
+        )?];
+
+        assert_eq!(position["count"], 230 * 3);
+        assert_eq!(primitive["targets"].as_array().map(Vec::len), Some(266));
+        assert_eq!(gltf["animations"].as_array().map(Vec::len), Some(19));
+        assert_eq!(
+            gltf["buffers"][0]["byteLength"].as_u64(),
+            Some(bin.len() as u64)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn collision_to_glb_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = pman[77].to_zlib()?.expect("`COLL` entries are compressed.");
+        let mesh = CollisionMesh::new(&bytes)?;
+        let glb = collision_to_glb(&mesh, "level");
+        let (gltf, _) = parse_glb(&glb)?;
+
+        assert_eq!(gltf["nodes"][0]["name"], "level");
+        assert_eq!(gltf["materials"].as_array().map(Vec::len), Some(97));
+        assert!(gltf["meshes"]
+            .as_array()
+            .is_some_and(|meshes| !meshes.is_empty()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+//! PNG export, behind the `export-image` feature.
+
+use crate::{
+    error::{Error, Result},
+    format::{
+        palette::Palette,
+        skybox::{CubeFace, Skybox},
+        texture::Texture,
+    },
+};
+use std::io;
+
+/// Encodes 8-bit RGBA `pixels`, row by row, as a PNG file.
+///
+/// # Errors
+///
+/// If there are not `width * height` pixels.
+pub fn encode_png(width: u32, height: u32, pixels: &[[u8; 4]]) -> Result<Vec<u8>> {
+    if pixels.len() != (width as usize).saturating_mul(height as usize) {
+        return Err(Error::InvalidImageSize {
+            width,
+            height,
+            len: pixels.len(),
+        });
+    }
+
+    let mut buf = Vec::new();
+    let mut encoder = png::Encoder::new(&mut buf, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels.as_flattened()))
+        .map_err(io::Error::other)?;
+
+    Ok(buf)
+}
+
+/// Encodes `texture` as a PNG file, with the colors of `palette`.
+///
+/// # Errors
+///
+/// If the texture doesn't have `width * height` pixels.
+pub fn texture_to_png(texture: &Texture, palette: &Palette) -> Result<Vec<u8>> {
+    encode_png(texture.width, texture.height, &texture.to_rgba(palette))
+}
+
+/// Encodes `skybox` as a PNG file, with the colors of its own palette.
+///
+/// # Errors
+///
+/// If the skybox doesn't have `width * height` pixels.
+pub fn skybox_to_png(skybox: &Skybox) -> Result<Vec<u8>> {
+    encode_png(skybox.width, skybox.height, &skybox.to_rgba())
+}
+
+/// Encodes `texture` as a grayscale PNG file, where each gray level is the palette index of the
+/// pixel.
+///
+/// # Errors
+///
+/// If the texture doesn't have `width * height` pixels.
+pub fn texture_indices_to_png(texture: &Texture) -> Result<Vec<u8>> {
+    let pixels: Vec<_> = texture.pixels.iter().map(|&i| [i, i, i, 0xFF]).collect();
+
+    encode_png(texture.width, texture.height, &pixels)
+}
+
+/// Converts a texture entry into a PNG file of its palette indices; the palettes of the
+/// textures are not known yet, so none is applied.
+pub(super) fn texture(bytes: &[u8]) -> Result<Vec<u8>> {
+    texture_indices_to_png(&Texture::new(bytes)?)
+}
+
+/// Encodes `skybox` as a PNG file of an equirectangular panorama; see
+/// [`Skybox::to_equirectangular`].
+///
+/// # Errors
+///
+/// If the skybox doesn't have `width * height` pixels.
+pub fn skybox_to_equirectangular_png(skybox: &Skybox) -> Result<Vec<u8>> {
+    skybox_to_png(&skybox.to_equirectangular())
+}
+
+/// Encodes every face of a cube map of `skybox` as a `size` by `size` PNG file; see
+/// [`Skybox::to_cube_face`].
+///
+/// # Errors
+///
+/// If the skybox doesn't have `width * height` pixels.
+pub fn skybox_to_cube_pngs(skybox: &Skybox, size: u32) -> Result<Vec<(CubeFace, Vec<u8>)>> {
+    CubeFace::ALL
+        .into_iter()
+        .map(|face| Ok((face, skybox_to_png(&skybox.to_cube_face(face, size))?)))
+        .collect()
+}
+
+/// Converts a skybox entry into the panorama as it is, the equirectangular one (suffixed with
+/// `_equirect`), and the faces of a cube map (suffixed with their [`CubeFace::label`]).
+pub(super) fn skybox(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let skybox = Skybox::new(bytes)?;
+    let mut images = vec![
+        (String::new(), skybox_to_png(&skybox)?),
+        ("_equirect".into(), skybox_to_equirectangular_png(&skybox)?),
+    ];
+
+    for (face, png) in skybox_to_cube_pngs(&skybox, skybox.height)? {
+        images.push((format!("_{}", face.label()), png));
+    }
+
+    Ok(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, export};
+
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    fn decode(bytes: &[u8]) -> eyre::Result<(png::OutputInfo, Vec<u8>)> {
+        let mut reader = png::Decoder::new(bytes).read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        Ok((info, buf))
+    }
+
+    #[test]
+    fn encode_png_test() -> eyre::Result<()> {
+        let pixels = [[0xFF, 0, 0, 0xFF], [0, 0xFF, 0, 0x80]];
+        let (info, buf) = decode(&encode_png(2, 1, &pixels)?)?;
+
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(buf, pixels.as_flattened());
+
+        assert_eq!(
+            encode_png(2, 2, &pixels).unwrap_err().kind(),
+            ErrorKind::InvalidImageSize
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn texture_indices_to_png_test() -> eyre::Result<()> {
+        let texture = Texture {
+            width: 2,
+            height: 1,
+            pixels: vec![0x00, 0x7F],
+        };
+        let (_, buf) = decode(&texture_indices_to_png(&texture)?)?;
+
+        assert_eq!(buf, [0, 0, 0, 0xFF, 0x7F, 0x7F, 0x7F, 0xFF]);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn convert_image_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in [60, 72] {
+            let converted = export::convert(&pman, index, "image", export::Options::default())?;
+            let (info, _) = decode(&converted[0].bytes)?;
+
+            assert_eq!(converted[0].file_name, "image.png");
+            assert_eq!((info.width, info.height), (256, 256), "entry {index}");
+        }
+
+        let converted = export::convert(&pman, 60, "sky", export::Options::default())?;
+        let names: Vec<_> = converted.iter().map(|c| c.file_name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "sky.png",
+                "sky_equirect.png",
+                "sky_px.png",
+                "sky_nx.png",
+                "sky_py.png",
+                "sky_ny.png",
+                "sky_pz.png",
+                "sky_nz.png"
+            ]
+        );
+        assert_eq!(decode(&converted[1].bytes)?.0.width, 1024);
+        assert_eq!(decode(&converted[2].bytes)?.0.height, 256);
+
+        assert_eq!(
+            export::convert(&pman, 0, "tables", export::Options::default())?,
+            []
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+//! Converters from the formats of the game into the formats that other tools understand.
+
+pub mod collision;
+pub mod gltf;
+#[cfg(feature = "export-image")]
+pub mod image;
+#[cfg(feature = "fuse")]
+pub mod mount;
+pub mod obj;
+pub mod palette;
+pub mod peek;
+pub mod po;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod text;
+pub mod tree;
+pub mod wav;
+pub mod waypoint;
+
+use crate::{
+    error::Result,
+    format::{
+        audio::{AudioClip, SoundArchive},
+        collision::CollisionMesh,
+        detect::FileType,
+        model::Model,
+        palette::Palette,
+        pman::{self, PmanFile},
+        text::{TextBank, ENGLISH_TEXT_BANK, TEXT_BANKS},
+        waypoint::WaypointGraph,
+    },
+};
+use palette::PaletteFormat;
+use text::TextFormat;
+
+/// The format that meshes (models and collision meshes) are converted into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MeshFormat {
+    /// An OBJ, and its MTL; see [`obj`].
+    #[default]
+    Obj,
+    /// A binary glTF, with the animations of models; see [`gltf`].
+    Glb,
+}
+
+/// How [`convert`] converts every type of entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Options {
+    pub mesh: MeshFormat,
+    pub text: TextFormat,
+}
+
+/// A file that an entry was converted into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Converted {
+    /// The name of the file, with the extension of its format; e.g. `name.png`.
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Converts the entry at `index` of `pman` into the format that suits its type; see
+/// [`PmanFileData::detect_type`](crate::format::pman::PmanFileData::detect_type).
+///
+/// Files are named `{name}.{extension}`; some formats are made of more than one file (e.g. an OBJ
+/// and its MTL), that refer to each other by those names. Collision meshes also get a JSON file,
+/// with the contents of every brush and the surface of every face; skyboxes also get an
+/// equirectangular panorama (`{name}_equirect.png`), and the faces of a cube map (e.g.
+/// `{name}_px.png`). Archives are converted into a file for each of their entries, named
+/// `{name}_{index}.{extension}`. It is empty if `index` is out of bounds, or if there is no
+/// converter for its type; some converters are behind a feature.
+///
+/// # Errors
+///
+/// If the entry (or another entry that it needs; e.g. its palette) can't be parsed, or if it can't
+/// be encoded.
+pub fn convert(
+    pman: &PmanFile,
+    index: usize,
+    name: &str,
+    options: Options,
+) -> Result<Vec<Converted>> {
+    let Some(file) = pman.files().get(index) else {
+        return Ok(Vec::new());
+    };
+    let zlib = file.to_zlib()?;
+    let bytes = zlib.as_deref().unwrap_or(file.bytes());
+
+    let converted = match file.detect_type() {
+        #[cfg(feature = "export-image")]
+        Some(FileType::Texture) => vec![("png", image::texture(bytes)?)],
+        #[cfg(feature = "export-image")]
+        Some(FileType::Skybox) => {
+            return Ok(image::skybox(bytes)?
+                .into_iter()
+                .map(|(suffix, bytes)| Converted {
+                    file_name: format!("{name}{suffix}.png"),
+                    bytes,
+                })
+                .collect());
+        }
+        Some(FileType::Palette) => {
+            let format = PaletteFormat::Gimp;
+            let palette = Palette::new(bytes)?;
+
+            vec![(format.extension(), format.encode(&palette, name))]
+        }
+        Some(FileType::Model) => {
+            let model = Model::new(bytes)?;
+
+            match options.mesh {
+                MeshFormat::Obj => obj::model_to_obj(&model, 0, name)
+                    .map(|obj| vec![("obj", obj.obj.into_bytes()), ("mtl", obj.mtl.into_bytes())])
+                    .unwrap_or_default(),
+                MeshFormat::Glb => vec![("glb", gltf::model_to_glb(&model, name))],
+            }
+        }
+        Some(FileType::Collision) => {
+            let mesh = CollisionMesh::new(bytes)?;
+            let json = collision::collision_to_json(&mesh);
+            let json = serde_json::to_vec_pretty(&json).map_err(std::io::Error::from)?;
+
+            match options.mesh {
+                MeshFormat::Obj => {
+                    let obj = obj::collision_to_obj(&mesh, name);
+
+                    vec![
+                        ("obj", obj.obj.into_bytes()),
+                        ("mtl", obj.mtl.into_bytes()),
+                        ("json", json),
+                    ]
+                }
+                MeshFormat::Glb => {
+                    vec![("glb", gltf::collision_to_glb(&mesh, name)), ("json", json)]
+                }
+            }
+        }
+        Some(FileType::Waypoint) => {
+            let graph = WaypointGraph::new(bytes)?;
+            let json = waypoint::graph_to_json(&graph);
+
+            vec![(
+                "json",
+                serde_json::to_vec_pretty(&json).map_err(std::io::Error::from)?,
+            )]
+        }
+        Some(FileType::Text) => {
+            let bank = TextBank::new(bytes)?;
+            let bytes = if options.text == TextFormat::Po {
+                let english = pman.files().get(ENGLISH_TEXT_BANK).map(|file| {
+                    let zlib = file.to_zlib()?;
+
+                    TextBank::new(zlib.as_deref().unwrap_or(file.bytes()))
+                });
+                let language = TEXT_BANKS
+                    .iter()
+                    .find(|(i, _)| *i == index)
+                    .map(|(_, language)| *language);
+
+                po::bank_to_po(&bank, english.transpose()?.as_ref(), language).into_bytes()
+            } else {
+                options.text.encode(&bank)
+            };
+
+            vec![(options.text.extension(), bytes)]
+        }
+        Some(FileType::Sound) => {
+            let tsnd = SoundArchive::new(bytes)?;
+            let mut clips = Vec::with_capacity(tsnd.banks[1].len());
+
+            for (index, entry) in tsnd.banks[1].iter().enumerate() {
+                let zlib = pman::decompress(entry)?;
+                let clip = AudioClip::new(zlib.as_deref().unwrap_or(entry))?;
+
+                clips.push(Converted {
+                    file_name: format!("{name}_{index:03}.wav"),
+                    bytes: wav::clip_to_wav(&clip),
+                });
+            }
+
+            return Ok(clips);
+        }
+        Some(_) | None => Vec::new(),
+    };
+
+    Ok(converted
+        .into_iter()
+        .map(|(extension, bytes)| Converted {
+            file_name: format!("{name}.{extension}"),
+            bytes,
+        })
+        .collect())
+}
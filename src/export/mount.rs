@@ -0,0 +1,585 @@
+//! A read-only view of an archive as a filesystem, that is mounted with FUSE; see [`mount`].
+//!
+//! Every entry is a file of the root directory, named after its offset (like on an extraction), but
+//! with the extension of its type; e.g. `0001A2B4.texture`. `ZL` entries are decompressed when they
+//! are first read.
+//!
+//! The FUSE protocol is spoken over `/dev/fuse` directly, so only Linux is supported.
+
+use crate::{
+    error::{Error, Result},
+    format::pman::{Compression, PmanFile},
+};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    ffi::CString,
+    fs::{self, File},
+    io::{self, Read, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+    process::Command,
+    time::SystemTime,
+};
+
+const ROOT: u64 = 1;
+/// How long the kernel can cache names and attributes, in seconds; the archive never changes.
+const TTL: u64 = 3600;
+/// The biggest request that the kernel sends; reads are split into chunks of up to this size.
+const MAX_WRITE: u32 = 0x20000;
+/// [`MAX_WRITE`], in pages.
+const MAX_PAGES: u16 = 32;
+const BLOCK_SIZE: u32 = 512;
+
+const IN_HEADER_SIZE: usize = 40;
+const OUT_HEADER_SIZE: usize = 16;
+
+const FUSE_LOOKUP: u32 = 1;
+const FUSE_FORGET: u32 = 2;
+const FUSE_GETATTR: u32 = 3;
+const FUSE_OPEN: u32 = 14;
+const FUSE_READ: u32 = 15;
+const FUSE_STATFS: u32 = 17;
+const FUSE_RELEASE: u32 = 18;
+const FUSE_FLUSH: u32 = 25;
+const FUSE_INIT: u32 = 26;
+const FUSE_OPENDIR: u32 = 27;
+const FUSE_READDIR: u32 = 28;
+const FUSE_RELEASEDIR: u32 = 29;
+const FUSE_ACCESS: u32 = 34;
+const FUSE_INTERRUPT: u32 = 36;
+const FUSE_DESTROY: u32 = 38;
+const FUSE_BATCH_FORGET: u32 = 42;
+
+/// The version of the protocol that is spoken; `7.31`.
+const KERNEL_VERSION: (u32, u32) = (7, 31);
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    bytes
+        .get(offset..offset + 4)
+        .map_or(0, |b| u32::from_ne_bytes(b.try_into().unwrap()))
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    bytes
+        .get(offset..offset + 8)
+        .map_or(0, |b| u64::from_ne_bytes(b.try_into().unwrap()))
+}
+
+/// A reply of the filesystem; its body, or an `errno`.
+type Reply = std::result::Result<Vec<u8>, i32>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+    name: String,
+    /// The size of the (decompressed) data of the entry.
+    size: u64,
+}
+
+/// The files of a mounted archive, and the answers to the requests of the kernel.
+#[derive(Debug)]
+pub struct VirtualFs {
+    pman: PmanFile,
+    nodes: Vec<Node>,
+    /// The decompressed data of the entries that were read.
+    cache: HashMap<usize, Vec<u8>>,
+    uid: u32,
+    gid: u32,
+    /// When the archive was mounted, in seconds since the epoch.
+    time: u64,
+}
+
+impl VirtualFs {
+    #[must_use]
+    pub fn new(pman: PmanFile) -> Self {
+        let mut offset = pman.size_upto_file_data();
+        let nodes = pman
+            .files()
+            .iter()
+            .map(|file| {
+                let extension = file.detect_type().map_or("dat", |t| t.extension());
+                let size = match file.compression() {
+                    Compression::Zlib { declared_len } => declared_len,
+                    _ => file.bytes().len(),
+                };
+                let node = Node {
+                    name: format!("{offset:08X}.{extension}"),
+                    size: size as u64,
+                };
+                offset += file.bytes().len();
+
+                node
+            })
+            .collect();
+        let time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+
+        Self {
+            pman,
+            nodes,
+            cache: HashMap::new(),
+            uid: 0,
+            gid: 0,
+            time,
+        }
+    }
+
+    /// The names of the files of every entry, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(|node| node.name.as_str())
+    }
+
+    /// Reads up to `size` bytes, at `offset`, of the file of the entry at `index`.
+    ///
+    /// # Errors
+    ///
+    /// If the entry can't be decompressed.
+    ///
+    /// # Panics
+    ///
+    /// If there is no entry at `index`.
+    pub fn read(&mut self, index: usize, offset: u64, size: usize) -> Result<&[u8]> {
+        let file = &self.pman.files()[index];
+        let data = if file.compression() == Compression::None {
+            file.bytes()
+        } else {
+            match self.cache.entry(index) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    entry.insert(file.to_zlib()?.unwrap_or_else(|| file.bytes().to_vec()))
+                }
+            }
+        };
+
+        let start = usize::try_from(offset).map_or(data.len(), |o| o.min(data.len()));
+        let end = start.saturating_add(size).min(data.len());
+
+        Ok(&data[start..end])
+    }
+
+    /// The entry of the node `ino`; `None` for the root directory.
+    fn entry(&self, ino: u64) -> std::result::Result<Option<usize>, i32> {
+        match ino {
+            ROOT => Ok(None),
+            _ => usize::try_from(ino - 2)
+                .ok()
+                .filter(|&index| index < self.nodes.len())
+                .map(Some)
+                .ok_or(libc::ENOENT),
+        }
+    }
+
+    /// A `fuse_attr`.
+    fn attr(&self, ino: u64) -> std::result::Result<Vec<u8>, i32> {
+        let (size, mode, nlink) = match self.entry(ino)? {
+            None => (0, libc::S_IFDIR | 0o555, 2),
+            Some(index) => (self.nodes[index].size, libc::S_IFREG | 0o444, 1),
+        };
+
+        let mut attr = Vec::with_capacity(88);
+        for value in [ino, size, size.div_ceil(u64::from(BLOCK_SIZE))] {
+            attr.extend(value.to_ne_bytes());
+        }
+        for _ in 0..3 {
+            attr.extend(self.time.to_ne_bytes());
+        }
+        for value in [0, 0, 0, mode, nlink, self.uid, self.gid, 0, BLOCK_SIZE, 0] {
+            attr.extend(value.to_ne_bytes());
+        }
+
+        Ok(attr)
+    }
+
+    /// A `fuse_entry_out`.
+    fn entry_out(&self, ino: u64) -> Reply {
+        let mut out = Vec::with_capacity(128);
+        for value in [ino, 0, TTL, TTL] {
+            out.extend(value.to_ne_bytes());
+        }
+        out.extend([0; 8]);
+        out.extend(self.attr(ino)?);
+
+        Ok(out)
+    }
+
+    fn init(body: &[u8]) -> Reply {
+        let (major, minor) = (u32_at(body, 0), u32_at(body, 4));
+        if major < KERNEL_VERSION.0 {
+            return Err(libc::EPROTO);
+        }
+        // the kernel speaks any version that is not newer than its own.
+        let minor = if major == KERNEL_VERSION.0 {
+            minor.min(KERNEL_VERSION.1)
+        } else {
+            KERNEL_VERSION.1
+        };
+
+        let mut out = Vec::with_capacity(64);
+        for value in [KERNEL_VERSION.0, minor, u32_at(body, 8), 0] {
+            out.extend(value.to_ne_bytes());
+        }
+        // `max_background`, `congestion_threshold`, `max_write`, `time_gran` and `max_pages`.
+        out.extend(16u16.to_ne_bytes());
+        out.extend(12u16.to_ne_bytes());
+        out.extend(MAX_WRITE.to_ne_bytes());
+        out.extend(1u32.to_ne_bytes());
+        out.extend(MAX_PAGES.to_ne_bytes());
+        out.resize(64, 0);
+
+        Ok(out)
+    }
+
+    fn lookup(&self, parent: u64, body: &[u8]) -> Reply {
+        if parent != ROOT {
+            return Err(libc::ENOENT);
+        }
+        let name = body.split(|&b| b == 0).next().unwrap_or_default();
+        let index = self
+            .nodes
+            .iter()
+            .position(|node| node.name.as_bytes() == name)
+            .ok_or(libc::ENOENT)?;
+
+        self.entry_out(index as u64 + 2)
+    }
+
+    fn open(&self, ino: u64, body: &[u8]) -> Reply {
+        if self.entry(ino)?.is_none() {
+            return Err(libc::EISDIR);
+        }
+        if u32_at(body, 0) & libc::O_ACCMODE as u32 != libc::O_RDONLY as u32 {
+            return Err(libc::EROFS);
+        }
+
+        // `fh`, and `FOPEN_KEEP_CACHE`; the data never changes.
+        let mut out = vec![0; 16];
+        out[8..12].copy_from_slice(&2u32.to_ne_bytes());
+
+        Ok(out)
+    }
+
+    fn read_request(&mut self, ino: u64, body: &[u8]) -> Reply {
+        let index = self.entry(ino)?.ok_or(libc::EISDIR)?;
+        let (offset, size) = (u64_at(body, 8), u32_at(body, 16) as usize);
+
+        self.read(index, offset, size)
+            .map(<[u8]>::to_vec)
+            .map_err(|_| libc::EIO)
+    }
+
+    /// The `fuse_dirent`s of the root directory, from the one at `offset`.
+    #[allow(clippy::cast_possible_truncation)]
+    fn readdir(&self, ino: u64, body: &[u8]) -> Reply {
+        if self.entry(ino)?.is_some() {
+            return Err(libc::ENOTDIR);
+        }
+        let (offset, size) = (u64_at(body, 8), u32_at(body, 16) as usize);
+
+        let dirs = [(ROOT, "."), (ROOT, "..")].map(|(ino, name)| (ino, name, libc::DT_DIR));
+        let files = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (index as u64 + 2, node.name.as_str(), libc::DT_REG));
+
+        let mut out = Vec::new();
+        for (position, (ino, name, kind)) in dirs.into_iter().chain(files).enumerate() {
+            if (position as u64) < offset {
+                continue;
+            }
+            let len = (24 + name.len()).next_multiple_of(8);
+            if out.len() + len > size {
+                break;
+            }
+
+            for value in [ino, position as u64 + 1] {
+                out.extend(value.to_ne_bytes());
+            }
+            out.extend((name.len() as u32).to_ne_bytes());
+            out.extend(u32::from(kind).to_ne_bytes());
+            out.extend(name.as_bytes());
+            out.resize(out.len().next_multiple_of(8), 0);
+        }
+
+        Ok(out)
+    }
+
+    /// A `fuse_kstatfs`.
+    fn statfs(&self) -> Vec<u8> {
+        let blocks: u64 = self
+            .nodes
+            .iter()
+            .map(|node| node.size.div_ceil(u64::from(BLOCK_SIZE)))
+            .sum();
+
+        let mut out = Vec::with_capacity(80);
+        for value in [blocks, 0, 0, self.nodes.len() as u64 + 1, 0] {
+            out.extend(value.to_ne_bytes());
+        }
+        for value in [BLOCK_SIZE, 255, BLOCK_SIZE] {
+            out.extend(value.to_ne_bytes());
+        }
+        out.resize(80, 0);
+
+        out
+    }
+
+    /// Answers a request of the kernel; `None` if it doesn't need a reply (or if it is malformed).
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn handle(&mut self, request: &[u8]) -> Option<Vec<u8>> {
+        let header = request.get(..IN_HEADER_SIZE)?;
+        let body = &request[IN_HEADER_SIZE..];
+        let (opcode, unique, ino) = (u32_at(header, 4), u64_at(header, 8), u64_at(header, 16));
+
+        let reply = match opcode {
+            FUSE_INIT => Self::init(body),
+            FUSE_LOOKUP => self.lookup(ino, body),
+            FUSE_GETATTR => self.attr(ino).map(|attr| {
+                let mut out = Vec::with_capacity(104);
+                out.extend(TTL.to_ne_bytes());
+                out.extend([0; 8]);
+                out.extend(attr);
+                out
+            }),
+            FUSE_OPEN => self.open(ino, body),
+            FUSE_READ => self.read_request(ino, body),
+            FUSE_OPENDIR => match self.entry(ino) {
+                Ok(None) => Ok(vec![0; 16]),
+                Ok(Some(_)) => Err(libc::ENOTDIR),
+                Err(errno) => Err(errno),
+            },
+            FUSE_READDIR => self.readdir(ino, body),
+            FUSE_STATFS => Ok(self.statfs()),
+            FUSE_RELEASE | FUSE_RELEASEDIR | FUSE_FLUSH | FUSE_ACCESS | FUSE_DESTROY => {
+                Ok(Vec::new())
+            }
+            FUSE_FORGET | FUSE_BATCH_FORGET | FUSE_INTERRUPT => return None,
+            _ => Err(libc::ENOSYS),
+        };
+
+        let (error, body) = match reply {
+            Ok(body) => (0, body),
+            Err(errno) => (-errno, Vec::new()),
+        };
+        let mut out = Vec::with_capacity(OUT_HEADER_SIZE + body.len());
+        out.extend(((OUT_HEADER_SIZE + body.len()) as u32).to_ne_bytes());
+        out.extend(error.to_ne_bytes());
+        out.extend(unique.to_ne_bytes());
+        out.extend(body);
+
+        Some(out)
+    }
+}
+
+/// Mounts `pman` at `mountpoint`, and answers the requests of the kernel until it is unmounted
+/// (e.g. with `umount`, or `fusermount -u`).
+///
+/// Without the privileges to mount it, `fusermount3` (or `fusermount`) mounts it instead.
+///
+/// # Errors
+///
+/// If it can't be mounted, or if `/dev/fuse` can't be read or written.
+pub fn mount(pman: PmanFile, mountpoint: impl AsRef<Path>) -> Result<()> {
+    let mountpoint = mountpoint.as_ref();
+    let mut vfs = VirtualFs::new(pman);
+    // SAFETY: these never fail.
+    (vfs.uid, vfs.gid) = unsafe { (libc::getuid(), libc::getgid()) };
+
+    let mut device = open_device(mountpoint, vfs.uid, vfs.gid)?;
+    let mut buf = vec![0; MAX_WRITE as usize + 0x1000];
+
+    loop {
+        let len = match device.read(&mut buf) {
+            Ok(len) => len,
+            // the filesystem was unmounted.
+            Err(err) if err.raw_os_error() == Some(libc::ENODEV) => return Ok(()),
+            // the request was interrupted before it was read.
+            Err(err) if matches!(err.raw_os_error(), Some(libc::EINTR | libc::ENOENT)) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let request = &buf[..len];
+
+        if let Some(reply) = vfs.handle(request) {
+            match device.write(&reply) {
+                // the request was interrupted while it was answered.
+                Err(err) if err.raw_os_error() == Some(libc::ENOENT) => {}
+                Err(err) => return Err(err.into()),
+                Ok(_) => {}
+            }
+        }
+        if u32_at(request, 4) == FUSE_DESTROY {
+            return Ok(());
+        }
+    }
+}
+
+fn open_device(mountpoint: &Path, uid: u32, gid: u32) -> Result<File> {
+    let device = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/fuse")?;
+    let target = CString::new(mountpoint.as_os_str().as_bytes())
+        .map_err(|_| Error::from(io::Error::from(io::ErrorKind::InvalidInput)))?;
+    let options = CString::new(format!(
+        "fd={},rootmode=40000,user_id={uid},group_id={gid},default_permissions",
+        device.as_raw_fd()
+    ))
+    .expect("there are no nul bytes.");
+
+    // SAFETY: every pointer is a valid, nul terminated, string.
+    let result = unsafe {
+        libc::mount(
+            c"rashen".as_ptr(),
+            target.as_ptr(),
+            c"fuse.rashen".as_ptr(),
+            libc::MS_RDONLY | libc::MS_NOSUID | libc::MS_NODEV,
+            options.as_ptr().cast(),
+        )
+    };
+    if result == 0 {
+        return Ok(device);
+    }
+
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EPERM) {
+        fusermount(mountpoint)
+    } else {
+        Err(err.into())
+    }
+}
+
+/// Mounts with `fusermount`, which sends the opened `/dev/fuse` back through a socket.
+fn fusermount(mountpoint: &Path) -> Result<File> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for both sockets.
+    if unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: both sockets were just opened, and nothing else owns them.
+    let (ours, theirs) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+    let status = ["fusermount3", "fusermount"]
+        .into_iter()
+        .find_map(|program| {
+            Command::new(program)
+                .args(["-o", "ro,nosuid,nodev,fsname=rashen,subtype=rashen", "--"])
+                .arg(mountpoint)
+                .env("_FUSE_COMMFD", theirs.as_raw_fd().to_string())
+                .status()
+                .ok()
+        })
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "there is no `fusermount`"))?;
+    drop(theirs);
+
+    if !status.success() {
+        return Err(io::Error::other(format!("`fusermount` failed; {status}")).into());
+    }
+
+    receive_fd(&ours)
+}
+
+fn receive_fd(socket: &OwnedFd) -> Result<File> {
+    let mut byte = [0u8];
+    let mut iov = libc::iovec {
+        iov_base: byte.as_mut_ptr().cast(),
+        iov_len: byte.len(),
+    };
+    let mut control = [0u64; 8];
+    // SAFETY: an all zero `msghdr` is valid.
+    let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+    message.msg_iov = &raw mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr().cast();
+    message.msg_controllen = std::mem::size_of_val(&control) as _;
+
+    // SAFETY: `message` points to buffers that outlive the call.
+    if unsafe { libc::recvmsg(socket.as_raw_fd(), &raw mut message, 0) } <= 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    // SAFETY: `message` was filled by `recvmsg`, and the control message is checked before it is
+    // read.
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&raw const message);
+        if cmsg.is_null() || (*cmsg).cmsg_type != libc::SCM_RIGHTS {
+            return Err(io::Error::other("`fusermount` didn't send `/dev/fuse`").into());
+        }
+        let fd = libc::CMSG_DATA(cmsg).cast::<i32>().read_unaligned();
+
+        Ok(File::from_raw_fd(fd))
+    }
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    /// A request, with its `fuse_in_header`.
+    #[allow(clippy::cast_possible_truncation)]
+    fn request(opcode: u32, ino: u64, body: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(((IN_HEADER_SIZE + body.len()) as u32).to_ne_bytes());
+        out.extend(opcode.to_ne_bytes());
+        out.extend(7u64.to_ne_bytes());
+        out.extend(ino.to_ne_bytes());
+        out.resize(IN_HEADER_SIZE, 0);
+        out.extend(body);
+        out
+    }
+
+    /// The error, and the body, of a reply.
+    fn reply(vfs: &mut VirtualFs, request: &[u8]) -> (i32, Vec<u8>) {
+        let reply = vfs.handle(request).expect("the request has a reply.");
+        assert_eq!(u32_at(&reply, 0) as usize, reply.len());
+        assert_eq!(u64_at(&reply, 8), 7);
+
+        (
+            u32_at(&reply, 4).cast_signed(),
+            reply[OUT_HEADER_SIZE..].to_vec(),
+        )
+    }
+
+    #[test]
+    fn virtual_fs_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let german = pman[156].to_zlib()?.expect("text banks are compressed.");
+        let mut vfs = VirtualFs::new(pman);
+
+        let (error, init) = reply(&mut vfs, &request(FUSE_INIT, 0, &[7, 0, 0, 0, 40, 0, 0, 0]));
+        assert_eq!((error, init.len()), (0, 64));
+        assert_eq!((u32_at(&init, 0), u32_at(&init, 4)), KERNEL_VERSION);
+
+        let name = vfs.names().nth(156).unwrap().to_owned();
+        assert_eq!(name.rsplit_once('.').map(|(_, e)| e), Some("text"));
+        let (error, entry) = reply(&mut vfs, &request(FUSE_LOOKUP, ROOT, name.as_bytes()));
+        assert_eq!(error, 0);
+        let ino = u64_at(&entry, 0);
+        // `fuse_entry_out::attr::size`.
+        assert_eq!(u64_at(&entry, 48), german.len() as u64);
+
+        let mut read = vec![0; 24];
+        read[8..16].copy_from_slice(&4u64.to_ne_bytes());
+        read[16..20].copy_from_slice(&16u32.to_ne_bytes());
+        let (error, data) = reply(&mut vfs, &request(FUSE_READ, ino, &read));
+        assert_eq!((error, data.as_slice()), (0, &german[4..20]));
+
+        read[16..20].copy_from_slice(&0x1000u32.to_ne_bytes());
+        let (error, dir) = reply(&mut vfs, &request(FUSE_READDIR, ROOT, &read));
+        assert_eq!(error, 0);
+        // the first 4 entries are skipped; `.`, `..` and the first two files.
+        assert_eq!(u64_at(&dir, 0), 4);
+
+        let (error, _) = reply(&mut vfs, &request(FUSE_LOOKUP, ROOT, b"missing\0"));
+        assert_eq!(error, -libc::ENOENT);
+        let (error, _) = reply(&mut vfs, &request(FUSE_OPEN, ino, &[2, 0, 0, 0]));
+        assert_eq!(error, -libc::EROFS);
+        assert!(vfs.handle(&request(FUSE_FORGET, ino, &[])).is_none());
+
+        Ok(())
+    }
+}
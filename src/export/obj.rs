@@ -0,0 +1,204 @@
+//! Wavefront OBJ (and MTL) export for models and collision meshes.
+
+use crate::format::{
+    collision::{CollisionMesh, Contents, Material, Surface},
+    model::Model,
+};
+use std::fmt::Write;
+
+/// An OBJ file, and the MTL file with its materials.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Obj {
+    pub obj: String,
+    pub mtl: String,
+}
+
+/// Converts a frame of `model` into an OBJ file, that refers to its materials as `{name}.mtl`.
+///
+/// The texture is referred to as `{name}.png`, since there is no way to decode it yet; see
+/// [`Model::texture`]. Returns `None` if the model doesn't have `frame`.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn model_to_obj(model: &Model, frame: usize, name: &str) -> Option<Obj> {
+    let positions = model.frames.get(frame)?.positions();
+    let mut obj = format!("mtllib {name}.mtl\no {name}\n");
+    let (width, height) = (
+        model.texture_width.max(1) as f32,
+        model.texture_height.max(1) as f32,
+    );
+
+    // writing into a `String` can't fail.
+    for [x, y, z] in positions {
+        _ = writeln!(obj, "v {x} {y} {z}");
+    }
+    for [u, v] in model.faces.iter().flat_map(|face| face.uvs) {
+        // OBJ puts the origin of the texture on its bottom left corner.
+        _ = writeln!(
+            obj,
+            "vt {} {}",
+            f32::from(u) / width,
+            1.0 - f32::from(v) / height
+        );
+    }
+
+    obj.push_str("usemtl texture\n");
+    for (index, face) in model.faces.iter().enumerate() {
+        let [a, b, c] = face.vertices.map(|v| usize::from(v) + 1);
+        let uv = index * 3 + 1;
+
+        _ = writeln!(obj, "f {a}/{uv} {b}/{} {c}/{}", uv + 1, uv + 2);
+    }
+
+    let mtl = format!("newmtl texture\nKd 1 1 1\nmap_Kd {name}.png\n");
+
+    Some(Obj { obj, mtl })
+}
+
+/// Converts the brushes of `mesh` into an OBJ file, that refers to its materials as
+/// `{name}.mtl`; there is one object for every model of the mesh.
+///
+/// Faces are grouped by the contents of their brush and by their surface, as
+/// `g {contents}_{surface}`; e.g. `g water_floor`. Every material refers to its texture as
+/// `{material name}.png`.
+#[must_use]
+pub fn collision_to_obj(mesh: &CollisionMesh, name: &str) -> Obj {
+    let mut obj = format!("mtllib {name}.mtl\n");
+    let mut vertex_count = 0;
+
+    for (index, model) in mesh.models.iter().enumerate() {
+        _ = writeln!(obj, "o model_{index}");
+        // the contents of the brush and the surface of the face, e.g. `g water_floor`.
+        let mut group = None;
+
+        let first = model.first_brush as usize;
+        for brush in first..first.saturating_add(model.brush_count as usize) {
+            let Some(material) = mesh.brushes.get(brush).map(|b| b.material) else {
+                continue;
+            };
+            let contents = mesh
+                .material_of_brush(brush)
+                .map_or(Contents::Unknown, Material::contents);
+
+            _ = writeln!(obj, "usemtl material_{material}");
+            for (plane, polygon) in mesh.brush_faces_by_plane(brush) {
+                let surface = Surface::of(plane.normal_f32());
+                if group != Some((contents, surface)) {
+                    _ = writeln!(obj, "g {}_{}", contents.name(), surface.name());
+                    group = Some((contents, surface));
+                }
+
+                for [x, y, z] in &polygon {
+                    _ = writeln!(obj, "v {x} {y} {z}");
+                }
+                obj.push('f');
+                for vertex in 0..polygon.len() {
+                    _ = write!(obj, " {}", vertex_count + vertex + 1);
+                }
+                obj.push('\n');
+                vertex_count += polygon.len();
+            }
+        }
+    }
+
+    let mut mtl = String::new();
+    for (index, material) in mesh.materials.iter().enumerate() {
+        _ = writeln!(
+            mtl,
+            "newmtl material_{index}\nKd 1 1 1\nmap_Kd {}.png",
+            material.name
+        );
+    }
+
+    Obj { obj, mtl }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::collision::{Brush, Model as CollisionModel, Plane};
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    fn count(text: &str, prefix: &str) -> usize {
+        text.lines().filter(|line| line.starts_with(prefix)).count()
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn model_to_obj_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = pman[10].to_zlib()?.expect("models are compressed.");
+        let model = Model::new(&bytes)?;
+        let obj = model_to_obj(&model, 0, "model").expect("it has a first frame.");
+
+        assert_eq!(count(&obj.obj, "v "), 118);
+        assert_eq!(count(&obj.obj, "vt "), 230 * 3);
+        assert_eq!(count(&obj.obj, "f "), 230);
+        assert!(obj.obj.starts_with("mtllib model.mtl\n"));
+        assert!(obj.mtl.contains("map_Kd model.png"));
+        assert_eq!(model_to_obj(&model, model.frames.len(), "model"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collision_to_obj_test() {
+        const ONE: i32 = 1 << 24;
+
+        // a cube from `-1` to `1`.
+        let planes = [0, 1, 2].into_iter().flat_map(|axis| {
+            let mut normal = [0; 3];
+            normal[axis] = ONE;
+
+            [
+                Plane {
+                    normal,
+                    distance: ONE,
+                },
+                Plane {
+                    normal: normal.map(|n| -n),
+                    distance: ONE,
+                },
+            ]
+        });
+        let mesh = CollisionMesh {
+            models: vec![CollisionModel {
+                first_brush: 0,
+                brush_count: 1,
+            }],
+            brushes: vec![Brush {
+                first_side: 0,
+                material: 0,
+                side_count: 6,
+            }],
+            brush_sides: (0..6).collect(),
+            materials: vec![Material {
+                name: "textures/test".into(),
+                flags: 1,
+            }],
+            planes: planes.collect(),
+            ..Default::default()
+        };
+        let obj = collision_to_obj(&mesh, "level");
+
+        assert_eq!(count(&obj.obj, "v "), 6 * 4);
+        assert_eq!(count(&obj.obj, "f "), 6);
+        // the sides go `+X`, `-X`, `+Y`, `-Y`, `+Z` and `-Z`; the 4 walls share a group.
+        assert_eq!(count(&obj.obj, "g solid_wall"), 1);
+        assert_eq!(count(&obj.obj, "g solid_floor"), 1);
+        assert_eq!(count(&obj.obj, "g solid_ceiling"), 1);
+        assert!(obj
+            .obj
+            .lines()
+            .filter_map(|line| line.strip_prefix("v "))
+            .flat_map(str::split_whitespace)
+            .all(|x| (x.parse::<f32>().unwrap_or_default().abs() - 1.0).abs() < 1e-3));
+        assert_eq!(
+            obj.mtl,
+            "newmtl material_0\nKd 1 1 1\nmap_Kd textures/test.png\n"
+        );
+    }
+}
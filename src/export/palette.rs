@@ -0,0 +1,142 @@
+//! Palette export into the formats of image editors.
+
+use crate::format::palette::Palette;
+use std::fmt::Write;
+
+/// A palette file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaletteFormat {
+    /// GIMP (and Inkscape, Krita, ...) `.gpl`.
+    Gimp,
+    /// Adobe (Photoshop) `.act`.
+    Adobe,
+    /// JASC (Paint Shop Pro, Aseprite, ...) `.pal`.
+    Jasc,
+}
+
+impl PaletteFormat {
+    /// The extension of the format, without the leading dot.
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gimp => "gpl",
+            Self::Adobe => "act",
+            Self::Jasc => "pal",
+        }
+    }
+
+    /// The format of a file with `extension` (without the leading dot), if it is a palette one.
+    #[must_use]
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        [Self::Gimp, Self::Adobe, Self::Jasc]
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Encodes `palette` on this format; `name` is only used by the formats that have one.
+    #[must_use]
+    pub fn encode(self, palette: &Palette, name: &str) -> Vec<u8> {
+        match self {
+            Self::Gimp => to_gpl(palette, name).into_bytes(),
+            Self::Adobe => to_act(palette),
+            Self::Jasc => to_jasc_pal(palette).into_bytes(),
+        }
+    }
+}
+
+/// A GIMP palette; one `r g b index` line for every color.
+#[must_use]
+pub fn to_gpl(palette: &Palette, name: &str) -> String {
+    let mut gpl = format!("GIMP Palette\nName: {name}\nColumns: 16\n#\n");
+
+    for (index, [r, g, b, _]) in palette.to_rgba().into_iter().enumerate() {
+        // writing into a `String` can't fail.
+        _ = writeln!(gpl, "{r:3} {g:3} {b:3}\t{index}");
+    }
+
+    gpl
+}
+
+/// An Adobe color table; the 256 colors as 8-bit RGB, with no header.
+#[must_use]
+pub fn to_act(palette: &Palette) -> Vec<u8> {
+    palette
+        .to_rgba()
+        .iter()
+        .flat_map(|&[r, g, b, _]| [r, g, b])
+        .collect()
+}
+
+/// A JASC palette; a header, and one `r g b` line for every color, with `\r\n` line endings.
+#[must_use]
+pub fn to_jasc_pal(palette: &Palette) -> String {
+    let colors = palette.to_rgba();
+    let mut pal = format!("JASC-PAL\r\n0100\r\n{}\r\n", colors.len());
+
+    for [r, g, b, _] in colors {
+        _ = write!(pal, "{r} {g} {b}\r\n");
+    }
+
+    pal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::palette::COLOR_COUNT;
+    #[cfg(feature = "packfile")]
+    use crate::{export, format::pman::PmanFile};
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    fn palette() -> Palette {
+        let mut colors = [0; COLOR_COUNT];
+        colors[1] = 0x0F80;
+
+        Palette { colors }
+    }
+
+    #[test]
+    fn palette_gpl_test() {
+        let gpl = to_gpl(&palette(), "test");
+        let lines: Vec<_> = gpl.lines().collect();
+
+        assert_eq!(
+            lines[..4],
+            ["GIMP Palette", "Name: test", "Columns: 16", "#"]
+        );
+        assert_eq!(lines[5], "255 136   0\t1");
+        assert_eq!(lines.len(), 4 + COLOR_COUNT);
+    }
+
+    #[test]
+    fn palette_act_test() {
+        let act = to_act(&palette());
+
+        assert_eq!(act.len(), COLOR_COUNT * 3);
+        assert_eq!(act[3..6], [0xFF, 0x88, 0x00]);
+    }
+
+    #[test]
+    fn palette_jasc_test() {
+        let pal = to_jasc_pal(&palette());
+
+        assert!(pal.starts_with("JASC-PAL\r\n0100\r\n256\r\n0 0 0\r\n255 136 0\r\n"));
+        assert_eq!(pal.matches("\r\n").count(), 3 + COLOR_COUNT);
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn convert_palette_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let converted = export::convert(&pman, 66, "palette", export::Options::default())?;
+
+        assert_eq!(converted[0].file_name, "palette.gpl");
+        assert!(converted[0]
+            .bytes
+            .starts_with(b"GIMP Palette\nName: palette\n"));
+
+        Ok(())
+    }
+}
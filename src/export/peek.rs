@@ -0,0 +1,202 @@
+//! Short previews of entries, for a terminal; see [`peek`].
+
+use crate::{
+    error::Result,
+    format::{
+        detect::FileType,
+        palette::Palette,
+        pman::{Compression, PmanFile},
+        skybox::Skybox,
+        text::TextBank,
+        texture::Texture,
+    },
+};
+use std::fmt::Write;
+
+/// How many bytes a hex dump shows.
+const PREVIEW_BYTES: usize = 0x100;
+/// How many strings of a text bank are shown.
+const PREVIEW_STRINGS: usize = 8;
+/// How many characters of a string are shown.
+const PREVIEW_CHARS: usize = 64;
+/// How many pixels of a texture are shown; the start of its first row.
+const PREVIEW_PIXELS: usize = 16;
+/// How many colors of a palette are shown on a row.
+const SWATCHES_PER_ROW: usize = 16;
+
+/// A block of `color`; a background colored with an ANSI escape (or its `#RRGGBB` code, without
+/// `ansi`).
+fn swatch(out: &mut String, [r, g, b, _]: [u8; 4], ansi: bool) {
+    if ansi {
+        _ = write!(out, "\x1b[48;2;{r};{g};{b}m  \x1b[0m");
+    } else {
+        _ = write!(out, "#{r:02X}{g:02X}{b:02X} ");
+    }
+}
+
+/// The first [`PREVIEW_BYTES`] of `bytes` as hex, next to them as ASCII; 16 bytes a line.
+fn hex_dump(out: &mut String, bytes: &[u8]) {
+    for (line, chunk) in bytes.chunks(16).take(PREVIEW_BYTES / 16).enumerate() {
+        _ = write!(out, "{:08X}  ", line * 16);
+        for byte in chunk {
+            _ = write!(out, "{byte:02X} ");
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+            .collect();
+        _ = writeln!(out, "{:pad$}|{ascii}|", "", pad = (16 - chunk.len()) * 3);
+    }
+    if bytes.len() > PREVIEW_BYTES {
+        _ = writeln!(out, "... and {} more bytes", bytes.len() - PREVIEW_BYTES);
+    }
+}
+
+fn text_bank(out: &mut String, bank: &TextBank) {
+    _ = writeln!(out, "{} strings, {:?}", bank.entries.len(), bank.encoding);
+    for (id, text) in bank.entries.iter().take(PREVIEW_STRINGS) {
+        let mut preview: String = text.chars().take(PREVIEW_CHARS).collect();
+        if text.chars().nth(PREVIEW_CHARS).is_some() {
+            preview.push_str("...");
+        }
+        _ = writeln!(out, "{id:5}: {preview:?}");
+    }
+    if bank.entries.len() > PREVIEW_STRINGS {
+        _ = writeln!(out, "... and {} more", bank.entries.len() - PREVIEW_STRINGS);
+    }
+}
+
+fn palette(out: &mut String, palette: &Palette, ansi: bool) {
+    for row in palette.to_rgba().chunks(SWATCHES_PER_ROW) {
+        for &color in row {
+            swatch(out, color, ansi);
+        }
+        out.push('\n');
+    }
+}
+
+/// The size of an indexed image, and the start of its first row; as colors, if it has a palette.
+fn pixels(out: &mut String, (width, height): (u32, u32), pixels: &[u8], palette: Option<&Palette>) {
+    _ = writeln!(out, "{width}x{height}, 8 bits per pixel");
+
+    let first = &pixels[..pixels.len().min(PREVIEW_PIXELS)];
+    _ = writeln!(out, "first pixels: {first:02X?}");
+    if let Some(palette) = palette {
+        let colors = palette.to_rgba();
+        out.push_str("              ");
+        for &pixel in first {
+            swatch(out, colors[usize::from(pixel)], true);
+        }
+        out.push('\n');
+    }
+}
+
+/// A short preview of the entry at `index` of `pman`, that suits its type: the first strings of
+/// text banks, the colors of palettes, the size and the first pixels of textures and skyboxes, and
+/// a hex dump of anything else. Colors are shown with ANSI escapes, if `ansi`; otherwise, palettes
+/// are shown as `#RRGGBB` codes, and skyboxes only as indices. Textures are always shown as
+/// indices, since their palettes are not known for sure yet.
+///
+/// It is `None` if `index` is out of bounds.
+///
+/// # Errors
+///
+/// If the entry can't be decompressed, or if it can't be parsed as the type that it was detected
+/// as.
+pub fn peek(pman: &PmanFile, index: usize, ansi: bool) -> Result<Option<String>> {
+    let Some(file) = pman.files().get(index) else {
+        return Ok(None);
+    };
+    let zlib = file.to_zlib()?;
+    let bytes = zlib.as_deref().unwrap_or(file.bytes());
+    let file_type = file.detect_type();
+
+    let mut out = format!(
+        "entry {index}: {}, {} bytes",
+        file_type.map_or("unknown", FileType::extension),
+        bytes.len()
+    );
+    if let Compression::Zlib { .. } = file.compression() {
+        _ = write!(out, " (ZL, {} stored)", file.bytes().len());
+    }
+    out.push('\n');
+
+    match file_type {
+        Some(FileType::Text) => text_bank(&mut out, &TextBank::new(bytes)?),
+        Some(FileType::Palette) => palette(&mut out, &Palette::new(bytes)?, ansi),
+        Some(FileType::Texture) => {
+            let texture = Texture::new(bytes)?;
+
+            pixels(
+                &mut out,
+                (texture.width, texture.height),
+                &texture.pixels,
+                None,
+            );
+        }
+        Some(FileType::Skybox) => {
+            let skybox = Skybox::new(bytes)?;
+
+            pixels(
+                &mut out,
+                (skybox.width, skybox.height),
+                &skybox.pixels,
+                Some(&skybox.palette).filter(|_| ansi),
+            );
+        }
+        _ => hex_dump(&mut out, bytes),
+    }
+
+    Ok(Some(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixture,
+        format::{palette::COLOR_COUNT, pman, texture::TEXTURE_SIZE},
+    };
+
+    #[test]
+    fn peek_test() -> eyre::Result<()> {
+        let mut palette = Palette {
+            colors: [0; COLOR_COUNT],
+        };
+        palette.colors[1] = 0x0F00;
+        let bank = TextBank {
+            entries: (0..10).map(|id| (id, format!("string {id}"))).collect(),
+            ..TextBank::default()
+        };
+        let files = [
+            b"PMAN?".to_vec(),
+            pman::compress(&bank.to_bytes()?)?,
+            palette.to_bytes(),
+            vec![1; (TEXTURE_SIZE * TEXTURE_SIZE) as usize],
+        ];
+        let pman = PmanFile::new(&fixture::pack(fixture::COPYRIGHT, &files))?;
+
+        let hex = peek(&pman, 0, false)?.expect("the entry exists.");
+        assert!(hex.starts_with("entry 0: pman, 5 bytes\n"));
+        assert!(hex.contains("\n00000000  50 4D 41 4E 3F "));
+        assert!(hex.ends_with(" |PMAN?|\n"));
+
+        let text = peek(&pman, 1, false)?.expect("the entry exists.");
+        assert!(text.contains("(ZL, "));
+        assert!(text.contains("    7: \"string 7\"\n"));
+        assert!(!text.contains("string 8"));
+        assert!(text.ends_with("... and 2 more\n"));
+
+        let colors = peek(&pman, 2, false)?.expect("the entry exists.");
+        assert!(colors.contains("\n#000000 #FF0000 #000000 "));
+        let ansi = peek(&pman, 2, true)?.expect("the entry exists.");
+        assert!(ansi.contains("\x1b[48;2;255;0;0m  \x1b[0m"));
+
+        let texture = peek(&pman, 3, false)?.expect("the entry exists.");
+        assert!(texture.contains("256x256, 8 bits per pixel\nfirst pixels: [01, 01,"));
+
+        assert_eq!(peek(&pman, 4, false)?, None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+//! Gettext PO export (and import) for text banks, for translation tools like Poedit or Weblate.
+//!
+//! Every string is a message, with its id as the context and the english string as the source;
+//! e.g.
+//!
+//! ```po
+//! msgctxt "1"
+//! msgid "Press START"
+//! msgstr "START drücken"
+//! ```
+//!
+//! The id is the context, so strings that are the same in english can still be translated apart.
+
+use crate::{
+    error::{Error, Result},
+    format::text::TextBank,
+};
+use std::fmt::Write;
+
+const FORMAT: &str = "PO";
+
+fn invalid(line: usize, reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format: FORMAT,
+        reason: format!("line {}: {}", line + 1, reason.into()),
+    }
+}
+
+/// Escapes `text` as the lines of a PO string; strings with line breaks are split after each one.
+fn write_string(po: &mut String, keyword: &str, text: &str) {
+    let escaped = |line: &str| {
+        line.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\t', "\\t")
+            .replace('\r', "\\r")
+            .replace('\n', "\\n")
+    };
+    let lines: Vec<_> = text.split_inclusive('\n').collect();
+
+    // writing into a `String` can't fail.
+    if lines.len() > 1 {
+        _ = writeln!(po, "{keyword} \"\"");
+        for line in lines {
+            _ = writeln!(po, "\"{}\"", escaped(line));
+        }
+    } else {
+        _ = writeln!(po, "{keyword} \"{}\"", escaped(text));
+    }
+}
+
+/// Converts `bank` into a PO file, of the ISO 639-1 `language` (if it is known).
+///
+/// The string with the same id on `english` is the source of every message, and the string of
+/// `bank` is its translation; strings that are not on `english` use themselves as the source. With
+/// no `english` bank (or with `bank` being the english one), it is a template, whose translations
+/// are all empty.
+#[must_use]
+pub fn bank_to_po(bank: &TextBank, english: Option<&TextBank>, language: Option<&str>) -> String {
+    let template = english.is_none_or(|english| english == bank);
+    let mut po = String::from("msgid \"\"\nmsgstr \"\"\n");
+
+    if let Some(language) = language.filter(|_| !template) {
+        _ = writeln!(po, "\"Language: {language}\\n\"");
+    }
+    po.push_str("\"MIME-Version: 1.0\\n\"\n");
+    po.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    po.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+
+    for (id, string) in &bank.entries {
+        let source = english
+            .and_then(|english| english.get(*id))
+            .unwrap_or(string);
+
+        po.push('\n');
+        _ = writeln!(po, "msgctxt \"{id}\"");
+        write_string(&mut po, "msgid", source);
+        write_string(&mut po, "msgstr", if template { "" } else { string });
+    }
+
+    po
+}
+
+/// Reads the string of a quoted PO line; e.g. `"a\"b"`.
+fn read_string(line: &str, index: usize) -> Result<String> {
+    let inner = line
+        .strip_prefix('"')
+        .and_then(|line| line.strip_suffix('"'))
+        .ok_or_else(|| invalid(index, "a string must be quoted"))?;
+    let mut string = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+
+        string.push(match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some(c @ ('\\' | '"')) => c,
+            c => {
+                return Err(invalid(
+                    index,
+                    format!("`\\{}` is not an escape", c.unwrap_or(' ')),
+                ))
+            }
+        });
+    }
+
+    Ok(string)
+}
+
+/// A message, as it is being read.
+#[derive(Default)]
+struct Message {
+    context: Option<String>,
+    source: Option<String>,
+    translation: Option<String>,
+}
+
+impl Message {
+    fn field(&mut self, keyword: &str) -> Option<&mut Option<String>> {
+        match keyword {
+            "msgctxt" => Some(&mut self.context),
+            "msgid" => Some(&mut self.source),
+            "msgstr" => Some(&mut self.translation),
+            _ => None,
+        }
+    }
+
+    /// The id and the string of the message; the translation, or the source if it is not
+    /// translated yet. The header (the message without a context and source) is `None`.
+    fn finish(self, line: usize) -> Result<Option<(u32, String)>> {
+        let Some(context) = self.context else {
+            return match self.source.as_deref() {
+                None | Some("") => Ok(None),
+                Some(_) => Err(invalid(line, "a message doesn't have a `msgctxt` id")),
+            };
+        };
+        let id = context
+            .parse()
+            .map_err(|_| invalid(line, format!("`{context}` is not an id")))?;
+        let translation = self.translation.filter(|t| !t.is_empty());
+
+        Ok(Some((id, translation.or(self.source).unwrap_or_default())))
+    }
+}
+
+/// Reads the strings of [`bank_to_po`] back, in the order of the messages.
+///
+/// Untranslated messages (those with an empty `msgstr`) keep their english string. Comments,
+/// flags (e.g. `#, fuzzy`) and obsolete messages are ignored. The encoding of the bank is the
+/// default one; copy it over from the original bank if it is different.
+///
+/// # Errors
+///
+/// If a line is not a known keyword or a quoted string, if a message doesn't have a numeric
+/// `msgctxt`, or if it uses plurals.
+pub fn bank_from_po(po: &str) -> Result<TextBank> {
+    let mut entries = Vec::new();
+    let mut message = Message::default();
+    // the keyword that a continued line is appended to.
+    let mut current = None;
+    let mut last_line = 0;
+
+    for (index, line) in po.lines().enumerate() {
+        let line = line.trim();
+        last_line = index;
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('"') {
+            let string = read_string(line, index)?;
+            let field = current
+                .and_then(|keyword| message.field(keyword))
+                .and_then(Option::as_mut)
+                .ok_or_else(|| invalid(index, "a string doesn't continue a keyword"))?;
+            field.push_str(&string);
+            continue;
+        }
+
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        if keyword == "msgid_plural" {
+            return Err(invalid(index, "plurals are not supported"));
+        }
+        if message.field(keyword).is_none() {
+            return Err(invalid(index, format!("`{keyword}` is not a keyword")));
+        }
+
+        let string = read_string(rest.trim(), index)?;
+        if keyword == "msgctxt" || (keyword == "msgid" && message.translation.is_some()) {
+            entries.extend(std::mem::take(&mut message).finish(index)?);
+        }
+        if let Some(field) = message.field(keyword) {
+            *field = Some(string);
+        }
+        current = Some(keyword);
+    }
+
+    entries.extend(message.finish(last_line)?);
+
+    Ok(TextBank {
+        entries,
+        ..TextBank::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    #[cfg(feature = "packfile")]
+    use crate::format::{pman::PmanFile, text::TEXT_BANKS};
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn po_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bank = |index: usize| -> eyre::Result<(TextBank, Vec<u8>)> {
+            let bytes = pman[index].to_zlib()?.expect("text banks are compressed.");
+
+            Ok((TextBank::new(&bytes)?, bytes))
+        };
+        let (english, _) = bank(152)?;
+
+        for (index, language) in TEXT_BANKS {
+            let (bank, bytes) = bank(index)?;
+            let po = bank_to_po(&bank, Some(&english), Some(language));
+
+            assert_eq!(bank_from_po(&po)?.to_bytes()?, bytes, "entry {index}");
+        }
+
+        let po = bank_to_po(&bank(156)?.0, Some(&english), Some("de"));
+        assert!(po.contains("msgctxt \"1\"\nmsgid \"Press START\"\nmsgstr \"START drücken\"\n"));
+        assert!(po.contains("\"Language: de\\n\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn po_escape_test() -> eyre::Result<()> {
+        let bank = TextBank {
+            entries: vec![(0, "a\"b\\c".into()), (1, "†one\r\ntwo|".into())],
+            ..TextBank::default()
+        };
+        let po = bank_to_po(&bank, None, None);
+
+        assert!(po.contains("msgid \"\"\n\"†one\\r\\n\"\n\"two|\"\nmsgstr \"\"\n"));
+        // a template has no translations, so the english strings are kept.
+        assert_eq!(bank_from_po(&po)?, bank);
+
+        let po = "#, fuzzy\nmsgctxt \"3\"\nmsgid \"a\"\nmsgstr \"\"\n\"b\"\n";
+        assert_eq!(bank_from_po(po)?.entries, [(3, "b".into())]);
+
+        for po in [
+            "msgid \"a\"\nmsgstr \"b\"\n",
+            "msgctxt \"a\"\nmsgid \"a\"\nmsgstr \"b\"\n",
+            "msgctxt \"0\"\nmsgid \"a\nmsgstr \"b\"\n",
+            "msgctxt \"0\"\nmsgid \"a\"\nmsgid_plural \"as\"\n",
+        ] {
+            assert_eq!(
+                bank_from_po(po).unwrap_err().kind(),
+                ErrorKind::InvalidImport
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+//! A local HTTP server that previews an archive, for web UIs and other tools; see [`serve`].
+//!
+//! Every response is JSON, unless it is the data of an entry (or of a preview):
+//!
+//! - `GET /entries`: an index of the entries; their offset, size, compression and type.
+//! - `GET /entries/{index}`: the (decompressed) data of an entry.
+//! - `GET /entries/{index}/previews`: the names of the files that the entry is converted into; see
+//!   [`convert`](super::convert).
+//! - `GET /entries/{index}/previews/{name}`: one of those files; e.g. a PNG, a WAV or a JSON.
+
+use super::{convert, Converted, MeshFormat, Options};
+use crate::{
+    error::{Error, Result},
+    format::{
+        cache::ZlibCache,
+        detect::FileType,
+        pman::{Compression, PmanFile},
+    },
+};
+use serde_json::{json, Value};
+use std::{collections::HashMap, io, net::ToSocketAddrs};
+
+/// The converters that previews are made with; the formats that browsers can show.
+const PREVIEW_OPTIONS: Options = Options {
+    mesh: MeshFormat::Glb,
+    text: super::text::TextFormat::Json,
+};
+
+/// How many bytes of decompressed entries are kept, for entries that are asked for again.
+const ZLIB_CACHE_BUDGET: usize = 64 * 1024 * 1024;
+
+/// An answer of the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, json: &Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: json.to_string().into_bytes(),
+        }
+    }
+
+    fn not_found(path: &str) -> Self {
+        Self::json(
+            404,
+            &json!({ "error": format!("there is nothing at `{path}`") }),
+        )
+    }
+}
+
+/// The content type of a file, by its extension.
+fn content_type(file_name: &str) -> &'static str {
+    match file_name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("png") => "image/png",
+        Some("wav") => "audio/wav",
+        Some("json") => "application/json",
+        Some("glb") => "model/gltf-binary",
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("obj" | "mtl" | "po" | "gpl") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// An archive, and the previews of its entries that were made.
+#[derive(Debug)]
+pub struct Previewer {
+    pman: PmanFile,
+    /// The previews of every entry that was asked for.
+    cache: HashMap<usize, Vec<Converted>>,
+    zlibs: ZlibCache,
+}
+
+impl Previewer {
+    #[must_use]
+    pub fn new(pman: PmanFile) -> Self {
+        Self {
+            pman,
+            cache: HashMap::new(),
+            zlibs: ZlibCache::new(ZLIB_CACHE_BUDGET),
+        }
+    }
+
+    /// The index of `GET /entries`.
+    #[must_use]
+    pub fn index(&self) -> Value {
+        let mut offset = self.pman.size_upto_file_data();
+        let entries: Vec<_> = self
+            .pman
+            .files()
+            .iter()
+            .enumerate()
+            .map(|(index, file)| {
+                let (compression, size) = match file.compression() {
+                    Compression::Zlib { declared_len } => ("zlib", declared_len),
+                    Compression::None => ("none", file.bytes().len()),
+                    Compression::Unknown { .. } => ("unknown", file.bytes().len()),
+                };
+                let entry = json!({
+                    "index": index,
+                    "offset": offset,
+                    "stored_size": file.bytes().len(),
+                    "size": size,
+                    "compression": compression,
+                    "type": file.detect_type().map(FileType::extension),
+                    "url": format!("/entries/{index}"),
+                });
+                offset += file.bytes().len();
+
+                entry
+            })
+            .collect();
+
+        json!({
+            "copyright": self.pman.copyright(),
+            "entries": entries,
+        })
+    }
+
+    fn previews(&mut self, index: usize) -> Result<&[Converted]> {
+        if !self.cache.contains_key(&index) {
+            let name = format!("{index:03}");
+            let previews = convert(&self.pman, index, &name, PREVIEW_OPTIONS)?;
+            self.cache.insert(index, previews);
+        }
+
+        Ok(&self.cache[&index])
+    }
+
+    fn entry(&mut self, index: usize, rest: &[&str], path: &str) -> Result<Response> {
+        let Some(file) = self.pman.files().get(index) else {
+            return Ok(Response::not_found(path));
+        };
+
+        match rest {
+            [] => Ok(Response {
+                status: 200,
+                content_type: "application/octet-stream",
+                body: self
+                    .zlibs
+                    .get(&self.pman, index)?
+                    .map_or_else(|| file.bytes().to_vec(), |zlib| zlib.to_vec()),
+            }),
+            ["previews"] => {
+                let previews: Vec<_> = self
+                    .previews(index)?
+                    .iter()
+                    .map(|preview| {
+                        json!({
+                            "name": preview.file_name,
+                            "size": preview.bytes.len(),
+                            "url": format!("/entries/{index}/previews/{}", preview.file_name),
+                        })
+                    })
+                    .collect();
+
+                Ok(Response::json(200, &Value::from(previews)))
+            }
+            ["previews", name] => Ok(self
+                .previews(index)?
+                .iter()
+                .find(|preview| preview.file_name == *name)
+                .map_or_else(
+                    || Response::not_found(path),
+                    |preview| Response {
+                        status: 200,
+                        content_type: content_type(&preview.file_name),
+                        body: preview.bytes.clone(),
+                    },
+                )),
+            _ => Ok(Response::not_found(path)),
+        }
+    }
+
+    /// Answers a `GET` of `path`; failures to convert an entry are `500`s, with the JSON report of
+    /// the error as their body.
+    pub fn respond(&mut self, path: &str) -> Response {
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let segments: Vec<_> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        match segments[..] {
+            [] | ["entries"] => Response::json(200, &self.index()),
+            ["entries", index, ref rest @ ..] => match index.parse() {
+                Ok(index) => self
+                    .entry(index, rest, path)
+                    .unwrap_or_else(|err| Response::json(500, &err.to_json())),
+                Err(_) => Response::not_found(path),
+            },
+            _ => Response::not_found(path),
+        }
+    }
+}
+
+/// Serves previews of `pman` on `addr` (e.g. `127.0.0.1:8080`), until the process is stopped.
+///
+/// # Errors
+///
+/// If the server can't listen on `addr`.
+pub fn serve(pman: PmanFile, addr: impl ToSocketAddrs) -> Result<()> {
+    use tiny_http::{Header, Method, Server};
+
+    let server = Server::http(addr).map_err(|err| Error::Io(io::Error::other(err)))?;
+    let mut previewer = Previewer::new(pman);
+
+    for request in server.incoming_requests() {
+        let response = if matches!(request.method(), Method::Get | Method::Head) {
+            previewer.respond(request.url())
+        } else {
+            Response::json(405, &json!({ "error": "only `GET` is supported" }))
+        };
+        let headers = [
+            ("Content-Type", response.content_type),
+            // so web UIs that are served from somewhere else can fetch it.
+            ("Access-Control-Allow-Origin", "*"),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Header::from_bytes(name.as_bytes(), value.as_bytes()).ok());
+        let mut reply =
+            tiny_http::Response::from_data(response.body).with_status_code(response.status);
+        for header in headers {
+            reply.add_header(header);
+        }
+
+        // the client might be gone already; that is not a failure of the server.
+        _ = request.respond(reply);
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    fn respond_test() -> eyre::Result<()> {
+        let mut previewer = Previewer::new(PmanFile::new(INPUT)?);
+
+        let index = previewer.respond("/entries");
+        let json: Value = serde_json::from_slice(&index.body)?;
+        assert_eq!(index.status, 200);
+        assert_eq!(json["entries"].as_array().map(Vec::len), Some(158));
+        assert_eq!(json["entries"][72]["type"], "texture");
+
+        let entry = previewer.respond("/entries/66");
+        assert_eq!(entry.body.len(), crate::format::palette::PALETTE_SIZE);
+
+        let previews = previewer.respond("/entries/72/previews");
+        let json: Value = serde_json::from_slice(&previews.body)?;
+        assert_eq!(json[0]["url"], "/entries/72/previews/072.png");
+
+        let png = previewer.respond("/entries/72/previews/072.png?raw");
+        assert_eq!(png.content_type, "image/png");
+        assert!(png.body.starts_with(b"\x89PNG"));
+
+        for path in ["/entries/158", "/entries/72/previews/nope.png", "/nope"] {
+            assert_eq!(previewer.respond(path).status, 404, "{path}");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+//! CSV and JSON export (and import) for text banks, so they can be translated in a spreadsheet.
+//!
+//! Both keep the strings in the order of the bank, next to their id, and keep every control
+//! character (e.g. `†`, `|` and `\r`) as it is.
+//!
+//! ```csv
+//! id,text
+//! 0,PRESS START
+//! 1,"Hello,
+//! world"
+//! ```
+//!
+//! ```json
+//! { "strings": [{ "id": 0, "text": "PRESS START" }] }
+//! ```
+
+use crate::{
+    error::{Error, Result},
+    format::text::TextBank,
+};
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+const CSV_HEADER: &str = "id,text";
+
+fn invalid(format: &'static str, reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format,
+        reason: reason.into(),
+    }
+}
+
+/// The format that text banks are converted into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextFormat {
+    /// Comma separated values, with an `id,text` header; see [`bank_to_csv`].
+    #[default]
+    Csv,
+    /// See [`bank_to_json`].
+    Json,
+    /// A gettext PO file; see [`po`](super::po).
+    Po,
+}
+
+impl TextFormat {
+    /// The extension of the files of this format.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Po => "po",
+        }
+    }
+
+    /// The format of the files with `extension`; e.g. `csv`.
+    #[must_use]
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        [Self::Csv, Self::Json, Self::Po]
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Converts `bank` into this format; PO files are templates, see
+    /// [`bank_to_po`](super::po::bank_to_po) to fill them with the english strings.
+    #[must_use]
+    pub fn encode(self, bank: &TextBank) -> Vec<u8> {
+        match self {
+            Self::Csv => bank_to_csv(bank).into_bytes(),
+            // a `Value` always serializes.
+            Self::Json => serde_json::to_vec_pretty(&bank_to_json(bank)).unwrap_or_default(),
+            Self::Po => super::po::bank_to_po(bank, None, None).into_bytes(),
+        }
+    }
+
+    /// Reads the strings of a bank out of this format.
+    ///
+    /// # Errors
+    ///
+    /// See [`bank_from_csv`], [`bank_from_json`] and [`bank_from_po`](super::po::bank_from_po).
+    pub fn decode(self, bytes: &[u8]) -> Result<TextBank> {
+        let utf8 = |format| {
+            std::str::from_utf8(bytes).map_err(|_| invalid(format, "it is not valid UTF-8"))
+        };
+
+        match self {
+            Self::Csv => bank_from_csv(utf8("text CSV")?),
+            Self::Po => super::po::bank_from_po(utf8("PO")?),
+            Self::Json => {
+                let json = serde_json::from_slice(bytes)
+                    .map_err(|err| invalid("text JSON", err.to_string()))?;
+
+                bank_from_json(&json)
+            }
+        }
+    }
+}
+
+/// Quotes `field` if it has a comma, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Converts the strings of `bank` into CSV; lines end with `\n`, and line breaks inside of a
+/// string are kept inside of its quotes.
+#[must_use]
+pub fn bank_to_csv(bank: &TextBank) -> String {
+    let mut csv = format!("{CSV_HEADER}\n");
+
+    for (id, string) in &bank.entries {
+        // writing into a `String` can't fail.
+        _ = writeln!(csv, "{id},{}", csv_field(string));
+    }
+
+    csv
+}
+
+/// Splits `csv` into records, and every record into its fields.
+fn csv_records(csv: &str) -> Result<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut chars = csv.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err(invalid("text CSV", "a quoted field is never closed")),
+                }
+            },
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Reads the strings of [`bank_to_csv`] back, in the order of the rows; the header is optional.
+///
+/// The encoding of the bank is the default one; copy it over from the original bank if it is
+/// different.
+///
+/// # Errors
+///
+/// If a row doesn't have exactly an id and a string, or if an id is not a number.
+pub fn bank_from_csv(csv: &str) -> Result<TextBank> {
+    let mut records = csv_records(csv.strip_prefix('\u{FEFF}').unwrap_or(csv))?;
+    if records
+        .first()
+        .is_some_and(|record| record.join(",") == CSV_HEADER)
+    {
+        records.remove(0);
+    }
+
+    let entries = records
+        .into_iter()
+        .enumerate()
+        .map(|(row, record)| {
+            let [id, string] = <[String; 2]>::try_from(record)
+                .map_err(|_| invalid("text CSV", format!("row {row} doesn't have 2 fields")))?;
+            let id = id
+                .trim()
+                .parse()
+                .map_err(|_| invalid("text CSV", format!("`{id}` is not an id")))?;
+
+            Ok((id, string))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(TextBank {
+        entries,
+        ..TextBank::default()
+    })
+}
+
+/// Converts the strings of `bank` into JSON.
+#[must_use]
+pub fn bank_to_json(bank: &TextBank) -> Value {
+    let strings: Vec<_> = bank
+        .entries
+        .iter()
+        .map(|(id, text)| json!({ "id": id, "text": text }))
+        .collect();
+
+    json!({ "strings": strings })
+}
+
+/// Reads the strings of [`bank_to_json`] back, in the order of the list.
+///
+/// The encoding of the bank is the default one; copy it over from the original bank if it is
+/// different.
+///
+/// # Errors
+///
+/// If there is no `strings` list, or if a string doesn't have an id and a text.
+pub fn bank_from_json(json: &Value) -> Result<TextBank> {
+    let strings = json["strings"]
+        .as_array()
+        .ok_or_else(|| invalid("text JSON", "there is no `strings` list"))?;
+    let entries = strings
+        .iter()
+        .enumerate()
+        .map(|(index, string)| {
+            let id = string["id"].as_u64().and_then(|id| u32::try_from(id).ok());
+            let text = string["text"].as_str();
+
+            id.zip(text)
+                .map(|(id, text)| (id, text.to_owned()))
+                .ok_or_else(|| {
+                    invalid(
+                        "text JSON",
+                        format!("string {index} doesn't have an id and a text"),
+                    )
+                })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(TextBank {
+        entries,
+        ..TextBank::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    /// The text banks; english (twice), french, italian, german and spanish.
+    #[cfg(feature = "packfile")]
+    const TEXT_ENTRIES: [usize; 6] = [152, 153, 154, 155, 156, 157];
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn text_export_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in TEXT_ENTRIES {
+            let bytes = pman[index].to_zlib()?.expect("text banks are compressed.");
+            let bank = TextBank::new(&bytes)?;
+
+            for format in [TextFormat::Csv, TextFormat::Json, TextFormat::Po] {
+                let decoded = format.decode(&format.encode(&bank))?;
+
+                assert_eq!(decoded.to_bytes()?, bytes, "entry {index} as {format:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn text_csv_test() -> eyre::Result<()> {
+        let bank = TextBank {
+            entries: vec![
+                (0, "a, \"b\"".into()),
+                (4, "†c\r\nd|".into()),
+                (2, String::new()),
+            ],
+            ..TextBank::default()
+        };
+        let csv = bank_to_csv(&bank);
+
+        assert_eq!(csv, "id,text\n0,\"a, \"\"b\"\"\"\n4,\"†c\r\nd|\"\n2,\n");
+        assert_eq!(bank_from_csv(&csv)?, bank);
+        // spreadsheets might end rows with `\r\n`, and leave the header out.
+        assert_eq!(
+            bank_from_csv("0,\"a, \"\"b\"\"\"\r\n4,\"†c\r\nd|\"\r\n2,")?,
+            bank
+        );
+
+        for csv in ["0,a,b\n", "zero,a\n", "0,\"a\n"] {
+            assert_eq!(
+                bank_from_csv(csv).unwrap_err().kind(),
+                ErrorKind::InvalidImport
+            );
+        }
+
+        Ok(())
+    }
+}
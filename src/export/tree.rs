@@ -0,0 +1,106 @@
+//! An indented overview of an archive: every entry, what it is, and the chunks nested inside of it;
+//! see [`tree`].
+
+use crate::format::{chunk::Chunk, detect, pman::PmanFile};
+use std::fmt::Write;
+
+/// What the data of `chunk` is, and how big it is; e.g. `texture, 65536 bytes, ZL`.
+fn describe(chunk: &Chunk) -> String {
+    let file_type = detect::detect(&chunk.data)
+        .first()
+        .map(|guess| guess.file_type.extension())
+        .or_else(|| chunk.tag_str())
+        .unwrap_or("unknown");
+    // the banks of a `TSND` have no data of their own.
+    let mut label = match (chunk.data.is_empty(), chunk.children.is_empty()) {
+        (true, false) => "bank".to_owned(),
+        (true, true) => "empty".to_owned(),
+        _ => format!("{file_type}, {} bytes", chunk.data.len()),
+    };
+    if chunk.compression.is_some() {
+        label.push_str(", ZL");
+    }
+
+    label
+}
+
+/// The branch that an item is drawn with, and the indent of the items below it.
+fn branch(last: bool) -> (&'static str, &'static str) {
+    if last {
+        ("└── ", "    ")
+    } else {
+        ("├── ", "│   ")
+    }
+}
+
+/// Writes the children of `chunk`, one per line, after `prefix`.
+fn write_children(out: &mut String, chunk: &Chunk, prefix: &str) {
+    for (index, child) in chunk.children.iter().enumerate() {
+        let (branch, indent) = branch(index + 1 == chunk.children.len());
+
+        _ = writeln!(out, "{prefix}{branch}{index:03}: {}", describe(child));
+        write_children(out, child, &format!("{prefix}{indent}"));
+    }
+}
+
+/// The entries of `pman` as a tree under `name` (e.g. the path of the archive); one line for every
+/// entry, with its offset and what it is, and one for every chunk inside of it (see
+/// [`Chunk::walk`]).
+///
+/// Entries that can't be walked are shown with the error, instead of failing the whole tree.
+#[must_use]
+pub fn tree(pman: &PmanFile, name: &str) -> String {
+    let mut out = format!(
+        "{name} ({} entries, {})\n",
+        pman.files().len(),
+        pman.version()
+    );
+    let mut offset = pman.size_upto_file_data();
+
+    for (index, file) in pman.files().iter().enumerate() {
+        let (branch, indent) = branch(index + 1 == pman.files().len());
+        let line = format!("{branch}{index:03} @ 0x{offset:08X}");
+
+        match Chunk::walk(file.bytes()) {
+            Ok(chunk) => {
+                _ = writeln!(out, "{line}: {}", describe(&chunk));
+                write_children(&mut out, &chunk, indent);
+            }
+            Err(err) => _ = writeln!(out, "{line}: {} bytes, {err}", file.bytes().len()),
+        }
+        offset += file.bytes().len();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn tree_test() -> eyre::Result<()> {
+        let mut files = fixture::files();
+        // a `ZL` chunk whose data is not a zlib stream.
+        files.push(b"ZL\x04\x00\x00data".to_vec());
+        let pman = PmanFile::new(&fixture::pack(fixture::COPYRIGHT, &files))?;
+
+        assert_eq!(
+            tree(&pman, "packfile.dat"),
+            "\
+packfile.dat (6 entries, unknown)
+├── 000 @ 0x000000A0: dat, 257 bytes
+├── 001 @ 0x000001A1: unknown, 191 bytes, ZL
+├── 002 @ 0x00000202: empty
+├── 003 @ 0x00000202: unknown, 382 bytes, ZL
+│   ├── 000: unknown, 191 bytes
+│   └── 001: unknown, 191 bytes
+├── 004 @ 0x000002C4: unknown, 4 bytes, ZL
+└── 005 @ 0x000002D5: 9 bytes, invalid `ZL` data
+"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! RIFF WAVE export for the instruments of the sound archive.
+
+use crate::format::audio::{AudioClip, AudioCodec};
+
+const PCM_FORMAT: u16 = 1;
+/// The MIDI note that plays a sample at its own rate; the middle C.
+const UNITY_NOTE: u32 = 60;
+
+/// Appends a RIFF chunk, padded to an even size.
+fn push_chunk(buf: &mut Vec<u8>, id: [u8; 4], data: &[u8]) {
+    buf.extend_from_slice(&id);
+    buf.extend_from_slice(&u32::try_from(data.len()).unwrap_or(u32::MAX).to_le_bytes());
+    buf.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        buf.push(0);
+    }
+}
+
+/// Encodes `clip` as a WAV file; 8-bit PCM, with its sample rate and channel count.
+///
+/// Looped clips get a `smpl` chunk with their loop, so samplers (and trackers) can keep it.
+#[must_use]
+pub fn clip_to_wav(clip: &AudioClip) -> Vec<u8> {
+    let AudioCodec::Pcm8 = clip.codec();
+    let channels = clip.channels();
+    let rate = clip.sample_rate();
+    let block_align = channels;
+
+    let mut fmt = Vec::with_capacity(16);
+    fmt.extend_from_slice(&PCM_FORMAT.to_le_bytes());
+    fmt.extend_from_slice(&channels.to_le_bytes());
+    fmt.extend_from_slice(&rate.to_le_bytes());
+    fmt.extend_from_slice(&(rate * u32::from(block_align)).to_le_bytes());
+    fmt.extend_from_slice(&block_align.to_le_bytes());
+    fmt.extend_from_slice(&8u16.to_le_bytes());
+
+    // 8-bit WAV samples are unsigned.
+    let data: Vec<_> = clip
+        .samples
+        .iter()
+        .map(|&s| s.cast_unsigned() ^ 0x80)
+        .collect();
+
+    let mut body = b"WAVE".to_vec();
+    push_chunk(&mut body, *b"fmt ", &fmt);
+    push_chunk(&mut body, *b"data", &data);
+
+    if clip.is_looped() {
+        // the loop of a clip counts half samples.
+        let (start, end) = (clip.loop_start / 2, (clip.loop_end / 2).saturating_sub(1));
+        let mut smpl = Vec::with_capacity(60);
+
+        for field in [0, 0, 1_000_000_000 / rate.max(1), UNITY_NOTE, 0, 0, 0, 1, 0] {
+            smpl.extend_from_slice(&u32::to_le_bytes(field));
+        }
+        // the cue point id, the loop type (forward), its bounds, its fraction and its play count.
+        for field in [0, 0, start, end, 0, 0] {
+            smpl.extend_from_slice(&u32::to_le_bytes(field));
+        }
+
+        push_chunk(&mut body, *b"smpl", &smpl);
+    }
+
+    let mut wav = Vec::with_capacity(8 + body.len());
+    push_chunk(&mut wav, *b"RIFF", &body);
+
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::audio::LOOP_FLAG;
+    #[cfg(feature = "packfile")]
+    use crate::{export, format::pman::PmanFile};
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    fn u32_at(buf: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap_or_default())
+    }
+
+    #[test]
+    fn clip_to_wav_test() {
+        let mut clip = AudioClip {
+            samples: vec![0, -128, 127],
+            ..Default::default()
+        };
+        let wav = clip_to_wav(&clip);
+
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(u32_at(&wav, 4) as usize, wav.len() - 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u32_at(&wav, 24), clip.sample_rate());
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(wav[44..47], [0x80, 0x00, 0xFF]);
+        // the odd sized data chunk is padded.
+        assert_eq!(wav.len(), 48);
+
+        clip.flags |= LOOP_FLAG;
+        clip.loop_start = 2;
+        clip.loop_end = 6;
+        let wav = clip_to_wav(&clip);
+
+        assert_eq!(&wav[48..52], b"smpl");
+        assert_eq!((u32_at(&wav, 100), u32_at(&wav, 104)), (1, 2));
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn convert_sound_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let converted = export::convert(&pman, 151, "sound", export::Options::default())?;
+
+        assert_eq!(converted.len(), 239);
+        assert_eq!(converted[0].file_name, "sound_000.wav");
+        assert!(converted.iter().all(|wav| wav.bytes.starts_with(b"RIFF")));
+
+        Ok(())
+    }
+}
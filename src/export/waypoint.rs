@@ -0,0 +1,332 @@
+//! JSON export (and import) for waypoint graphs.
+//!
+//! ```json
+//! {
+//!   "nodes": [{
+//!     "id": 0,
+//!     "position": [5.0, 8.9375, -0.21484375],
+//!     "fixed": [327680, ...],
+//!     "flags": 0
+//!   }],
+//!   "edges": [{ "from": 0, "to": 39 }],
+//!   "routes": [[[1, 39], [2, 39], ...]],
+//!   "cells": ["0001...", ...]
+//! }
+//! ```
+//!
+//! `fixed` is the exact 16.16 position, so it wins over `position` on import; remove it after
+//! moving a node. `routes` can be removed too, after editing `edges`; they are rebuilt from them.
+
+use crate::{
+    error::{Error, Result},
+    format::waypoint::{Route, Waypoint, WaypointGraph},
+};
+use serde_json::{json, Value};
+use std::{collections::VecDeque, fmt::Write};
+
+const FORMAT: &str = "waypoint JSON";
+/// The scale of the 16.16 fixed point positions.
+const POSITION_SCALE: f32 = 65_536.0;
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format: FORMAT,
+        reason: reason.into(),
+    }
+}
+
+/// Converts `graph` into JSON.
+#[must_use]
+pub fn graph_to_json(graph: &WaypointGraph) -> Value {
+    let nodes: Vec<_> = graph
+        .waypoints
+        .iter()
+        .enumerate()
+        .map(|(id, waypoint)| {
+            json!({
+                "id": id,
+                "position": waypoint.position_f32(),
+                "fixed": waypoint.position,
+                "flags": waypoint.flags,
+            })
+        })
+        .collect();
+    let edges: Vec<_> = (0..graph.waypoints.len())
+        .flat_map(|from| {
+            graph
+                .links(from)
+                .map(move |to| json!({ "from": from, "to": to }))
+        })
+        .collect();
+    let routes: Vec<_> = graph
+        .routes
+        .iter()
+        .map(|routes| {
+            routes
+                .iter()
+                .map(|route| json!([route.target, route.next]))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let cells: Vec<_> = graph
+        .cells
+        .iter()
+        .map(|cell| {
+            cell.iter().fold(String::new(), |mut hex, byte| {
+                // writing into a `String` can't fail.
+                _ = write!(hex, "{byte:02x}");
+                hex
+            })
+        })
+        .collect();
+
+    json!({ "nodes": nodes, "edges": edges, "routes": routes, "cells": cells })
+}
+
+fn as_u64(value: &Value, what: &str) -> Result<u64> {
+    value
+        .as_u64()
+        .ok_or_else(|| invalid(format!("{what} is not a positive integer")))
+}
+
+fn read_node(node: &Value, index: usize) -> Result<Waypoint> {
+    let position = if let Some(fixed) = node["fixed"].as_array() {
+        fixed
+            .iter()
+            .map(|x| x.as_i64().and_then(|x| i32::try_from(x).ok()))
+            .collect::<Option<Vec<_>>>()
+    } else {
+        #[allow(clippy::cast_possible_truncation)]
+        node["position"].as_array().and_then(|position| {
+            position
+                .iter()
+                .map(|x| {
+                    x.as_f64()
+                        .map(|x| (x as f32 * POSITION_SCALE).round() as i32)
+                })
+                .collect()
+        })
+    };
+    let position: [i32; 3] = position
+        .and_then(|position| position.try_into().ok())
+        .ok_or_else(|| invalid(format!("node {index} doesn't have a 3D position")))?;
+    let flags = match &node["flags"] {
+        Value::Null => 0,
+        flags => u32::try_from(as_u64(flags, "a flag")?)
+            .map_err(|_| invalid(format!("the flags of node {index} don't fit on an `u32`")))?,
+    };
+
+    Ok(Waypoint { position, flags })
+}
+
+fn read_u16(value: &Value, what: &str) -> Result<u16> {
+    u16::try_from(as_u64(value, what)?)
+        .map_err(|_| invalid(format!("{what} doesn't fit on an `u16`")))
+}
+
+/// The next hop from every waypoint to every other waypoint that it can reach, by the fewest
+/// links.
+fn build_routes(count: usize, edges: &[(u16, u16)]) -> Vec<Vec<Route>> {
+    let mut links = vec![Vec::new(); count];
+    for &(from, to) in edges {
+        links[usize::from(from)].push(to);
+    }
+
+    (0..count)
+        .map(|start| {
+            // the first hop that reached every waypoint.
+            let mut first_hop: Vec<Option<u16>> = vec![None; count];
+            let mut queue = VecDeque::from([start]);
+
+            while let Some(current) = queue.pop_front() {
+                for &next in &links[current] {
+                    let next_index = usize::from(next);
+
+                    if next_index != start && first_hop[next_index].is_none() {
+                        first_hop[next_index] = Some(first_hop[current].unwrap_or(next));
+                        queue.push_back(next_index);
+                    }
+                }
+            }
+
+            first_hop
+                .into_iter()
+                .zip(0u16..)
+                .filter_map(|(next, target)| {
+                    Some(Route {
+                        target,
+                        next: next?,
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Builds a waypoint graph out of the JSON of [`graph_to_json`].
+///
+/// # Errors
+///
+/// If a field is missing or out of range, if an edge points to a node that doesn't exist, or if
+/// there are more than `u16::MAX` nodes.
+pub fn graph_from_json(json: &Value) -> Result<WaypointGraph> {
+    let nodes = json["nodes"]
+        .as_array()
+        .ok_or_else(|| invalid("there is no `nodes` list"))?;
+    if nodes.len() > usize::from(u16::MAX) {
+        return Err(invalid(format!("there are {} nodes", nodes.len())));
+    }
+
+    let waypoints = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| read_node(node, index))
+        .collect::<Result<Vec<_>>>()?;
+
+    let routes = if let Some(routes) = json["routes"].as_array() {
+        if routes.len() != waypoints.len() {
+            return Err(invalid("there must be a list of routes for every node"));
+        }
+
+        routes
+            .iter()
+            .map(|routes| {
+                routes
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|route| {
+                        Ok(Route {
+                            target: read_u16(&route[0], "a route target")?,
+                            next: read_u16(&route[1], "a route hop")?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        let edges = json["edges"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|edge| {
+                Ok((
+                    read_u16(&edge["from"], "`from`")?,
+                    read_u16(&edge["to"], "`to`")?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some((from, to)) = edges
+            .iter()
+            .find(|(from, to)| usize::from(*from.max(to)) >= waypoints.len())
+        {
+            return Err(invalid(format!(
+                "the edge from {from} to {to} points out of the nodes"
+            )));
+        }
+
+        build_routes(waypoints.len(), &edges)
+    };
+
+    let cells = json["cells"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|cell| {
+            let hex = cell.as_str().unwrap_or_default();
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|i| {
+                    hex.get(i..i + 2)
+                        .and_then(|x| u8::from_str_radix(x, 16).ok())
+                })
+                .collect::<Option<Vec<_>>>();
+
+            bytes
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| invalid(format!("`{hex}` is not a cell")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(WaypointGraph {
+        waypoints,
+        routes,
+        cells,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[cfg(feature = "packfile")]
+    fn graph() -> eyre::Result<WaypointGraph> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = pman[78].to_zlib()?.expect("`TWPT` entries are compressed.");
+
+        Ok(WaypointGraph::new(&bytes)?)
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn waypoint_json_round_trip_test() -> eyre::Result<()> {
+        let graph = graph()?;
+        let json = graph_to_json(&graph);
+
+        assert_eq!(json["nodes"].as_array().map(Vec::len), Some(85));
+        assert_eq!(
+            json["nodes"][0]["fixed"],
+            json!(graph.waypoints[0].position)
+        );
+        assert_eq!(graph_from_json(&json)?, graph);
+
+        Ok(())
+    }
+
+    #[test]
+    fn waypoint_json_edges_test() -> eyre::Result<()> {
+        // a line, `0 - 1 - 2`.
+        let json = json!({
+            "nodes": [
+                { "position": [0.0, 0.0, 0.0] },
+                { "position": [1.0, 0.0, 0.0] },
+                { "position": [2.5, 0.0, 0.0] },
+            ],
+            "edges": [
+                { "from": 0, "to": 1 }, { "from": 1, "to": 0 },
+                { "from": 1, "to": 2 }, { "from": 2, "to": 1 },
+            ],
+        });
+        let graph = graph_from_json(&json)?;
+        let route = |target, next| Route { target, next };
+
+        assert_eq!(graph.waypoints[2].position, [0x2_8000, 0, 0]);
+        assert_eq!(graph.routes[0], [route(1, 1), route(2, 1)]);
+        assert_eq!(graph.routes[1], [route(0, 0), route(2, 2)]);
+        assert_eq!(graph.links(1).collect::<Vec<_>>(), [0, 2]);
+
+        let json = json!({ "nodes": [{}], "edges": [] });
+        assert_eq!(
+            graph_from_json(&json).unwrap_err().kind(),
+            ErrorKind::InvalidImport
+        );
+
+        let json =
+            json!({ "nodes": [{ "position": [0, 0, 0] }], "edges": [{ "from": 0, "to": 1 }] });
+        assert_eq!(
+            graph_from_json(&json).unwrap_err().kind(),
+            ErrorKind::InvalidImport
+        );
+
+        Ok(())
+    }
+}
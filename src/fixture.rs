@@ -0,0 +1,83 @@
+//! Small archives that are built on the fly, so tests don't need the `packfile.dat` of the game;
+//! the tests that do are behind the `packfile` feature.
+
+use crate::format::pman;
+
+/// The copyright notice of the `1.0.6` release.
+pub const COPYRIGHT: &str = "Copyright (c) 2004 Torus Games Pty. Ltd.";
+
+/// Data that compresses well, for the `ZL` entries.
+pub const TEXT: &[u8] = b"Ashen is a first-person shooter for the N-Gage; this line is repeated, \
+    so it can be compressed. Ashen is a first-person shooter for the N-Gage; this line is \
+    repeated, so it can be compressed.";
+
+/// Raw bytes; their size is not aligned, so the entry after them is preceded by padding.
+pub const RAW: usize = 0;
+/// [`TEXT`], `ZL` compressed.
+pub const ZLIB: usize = 1;
+/// An entry without data.
+pub const EMPTY: usize = 2;
+/// [`TEXT`] twice, as two `ZL` chunks.
+pub const CHUNKS: usize = 3;
+/// `ZL` compressed too; the archive ends right after it.
+pub const LAST: usize = 4;
+/// How many entries [`archive`] has.
+pub const FILE_COUNT: usize = 5;
+
+/// The data of every entry of [`archive`], by index.
+#[must_use]
+pub fn files() -> Vec<Vec<u8>> {
+    let zlib = |bytes: &[u8]| pman::compress(bytes).expect("the data can be compressed.");
+
+    vec![
+        (0..=u8::MAX).chain([0]).collect(),
+        zlib(TEXT),
+        vec![],
+        [zlib(TEXT), zlib(TEXT)].concat(),
+        zlib(b"last"),
+    ]
+}
+
+/// A valid archive with the entries of [`files`], and the copyright notice of the `1.0.6` release.
+#[must_use]
+pub fn archive() -> Vec<u8> {
+    pack(COPYRIGHT, &files())
+}
+
+/// How many bytes of padding [`pack`] puts after `files`; the last one is not padded.
+#[must_use]
+pub fn padding(files: &[Vec<u8>]) -> usize {
+    files
+        .iter()
+        .rev()
+        .skip(1)
+        .map(|file| file.len().next_multiple_of(4) - file.len())
+        .sum()
+}
+
+/// Packs `files` like the game does: every entry but the last is padded with zeros, so the next one
+/// is aligned to 4 bytes.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn pack(copyright: &str, files: &[Vec<u8>]) -> Vec<u8> {
+    let mut header = b"PMAN".to_vec();
+    header.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    header.extend_from_slice(copyright.as_bytes());
+    header.resize(0x40, 0);
+
+    let mut table = vec![];
+    let mut data = vec![];
+    let data_start = header.len() + files.len() * 0x10;
+    for (index, file) in files.iter().enumerate() {
+        for value in [0, data_start + data.len(), file.len(), 0] {
+            table.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+
+        data.extend_from_slice(file);
+        if index + 1 < files.len() {
+            data.resize(data.len().next_multiple_of(4), 0);
+        }
+    }
+
+    [header, table, data].concat()
+}
@@ -0,0 +1,190 @@
+use super::{
+    model::{Frame, Model},
+    Result,
+};
+use crate::error::{Error, ErrorKind};
+use nom::{multi::count, number::complete::le_u32, Parser};
+use nom_supreme::ParserExt;
+use std::ops::Range;
+
+/// The size of a row of the animation table; the frame count, and the offset of the frames.
+pub(super) const ANIMATION_SIZE: usize = 8;
+
+/// An animation of a [`Model`]; the keyframes that it plays, in order.
+///
+/// Models are animated by swapping whole frames, so there are no bones; every keyframe is a
+/// complete pose. Keyframes have no duration either, the game plays them at a fixed rate.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Animation {
+    /// Indices into [`Model::frames`].
+    pub frames: Vec<u32>,
+}
+
+impl Animation {
+    /// The frames of this animation, if they are a contiguous run of [`Model::frames`]; they are on
+    /// every model of the `1.0.6` release.
+    #[must_use]
+    pub fn frame_range(&self) -> Option<Range<u32>> {
+        let (&first, &last) = (self.frames.first()?, self.frames.last()?);
+        let contiguous = self
+            .frames
+            .windows(2)
+            .all(|w| w[0].checked_add(1) == Some(w[1]));
+
+        contiguous.then(|| first..last + 1)
+    }
+
+    /// The poses of this animation, in the order they are played.
+    ///
+    /// Frames that are not on `model` are skipped.
+    pub fn keyframes<'a>(&'a self, model: &'a Model) -> impl Iterator<Item = &'a Frame> + 'a {
+        self.frames
+            .iter()
+            .filter_map(|&frame| model.frames.get(frame as usize))
+    }
+
+    /// The weights of the `target_count` morph targets of a model (see [`Model::morph_targets`])
+    /// at every keyframe, in the order they are played; `1.0` for the frame of the keyframe, and
+    /// `0.0` for the rest.
+    pub fn morph_weights(&self, target_count: usize) -> impl Iterator<Item = Vec<f32>> + '_ {
+        self.frames.iter().map(move |&frame| {
+            (0..target_count)
+                .map(|target| f32::from(u8::from(target == frame as usize)))
+                .collect()
+        })
+    }
+}
+
+/// Reads the animation table at `offset` (from the start of the model), and the frames of every
+/// animation, which come right after it.
+pub(super) fn read_animations(
+    input: &[u8],
+    offset: usize,
+    animation_count: usize,
+    frame_count: usize,
+) -> Result<'_, Vec<Animation>> {
+    let (mut input, table) = count(le_u32.and(le_u32), animation_count)
+        .context("animation table")
+        .parse(input)?;
+    let mut offset = offset + table.len() * ANIMATION_SIZE;
+    let mut animations = Vec::with_capacity(table.len());
+
+    for (index, (len, start)) in table.into_iter().enumerate() {
+        if start as usize != offset {
+            return super::fail(
+                input,
+                ErrorKind::InvalidFormat,
+                format!("the frames of animation {index} are not at {offset:#X}"),
+                0,
+            );
+        }
+
+        let (rest, frames) = count(le_u32, len as usize)
+            .context("animation")
+            .parse(input)?;
+
+        if let Some(frame) = frames.iter().find(|&&frame| frame as usize >= frame_count) {
+            return super::fail(
+                input,
+                ErrorKind::InvalidFormat,
+                format!("animation {index} plays frame {frame}, but there are {frame_count}"),
+                frames.len() * 4,
+            );
+        }
+
+        offset += frames.len() * 4;
+        animations.push(Animation { frames });
+        input = rest;
+    }
+
+    Ok((input, animations))
+}
+
+/// Writes the animation table, that will be at `offset` (from the start of the model), and the
+/// frames of every animation.
+///
+/// # Errors
+///
+/// If an animation plays a frame that is not one of the `frame_count` frames of its model, or if
+/// an offset doesn't fit on an `u32`.
+pub(super) fn write_animations(
+    buf: &mut Vec<u8>,
+    animations: &[Animation],
+    offset: usize,
+    frame_count: usize,
+) -> crate::error::Result<()> {
+    let mut start = offset + animations.len() * ANIMATION_SIZE;
+
+    for (index, animation) in animations.iter().enumerate() {
+        if let Some(frame) = animation
+            .frames
+            .iter()
+            .find(|&&frame| frame as usize >= frame_count)
+        {
+            return Err(Error::InvalidModel {
+                reason: format!(
+                    "animation {index} plays frame {frame}, but there are {frame_count}"
+                ),
+            });
+        }
+
+        let len = super::count_u32("animation frames", animation.frames.len())?;
+        let start_u32 = u32::try_from(start).map_err(|_| Error::ArchiveTooLarge { size: start })?;
+
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(&start_u32.to_le_bytes());
+        start += animation.frames.len() * 4;
+    }
+
+    for frame in animations.iter().flat_map(|a| &a.frames) {
+        buf.extend_from_slice(&frame.to_le_bytes());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animation_frame_range_test() {
+        let animation = |frames: &[u32]| Animation {
+            frames: frames.to_vec(),
+        };
+
+        assert_eq!(animation(&[4, 5, 6]).frame_range(), Some(4..7));
+        assert_eq!(animation(&[0]).frame_range(), Some(0..1));
+        assert_eq!(animation(&[1, 0]).frame_range(), None);
+        assert_eq!(animation(&[]).frame_range(), None);
+    }
+
+    #[test]
+    fn animation_keyframes_test() {
+        let model = Model {
+            frames: vec![
+                Frame::default(),
+                Frame {
+                    unknown: 1,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let animation = Animation {
+            frames: vec![1, 0, 7],
+        };
+        let unknowns = animation.keyframes(&model).map(|f| f.unknown);
+
+        assert_eq!(unknowns.collect::<Vec<_>>(), [1, 0]);
+    }
+
+    #[test]
+    fn animation_morph_weights_test() {
+        let animation = Animation { frames: vec![2, 0] };
+        let weights: Vec<_> = animation.morph_weights(3).collect();
+
+        assert_eq!(weights, [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0]]);
+    }
+}
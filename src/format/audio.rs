@@ -0,0 +1,474 @@
+use super::Result;
+use crate::error::ErrorKind;
+use nom::{
+    bytes::complete::take,
+    combinator::{eof, rest, verify},
+    multi::count,
+    number::complete::{le_i32, le_u16, le_u32},
+    sequence::terminated,
+    Parser,
+};
+use nom_supreme::{tag::complete::tag, ParserExt};
+
+const HEADER_MAGIC_STRING: &[u8; 4] = b"TSND";
+pub const BANK_COUNT: usize = 4;
+const HEADER_SIZE: usize = 4 + BANK_COUNT * ROW_SIZE;
+/// The size of a row of the header, and of the table of a bank; `offset`, `size` and a zeroed
+/// `u32`.
+const ROW_SIZE: usize = 12;
+const DATA_ALIGNMENT: usize = 4;
+
+/// The offsets of the sections of an [`AudioClip`], which never change.
+const INSTRUMENT_OFFSET: u32 = 16;
+const SAMPLE_HEADER_OFFSET: u32 = 792;
+const SAMPLES_OFFSET: u32 = 812;
+pub const INSTRUMENT_SIZE: usize = (SAMPLE_HEADER_OFFSET - INSTRUMENT_OFFSET) as usize;
+/// Set on [`AudioClip::flags`] if the clip loops.
+pub const LOOP_FLAG: u16 = 1;
+// TODO(Unavailable): the real rate depends on the note that plays the clip; this one just sounds
+// right for most sound effects.
+pub const SAMPLE_RATE: u32 = 8_000;
+
+/// The `TSND` entry of the archive; every song and sound effect of the game.
+///
+/// Entries are kept as they are, compressed or not; see [`Self::banks`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SoundArchive {
+    /// In order:
+    ///
+    /// - The songs; `ZL` compressed tracker modules.
+    /// - The instruments; `ZL` compressed [`AudioClip`]s.
+    /// - Small tables of offsets; maybe the sequences of the sound effects.
+    /// - Always empty.
+    // TODO(Unavailable): parse the songs, and figure out what the third bank is.
+    pub banks: [Vec<Vec<u8>>; BANK_COUNT],
+    /// The bytes after the last bank; always zeroed.
+    pub trailing_bytes: Vec<u8>,
+}
+
+/// Where the parser is at, from the start of the `TSND` entry.
+fn position(start: &[u8], input: &[u8]) -> usize {
+    start.len() - input.len()
+}
+
+/// Reads an `(offset, size)` row, and the zeroed `u32` that follows it.
+fn read_row(input: &[u8]) -> Result<'_, (usize, usize)> {
+    let (input, (offset, size)) = terminated(le_u32.and(le_u32), super::u32_zero)(input)?;
+
+    Ok((input, (offset as usize, size as usize)))
+}
+
+/// Skips the bytes up to `offset`, which can't be more than the data alignment.
+fn skip_to<'a>(start: &'a [u8], input: &'a [u8], offset: usize) -> Result<'a, ()> {
+    let padding = offset.checked_sub(position(start, input));
+
+    match padding {
+        // the `1.0.6` release has garbage, rather than zeros, on the padding.
+        Some(padding) if padding < DATA_ALIGNMENT => {
+            let (input, _) = take(padding).context("padding").parse(input)?;
+            Ok((input, ()))
+        }
+        _ => super::fail(
+            input,
+            ErrorKind::InvalidFormat,
+            format!("{offset:#X} is not right after the previous data"),
+            0,
+        ),
+    }
+}
+
+fn read_bank<'a>(start: &'a [u8], input: &'a [u8], size: usize) -> Result<'a, Vec<Vec<u8>>> {
+    let (input, entry_count) = le_u32.context("entry count").parse(input)?;
+    let (mut input, rows) = count(read_row, entry_count as usize)
+        .context("entry table")
+        .parse(input)?;
+
+    if rows.len() * ROW_SIZE + 4 != size {
+        return super::fail(
+            input,
+            ErrorKind::InvalidFormat,
+            format!("a bank with {entry_count} entries can't be {size:#X} bytes long"),
+            0,
+        );
+    }
+
+    let mut entries = Vec::with_capacity(rows.len());
+    for (offset, size) in rows {
+        let (rest, ()) = skip_to(start, input, offset)?;
+        let (rest, entry) = take(size).context("entry").parse(rest)?;
+
+        entries.push(entry.to_vec());
+        input = rest;
+    }
+
+    Ok((input, entries))
+}
+
+fn read_tsnd(start: &[u8]) -> Result<'_, SoundArchive> {
+    let (input, _) = super::with_kind(ErrorKind::BadMagic, tag(&HEADER_MAGIC_STRING[..]))
+        .context("magic string")
+        .parse(start)?;
+    let (mut input, rows) = count(read_row, BANK_COUNT).context("header").parse(input)?;
+    let mut banks: [Vec<Vec<u8>>; BANK_COUNT] = Default::default();
+
+    for (bank, (offset, size)) in banks.iter_mut().zip(rows) {
+        let (rest, ()) = skip_to(start, input, offset)?;
+        let (rest, entries) = (|i| read_bank(start, i, size))
+            .context("bank")
+            .parse(rest)?;
+
+        *bank = entries;
+        input = rest;
+    }
+
+    let (input, trailing_bytes) = verify(rest, |b: &[u8]| b.iter().all(|&b| b == 0))
+        .context("trailing bytes")
+        .parse(input)?;
+
+    Ok((
+        input,
+        SoundArchive {
+            banks,
+            trailing_bytes: trailing_bytes.to_vec(),
+        },
+    ))
+}
+
+impl SoundArchive {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid `TSND` entry.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "TSND", read_tsnd)
+    }
+
+    /// If `bytes` looks like a `TSND` entry.
+    #[must_use]
+    pub fn is_tsnd(bytes: &[u8]) -> bool {
+        bytes.starts_with(HEADER_MAGIC_STRING)
+    }
+
+    /// Encodes the archive back; the padding between entries is zeroed.
+    ///
+    /// # Errors
+    ///
+    /// If there are more than `u32::MAX` entries on a bank, or if an offset doesn't fit on an
+    /// `u32`.
+    pub fn to_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        let align = |buf: &mut Vec<u8>| buf.resize(buf.len().next_multiple_of(DATA_ALIGNMENT), 0);
+        let offset = |x: usize| {
+            u32::try_from(x).map_err(|_| crate::error::Error::ArchiveTooLarge { size: x })
+        };
+        let mut buf = vec![0; HEADER_SIZE];
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+
+        header.extend_from_slice(HEADER_MAGIC_STRING);
+        for entries in &self.banks {
+            align(&mut buf);
+
+            let table = buf.len();
+            let size = 4 + entries.len() * ROW_SIZE;
+            header.extend_from_slice(&offset(table)?.to_le_bytes());
+            header.extend_from_slice(&offset(size)?.to_le_bytes());
+            header.extend_from_slice(&0u32.to_le_bytes());
+
+            buf.extend_from_slice(&super::count_u32("entries", entries.len())?.to_le_bytes());
+            buf.resize(table + size, 0);
+
+            for (index, entry) in entries.iter().enumerate() {
+                align(&mut buf);
+
+                let row = table + 4 + index * ROW_SIZE;
+                let entry_offset = offset(buf.len())?.to_le_bytes();
+                let entry_size = offset(entry.len())?.to_le_bytes();
+                buf[row..row + 4].copy_from_slice(&entry_offset);
+                buf[row + 4..row + 8].copy_from_slice(&entry_size);
+
+                buf.extend_from_slice(entry);
+            }
+        }
+
+        buf[..HEADER_SIZE].copy_from_slice(&header);
+        buf.extend_from_slice(&self.trailing_bytes);
+
+        Ok(buf)
+    }
+}
+
+/// How the samples of an [`AudioClip`] are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AudioCodec {
+    /// Signed 8-bit PCM; the only one used by the `1.0.6` release.
+    #[default]
+    Pcm8,
+}
+
+/// An instrument of the [`SoundArchive`]; a single mono clip of 8-bit PCM samples.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioClip {
+    // TODO(Unavailable): it looks like a `xm` instrument (note map and envelopes), but it is always
+    // zeroed, except for a single byte.
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_array::boxed"))]
+    pub instrument: Box<[u8; INSTRUMENT_SIZE]>,
+    /// See [`LOOP_FLAG`].
+    // TODO(Unavailable): `0x4000` is always set.
+    pub flags: u16,
+    /// Always `128` on the `1.0.6` release.
+    pub volume: u16,
+    // TODO(Unavailable): it is either `-98` or `1438`; a relative note, or the finetune?
+    pub pitch: i32,
+    /// Where the loop starts and ends; it seems to count half samples.
+    pub loop_start: u32,
+    pub loop_end: u32,
+    pub samples: Vec<i8>,
+}
+
+fn read_clip(input: &[u8]) -> Result<'_, AudioClip> {
+    let offset = |value| verify(le_u32, move |x| *x == value);
+    let (input, _) = offset(INSTRUMENT_OFFSET)
+        .and(offset(SAMPLE_HEADER_OFFSET))
+        .and(offset(SAMPLES_OFFSET))
+        .and(super::u32_zero)
+        .context("header")
+        .parse(input)?;
+    let (input, instrument) = take(INSTRUMENT_SIZE).context("instrument").parse(input)?;
+    let (input, (((flags, volume), pitch), (loop_start, loop_end))) = le_u16
+        .and(le_u16)
+        .and(le_i32)
+        .and(le_u32.and(le_u32))
+        .context("sample header")
+        .parse(input)?;
+    let (input, _) = super::u32_zero.context("sample header").parse(input)?;
+    let (input, samples) = rest(input)?;
+    let (input, _) = eof(input)?;
+
+    let mut buf = Box::new([0; INSTRUMENT_SIZE]);
+    buf.copy_from_slice(instrument);
+
+    Ok((
+        input,
+        AudioClip {
+            instrument: buf,
+            flags,
+            volume,
+            pitch,
+            loop_start,
+            loop_end,
+            samples: samples.iter().map(|&s| s.cast_signed()).collect(),
+        },
+    ))
+}
+
+impl AudioClip {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid (decompressed) entry of the instrument bank.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "audio clip", read_clip)
+    }
+
+    #[must_use]
+    pub fn codec(&self) -> AudioCodec {
+        AudioCodec::Pcm8
+    }
+
+    #[must_use]
+    pub fn channels(&self) -> u16 {
+        1
+    }
+
+    /// See [`SAMPLE_RATE`].
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    #[must_use]
+    pub fn is_looped(&self) -> bool {
+        self.flags & LOOP_FLAG != 0
+    }
+
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(SAMPLES_OFFSET as usize + self.samples.len());
+
+        for x in [INSTRUMENT_OFFSET, SAMPLE_HEADER_OFFSET, SAMPLES_OFFSET, 0] {
+            buf.extend_from_slice(&x.to_le_bytes());
+        }
+        buf.extend_from_slice(&self.instrument[..]);
+        buf.extend_from_slice(&self.flags.to_le_bytes());
+        buf.extend_from_slice(&self.volume.to_le_bytes());
+        buf.extend_from_slice(&self.pitch.to_le_bytes());
+        buf.extend_from_slice(&self.loop_start.to_le_bytes());
+        buf.extend_from_slice(&self.loop_end.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend(self.samples.iter().map(|&s| s.cast_unsigned()));
+
+        buf
+    }
+}
+
+impl Default for AudioClip {
+    fn default() -> Self {
+        Self {
+            instrument: Box::new([0; INSTRUMENT_SIZE]),
+            flags: 0x4000,
+            volume: 128,
+            pitch: 0,
+            loop_start: 0,
+            loop_end: 0,
+            samples: Vec::new(),
+        }
+    }
+}
+
+/// The layout of the `TSND` entry; see [`super::schema`].
+pub(super) fn schema() -> super::schema::Format {
+    use super::schema::{Field, Format, Kind, Size, TypeDef};
+
+    // a bank of the header, or an entry of a bank; offsets are from the start of the `TSND`.
+    let row = |id, data: Field| TypeDef {
+        id,
+        doc: None,
+        fields: vec![
+            Field::primitive("offset", "u4"),
+            Field::primitive("size", "u4"),
+            Field::magic("reserved", &[0; 4]),
+            data.at("offset").io("_root._io"),
+        ],
+    };
+
+    Format {
+        id: "tsnd",
+        title: "Ashen sound archive",
+        imports: vec![],
+        root: TypeDef {
+            id: "tsnd",
+            doc: Some("Banks of songs, instruments, tables and nothing; in that order.".into()),
+            fields: vec![
+                Field::magic("magic", HEADER_MAGIC_STRING),
+                Field::new("banks", Kind::Type("bank_row")).repeat(Size::Fixed(BANK_COUNT)),
+            ],
+        },
+        types: vec![
+            row(
+                "bank_row",
+                Field::new("body", Kind::Type("bank")).sized(Size::Expr("size".into())),
+            ),
+            TypeDef {
+                id: "bank",
+                doc: None,
+                fields: vec![
+                    Field::primitive("entry_count", "u4"),
+                    Field::new("entries", Kind::Type("entry_row"))
+                        .repeat(Size::Expr("entry_count".into())),
+                ],
+            },
+            row(
+                "entry_row",
+                Field::new("data", Kind::Bytes(Size::Expr("size".into())))
+                    .doc("`ZL` compressed, on the first two banks."),
+            ),
+        ],
+    }
+}
+
+/// The layout of an instrument of the `TSND` entry; see [`super::schema`].
+pub(super) fn clip_schema() -> super::schema::Format {
+    use super::schema::{Field, Format, Kind, Size, TypeDef};
+
+    Format {
+        id: "audio_clip",
+        title: "Ashen audio clip",
+        imports: vec![],
+        root: TypeDef {
+            id: "audio_clip",
+            doc: None,
+            fields: vec![
+                Field::magic("instrument_offset", &INSTRUMENT_OFFSET.to_le_bytes()),
+                Field::magic("sample_header_offset", &SAMPLE_HEADER_OFFSET.to_le_bytes()),
+                Field::magic("samples_offset", &SAMPLES_OFFSET.to_le_bytes()),
+                Field::magic("reserved", &[0; 4]),
+                Field::new("instrument", Kind::Bytes(Size::Fixed(INSTRUMENT_SIZE))),
+                Field::primitive("flags", "u2")
+                    .doc(format!("The clip loops if {LOOP_FLAG:#X} is set.")),
+                Field::primitive("volume", "u2"),
+                Field::primitive("pitch", "s4"),
+                Field::primitive("loop_start", "u4"),
+                Field::primitive("loop_end", "u4"),
+                Field::magic("reserved_2", &[0; 4]),
+                Field::new("samples", Kind::Bytes(Size::Eos)).doc("Signed 8-bit mono PCM."),
+            ],
+        },
+        types: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::{self, PmanFile};
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    #[cfg(feature = "packfile")]
+    const TSND_ENTRY: usize = 151;
+
+    #[cfg(feature = "packfile")]
+    fn tsnd() -> eyre::Result<SoundArchive> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = pman[TSND_ENTRY].bytes();
+
+        assert!(SoundArchive::is_tsnd(bytes));
+
+        Ok(SoundArchive::new(bytes)?)
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn tsnd_new_test() -> eyre::Result<()> {
+        let tsnd = tsnd()?;
+
+        assert_eq!(tsnd.banks.each_ref().map(Vec::len), [13, 239, 42, 0]);
+        assert_eq!(SoundArchive::new(&tsnd.to_bytes()?)?, tsnd);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn audio_clip_round_trip_test() -> eyre::Result<()> {
+        let tsnd = tsnd()?;
+
+        for (index, entry) in tsnd.banks[1].iter().enumerate() {
+            let bytes = pman::decompress(entry)?.expect("instruments are compressed.");
+            let clip = AudioClip::new(&bytes)?;
+
+            assert_eq!(clip.to_bytes(), bytes, "instrument {index}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn audio_clip_invalid_test() -> eyre::Result<()> {
+        let clip = AudioClip {
+            samples: vec![0, -1, 127],
+            ..Default::default()
+        };
+        let bytes = clip.to_bytes();
+
+        assert_eq!(AudioClip::new(&bytes)?, clip);
+        assert!(!clip.is_looped());
+        assert_eq!(
+            AudioClip::new(&bytes[..100]).unwrap_err().kind(),
+            ErrorKind::InvalidFormat
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+//! Decompressed entries that are kept around, up to a budget of bytes; see [`ZlibCache`].
+
+use super::pman::{Bytes, PmanFile};
+use std::collections::HashMap;
+
+/// A cached entry, and the data that it was decompressed from.
+#[derive(Debug)]
+struct Cached {
+    source: Bytes,
+    zlib: Bytes,
+    /// When it was last used; a bigger tick is more recent.
+    tick: u64,
+}
+
+/// The decompressed data of the entries of an archive that were used last, so entries that are
+/// used over and over (e.g. by a viewer that flips between textures) are only inflated once.
+///
+/// The least recently used entries are dropped once the decompressed data takes more than the
+/// budget; an entry that is bigger than the whole budget is never kept. Entries whose data
+/// changed since they were cached are decompressed again.
+#[derive(Debug)]
+pub struct ZlibCache {
+    budget: usize,
+    used: usize,
+    tick: u64,
+    entries: HashMap<usize, Cached>,
+}
+
+impl ZlibCache {
+    /// A cache that keeps up to `budget` bytes of decompressed data.
+    #[must_use]
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            used: 0,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// How many bytes of decompressed data are kept.
+    #[must_use]
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    #[must_use]
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// The decompressed data of the entry at `index` of `pman`; `None` if it is not `ZL`
+    /// compressed, or if there is no entry at `index`.
+    ///
+    /// # Errors
+    ///
+    /// See [`PmanFileData::to_zlib`](super::pman::PmanFileData::to_zlib).
+    pub fn get(&mut self, pman: &PmanFile, index: usize) -> crate::error::Result<Option<Bytes>> {
+        let Some(file) = pman.files().get(index) else {
+            return Ok(None);
+        };
+        self.tick += 1;
+
+        if let Some(cached) = self.entries.get_mut(&index) {
+            // the data is still the one that was cached; it is shared, so only the pointers are
+            // compared.
+            let source = file.shared();
+            if cached.source.as_ptr() == source.as_ptr() && cached.source.len() == source.len() {
+                cached.tick = self.tick;
+                return Ok(Some(cached.zlib.clone()));
+            }
+            self.remove(index);
+        }
+
+        let Some(zlib) = file.to_zlib()? else {
+            return Ok(None);
+        };
+        let zlib = Bytes::from(zlib);
+
+        if zlib.len() <= self.budget {
+            while self.used + zlib.len() > self.budget {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, cached)| cached.tick)
+                    .map(|(&index, _)| index);
+                match oldest {
+                    Some(oldest) => self.remove(oldest),
+                    None => break,
+                }
+            }
+
+            self.used += zlib.len();
+            self.entries.insert(
+                index,
+                Cached {
+                    source: file.shared().clone(),
+                    zlib: zlib.clone(),
+                    tick: self.tick,
+                },
+            );
+        }
+
+        Ok(Some(zlib))
+    }
+
+    /// Drops the entry at `index`, if it is kept.
+    pub fn remove(&mut self, index: usize) {
+        if let Some(cached) = self.entries.remove(&index) {
+            self.used -= cached.zlib.len();
+        }
+    }
+
+    /// Drops every entry.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture, format::pman};
+
+    #[test]
+    fn zlib_cache_test() -> eyre::Result<()> {
+        // three entries of the same size, and a raw one.
+        let files = [b"first", b"secnd", b"third"]
+            .map(|text| pman::compress(&text.repeat(0x40)))
+            .into_iter()
+            .chain([Ok(b"raw".to_vec())])
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        let mut pman = PmanFile::new(&fixture::pack(fixture::COPYRIGHT, &files))?;
+        // two of them fit, but not three.
+        let size = pman[0].to_zlib()?.map_or(0, |zlib| zlib.len());
+        let mut cache = ZlibCache::new(size * 5 / 2);
+
+        let first = cache.get(&pman, 0)?;
+        assert_eq!(first.as_deref(), pman[0].to_zlib()?.as_deref());
+        // the same data, without decompressing it again.
+        assert_eq!(
+            cache.get(&pman, 0)?.map(|b| b.as_ptr()),
+            first.map(|b| b.as_ptr())
+        );
+        assert_eq!(cache.get(&pman, 3)?, None);
+
+        _ = cache.get(&pman, 1)?;
+        _ = cache.get(&pman, 0)?;
+        _ = cache.get(&pman, 2)?;
+        // `1` was the least recently used.
+        assert!(cache.used() <= cache.budget());
+        assert!(cache.entries.contains_key(&0));
+        assert!(!cache.entries.contains_key(&1));
+
+        pman.files_mut()[0].set_bytes(pman::compress(b"changed")?);
+        assert_eq!(cache.get(&pman, 0)?.as_deref(), Some(&b"changed"[..]));
+
+        let mut tiny = ZlibCache::new(1);
+        assert!(tiny.get(&pman, 1)?.is_some());
+        assert_eq!(tiny.used(), 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use super::{
+    audio::SoundArchive,
+    pman::{self, Compression},
+};
+
+/// How deep [`Chunk::walk`] goes; no entry of the `1.0.6` release nests more than twice.
+const MAX_DEPTH: usize = 8;
+
+/// A section of an entry, and the sections that it contains.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Chunk {
+    /// The `FourCC` at the start of [`Self::data`], if any; e.g. `TSND`.
+    pub tag: Option<[u8; 4]>,
+    /// How the chunk was stored, before it was decompressed.
+    pub compression: Option<Compression>,
+    /// The (decompressed) data of the chunk, children included.
+    pub data: Vec<u8>,
+    pub children: Vec<Chunk>,
+}
+
+/// The `FourCC` at the start of `bytes`; 4 uppercase letters or digits.
+fn read_tag(bytes: &[u8]) -> Option<[u8; 4]> {
+    let tag = *bytes.first_chunk::<4>()?;
+
+    tag.iter()
+        .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        .then_some(tag)
+}
+
+/// The `ZL` chunks that are one after the other at the start of `bytes`, if there are more than
+/// one.
+fn zl_chunks(bytes: &[u8]) -> crate::error::Result<Vec<Vec<u8>>> {
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+
+    while let Some((chunk, len)) = pman::decompress_chunk(&bytes[offset..])? {
+        chunks.push(chunk);
+        offset += len;
+    }
+
+    Ok(if chunks.len() > 1 { chunks } else { Vec::new() })
+}
+
+impl Chunk {
+    /// Walks the sections of an entry; `ZL` chunks are decompressed, and known containers (only
+    /// `TSND`, for now) are split into their entries.
+    ///
+    /// # Errors
+    ///
+    /// If a `ZL` chunk, or a known container, is malformed.
+    pub fn walk(bytes: &[u8]) -> crate::error::Result<Self> {
+        Self::walk_at(bytes, 0)
+    }
+
+    fn walk_at(bytes: &[u8], depth: usize) -> crate::error::Result<Self> {
+        let compression = Compression::of(bytes);
+        let (data, compression) = match pman::decompress(bytes)? {
+            Some(zlib) => (zlib, Some(compression)),
+            None => (bytes.to_vec(), None),
+        };
+        let tag = read_tag(&data);
+        let mut chunk = Self {
+            tag,
+            compression,
+            data,
+            children: Vec::new(),
+        };
+
+        if depth >= MAX_DEPTH {
+            return Ok(chunk);
+        }
+
+        let children = if compression.is_some() {
+            zl_chunks(bytes)?
+                .into_iter()
+                .map(|data| Self::walk_at(&data, depth + 1))
+                .collect::<crate::error::Result<_>>()?
+        } else if SoundArchive::is_tsnd(&chunk.data) {
+            let tsnd = SoundArchive::new(&chunk.data)?;
+
+            tsnd.banks
+                .iter()
+                .map(|entries| {
+                    let children = entries
+                        .iter()
+                        .map(|entry| Self::walk_at(entry, depth + 2))
+                        .collect::<crate::error::Result<_>>()?;
+
+                    Ok(Self {
+                        children,
+                        ..Default::default()
+                    })
+                })
+                .collect::<crate::error::Result<_>>()?
+        } else {
+            Vec::new()
+        };
+
+        chunk.children = children;
+
+        Ok(chunk)
+    }
+
+    /// The tag of the chunk as a string, if it has one.
+    #[must_use]
+    pub fn tag_str(&self) -> Option<&str> {
+        self.tag
+            .as_ref()
+            .and_then(|tag| std::str::from_utf8(tag).ok())
+    }
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+    use crate::format::pman::PmanFile;
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    fn chunk_walk_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        let tsnd = Chunk::walk(pman[151].bytes())?;
+        assert_eq!(tsnd.tag_str(), Some("TSND"));
+        assert_eq!(tsnd.children.len(), 4);
+        assert_eq!(tsnd.children[1].children.len(), 239);
+        assert!(tsnd.children[1].children[0].compression.is_some());
+
+        let textures = Chunk::walk(pman[149].bytes())?;
+        assert_eq!(textures.children.len(), 267);
+
+        let coll = Chunk::walk(pman[77].bytes())?;
+        assert_eq!(coll.tag_str(), Some("COLL"));
+        assert_eq!(coll.children, []);
+
+        Ok(())
+    }
+}
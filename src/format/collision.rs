@@ -0,0 +1,885 @@
+use super::Result;
+use crate::error::{Error, ErrorKind};
+use nom::{
+    bytes::complete::take,
+    combinator::{eof, verify},
+    multi::{count, fill},
+    number::complete::{le_i16, le_i32, le_u32},
+    Parser,
+};
+use nom_supreme::{tag::complete::tag, ParserExt};
+
+const HEADER_SIZE: usize = 40;
+const HEADER_MAGIC_STRING: &[u8; 4] = b"COLL";
+const VERSION: u32 = 1;
+const MATERIAL_NAME_SIZE: usize = 64;
+/// How far a point can be in front of a plane and still count as behind it.
+const EPSILON: f32 = 1.0 / 1024.0;
+/// Half of the size of the square that the sides of a brush are cut out of; larger than any level.
+const HALF_EXTENT: f32 = 65_536.0;
+/// The scale of the 8.24 fixed point components of [`Plane::normal`], and of [`Plane::distance`].
+const NORMAL_SCALE: f32 = 16_777_216.0;
+
+/// A group of consecutive brushes; the first one is the level itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Model {
+    pub first_brush: u32,
+    pub brush_count: u32,
+}
+
+/// A convex volume, enclosed by the planes of its sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Brush {
+    /// The index of the first side on [`CollisionMesh::brush_sides`].
+    pub first_side: u32,
+    /// The index of the material on [`CollisionMesh::materials`].
+    pub material: u32,
+    pub side_count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Material {
+    /// The texture of the surface; e.g. `textures/wad2tga_level1/1_grl02h`.
+    pub name: String,
+    /// What fills the brushes of this material; see [`Self::contents`].
+    pub flags: u32,
+}
+
+impl Material {
+    /// What fills the brushes of this material.
+    ///
+    /// The bits match the contents flags of Quake 3 maps (which the levels were built with),
+    /// except for [`Contents::Sky`]; only 5 combinations are used by the `1.0.6` release.
+    /// Triggers are not part of the collision mesh.
+    // TODO(Unavailable): `0x800` is only used by `sky*` textures, but it might mean something
+    // else; e.g. that the surface is not drawn.
+    #[must_use]
+    pub fn contents(&self) -> Contents {
+        match self.flags {
+            0x0000_0001 => Contents::Solid,
+            0x0000_0801 => Contents::Sky,
+            0x2000_0020 => Contents::Water,
+            0x2001_0000 => Contents::PlayerClip,
+            0x2002_0000 => Contents::MonsterClip,
+            _ => Contents::Unknown,
+        }
+    }
+}
+
+/// What fills a brush; see [`Material::contents`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Contents {
+    /// Blocks everything; the walls, floors and ceilings of the level.
+    Solid,
+    /// Solid, but it is drawn as the sky.
+    Sky,
+    /// Can be swum through.
+    Water,
+    /// Invisible; only blocks the player.
+    PlayerClip,
+    /// Invisible; only blocks enemies.
+    MonsterClip,
+    Unknown,
+}
+
+impl Contents {
+    /// The name of the contents, in `snake_case`.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Solid => "solid",
+            Self::Sky => "sky",
+            Self::Water => "water",
+            Self::PlayerClip => "player_clip",
+            Self::MonsterClip => "monster_clip",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Which way a side of a brush faces; `Z` is up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Surface {
+    /// Faces up, and it is flat enough to walk on.
+    Floor,
+    Wall,
+    /// Faces down, and it is flat enough that it can't be a wall.
+    Ceiling,
+}
+
+impl Surface {
+    /// The smallest `Z` component of the normal of a floor; the same slope that Quake 3 can walk
+    /// on (about 45 degrees).
+    pub const MIN_FLOOR_NORMAL: f32 = 0.7;
+
+    /// The surface of a side with a (unit) `normal`.
+    #[must_use]
+    pub fn of(normal: [f32; 3]) -> Self {
+        if normal[2] >= Self::MIN_FLOOR_NORMAL {
+            Self::Floor
+        } else if normal[2] <= -Self::MIN_FLOOR_NORMAL {
+            Self::Ceiling
+        } else {
+            Self::Wall
+        }
+    }
+
+    /// The name of the surface, in `snake_case`.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Floor => "floor",
+            Self::Wall => "wall",
+            Self::Ceiling => "ceiling",
+        }
+    }
+}
+
+/// The points `p` where `dot(normal, p) == distance`; the normals of the sides of a brush point
+/// out of it.
+///
+/// Positions use the same units as the 16.16 positions of the `TWPT` entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plane {
+    /// A unit vector, as 8.24 fixed point numbers.
+    pub normal: [i32; 3],
+    /// As a 8.24 fixed point number.
+    pub distance: i32,
+}
+
+impl Plane {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn normal_f32(&self) -> [f32; 3] {
+        self.normal.map(|x| x as f32 / NORMAL_SCALE)
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn distance_f32(&self) -> f32 {
+        self.distance as f32 / NORMAL_SCALE
+    }
+
+    /// How far `point` is in front of the plane; it is negative if it is behind it.
+    #[must_use]
+    pub fn side(&self, point: [f32; 3]) -> f32 {
+        let normal = self.normal_f32();
+        let dot: f32 = normal.iter().zip(point).map(|(n, p)| n * p).sum();
+
+        dot - self.distance_f32()
+    }
+}
+
+/// Where a [`BspNode`] leads to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BspChild {
+    Node(usize),
+    Leaf(usize),
+}
+
+impl From<i16> for BspChild {
+    #[allow(clippy::cast_sign_loss)]
+    fn from(value: i16) -> Self {
+        // leaves are stored as `!index`, so they are always negative.
+        if value < 0 {
+            Self::Leaf(!value as usize)
+        } else {
+            Self::Node(value as usize)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BspNode {
+    /// The index of the splitting plane on [`CollisionMesh::planes`].
+    pub plane: u32,
+    /// The children in front of, and behind, the plane; see [`BspChild`].
+    pub children: [i16; 2],
+}
+
+impl BspNode {
+    #[must_use]
+    pub fn front(&self) -> BspChild {
+        self.children[0].into()
+    }
+
+    #[must_use]
+    pub fn back(&self) -> BspChild {
+        self.children[1].into()
+    }
+}
+
+/// The brushes that touch a convex region of the BSP tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Leaf {
+    /// The index of the first brush on [`CollisionMesh::leaf_brushes`].
+    pub first_brush: u32,
+    pub brush_count: u32,
+}
+
+/// The `COLL` entries of the archive; the collision geometry of a level.
+///
+/// The geometry is made of convex brushes, each one enclosed by a set of planes, and a BSP tree to
+/// find which brushes are near of a point.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionMesh {
+    pub models: Vec<Model>,
+    pub brushes: Vec<Brush>,
+    /// The index of the plane of every side of [`Self::brushes`].
+    pub brush_sides: Vec<u32>,
+    pub materials: Vec<Material>,
+    pub planes: Vec<Plane>,
+    /// The BSP tree; the first node is the root.
+    pub nodes: Vec<BspNode>,
+    pub leaves: Vec<Leaf>,
+    /// The index of the brushes of every leaf on [`Self::brushes`].
+    pub leaf_brushes: Vec<u32>,
+}
+
+fn read_header(input: &[u8]) -> Result<'_, [usize; 8]> {
+    let (input, header) = super::with_kind(ErrorKind::TruncatedHeader, take(HEADER_SIZE))
+        .context("header")
+        .parse(input)?;
+    let (header, _) = super::with_kind(ErrorKind::BadMagic, tag(&HEADER_MAGIC_STRING[..]))
+        .context("magic string")
+        .parse(header)?;
+    let (header, _) = verify(le_u32, |v| *v == VERSION)
+        .context("version")
+        .parse(header)?;
+
+    let mut counts = [0; 8];
+    let (header, ()) = fill(le_u32, &mut counts).context("counts").parse(header)?;
+    _ = eof(header)?;
+
+    Ok((input, counts.map(|c| c as usize)))
+}
+
+fn read_u32_pair(input: &[u8]) -> Result<'_, (u32, u32)> {
+    le_u32.and(le_u32).parse(input)
+}
+
+fn read_brush(input: &[u8]) -> Result<'_, Brush> {
+    let mut fields = [0; 3];
+    let (input, ()) = fill(le_u32, &mut fields)(input)?;
+    let [first_side, material, side_count] = fields;
+
+    Ok((
+        input,
+        Brush {
+            first_side,
+            material,
+            side_count,
+        },
+    ))
+}
+
+fn read_material(input: &[u8]) -> Result<'_, Material> {
+    let (input, name) = take(MATERIAL_NAME_SIZE)(input)?;
+    let (input, flags) = le_u32(input)?;
+    let name = name.split(|&b| b == 0).next().unwrap_or_default();
+
+    Ok((
+        input,
+        Material {
+            name: String::from_utf8_lossy(name).into(),
+            flags,
+        },
+    ))
+}
+
+fn read_plane(input: &[u8]) -> Result<'_, Plane> {
+    let mut fields = [0; 4];
+    let (input, ()) = fill(le_i32, &mut fields)(input)?;
+    let [x, y, z, distance] = fields;
+
+    Ok((
+        input,
+        Plane {
+            normal: [x, y, z],
+            distance,
+        },
+    ))
+}
+
+fn read_node(input: &[u8]) -> Result<'_, BspNode> {
+    let (input, plane) = le_u32(input)?;
+    let (input, front) = le_i16(input)?;
+    let (input, back) = le_i16(input)?;
+
+    Ok((
+        input,
+        BspNode {
+            plane,
+            children: [front, back],
+        },
+    ))
+}
+
+fn read_collision(input: &[u8]) -> Result<'_, CollisionMesh> {
+    let (input, counts) = read_header(input)?;
+    let [models, brushes, brush_sides, materials, planes, nodes, leaves, leaf_brushes] = counts;
+
+    let (input, models) = count(read_u32_pair, models)
+        .context("models")
+        .parse(input)?;
+    let (input, brushes) = count(read_brush, brushes).context("brushes").parse(input)?;
+    let (input, brush_sides) = count(le_u32, brush_sides)
+        .context("brush sides")
+        .parse(input)?;
+    let (input, materials) = count(read_material, materials)
+        .context("materials")
+        .parse(input)?;
+    let (input, planes) = count(read_plane, planes).context("planes").parse(input)?;
+    let (input, nodes) = count(read_node, nodes).context("nodes").parse(input)?;
+    let (input, leaves) = count(read_u32_pair, leaves)
+        .context("leaves")
+        .parse(input)?;
+    let (input, leaf_brushes) = count(le_u32, leaf_brushes)
+        .context("leaf brushes")
+        .parse(input)?;
+    let (input, _) = eof.context("end of data").parse(input)?;
+
+    Ok((
+        input,
+        CollisionMesh {
+            models: models
+                .into_iter()
+                .map(|(first_brush, brush_count)| Model {
+                    first_brush,
+                    brush_count,
+                })
+                .collect(),
+            brushes,
+            brush_sides,
+            materials,
+            planes,
+            nodes,
+            leaves: leaves
+                .into_iter()
+                .map(|(first_brush, brush_count)| Leaf {
+                    first_brush,
+                    brush_count,
+                })
+                .collect(),
+            leaf_brushes,
+        },
+    ))
+}
+
+impl CollisionMesh {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid (decompressed) `COLL` entry.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "COLL", read_collision)
+    }
+
+    /// Finds the leaf of the BSP tree that `point` is in.
+    ///
+    /// Returns `None` if the tree points out of [`Self::nodes`], [`Self::planes`] or
+    /// [`Self::leaves`], or if it has a cycle.
+    #[must_use]
+    pub fn find_leaf(&self, point: [f32; 3]) -> Option<usize> {
+        let mut child = if self.nodes.is_empty() {
+            BspChild::Leaf(0)
+        } else {
+            BspChild::Node(0)
+        };
+
+        // a path from the root never visits more nodes than there are.
+        for _ in 0..=self.nodes.len() {
+            match child {
+                BspChild::Leaf(leaf) => return (leaf < self.leaves.len()).then_some(leaf),
+                BspChild::Node(node) => {
+                    let node = self.nodes.get(node)?;
+                    let plane = self.planes.get(node.plane as usize)?;
+
+                    child = if plane.side(point) >= 0.0 {
+                        node.front()
+                    } else {
+                        node.back()
+                    };
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The indices, on [`Self::brushes`], of the brushes that touch `leaf`.
+    #[must_use]
+    pub fn brushes_of_leaf(&self, leaf: usize) -> &[u32] {
+        self.leaves
+            .get(leaf)
+            .and_then(|leaf| {
+                let start = leaf.first_brush as usize;
+
+                self.leaf_brushes
+                    .get(start..start.checked_add(leaf.brush_count as usize)?)
+            })
+            .unwrap_or_default()
+    }
+
+    /// The index, on [`Self::models`], of the model that `brush` is part of; `0` is the level
+    /// itself.
+    #[must_use]
+    pub fn model_of_brush(&self, brush: usize) -> Option<usize> {
+        self.models.iter().position(|model| {
+            let start = model.first_brush as usize;
+
+            (start..start.saturating_add(model.brush_count as usize)).contains(&brush)
+        })
+    }
+
+    /// The planes of the sides of `brush`.
+    pub fn brush_planes(&self, brush: usize) -> impl Iterator<Item = &Plane> + '_ {
+        let sides = self.brushes.get(brush).map_or(&[][..], |brush| {
+            let start = brush.first_side as usize;
+
+            self.brush_sides
+                .get(start..start.saturating_add(brush.side_count as usize))
+                .unwrap_or_default()
+        });
+
+        sides
+            .iter()
+            .filter_map(|&plane| self.planes.get(plane as usize))
+    }
+
+    /// The polygons of the sides of `brush`; their vertices go counterclockwise, when looked at
+    /// from outside of the brush.
+    ///
+    /// Sides that don't touch the brush (e.g. bevel planes) are skipped.
+    #[must_use]
+    pub fn brush_faces(&self, brush: usize) -> Vec<Vec<[f32; 3]>> {
+        self.brush_faces_by_plane(brush)
+            .into_iter()
+            .map(|(_, polygon)| polygon)
+            .collect()
+    }
+
+    /// The same as [`Self::brush_faces`], with the plane of every face.
+    #[must_use]
+    pub fn brush_faces_by_plane(&self, brush: usize) -> Vec<(&Plane, Vec<[f32; 3]>)> {
+        let planes: Vec<_> = self.brush_planes(brush).collect();
+
+        planes
+            .iter()
+            .map(|&plane| (plane, side_polygon(plane, &planes)))
+            .filter(|(_, polygon)| polygon.len() >= 3)
+            .collect()
+    }
+
+    /// The material of `brush`, if it has a valid one.
+    #[must_use]
+    pub fn material_of_brush(&self, brush: usize) -> Option<&Material> {
+        let material = self.brushes.get(brush)?.material;
+
+        self.materials.get(material as usize)
+    }
+
+    /// The axis aligned bounds, the minimum and the maximum corners, of `brush`.
+    ///
+    /// Returns `None` if the brush doesn't have a side for each of the 6 axis aligned planes.
+    #[must_use]
+    pub fn brush_bounds(&self, brush: usize) -> Option<([f32; 3], [f32; 3])> {
+        const ONE: i32 = 1 << 24;
+
+        let mut min = [None; 3];
+        let mut max = [None; 3];
+
+        for plane in self.brush_planes(brush) {
+            let Some(axis) = plane.normal.iter().position(|&n| n != 0) else {
+                continue;
+            };
+            let mut axial = [0; 3];
+            axial[axis] = ONE;
+
+            if plane.normal == axial {
+                max[axis] = Some(plane.distance_f32());
+            } else if plane.normal == axial.map(|n| -n) {
+                min[axis] = Some(-plane.distance_f32());
+            }
+        }
+
+        let [x0, y0, z0] = min;
+        let [x1, y1, z1] = max;
+
+        Some(([x0?, y0?, z0?], [x1?, y1?, z1?]))
+    }
+
+    /// # Errors
+    ///
+    /// If a material name doesn't fit on its 64 bytes (null terminated) field, or if any list has
+    /// more than `u32::MAX` items.
+    pub fn to_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        use super::count_u32;
+
+        if let Some(material) = self
+            .materials
+            .iter()
+            .find(|m| m.name.len() >= MATERIAL_NAME_SIZE)
+        {
+            return Err(Error::NameTooLong {
+                name: material.name.clone(),
+                max: MATERIAL_NAME_SIZE - 1,
+            });
+        }
+
+        let counts = [
+            count_u32("models", self.models.len())?,
+            count_u32("brushes", self.brushes.len())?,
+            count_u32("brush sides", self.brush_sides.len())?,
+            count_u32("materials", self.materials.len())?,
+            count_u32("planes", self.planes.len())?,
+            count_u32("nodes", self.nodes.len())?,
+            count_u32("leaves", self.leaves.len())?,
+            count_u32("leaf brushes", self.leaf_brushes.len())?,
+        ];
+        let mut buf = Vec::with_capacity(
+            HEADER_SIZE
+                + self.models.len() * 8
+                + self.brushes.len() * 12
+                + self.brush_sides.len() * 4
+                + self.materials.len() * (MATERIAL_NAME_SIZE + 4)
+                + self.planes.len() * 16
+                + self.nodes.len() * 8
+                + self.leaves.len() * 8
+                + self.leaf_brushes.len() * 4,
+        );
+
+        buf.extend_from_slice(HEADER_MAGIC_STRING);
+        buf.extend_from_slice(&VERSION.to_le_bytes());
+        for count in counts {
+            buf.extend_from_slice(&count.to_le_bytes());
+        }
+
+        for model in &self.models {
+            buf.extend_from_slice(&model.first_brush.to_le_bytes());
+            buf.extend_from_slice(&model.brush_count.to_le_bytes());
+        }
+        for brush in &self.brushes {
+            buf.extend_from_slice(&brush.first_side.to_le_bytes());
+            buf.extend_from_slice(&brush.material.to_le_bytes());
+            buf.extend_from_slice(&brush.side_count.to_le_bytes());
+        }
+        for side in &self.brush_sides {
+            buf.extend_from_slice(&side.to_le_bytes());
+        }
+        for material in &self.materials {
+            let mut name = [0; MATERIAL_NAME_SIZE];
+            name[..material.name.len()].copy_from_slice(material.name.as_bytes());
+
+            buf.extend_from_slice(&name);
+            buf.extend_from_slice(&material.flags.to_le_bytes());
+        }
+        for plane in &self.planes {
+            for x in plane.normal {
+                buf.extend_from_slice(&x.to_le_bytes());
+            }
+            buf.extend_from_slice(&plane.distance.to_le_bytes());
+        }
+        for node in &self.nodes {
+            buf.extend_from_slice(&node.plane.to_le_bytes());
+            buf.extend_from_slice(&node.children[0].to_le_bytes());
+            buf.extend_from_slice(&node.children[1].to_le_bytes());
+        }
+        for leaf in &self.leaves {
+            buf.extend_from_slice(&leaf.first_brush.to_le_bytes());
+            buf.extend_from_slice(&leaf.brush_count.to_le_bytes());
+        }
+        for brush in &self.leaf_brushes {
+            buf.extend_from_slice(&brush.to_le_bytes());
+        }
+
+        Ok(buf)
+    }
+}
+
+/// Cuts the polygon of `plane` out of the brush enclosed by `planes`.
+fn side_polygon(plane: &Plane, planes: &[&Plane]) -> Vec<[f32; 3]> {
+    let normal = plane.normal_f32();
+    let distance = plane.distance_f32();
+
+    // two axes of the plane, so a big square can be built on it.
+    let up = if normal[2].abs() > 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 0.0, 1.0]
+    };
+    let right = normalize(cross(up, normal));
+    let up = cross(normal, right);
+    let center = normal.map(|n| n * distance);
+
+    let mut polygon: Vec<_> = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+        .iter()
+        .map(|&(r, u)| {
+            std::array::from_fn(|i| {
+                center[i] + right[i] * r * HALF_EXTENT + up[i] * u * HALF_EXTENT
+            })
+        })
+        .collect();
+
+    for clip in planes {
+        if std::ptr::eq(*clip, plane) || polygon.is_empty() {
+            continue;
+        }
+
+        polygon = clip_polygon(&polygon, clip);
+    }
+
+    polygon
+}
+
+/// Keeps the part of `polygon` that is behind `plane`.
+fn clip_polygon(polygon: &[[f32; 3]], plane: &Plane) -> Vec<[f32; 3]> {
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+
+    for (index, &a) in polygon.iter().enumerate() {
+        let b = polygon[(index + 1) % polygon.len()];
+        let (side_a, side_b) = (plane.side(a), plane.side(b));
+
+        if side_a <= EPSILON {
+            clipped.push(a);
+        }
+        if (side_a > EPSILON) != (side_b > EPSILON) {
+            let t = side_a / (side_a - side_b);
+
+            clipped.push(std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t));
+        }
+    }
+
+    clipped
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    v.map(|x| x / len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::{error::ErrorKind, format::pman::PmanFile};
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    /// The `COLL` entries of every level.
+    #[cfg(feature = "packfile")]
+    const COLL_ENTRIES: [usize; 19] = [
+        77, 81, 85, 89, 93, 97, 101, 105, 109, 113, 117, 121, 125, 129, 132, 135, 138, 141, 144,
+    ];
+
+    #[cfg(feature = "packfile")]
+    fn coll_entry(index: usize) -> eyre::Result<Vec<u8>> {
+        let pman = PmanFile::new(INPUT)?;
+
+        Ok(pman[index]
+            .to_zlib()?
+            .expect("`COLL` entries are compressed."))
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn collision_new_test() -> eyre::Result<()> {
+        let coll = CollisionMesh::new(&coll_entry(77)?)?;
+
+        assert_eq!(coll.models.len(), 25);
+        assert_eq!(coll.brushes.len(), 2042);
+        assert_eq!(coll.brush_sides.len(), 14891);
+        assert_eq!(coll.materials.len(), 97);
+        assert_eq!(coll.planes.len(), 3065);
+        assert_eq!(coll.nodes.len(), 3347);
+        assert_eq!(coll.leaves.len(), 3373);
+        assert_eq!(coll.leaf_brushes.len(), 4458);
+
+        assert_eq!(coll.materials[0].name, "textures/wad2tga_level1/1_grl02h");
+        assert_eq!(coll.planes[0].normal, [0, 0, 1 << 24]);
+        assert!((coll.planes[0].normal_f32()[2] - 1.0).abs() < f32::EPSILON);
+        assert_eq!(coll.nodes[0].front(), BspChild::Node(1));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn collision_find_leaf_test() -> eyre::Result<()> {
+        let coll = CollisionMesh::new(&coll_entry(77)?)?;
+        let (mut found, mut total) = (0, 0);
+
+        for brush in 0..coll.brushes.len() {
+            let Some((min, max)) = coll.brush_bounds(brush) else {
+                continue;
+            };
+            let center = [0, 1, 2].map(|i| f32::midpoint(min[i], max[i]));
+            let leaf = coll.find_leaf(center).expect("the tree is valid.");
+
+            total += 1;
+            let brush = u32::try_from(brush)?;
+            found += usize::from(coll.brushes_of_leaf(leaf).contains(&brush));
+        }
+
+        // the center of a brush that is thinner than the precision of a `f32` can end up on the
+        // wrong side of its own planes.
+        assert_eq!(total, coll.brushes.len());
+        assert!(found * 100 >= total * 95, "{found} of {total}");
+
+        assert_eq!(coll.model_of_brush(0), Some(0));
+        assert_eq!(coll.model_of_brush(2008), Some(1));
+        assert_eq!(coll.model_of_brush(coll.brushes.len()), None);
+        assert_eq!(CollisionMesh::default().find_leaf([0.0; 3]), None);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn collision_contents_test() -> eyre::Result<()> {
+        for index in COLL_ENTRIES {
+            let coll = CollisionMesh::new(&coll_entry(index)?)?;
+
+            for material in &coll.materials {
+                assert_ne!(material.contents(), Contents::Unknown, "{material:?}");
+            }
+        }
+
+        let coll = CollisionMesh::new(&coll_entry(77)?)?;
+        let count = |contents| {
+            (0..coll.brushes.len())
+                .filter(|&brush| {
+                    coll.material_of_brush(brush).map(Material::contents) == Some(contents)
+                })
+                .count()
+        };
+        assert_eq!(count(Contents::Solid), 0x7BD);
+        assert_eq!(count(Contents::Water), 6);
+
+        assert_eq!(Surface::of([0.0, 0.0, 1.0]), Surface::Floor);
+        assert_eq!(Surface::of([0.0, 0.6, 0.8]), Surface::Floor);
+        assert_eq!(Surface::of([1.0, 0.0, 0.0]), Surface::Wall);
+        assert_eq!(Surface::of([0.0, 0.0, -1.0]), Surface::Ceiling);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn collision_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in COLL_ENTRIES {
+            let bytes = pman[index]
+                .to_zlib()?
+                .expect("`COLL` entries are compressed.");
+
+            assert_eq!(
+                CollisionMesh::new(&bytes)?.to_bytes()?,
+                bytes,
+                "entry {index}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn collision_invalid_test() -> eyre::Result<()> {
+        let mut bytes = coll_entry(77)?;
+
+        assert_eq!(
+            CollisionMesh::new(&bytes[..bytes.len() - 1])
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidFormat
+        );
+
+        bytes[0] = b'X';
+
+        assert_eq!(
+            CollisionMesh::new(&bytes).unwrap_err().kind(),
+            ErrorKind::BadMagic
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn collision_serde_test() -> eyre::Result<()> {
+        let coll = CollisionMesh::new(&coll_entry(77)?)?;
+
+        let json = serde_json::to_vec(&coll)?;
+        assert_eq!(serde_json::from_slice::<CollisionMesh>(&json)?, coll);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collision_snapshot_test() -> eyre::Result<()> {
+        // a floor brush, split by a single plane.
+        let coll = CollisionMesh {
+            models: vec![Model {
+                first_brush: 0,
+                brush_count: 1,
+            }],
+            brushes: vec![Brush {
+                first_side: 0,
+                material: 0,
+                side_count: 1,
+            }],
+            brush_sides: vec![0],
+            materials: vec![Material {
+                name: "textures/wad2tga_level1/1_grl02h".into(),
+                flags: 1,
+            }],
+            planes: vec![Plane {
+                normal: [0, 0, 0x100_0000],
+                distance: -0x40_0000,
+            }],
+            nodes: vec![BspNode {
+                plane: 0,
+                children: [!0, !1],
+            }],
+            leaves: vec![
+                Leaf {
+                    first_brush: 0,
+                    brush_count: 0,
+                },
+                Leaf {
+                    first_brush: 0,
+                    brush_count: 1,
+                },
+            ],
+            leaf_brushes: vec![0],
+        };
+
+        crate::snapshot::assert_parsed_snapshot!(
+            "collision",
+            CollisionMesh::new(&coll.to_bytes()?)?
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,342 @@
+//! Conversions between the packed colors of the game (and of other tools) and 8-bit RGBA.
+
+use super::palette::COLOR_COUNT;
+
+/// A packed 16-bit color format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    /// `0x0RGB`; the format of every [`Palette`](super::palette::Palette) of the game.
+    Rgb444,
+    /// `0b0RRRRRGGGGGBBBBB`
+    Rgb555,
+    /// `0bRRRRRGGGGGGBBBBB`
+    Rgb565,
+    /// `0xRGBA`
+    Rgba4444,
+}
+
+impl PixelFormat {
+    /// The shift and the bit count of the red, green, blue and alpha channels; alpha has no bits
+    /// on formats without it.
+    const fn channels(self) -> [(u16, u16); 4] {
+        match self {
+            Self::Rgb444 => [(8, 4), (4, 4), (0, 4), (0, 0)],
+            Self::Rgb555 => [(10, 5), (5, 5), (0, 5), (0, 0)],
+            Self::Rgb565 => [(11, 5), (5, 6), (0, 5), (0, 0)],
+            Self::Rgba4444 => [(12, 4), (8, 4), (4, 4), (0, 4)],
+        }
+    }
+
+    /// Expands a `color` of this format into 8-bit RGBA; alpha is opaque if the format has no
+    /// alpha channel.
+    #[must_use]
+    pub fn to_rgba(self, color: u16) -> [u8; 4] {
+        self.channels().map(|(shift, bits)| {
+            if bits == 0 {
+                u8::MAX
+            } else {
+                expand((color >> shift) & mask(bits), bits)
+            }
+        })
+    }
+
+    /// Reduces an 8-bit RGBA color into the nearest color of this format; alpha is ignored if the
+    /// format has no alpha channel.
+    #[must_use]
+    pub fn from_rgba(self, rgba: [u8; 4]) -> u16 {
+        self.channels()
+            .iter()
+            .zip(rgba)
+            .fold(0, |color, (&(shift, bits), channel)| {
+                color | reduce(channel, bits) << shift
+            })
+    }
+}
+
+const fn mask(bits: u16) -> u16 {
+    (1 << bits) - 1
+}
+
+/// Scales a channel of `bits` bits into 8 bits, so its maximum maps into `0xFF`.
+#[allow(clippy::cast_possible_truncation)]
+fn expand(value: u16, bits: u16) -> u8 {
+    let max = u32::from(mask(bits));
+
+    ((u32::from(value) * 255 + max / 2) / max) as u8
+}
+
+/// Scales an 8-bit channel into the nearest value of `bits` bits.
+#[allow(clippy::cast_possible_truncation)]
+fn reduce(value: u8, bits: u16) -> u16 {
+    let max = u32::from(mask(bits));
+
+    ((u32::from(value) * max + 127) / 255) as u16
+}
+
+/// Expands a `0x0RGB` color into 8-bit RGBA.
+#[must_use]
+pub fn rgb444_to_rgba(color: u16) -> [u8; 4] {
+    PixelFormat::Rgb444.to_rgba(color)
+}
+
+/// Reduces an 8-bit RGBA color into the nearest `0x0RGB` color; alpha is ignored.
+#[must_use]
+pub fn rgba_to_rgb444(rgba: [u8; 4]) -> u16 {
+    PixelFormat::Rgb444.from_rgba(rgba)
+}
+
+/// Expands a `0b0RRRRRGGGGGBBBBB` color into 8-bit RGBA.
+#[must_use]
+pub fn rgb555_to_rgba(color: u16) -> [u8; 4] {
+    PixelFormat::Rgb555.to_rgba(color)
+}
+
+/// Reduces an 8-bit RGBA color into the nearest `0b0RRRRRGGGGGBBBBB` color; alpha is ignored.
+#[must_use]
+pub fn rgba_to_rgb555(rgba: [u8; 4]) -> u16 {
+    PixelFormat::Rgb555.from_rgba(rgba)
+}
+
+/// Expands a `0bRRRRRGGGGGGBBBBB` color into 8-bit RGBA.
+#[must_use]
+pub fn rgb565_to_rgba(color: u16) -> [u8; 4] {
+    PixelFormat::Rgb565.to_rgba(color)
+}
+
+/// Reduces an 8-bit RGBA color into the nearest `0bRRRRRGGGGGGBBBBB` color; alpha is ignored.
+#[must_use]
+pub fn rgba_to_rgb565(rgba: [u8; 4]) -> u16 {
+    PixelFormat::Rgb565.from_rgba(rgba)
+}
+
+/// Expands a `0xRGBA` color into 8-bit RGBA.
+#[must_use]
+pub fn rgba4444_to_rgba(color: u16) -> [u8; 4] {
+    PixelFormat::Rgba4444.to_rgba(color)
+}
+
+/// Reduces an 8-bit RGBA color into the nearest `0xRGBA` color.
+#[must_use]
+pub fn rgba_to_rgba4444(rgba: [u8; 4]) -> u16 {
+    PixelFormat::Rgba4444.from_rgba(rgba)
+}
+
+/// Looks up every index of `pixels` on `colors`.
+#[must_use]
+pub fn apply_palette(pixels: &[u8], colors: &[[u8; 4]; COLOR_COUNT]) -> Vec<[u8; 4]> {
+    pixels.iter().map(|&i| colors[i as usize]).collect()
+}
+
+/// Picks the colors that represent `pixels` the best (by median cut); alpha is ignored, and every
+/// color is opaque.
+///
+/// The colors are split into boxes, by the channel with the widest range, until there are
+/// [`COLOR_COUNT`] boxes (or every box has a single color); each box is then averaged into a
+/// color. Unused colors are black.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn generate_palette(pixels: &[[u8; 4]]) -> [[u8; 4]; COLOR_COUNT] {
+    /// The channel with the widest range of `colors`, and that range.
+    fn widest(colors: &[[u8; 3]]) -> (usize, u8) {
+        (0..3)
+            .map(|c| {
+                let (min, max) = colors.iter().fold((u8::MAX, u8::MIN), |(min, max), color| {
+                    (min.min(color[c]), max.max(color[c]))
+                });
+
+                (c, max.saturating_sub(min))
+            })
+            .max_by_key(|&(_, range)| range)
+            .unwrap_or_default()
+    }
+
+    let mut boxes = vec![pixels
+        .iter()
+        .map(|&[r, g, b, _]| [r, g, b])
+        .collect::<Vec<_>>()];
+
+    while boxes.len() < COLOR_COUNT {
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, colors)| (index, widest(colors)))
+            .filter(|(_, (_, range))| *range > 0)
+            .max_by_key(|(_, (_, range))| *range)
+            .map(|(index, (channel, _))| (index, channel))
+        else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|color| color[channel]);
+        // split at the median, but never between two pixels of the same value.
+        let median = colors[colors.len() / 2][channel];
+        let split = match colors.partition_point(|color| color[channel] < median) {
+            0 => colors.partition_point(|color| color[channel] <= median),
+            split => split,
+        };
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut palette = [[0, 0, 0, u8::MAX]; COLOR_COUNT];
+    for (color, colors) in palette
+        .iter_mut()
+        .zip(boxes.iter().filter(|b| !b.is_empty()))
+    {
+        let len = colors.len() as u64;
+        for (c, channel) in color.iter_mut().take(3).enumerate() {
+            let sum: u64 = colors.iter().map(|color| u64::from(color[c])).sum();
+            *channel = ((sum + len / 2) / len) as u8;
+        }
+    }
+
+    palette
+}
+
+/// The index of the color of `colors` that is the nearest to `rgb`; alpha is ignored.
+fn nearest(rgb: [i32; 3], colors: &[[u8; 4]; COLOR_COUNT]) -> u8 {
+    let distance = |color: &[u8; 4]| -> i32 {
+        rgb.iter()
+            .zip(color)
+            .map(|(&a, &b)| (a - i32::from(b)).pow(2))
+            .sum()
+    };
+
+    colors
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, color)| distance(color))
+        .and_then(|(index, _)| u8::try_from(index).ok())
+        .unwrap_or_default()
+}
+
+/// Converts 8-bit RGBA `pixels`, row by row and `width` pixels per row, into indices of `colors`;
+/// alpha is ignored.
+///
+/// With `dither`, the error of every pixel is spread into its neighbours (Floyd-Steinberg), so
+/// gradients don't turn into bands of a single color.
+#[must_use]
+pub fn quantize(
+    pixels: &[[u8; 4]],
+    width: usize,
+    colors: &[[u8; 4]; COLOR_COUNT],
+    dither: bool,
+) -> Vec<u8> {
+    if !dither || width == 0 {
+        return pixels
+            .iter()
+            .map(|&[r, g, b, _]| nearest([r, g, b].map(i32::from), colors))
+            .collect();
+    }
+
+    // the error carried into the current row, and into the next one; one extra pixel on each side
+    // so the edges don't need to be special cased.
+    let mut current = vec![[0i32; 3]; width + 2];
+    let mut next = vec![[0i32; 3]; width + 2];
+    let mut indices = Vec::with_capacity(pixels.len());
+
+    for row in pixels.chunks(width) {
+        for (x, &[r, g, b, _]) in row.iter().enumerate() {
+            let mut rgb = [r, g, b].map(i32::from);
+            for (channel, error) in rgb.iter_mut().zip(current[x + 1]) {
+                *channel = (*channel + error / 16).clamp(0, 255);
+            }
+
+            let index = nearest(rgb, colors);
+            let color = colors[index as usize];
+            indices.push(index);
+
+            for c in 0..3 {
+                let error = rgb[c] - i32::from(color[c]);
+
+                current[x + 2][c] += error * 7;
+                next[x][c] += error * 3;
+                next[x + 1][c] += error * 5;
+                next[x + 2][c] += error;
+            }
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        next.fill([0; 3]);
+    }
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb444_test() {
+        assert_eq!(rgb444_to_rgba(0x0F80), [0xFF, 0x88, 0x00, 0xFF]);
+        assert_eq!(rgba_to_rgb444([0xFF, 0x88, 0x00, 0x00]), 0x0F80);
+        // rounds to the nearest 4-bit value.
+        assert_eq!(rgba_to_rgb444([0x07, 0x09, 0xF7, 0x00]), 0x001F);
+    }
+
+    #[test]
+    fn pixel_format_round_trip_test() {
+        for (format, used) in [
+            (PixelFormat::Rgb444, 0x0FFF),
+            (PixelFormat::Rgb555, 0x7FFF),
+            (PixelFormat::Rgb565, 0xFFFF),
+            (PixelFormat::Rgba4444, 0xFFFF),
+        ] {
+            for color in 0..=u16::MAX {
+                let color = color & used;
+
+                assert_eq!(format.from_rgba(format.to_rgba(color)), color, "{format:?}");
+            }
+        }
+
+        assert_eq!(rgb555_to_rgba(0x7C00), [0xFF, 0x00, 0x00, 0xFF]);
+        assert_eq!(rgb565_to_rgba(0x07E0), [0x00, 0xFF, 0x00, 0xFF]);
+        assert_eq!(rgba4444_to_rgba(0x00F8), [0x00, 0x00, 0xFF, 0x88]);
+        assert_eq!(rgba_to_rgb565([0x84, 0x82, 0x84, 0x00]), 0x8410);
+    }
+
+    #[test]
+    fn quantize_test() {
+        let mut colors = [[0, 0, 0, 0xFF]; COLOR_COUNT];
+        colors[1] = [0xFF, 0xFF, 0xFF, 0xFF];
+
+        let pixels = apply_palette(&[0, 1, 1, 0], &colors);
+        assert_eq!(quantize(&pixels, 2, &colors, false), [0, 1, 1, 0]);
+        assert_eq!(quantize(&pixels, 2, &colors, true), [0, 1, 1, 0]);
+
+        // a flat grey is either black or white without dithering, and half and half with it.
+        let grey = vec![[0x80, 0x80, 0x80, 0xFF]; 64];
+        let white = |indices: Vec<u8>| indices.iter().map(|&i| usize::from(i)).sum::<usize>();
+
+        assert_eq!(white(quantize(&grey, 8, &colors, false)), 64);
+        assert_eq!(white(quantize(&grey, 8, &colors, true)), 32);
+    }
+
+    #[test]
+    fn generate_palette_test() {
+        let pixels: Vec<_> = (0..=u8::MAX)
+            .flat_map(|r| [[r, 0, 0, 0xFF], [0, r, 0, 0xFF], [0, 0, r, 0xFF]])
+            .collect();
+        let colors = generate_palette(&pixels);
+        let indices = quantize(&pixels, 256, &colors, false);
+        let error = pixels
+            .iter()
+            .zip(apply_palette(&indices, &colors))
+            .flat_map(|(a, b)| (0..3).map(move |c| a[c].abs_diff(b[c])))
+            .max();
+
+        assert!(error.is_some_and(|error| error <= 4), "{error:?}");
+
+        // fewer colors than the palette can hold are kept as they are.
+        let pixels = [[1, 2, 3, 0], [200, 100, 50, 0xFF]];
+        let colors = generate_palette(&pixels);
+
+        assert_eq!(colors[..2], [[1, 2, 3, 0xFF], [200, 100, 50, 0xFF]]);
+        assert_eq!(colors[2], [0, 0, 0, 0xFF]);
+        assert_eq!(generate_palette(&[]), [[0, 0, 0, 0xFF]; COLOR_COUNT]);
+    }
+}
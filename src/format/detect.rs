@@ -0,0 +1,277 @@
+use super::{
+    model::Model,
+    palette::{Palette, PALETTE_SIZE},
+    skybox::Skybox,
+    text::TextBank,
+    texture::TEXTURE_SIZE,
+};
+
+/// What the data of an entry (or of any other blob) is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FileType {
+    /// A `PMAN` archive; e.g. a `packfile.dat` that is inside another one.
+    Packfile,
+    /// A `ZL` chunk, that hasn't been decompressed.
+    Zlib,
+    /// `COLL`
+    Collision,
+    /// `TWPT`
+    Waypoint,
+    /// `TSND`
+    Sound,
+    /// Mainly for language text banks. UTF-16
+    Text,
+    /// The color palette used for textures.
+    Palette,
+    Texture,
+    Skybox,
+    Model,
+    /// Data that doesn't look like anything; probably compressed with something that is not zlib.
+    Noise,
+}
+
+impl FileType {
+    pub const ALL: [Self; 11] = [
+        Self::Packfile,
+        Self::Zlib,
+        Self::Collision,
+        Self::Waypoint,
+        Self::Sound,
+        Self::Text,
+        Self::Palette,
+        Self::Texture,
+        Self::Skybox,
+        Self::Model,
+        Self::Noise,
+    ];
+
+    /// The type that is named by `extension`; the reverse of [`Self::extension`].
+    #[must_use]
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|file_type| file_type.extension() == extension)
+    }
+
+    /// The value that marks entries of this type on the `type` column of the entry table, when it
+    /// is filled in; see [`EntryTypes::Detected`](super::pman::EntryTypes::Detected). The values of
+    /// the original source code are not known, so these are our own; they never change, and `0`
+    /// is left for entries of an unknown type.
+    #[must_use]
+    pub fn type_code(self) -> u32 {
+        match self {
+            Self::Packfile => 1,
+            Self::Zlib => 2,
+            Self::Collision => 3,
+            Self::Waypoint => 4,
+            Self::Sound => 5,
+            Self::Text => 6,
+            Self::Palette => 7,
+            Self::Texture => 8,
+            Self::Skybox => 9,
+            Self::Model => 10,
+            Self::Noise => 11,
+        }
+    }
+
+    /// The type that is marked by `code`; the reverse of [`Self::type_code`].
+    #[must_use]
+    pub fn from_type_code(code: u32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|file_type| file_type.type_code() == code)
+    }
+
+    /// The extension that files of this type are named with; e.g. on a mount.
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Packfile => "pman",
+            Self::Zlib => "zlib",
+            Self::Collision => "coll",
+            Self::Waypoint => "twpt",
+            Self::Sound => "tsnd",
+            Self::Text => "text",
+            Self::Palette => "palette",
+            Self::Texture => "texture",
+            Self::Skybox => "skybox",
+            Self::Model => "model",
+            Self::Noise => "dat",
+        }
+    }
+}
+
+/// How sure a [`Guess`] is; ordered from the least to the most sure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Confidence {
+    /// Only the size, or the statistics, of the data match.
+    Possible,
+    /// The data parses as the guessed type.
+    Likely,
+    /// The data starts with the signature of the guessed type.
+    Certain,
+}
+
+/// A guess of what a blob is, and why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Guess {
+    pub file_type: FileType,
+    pub confidence: Confidence,
+    pub reason: &'static str,
+}
+
+/// A magic string that is at `offset` of every file of a type.
+struct Signature {
+    offset: usize,
+    magic: &'static [u8],
+    file_type: FileType,
+}
+
+const SIGNATURES: [Signature; 6] = [
+    Signature::new(0, b"PMAN", FileType::Packfile),
+    Signature::new(0, b"ZL", FileType::Zlib),
+    Signature::new(0, b"COLL", FileType::Collision),
+    Signature::new(0, b"TWPT", FileType::Waypoint),
+    Signature::new(0, b"TSND", FileType::Sound),
+    // Text banks start with the string count; the `1.0.6` release doesn't use a BOM, but others
+    // might.
+    Signature::new(4, b"\xFF\xFE", FileType::Text),
+];
+
+/// Above this (in bits per byte), data is most likely compressed; text, tables and indexed images
+/// stay well below it.
+const NOISE_ENTROPY: f64 = 7.5;
+
+impl Signature {
+    const fn new(offset: usize, magic: &'static [u8], file_type: FileType) -> Self {
+        Self {
+            offset,
+            magic,
+            file_type,
+        }
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        bytes
+            .get(self.offset..)
+            .is_some_and(|bytes| bytes.starts_with(self.magic))
+    }
+}
+
+/// The Shannon entropy of `bytes`, in bits per byte.
+#[allow(clippy::cast_precision_loss)]
+fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &byte in bytes {
+        counts[byte as usize] += 1;
+    }
+
+    let len = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count != 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Guesses what `bytes` is, from the most to the least likely; it is empty if nothing matches.
+///
+/// Signatures are checked first; then, the formats without one are tried by their size, and by
+/// parsing them. `ZL` chunks are not decompressed, so they are only ever guessed as
+/// [`FileType::Zlib`].
+#[must_use]
+pub fn detect(bytes: &[u8]) -> Vec<Guess> {
+    let mut guesses: Vec<_> = SIGNATURES
+        .iter()
+        .filter(|signature| signature.matches(bytes))
+        .map(|signature| Guess {
+            file_type: signature.file_type,
+            confidence: Confidence::Certain,
+            reason: "signature",
+        })
+        .collect();
+
+    if !guesses.is_empty() {
+        return guesses;
+    }
+
+    let mut guess = |file_type, confidence, reason| {
+        guesses.push(Guess {
+            file_type,
+            confidence,
+            reason,
+        });
+    };
+
+    if bytes.len() == PALETTE_SIZE && Palette::new(bytes).is_ok() {
+        guess(FileType::Palette, Confidence::Likely, "256 `0x0RGB` colors");
+    }
+    if Skybox::new(bytes).is_ok() {
+        guess(FileType::Skybox, Confidence::Likely, "parses as a skybox");
+    }
+    if Model::new(bytes).is_ok() {
+        guess(FileType::Model, Confidence::Likely, "parses as a model");
+    }
+    if TextBank::new(bytes).is_ok() {
+        guess(FileType::Text, Confidence::Likely, "parses as a text bank");
+    }
+    if bytes.len() == (TEXTURE_SIZE * TEXTURE_SIZE) as usize {
+        guess(FileType::Texture, Confidence::Possible, "256x256 bytes");
+    }
+    if !bytes.is_empty() && entropy(bytes) > NOISE_ENTROPY {
+        guess(FileType::Noise, Confidence::Possible, "high entropy");
+    }
+
+    // the sort is stable, so guesses of the same confidence keep the order they were tried in.
+    guesses.sort_by_key(|guess| std::cmp::Reverse(guess.confidence));
+    guesses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn detect_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let best = |bytes: &[u8]| detect(bytes).first().map(|guess| guess.file_type);
+
+        assert_eq!(best(INPUT), Some(FileType::Packfile));
+        assert_eq!(best(pman[10].bytes()), Some(FileType::Zlib));
+
+        for (index, file_type) in [
+            (10, FileType::Model),
+            (60, FileType::Skybox),
+            (66, FileType::Palette),
+            (72, FileType::Texture),
+            (77, FileType::Collision),
+            (78, FileType::Waypoint),
+            (151, FileType::Sound),
+            (152, FileType::Text),
+        ] {
+            assert_eq!(pman[index].detect_type(), Some(file_type), "entry {index}");
+        }
+
+        assert_eq!(best(&[]), None);
+        assert_eq!(best(&[0; 7]), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn detect_text_bom_test() {
+        let guesses = detect(b"\x01\x00\x00\x00\xFF\xFEa\x00\x00\x00");
+
+        assert_eq!(guesses[0].file_type, FileType::Text);
+        assert_eq!(guesses[0].confidence, Confidence::Certain);
+    }
+}
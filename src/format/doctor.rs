@@ -0,0 +1,261 @@
+//! Diagnoses the common problems of an archive, with advice on what to do about them; see
+//! [`diagnose`].
+
+use super::{
+    detect::{self, FileType},
+    pman::{Compression, ParseWarning, PmanFile, PmanVersion},
+    Endianness,
+};
+use crate::error::{Error, ErrorKind};
+use std::fmt;
+
+/// How much a [`Finding`] matters; ordered from the least to the most urgent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Something worth knowing; nothing is wrong.
+    Info,
+    /// The archive can be read, but something about it is unusual; the game might not like it.
+    Warning,
+    /// The archive (or some of its entries) can't be read as it is.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// Something that [`diagnose`] found, and what to do about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub summary: String,
+    pub advice: Option<&'static str>,
+}
+
+impl Finding {
+    fn new(severity: Severity, summary: impl Into<String>, advice: Option<&'static str>) -> Self {
+        Self {
+            severity,
+            summary: summary.into(),
+            advice,
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.summary)?;
+        if let Some(advice) = self.advice {
+            write!(f, "\n  -> {advice}")?;
+        }
+
+        Ok(())
+    }
+}
+
+const RECOPY: &str = "copy the archive again from the game card, the install folder or the `.sis` \
+    installer; the entries before the damage can still be read, and extracted";
+const TABLE: &str = "the entry table is damaged, or the archive was built by another tool; every \
+    entry is still read from its own offset, but the game might read them differently";
+
+/// The severity of `warning`, and what to do about it.
+fn triage(warning: &ParseWarning) -> (Severity, Option<&'static str>) {
+    match warning {
+        ParseWarning::TruncatedArchive { .. } | ParseWarning::TruncatedEntry { .. } => {
+            (Severity::Error, Some(RECOPY))
+        }
+        ParseWarning::EntryOverlap { .. } => (Severity::Error, Some(TABLE)),
+        ParseWarning::EntryOutOfOrder { .. } => (Severity::Warning, Some(TABLE)),
+        ParseWarning::TrailingBytes { .. } => (
+            Severity::Warning,
+            Some(
+                "something was appended to the archive (or it was copied over a bigger file); \
+                 they are kept on repacks, unless `--reproducible` is given",
+            ),
+        ),
+        ParseWarning::LargePadding { .. } => (Severity::Info, None),
+        ParseWarning::NonZeroEntryType { .. } => (
+            Severity::Info,
+            Some("the `type` column is not used by the `1.0.6` release; it is kept as it is"),
+        ),
+        ParseWarning::UnknownEntryType { .. } => (
+            Severity::Info,
+            Some("the entry is read as any other; its type is detected out of its data instead"),
+        ),
+    }
+}
+
+/// What to do about an entry that doesn't decompress; see [`PmanFile::validate`].
+fn zlib_advice(err: &Error) -> &'static str {
+    match err.kind() {
+        ErrorKind::ZlibSizeMismatch => {
+            "the `ZL` header declares another size than what the data inflates into; the entry \
+             was probably replaced by a tool that wrote a wrong header. Import it again with \
+             `rashen`, so the header is written from the data"
+        }
+        ErrorKind::DecompressionLimit => {
+            "the entry inflates into far more data than any entry of the game"
+        }
+        _ => RECOPY,
+    }
+}
+
+/// What `bytes` is, when it is not an archive.
+fn not_an_archive(bytes: &[u8]) -> Finding {
+    let summary = match detect::detect(bytes).first() {
+        _ if bytes.is_empty() => "the file is empty".to_owned(),
+        Some(guess) => format!(
+            "this is not a `PMAN` archive; it looks like {} data ({})",
+            guess.file_type.extension(),
+            guess.reason
+        ),
+        None => "this is not a `PMAN` archive; it doesn't start with `PMAN`".to_owned(),
+    };
+
+    Finding::new(
+        Severity::Error,
+        summary,
+        Some(
+            "give the `packfile.dat` of the game (or its install folder, the `.sis` installer \
+             or an image of the game card); an extracted entry is not an archive",
+        ),
+    )
+}
+
+/// Runs every check on `bytes`: whether it is an archive, of which release, whether it is
+/// truncated, whether its entries overlap, and whether its `ZL` chunks decompress into the size
+/// that they declare. The findings are sorted from the most to the least urgent; there is a
+/// [`Severity::Info`] one, at least.
+#[must_use]
+pub fn diagnose(bytes: &[u8]) -> Vec<Finding> {
+    if !bytes.starts_with(b"PMAN") {
+        return vec![not_an_archive(bytes)];
+    }
+    let pman = match PmanFile::new_lenient(bytes) {
+        Ok(pman) => pman,
+        Err(err) => {
+            return vec![Finding::new(
+                Severity::Error,
+                format!("the archive can't be read; {err}"),
+                Some(RECOPY),
+            )]
+        }
+    };
+
+    let mut findings = vec![Finding::new(
+        Severity::Info,
+        format!(
+            "a `PMAN` archive of {} entries, of the {} release{}",
+            pman.files().len(),
+            pman.version(),
+            match pman.endianness() {
+                Endianness::Little => "",
+                Endianness::Big => "; big-endian",
+            }
+        ),
+        (pman.version() == PmanVersion::Unknown)
+            .then_some("it is not the `1.0.6` release; it might be modded, or another build"),
+    )];
+
+    findings.extend(pman.warnings().iter().map(|warning| {
+        let (severity, advice) = triage(warning);
+
+        Finding::new(severity, warning.to_string(), advice)
+    }));
+    findings.extend(pman.validate().into_iter().map(|(index, err)| {
+        Finding::new(
+            Severity::Error,
+            format!("entry {index}: {err}"),
+            Some(zlib_advice(&err)),
+        )
+    }));
+    for (index, file) in pman.files().iter().enumerate() {
+        if let Compression::Unknown { magic } = file.compression() {
+            findings.push(Finding::new(
+                Severity::Warning,
+                format!(
+                    "entry {index} is compressed with `{}`, not `ZL`",
+                    String::from_utf8_lossy(&magic)
+                ),
+                Some("its data is kept as it is, but it can't be decompressed"),
+            ));
+        }
+    }
+
+    let unknown = pman
+        .files()
+        .iter()
+        .filter(|file| matches!(file.detect_type(), None | Some(FileType::Noise)))
+        .count();
+    if unknown > 0 {
+        findings.push(Finding::new(
+            Severity::Info,
+            format!("{unknown} entries are of an unknown type"),
+            Some("`rashen peek` shows what they start with"),
+        ));
+    }
+    if findings
+        .iter()
+        .all(|finding| finding.severity == Severity::Info)
+    {
+        findings.push(Finding::new(Severity::Info, "no problems found", None));
+    }
+
+    // the sort is stable, so findings of the same severity keep the order they were found in.
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture, format::pman};
+
+    fn summaries(findings: &[Finding], severity: Severity) -> Vec<&str> {
+        findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .map(|finding| finding.summary.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn diagnose_test() -> eyre::Result<()> {
+        let archive = fixture::archive();
+        let healthy = diagnose(&archive);
+
+        assert_eq!(healthy[0].severity, Severity::Info);
+        assert!(healthy[0]
+            .summary
+            .starts_with("a `PMAN` archive of 5 entries"));
+        assert_eq!(
+            healthy.last().map(|f| f.summary.as_str()),
+            Some("no problems found")
+        );
+
+        let truncated = diagnose(&archive[..archive.len() - 8]);
+        assert_eq!(truncated[0].severity, Severity::Error);
+        assert_eq!(truncated[0].advice, Some(RECOPY));
+
+        // a `ZL` header that declares one byte more than its data.
+        let mut files = fixture::files();
+        files[fixture::ZLIB][2] += 1;
+        let mismatch = diagnose(&fixture::pack(fixture::COPYRIGHT, &files));
+        let errors = summaries(&mismatch, Severity::Error);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with(&format!("entry {}: ", fixture::ZLIB)));
+
+        let not_an_archive = diagnose(&pman::compress(b"data")?);
+        assert_eq!(not_an_archive.len(), 1);
+        assert!(not_an_archive[0].summary.contains("zlib"));
+        assert_eq!(diagnose(b"")[0].summary, "the file is empty");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,563 @@
+//! The bitmap fonts of the game; see [`Font`].
+//!
+//! There is no font entry: every glyph is a sprite of the sprite table (entry `148`), whose pixels
+//! are a chunk of entry `150`. Sprites are stored as runs; a table with the offset of every row,
+//! and then every row as the offset of its first opaque pixel, how many opaque pixels follow, and
+//! their palette indices. A row without opaque pixels starts at the width of the sprite, and has
+//! minus the width as its count. The data is padded with zeros to 4 bytes.
+
+use super::{
+    pman::{self, PmanFile},
+    texture::{TextureInfo, TextureTable, SPRITE_STORE, SPRITE_TABLE},
+    Result,
+};
+use crate::error::{Error, ErrorKind};
+use nom::{
+    bytes::complete::take,
+    multi::count,
+    number::complete::{le_u32, u8},
+    Parser,
+};
+use nom_supreme::ParserExt;
+use std::ops::Range;
+
+/// The sprites of the digits of the HUD (the health, the ammo, ...), from `0` to `9`.
+pub const HUD_DIGITS: Range<usize> = 484..494;
+
+/// The opaque pixels of a row of a [`Glyph`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlyphRow {
+    /// How many transparent pixels come before [`Self::pixels`].
+    pub left: u8,
+    /// Indices into a palette; the rest of the row is transparent.
+    pub pixels: Vec<u8>,
+}
+
+/// A character of a [`Font`]; a sprite with the layout of the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Glyph {
+    pub width: u16,
+    pub height: u16,
+    /// Every row, from the top.
+    pub rows: Vec<GlyphRow>,
+}
+
+fn read_glyph_row(width: u16) -> impl Fn(&[u8]) -> Result<'_, GlyphRow> {
+    move |input| {
+        let (rest, left) = u8.context("left").parse(input)?;
+        let (rest, len) = u8.context("pixel count").parse(rest)?;
+
+        if u16::from(left) >= width {
+            return Ok((rest, GlyphRow::default()));
+        }
+        if u16::from(left) + u16::from(len) > width {
+            return super::fail(
+                input,
+                ErrorKind::InvalidFormat,
+                format!("a row of {len} pixels after {left} doesn't fit in {width} pixels"),
+                2,
+            );
+        }
+        let (rest, pixels) = take(len).context("pixels").parse(rest)?;
+
+        Ok((
+            rest,
+            GlyphRow {
+                left,
+                pixels: pixels.to_vec(),
+            },
+        ))
+    }
+}
+
+fn read_glyph(width: u16, height: u16) -> impl Fn(&[u8]) -> Result<'_, Glyph> {
+    move |input| {
+        let (rest, offsets) = count(le_u32, height.into())
+            .context("row offsets")
+            .parse(input)?;
+        let mut rows = Vec::with_capacity(offsets.len());
+
+        for offset in offsets {
+            let Some(row) = input.get(offset as usize..) else {
+                return super::fail(
+                    input,
+                    ErrorKind::InvalidFormat,
+                    format!("a row at {offset:#X} is past the end of the glyph"),
+                    input.len(),
+                );
+            };
+            let (_, row) = read_glyph_row(width).context("row").parse(row)?;
+            rows.push(row);
+        }
+
+        // every row was read from its offset; what is after the table is not read in order.
+        Ok((
+            &rest[rest.len()..],
+            Glyph {
+                width,
+                height,
+                rows,
+            },
+        ))
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidFont {
+        reason: reason.into(),
+    }
+}
+
+impl Glyph {
+    /// Reads a glyph of `width` by `height` pixels; the size is on the row of its sprite, not on
+    /// its data.
+    ///
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid (decompressed) sprite of that size.
+    pub fn new(bytes: &[u8], width: u16, height: u16) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "glyph", read_glyph(width, height))
+    }
+
+    /// The palette index of the pixel at `x` and `y`; `None` if it is transparent.
+    #[must_use]
+    pub fn pixel(&self, x: u16, y: u16) -> Option<u8> {
+        let row = self.rows.get(usize::from(y))?;
+        let x = usize::from(x).checked_sub(usize::from(row.left))?;
+
+        row.pixels.get(x).copied()
+    }
+
+    /// The pixels as 8-bit RGBA, row by row; transparent ones are `[0, 0, 0, 0]`.
+    #[must_use]
+    pub fn to_rgba(&self, colors: &[[u8; 4]]) -> Vec<[u8; 4]> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                self.pixel(x, y)
+                    .and_then(|index| colors.get(usize::from(index)))
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// The (decompressed) sprite of the glyph.
+    ///
+    /// # Errors
+    ///
+    /// If the glyph is wider than 255 pixels (the runs can't start past that), if it doesn't have
+    /// [`Self::height`] rows, or if a row doesn't fit in [`Self::width`].
+    pub fn to_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        let Ok(width) = u8::try_from(self.width) else {
+            return Err(invalid(format!(
+                "a glyph can't be {} pixels wide",
+                self.width
+            )));
+        };
+        if self.rows.len() != usize::from(self.height) {
+            return Err(invalid(format!(
+                "a glyph of {} rows has {}",
+                self.height,
+                self.rows.len()
+            )));
+        }
+
+        let mut offsets = Vec::with_capacity(self.rows.len() * 4);
+        let mut data = Vec::new();
+        for row in &self.rows {
+            // at most 65535 rows of 257 bytes; it fits.
+            #[allow(clippy::cast_possible_truncation)]
+            let offset = (self.rows.len() * 4 + data.len()) as u32;
+            offsets.extend_from_slice(&offset.to_le_bytes());
+
+            if row.pixels.is_empty() {
+                data.extend_from_slice(&[width, width.wrapping_neg()]);
+                continue;
+            }
+            let len = u8::try_from(row.pixels.len())
+                .ok()
+                .filter(|&len| u16::from(row.left) + u16::from(len) <= self.width)
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "a row of {} pixels after {} doesn't fit in {width} pixels",
+                        row.pixels.len(),
+                        row.left
+                    ))
+                })?;
+            data.extend_from_slice(&[row.left, len]);
+            data.extend_from_slice(&row.pixels);
+        }
+
+        offsets.append(&mut data);
+        offsets.resize(offsets.len().next_multiple_of(4), 0);
+
+        Ok(offsets)
+    }
+}
+
+/// A bitmap font; the glyphs of some characters, each one a sprite.
+///
+/// Only the digits of the HUD are known (see [`HUD_DIGITS`]); the text of the menus is drawn with
+/// glyphs that haven't been found yet.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Font {
+    /// The sprites that the glyphs were read from; see [`Self::write`].
+    pub sprites: Range<usize>,
+    pub glyphs: Vec<(char, Glyph)>,
+}
+
+/// The sprite table of `pman`, and the entry with the pixels of its sprites.
+fn sprite_entries(pman: &PmanFile) -> crate::error::Result<(TextureTable, &[u8])> {
+    let entry = |index| {
+        pman.files()
+            .get(index)
+            .map(pman::PmanFileData::bytes)
+            .ok_or_else(|| invalid(format!("the archive has no entry {index}")))
+    };
+
+    Ok((
+        TextureTable::new(entry(SPRITE_TABLE)?)?,
+        entry(SPRITE_STORE)?,
+    ))
+}
+
+/// The data of a glyph, as a chunk of the sprite store; it is only `ZL` compressed if that makes
+/// it smaller (or not bigger), like the sprites of the game.
+fn glyph_chunk(glyph: &Glyph) -> crate::error::Result<(Vec<u8>, usize)> {
+    let data = glyph.to_bytes()?;
+    let compressed = pman::compress(&data)?;
+    let unpacked = data.len();
+
+    Ok((
+        if compressed.len() <= data.len() {
+            compressed
+        } else {
+            data
+        },
+        unpacked,
+    ))
+}
+
+impl Font {
+    /// Reads the sprites of `sprites` as the glyphs of `characters`, in order.
+    ///
+    /// # Errors
+    ///
+    /// If there isn't a sprite for every character, if the sprite table (or the pixels of a
+    /// sprite) can't be read, or if a sprite is not a valid glyph.
+    pub fn read(
+        pman: &PmanFile,
+        sprites: Range<usize>,
+        characters: &str,
+    ) -> crate::error::Result<Self> {
+        let (table, store) = sprite_entries(pman)?;
+        let infos = table
+            .textures
+            .get(sprites.clone())
+            .filter(|infos| infos.len() == characters.chars().count())
+            .ok_or_else(|| {
+                invalid(format!(
+                    "there are not {} sprites at {sprites:?}",
+                    characters.chars().count()
+                ))
+            })?;
+
+        let glyphs = characters
+            .chars()
+            .zip(infos)
+            .map(|(character, info)| {
+                let chunk = info.chunk(store).ok_or_else(|| {
+                    invalid(format!("the glyph of `{character}` is out of bounds"))
+                })?;
+                let data = pman::decompress(chunk)?;
+                let glyph = Glyph::new(data.as_deref().unwrap_or(chunk), info.width, info.height)?;
+
+                Ok((character, glyph))
+            })
+            .collect::<crate::error::Result<_>>()?;
+
+        Ok(Self { sprites, glyphs })
+    }
+
+    /// The digits of the HUD; see [`HUD_DIGITS`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::read`].
+    pub fn hud(pman: &PmanFile) -> crate::error::Result<Self> {
+        Self::read(pman, HUD_DIGITS, "0123456789")
+    }
+
+    #[must_use]
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs
+            .iter()
+            .find(|(c, _)| *c == character)
+            .map(|(_, glyph)| glyph)
+    }
+
+    /// The height of the tallest glyph.
+    #[must_use]
+    pub fn line_height(&self) -> u16 {
+        self.glyphs
+            .iter()
+            .map(|(_, glyph)| glyph.height)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// How many pixels wide `text` is, with the glyphs side by side; `None` if the font doesn't
+    /// have one of its characters.
+    #[must_use]
+    pub fn width_of(&self, text: &str) -> Option<u32> {
+        text.chars()
+            .map(|c| self.glyph(c).map(|glyph| u32::from(glyph.width)))
+            .sum()
+    }
+
+    /// Writes the glyphs into the sprites of `pman`; the first ones replace [`Self::sprites`], and
+    /// the ones past them (e.g. the extra characters of a translation) are added to the end of the
+    /// sprite table. The other sprites are kept as they are, but moved to fit.
+    ///
+    /// Returns the sprite of every glyph, in order.
+    ///
+    /// # Errors
+    ///
+    /// If the sprite table can't be read, if [`Self::sprites`] are not on it, or if a glyph can't
+    /// be written; see [`Glyph::to_bytes`].
+    pub fn write(&self, pman: &mut PmanFile) -> crate::error::Result<Vec<usize>> {
+        let (mut table, store) = sprite_entries(pman)?;
+        if self.sprites.end > table.textures.len() {
+            return Err(invalid(format!(
+                "there are no sprites at {:?}",
+                self.sprites
+            )));
+        }
+        let (replacing, extra) = self
+            .glyphs
+            .split_at(self.glyphs.len().min(self.sprites.len()));
+
+        let mut data = Vec::with_capacity(store.len());
+        let mut place = |info: &mut TextureInfo, chunk: &[u8], unpacked: usize| {
+            info.offset = super::count_u32("sprite bytes", data.len())?;
+            info.size = super::count_u32("sprite bytes", chunk.len())?;
+            info.unpacked_size = super::count_u32("sprite bytes", unpacked)?;
+            data.extend_from_slice(chunk);
+
+            crate::error::Result::Ok(())
+        };
+
+        for (index, info) in table.textures.iter_mut().enumerate() {
+            let glyph = index
+                .checked_sub(self.sprites.start)
+                .and_then(|i| replacing.get(i));
+
+            if let Some((_, glyph)) = glyph {
+                let (chunk, unpacked) = glyph_chunk(glyph)?;
+                (info.width, info.height) = (glyph.width, glyph.height);
+                place(info, &chunk, unpacked)?;
+            } else {
+                let chunk = info
+                    .chunk(store)
+                    .ok_or_else(|| invalid(format!("sprite {index} is out of bounds")))?;
+                let unpacked = info.unpacked_size as usize;
+                place(info, chunk, unpacked)?;
+            }
+        }
+        let added = table.textures.len();
+        for (_, glyph) in extra {
+            let (chunk, unpacked) = glyph_chunk(glyph)?;
+            let mut info = TextureInfo {
+                width: glyph.width,
+                height: glyph.height,
+                ..TextureInfo::default()
+            };
+
+            place(&mut info, &chunk, unpacked)?;
+            table.textures.push(info);
+        }
+
+        pman.files_mut()[SPRITE_TABLE].set_bytes(table.to_bytes());
+        pman.files_mut()[SPRITE_STORE].set_bytes(data);
+
+        Ok((self.sprites.start..self.sprites.start + replacing.len())
+            .chain(added..added + extra.len())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    /// A 4x3 glyph; a bar on top, nothing, and three pixels on the right.
+    fn glyph() -> Glyph {
+        let row = |left, pixels: &[u8]| GlyphRow {
+            left,
+            pixels: pixels.to_vec(),
+        };
+
+        Glyph {
+            width: 4,
+            height: 3,
+            rows: vec![row(0, &[1, 2, 3, 4]), row(0, &[]), row(1, &[5, 6, 7])],
+        }
+    }
+
+    /// An archive whose sprite table has `glyphs`, the first one raw and the rest compressed.
+    fn archive(glyphs: &[Glyph]) -> eyre::Result<PmanFile> {
+        let mut files = vec![vec![]; SPRITE_STORE + 1];
+        let mut table = TextureTable::default();
+
+        for (index, glyph) in glyphs.iter().enumerate() {
+            let data = glyph.to_bytes()?;
+            let chunk = if index == 0 {
+                data.clone()
+            } else {
+                pman::compress(&data)?
+            };
+
+            table.textures.push(TextureInfo {
+                width: glyph.width,
+                height: glyph.height,
+                offset: files[SPRITE_STORE].len().try_into()?,
+                size: chunk.len().try_into()?,
+                unpacked_size: data.len().try_into()?,
+                ..TextureInfo::default()
+            });
+            files[SPRITE_STORE].extend(chunk);
+        }
+        files[SPRITE_TABLE] = table.to_bytes();
+
+        Ok(PmanFile::new(&fixture::pack(fixture::COPYRIGHT, &files))?)
+    }
+
+    #[test]
+    fn glyph_test() -> eyre::Result<()> {
+        let glyph = glyph();
+        let bytes = glyph.to_bytes()?;
+
+        assert_eq!(
+            bytes,
+            [
+                [0x0C, 0, 0, 0, 0x12, 0, 0, 0, 0x14, 0, 0, 0].as_slice(),
+                &[0, 4, 1, 2, 3, 4],
+                // a row without pixels.
+                &[4, 0xFC],
+                &[1, 3, 5, 6, 7],
+                // the padding.
+                &[0, 0, 0],
+            ]
+            .concat()
+        );
+        assert_eq!(Glyph::new(&bytes, 4, 3)?, glyph);
+        assert_eq!((glyph.pixel(3, 0), glyph.pixel(0, 2)), (Some(4), None));
+
+        let colors: Vec<_> = (0..8).map(|index| [index, 0, 0, 0xFF]).collect();
+        let rgba = glyph.to_rgba(&colors);
+        assert_eq!(rgba.len(), 12);
+        assert_eq!(
+            (rgba[0], rgba[4], rgba[11]),
+            ([1, 0, 0, 0xFF], [0; 4], [7, 0, 0, 0xFF])
+        );
+
+        // a row that points past the end, and one that is wider than the glyph.
+        let mut invalid = bytes.clone();
+        invalid[8] = 0x40;
+        assert_eq!(
+            Glyph::new(&invalid, 4, 3).unwrap_err().kind(),
+            ErrorKind::InvalidFormat
+        );
+        assert_eq!(
+            Glyph::new(&bytes, 3, 3).unwrap_err().kind(),
+            ErrorKind::InvalidFormat
+        );
+        let mut wide = glyph.clone();
+        wide.rows[2].left = 2;
+        assert_eq!(wide.to_bytes().unwrap_err().kind(), ErrorKind::InvalidFont);
+
+        Ok(())
+    }
+
+    #[test]
+    fn font_test() -> eyre::Result<()> {
+        let mut tall = glyph();
+        tall.height = 4;
+        tall.rows.push(tall.rows[0].clone());
+        let mut pman = archive(&[glyph(), tall.clone()])?;
+
+        let mut font = Font::read(&pman, 0..2, "01")?;
+        assert_eq!(font.glyph('1'), Some(&tall));
+        assert_eq!(font.line_height(), 4);
+        assert_eq!(font.width_of("0110"), Some(16));
+        assert_eq!(font.width_of("012"), None);
+        assert!(Font::read(&pman, 0..2, "012").is_err());
+
+        // a translation changes a glyph, and adds one.
+        font.glyphs[0].1.rows[1].pixels = vec![7];
+        font.glyphs.push(('2', glyph()));
+        assert_eq!(font.write(&mut pman)?, [0, 1, 2]);
+
+        let pman = PmanFile::new(&pman.into_bytes()?)?;
+        assert_eq!(Font::read(&pman, 0..2, "01")?.glyphs, font.glyphs[..2]);
+        assert_eq!(Font::read(&pman, 2..3, "2")?.glyphs, font.glyphs[2..]);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn font_hud_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(INPUT)?;
+        let font = Font::hud(&pman)?;
+        let sizes: Vec<_> = font
+            .glyphs
+            .iter()
+            .map(|(_, glyph)| (glyph.width, glyph.height))
+            .collect();
+
+        assert_eq!(font.glyphs.len(), 10);
+        assert_eq!(sizes[..3], [(6, 11), (4, 10), (7, 11)]);
+        assert_eq!(font.line_height(), 11);
+        assert!(Font::read(&pman, HUD_DIGITS, "012").is_err());
+
+        // every glyph is written back as it was.
+        let (table, store) = sprite_entries(&pman)?;
+        for (info, (character, glyph)) in table.textures[HUD_DIGITS].iter().zip(&font.glyphs) {
+            let chunk = info.chunk(store).expect("it is inside of the store entry.");
+            let data = pman::decompress(chunk)?;
+
+            assert_eq!(
+                glyph.to_bytes()?,
+                data.as_deref().unwrap_or(chunk),
+                "`{character}`"
+            );
+        }
+
+        // a translation changes a glyph, and adds one.
+        let mut translated = font.clone();
+        translated.glyphs[0].1.rows[1].pixels = vec![7];
+        translated.glyphs.push(('-', font.glyphs[1].1.clone()));
+        let len = table.textures.len();
+        let sprites = translated.write(&mut pman)?;
+        assert_eq!(sprites.len(), 11);
+        assert_eq!(sprites[10], len);
+
+        let pman = PmanFile::new(&pman.into_bytes()?)?;
+        assert_eq!(Font::hud(&pman)?.glyphs, translated.glyphs[..10]);
+        assert_eq!(
+            Font::read(&pman, len..len + 1, "-")?.glyphs,
+            translated.glyphs[10..]
+        );
+
+        Ok(())
+    }
+}
@@ -1,16 +1,198 @@
+pub mod animation;
+pub mod audio;
+pub mod cache;
+pub mod chunk;
+pub mod collision;
+pub mod color;
+pub mod detect;
+pub mod doctor;
+pub mod font;
+pub mod model;
+pub mod palette;
+pub mod patch;
 pub mod pman;
+pub mod pool;
+pub mod schema;
+pub mod skybox;
+pub mod text;
+pub mod texture;
+pub mod waypoint;
 
-pub(super) type Result<'a, T> = nom::IResult<&'a [u8], T>;
+use crate::error::{ErrorKind, InvalidFormatError, NomContext, NomError, Violation};
+
+pub(super) type Result<'a, T> = nom::IResult<&'a [u8], T, NomError<'a>>;
+
+/// (De)serializes arrays of any length as sequences; `serde` only implements them up to 32 items.
+#[cfg(feature = "serde")]
+pub(super) mod serde_array {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, T, const N: usize>(array: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        serializer.collect_seq(array)
+    }
+
+    pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        let items = Vec::<T>::deserialize(deserializer)?;
+        let len = items.len();
+
+        items
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(len, &N.to_string().as_str()))
+    }
+
+    /// Like [`self`], but for boxed arrays.
+    pub mod boxed {
+        use serde::{Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S, T, const N: usize>(
+            array: &[T; N],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+            T: Serialize,
+        {
+            super::serialize(array, serializer)
+        }
+
+        pub fn deserialize<'de, D, T, const N: usize>(
+            deserializer: D,
+        ) -> Result<Box<[T; N]>, D::Error>
+        where
+            D: Deserializer<'de>,
+            T: serde::Deserialize<'de>,
+        {
+            super::deserialize(deserializer).map(Box::new)
+        }
+    }
+}
+
+/// Runs `parser` over the whole `input`, so failures can point at an offset of it.
+pub(super) fn parse_all<'a, T, P>(
+    input: &'a [u8],
+    context: NomContext,
+    parser: P,
+) -> crate::error::Result<T>
+where
+    P: nom::Parser<&'a [u8], T, NomError<'a>>,
+{
+    use nom::Parser;
+    use nom_supreme::ParserExt;
+
+    match parser.context(context).parse(input) {
+        Ok((_, output)) => Ok(output),
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+            Err(InvalidFormatError::from(err).into())
+        }
+        Err(nom::Err::Incomplete(_)) => unreachable!("only `complete` parsers are used."),
+    }
+}
+
+/// Fails at `input` with a custom `message` that labels the next `len` bytes.
+pub(super) fn fail<S, T>(input: &[u8], kind: ErrorKind, message: S, len: usize) -> Result<'_, T>
+where
+    S: Into<String>,
+{
+    fail_with(input, Violation::new(kind, message, len))
+}
+
+/// Like [`fail`], but with a [`Violation`] that can carry its own help.
+pub(super) fn fail_with<T>(input: &[u8], violation: Violation) -> Result<'_, T> {
+    use nom::error::{ErrorKind, FromExternalError};
+
+    Err(nom::Err::Error(NomError::from_external_error(
+        input,
+        ErrorKind::Verify,
+        violation,
+    )))
+}
+
+/// Runs `parser`, and tags its failures with `kind`; unless it already failed with a
+/// [`Violation`] of its own.
+pub(super) fn with_kind<'a, T, P>(
+    kind: ErrorKind,
+    mut parser: P,
+) -> impl FnMut(&'a [u8]) -> Result<'a, T>
+where
+    P: nom::Parser<&'a [u8], T, NomError<'a>>,
+{
+    move |input| {
+        parser
+            .parse(input)
+            .map_err(|err| err.map(|err| crate::error::with_kind(err, kind)))
+    }
+}
+
+/// Converts the length of a list into the `u32` count that is written before it.
+pub(super) fn count_u32(what: &'static str, len: usize) -> crate::error::Result<u32> {
+    u32::try_from(len).map_err(|_| crate::error::Error::TooManyItems { what, len })
+}
 
 /// Reads an `u32` and verifies if it is zero.
-pub(super) fn u32_zero(input: &[u8]) -> Result<u32> {
-    use nom::{combinator::verify, number::complete::le_u32};
+pub(super) fn u32_zero(input: &[u8]) -> Result<'_, u32> {
+    Endianness::Little.u32_zero(input)
+}
+
+/// The byte order of the numbers of a `packfile.dat`.
+///
+/// The N-Gage releases are little-endian; builds of the engine for big-endian consoles write the
+/// header and the entry table the other way around. Only the container is read with it; the data
+/// of the entries is read as it is on the `1.0.6` release.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
 
-    verify(le_u32, |x| *x == 0)(input)
+impl Endianness {
+    /// Guesses the byte order of `count`, the number of entries of an archive; it is small enough
+    /// that the wrong byte order reads it as a much bigger number. Ties (e.g. `0`) are
+    /// little-endian.
+    pub(super) fn detect(count: [u8; 4]) -> Self {
+        if u32::from_be_bytes(count) < u32::from_le_bytes(count) {
+            Self::Big
+        } else {
+            Self::Little
+        }
+    }
+
+    /// Reads an `u32` in this byte order.
+    pub(super) fn u32(self, input: &[u8]) -> Result<'_, u32> {
+        use nom::number::{complete::u32, Endianness};
+
+        u32(match self {
+            Self::Little => Endianness::Little,
+            Self::Big => Endianness::Big,
+        })(input)
+    }
+
+    /// Like [`u32_zero`], but in this byte order.
+    pub(super) fn u32_zero(self, input: &[u8]) -> Result<'_, u32> {
+        nom::combinator::verify(|input| self.u32(input), |x| *x == 0)(input)
+    }
+
+    /// The bytes of `value`, in this byte order.
+    pub(super) fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            Self::Little => value.to_le_bytes(),
+            Self::Big => value.to_be_bytes(),
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub(super) struct FileEntry {
+    /// Always `0` on the `1.0.6` release.
+    pub r#type: u32,
     pub offset: usize,
     pub size: usize,
 }
@@ -18,28 +200,27 @@ pub(super) struct FileEntry {
 impl FileEntry {
     pub(super) fn new(offset: u32, size: u32) -> Self {
         Self {
+            r#type: 0,
             offset: offset as usize,
             size: size as usize,
         }
     }
 
-    pub(super) fn with_size(mut self, size: u32) -> Self {
-        self.size = size as usize;
+    pub(super) fn with_type(mut self, r#type: u32) -> Self {
+        self.r#type = r#type;
 
         self
     }
 
-    pub(super) fn from_bytes(input: &[u8]) -> Result<Self> {
-        #[rustfmt::skip]
-        use nom::{
-            multi::fill,
-            number::complete::le_u32,
-        };
+    pub(super) fn from_bytes(input: &[u8], endianness: Endianness) -> Result<'_, Self> {
+        use nom::multi::fill;
 
-        let mut fields = [0; 2];
-        // let (input, _) = terminated(fill(le_u32, &mut fields), u32_zero)(input)?;
-        let (input, _) = fill(le_u32, &mut fields)(input)?;
+        let mut fields = [0; 3];
+        let (input, ()) = fill(|input| endianness.u32(input), &mut fields)(input)?;
 
-        Ok((input, FileEntry::new(fields[0], fields[1])))
+        Ok((
+            input,
+            FileEntry::new(fields[1], fields[2]).with_type(fields[0]),
+        ))
     }
 }
@@ -0,0 +1,497 @@
+use super::{
+    animation::{self, Animation, ANIMATION_SIZE},
+    Result,
+};
+use crate::error::{Error, ErrorKind};
+use nom::{
+    bytes::complete::take,
+    combinator::{eof, verify},
+    multi::{count, fill},
+    number::complete::{le_i32, le_u16, le_u32},
+    Parser,
+};
+use nom_supreme::ParserExt;
+
+const HEADER_SIZE: usize = 60;
+const FACE_SIZE: usize = 18;
+/// The size of the scale, the translation and the unknown `u32` of a frame.
+const FRAME_HEADER_SIZE: usize = 28;
+const VERTEX_SIZE: usize = 4;
+const DATA_ALIGNMENT: usize = 4;
+/// The scale of the 16.16 fixed point components of [`Frame::scale`] and [`Frame::translate`].
+const FIXED_SCALE: f32 = 65_536.0;
+
+/// A triangle of a [`Model`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Face {
+    /// Indices into [`Frame::vertices`].
+    pub vertices: [u16; 3],
+    /// The texture coordinates of every vertex, in texels of [`Model::texture`].
+    pub uvs: [[u16; 2]; 3],
+}
+
+/// A pose of a [`Model`]; static models only have one.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frame {
+    /// As 16.16 fixed point numbers.
+    pub scale: [i32; 3],
+    /// As 16.16 fixed point numbers.
+    pub translate: [i32; 3],
+    // TODO(Unavailable): it changes on every frame; maybe a bounding radius?
+    pub unknown: u32,
+    /// The compressed position of every vertex (`x`, `y`, `z`); see [`Self::positions`].
+    // TODO(Unavailable): the 4th byte is likely a normal index, like on `md2` models.
+    pub vertices: Vec<[u8; 4]>,
+    // TODO(Unavailable): there is one byte for each face; probably normals too.
+    pub face_data: Vec<u8>,
+}
+
+impl Frame {
+    /// The position of every vertex; `byte / 256 * scale + translate`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn positions(&self) -> Vec<[f32; 3]> {
+        let scale = self.scale.map(|x| x as f32 / FIXED_SCALE);
+        let translate = self.translate.map(|x| x as f32 / FIXED_SCALE);
+
+        self.vertices
+            .iter()
+            .map(|v| std::array::from_fn(|i| f32::from(v[i]) / 256.0 * scale[i] + translate[i]))
+            .collect()
+    }
+}
+
+/// The models of the game; enemies, weapons and pickups.
+///
+/// Every model is a single textured mesh, animated by swapping whole frames (vertex animation);
+/// there is no sub-object hierarchy.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Model {
+    pub faces: Vec<Face>,
+    pub texture_width: u32,
+    pub texture_height: u32,
+    /// Indices into a palette, row by row.
+    // TODO(Unavailable): find out which palette the models use.
+    pub texture: Vec<u8>,
+    pub frames: Vec<Frame>,
+    /// Static models have a single one, with their only frame.
+    pub animations: Vec<Animation>,
+}
+
+/// The offsets of every section of a model, which are all derived from its counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Layout {
+    texture: usize,
+    frames: usize,
+    frame_size: usize,
+    animations: usize,
+}
+
+impl Layout {
+    fn new(faces: usize, vertices: usize, texture_size: usize, frames: usize) -> Self {
+        // the counts come from the header, so they can't be trusted to not overflow.
+        let align =
+            |x: usize| x.saturating_add(DATA_ALIGNMENT - 1) / DATA_ALIGNMENT * DATA_ALIGNMENT;
+        let texture = faces.saturating_mul(FACE_SIZE).saturating_add(HEADER_SIZE);
+        let frame_size = align(
+            vertices
+                .saturating_mul(VERTEX_SIZE)
+                .saturating_add(FRAME_HEADER_SIZE + faces),
+        );
+        let frames_offset = align(texture.saturating_add(texture_size));
+
+        Self {
+            texture,
+            frames: frames_offset,
+            frame_size,
+            animations: frames
+                .saturating_mul(frame_size)
+                .saturating_add(frames_offset),
+        }
+    }
+}
+
+struct Header {
+    face_count: usize,
+    vertex_count: usize,
+    texture_width: u32,
+    texture_height: u32,
+    frame_count: usize,
+    animation_count: usize,
+    layout: Layout,
+}
+
+fn read_header(input: &[u8]) -> Result<'_, Header> {
+    let (rest, header) = super::with_kind(ErrorKind::TruncatedHeader, take(HEADER_SIZE))
+        .context("header")
+        .parse(input)?;
+    let mut fields = [0; HEADER_SIZE / 4];
+    let (_, ()) = fill(le_u32, &mut fields)(header)?;
+    let field = |i: usize| fields[i] as usize;
+    let (face_count, vertex_count, frame_count) = (field(0), field(1), field(4));
+    let (texture_width, texture_height) = (fields[2], fields[3]);
+    let texture_size = (texture_width as usize).saturating_mul(texture_height as usize);
+
+    let layout = Layout::new(face_count, vertex_count, texture_size, frame_count);
+    // the frame size, then the offsets of the texture, the faces, the frames and the animations.
+    let actual = [5, 7, 8, 9, 10].map(field);
+    let expected = [
+        layout.frame_size,
+        layout.texture,
+        HEADER_SIZE,
+        layout.frames,
+        layout.animations,
+    ];
+    if actual != expected || fields[11..].iter().any(|&x| x != 0) {
+        return super::fail(
+            input,
+            ErrorKind::InvalidFormat,
+            "the offsets of the header don't match its counts",
+            HEADER_SIZE,
+        );
+    }
+
+    Ok((
+        rest,
+        Header {
+            face_count,
+            vertex_count,
+            texture_width,
+            texture_height,
+            frame_count,
+            animation_count: field(6),
+            layout,
+        },
+    ))
+}
+
+fn read_face(input: &[u8]) -> Result<'_, Face> {
+    let mut fields = [0; 9];
+    let (input, ()) = fill(le_u16, &mut fields)(input)?;
+    let [v0, u0, t0, v1, u1, t1, v2, u2, t2] = fields;
+
+    Ok((
+        input,
+        Face {
+            vertices: [v0, v1, v2],
+            uvs: [[u0, t0], [u1, t1], [u2, t2]],
+        },
+    ))
+}
+
+fn read_vertex(input: &[u8]) -> Result<'_, [u8; 4]> {
+    let (input, vertex) = take(VERTEX_SIZE)(input)?;
+    let mut buf = [0; VERTEX_SIZE];
+    buf.copy_from_slice(vertex);
+
+    Ok((input, buf))
+}
+
+fn read_frame<'a>(input: &'a [u8], header: &Header) -> Result<'a, Frame> {
+    let mut scale = [0; 3];
+    let mut translate = [0; 3];
+    let (input, ()) = fill(le_i32, &mut scale)(input)?;
+    let (input, ()) = fill(le_i32, &mut translate)(input)?;
+    let (input, unknown) = le_u32(input)?;
+    let (input, vertices) = count(read_vertex, header.vertex_count)
+        .context("vertices")
+        .parse(input)?;
+    let (input, face_data) = take(header.face_count).context("face data").parse(input)?;
+    let len = FRAME_HEADER_SIZE + header.vertex_count * VERTEX_SIZE + header.face_count;
+    let (input, _) = padding(input, header.layout.frame_size - len)?;
+
+    Ok((
+        input,
+        Frame {
+            scale,
+            translate,
+            unknown,
+            vertices,
+            face_data: face_data.to_vec(),
+        },
+    ))
+}
+
+/// The `len` zeroed bytes that align the next section.
+fn padding(input: &[u8], len: usize) -> Result<'_, &[u8]> {
+    verify(take(len), |bytes: &[u8]| bytes.iter().all(|&b| b == 0))
+        .context("padding")
+        .parse(input)
+}
+
+fn read_model(input: &[u8]) -> Result<'_, Model> {
+    let (input, header) = read_header(input)?;
+    let (input, faces) = count(read_face, header.face_count)
+        .context("faces")
+        .parse(input)?;
+    let texture_size =
+        (header.texture_width as usize).saturating_mul(header.texture_height as usize);
+    let (input, texture) = take(texture_size).context("texture").parse(input)?;
+    let (input, _) = padding(
+        input,
+        header.layout.frames - header.layout.texture - texture_size,
+    )?;
+    let (input, frames) = count(|i| read_frame(i, &header), header.frame_count)
+        .context("frames")
+        .parse(input)?;
+    let (input, animations) = animation::read_animations(
+        input,
+        header.layout.animations,
+        header.animation_count,
+        header.frame_count,
+    )?;
+    let (input, _) = eof.context("end of data").parse(input)?;
+
+    Ok((
+        input,
+        Model {
+            faces,
+            texture_width: header.texture_width,
+            texture_height: header.texture_height,
+            texture: texture.to_vec(),
+            frames,
+            animations,
+        },
+    ))
+}
+
+impl Model {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid (decompressed) model entry.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "model", read_model)
+    }
+
+    /// The vertex count of every frame.
+    #[must_use]
+    pub fn vertex_count(&self) -> usize {
+        self.frames.first().map_or(0, |frame| frame.vertices.len())
+    }
+
+    /// Every frame as a morph target of the first one (the base pose); how far every vertex moves
+    /// from it. Models have no skeleton to rig, so animations blend these targets instead; see
+    /// [`Animation::morph_weights`].
+    #[must_use]
+    pub fn morph_targets(&self) -> Vec<Vec<[f32; 3]>> {
+        let base = self
+            .frames
+            .first()
+            .map(Frame::positions)
+            .unwrap_or_default();
+
+        self.frames
+            .iter()
+            .map(|frame| {
+                frame
+                    .positions()
+                    .iter()
+                    .zip(&base)
+                    .map(|(p, b)| std::array::from_fn(|i| p[i] - b[i]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// # Errors
+    ///
+    /// If [`Self::texture`] doesn't match its dimensions, if the frames don't have the same
+    /// vertex count, or one byte of [`Frame::face_data`] for each face, or if an animation plays a
+    /// frame that doesn't exist.
+    pub fn to_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        use super::count_u32;
+
+        let texture_size =
+            (self.texture_width as usize).saturating_mul(self.texture_height as usize);
+        if self.texture.len() != texture_size {
+            return Err(Error::InvalidImageSize {
+                width: self.texture_width,
+                height: self.texture_height,
+                len: self.texture.len(),
+            });
+        }
+
+        let vertex_count = self.vertex_count();
+        for (index, frame) in self.frames.iter().enumerate() {
+            let reason = if frame.vertices.len() != vertex_count {
+                format!("frame {index} doesn't have {vertex_count} vertices, like frame 0")
+            } else if frame.face_data.len() != self.faces.len() {
+                format!("frame {index} doesn't have a byte for each face")
+            } else {
+                continue;
+            };
+
+            return Err(Error::InvalidModel { reason });
+        }
+
+        let layout = Layout::new(
+            self.faces.len(),
+            vertex_count,
+            texture_size,
+            self.frames.len(),
+        );
+        let frame_count = self
+            .animations
+            .iter()
+            .map(|a| a.frames.len())
+            .sum::<usize>();
+        let size = layout.animations + self.animations.len() * ANIMATION_SIZE + frame_count * 4;
+        let offset = |x: usize| u32::try_from(x).map_err(|_| Error::ArchiveTooLarge { size });
+        let header = [
+            count_u32("faces", self.faces.len())?,
+            count_u32("vertices", vertex_count)?,
+            self.texture_width,
+            self.texture_height,
+            count_u32("frames", self.frames.len())?,
+            offset(layout.frame_size)?,
+            count_u32("animations", self.animations.len())?,
+            offset(layout.texture)?,
+            offset(HEADER_SIZE)?,
+            offset(layout.frames)?,
+            offset(layout.animations)?,
+            0,
+            0,
+            0,
+            0,
+        ];
+        let mut buf = Vec::with_capacity(size);
+
+        for x in header {
+            buf.extend_from_slice(&x.to_le_bytes());
+        }
+        for face in &self.faces {
+            for (vertex, [u, v]) in face.vertices.iter().zip(face.uvs) {
+                buf.extend_from_slice(&vertex.to_le_bytes());
+                buf.extend_from_slice(&u.to_le_bytes());
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        buf.extend_from_slice(&self.texture);
+        buf.resize(layout.frames, 0);
+
+        for frame in &self.frames {
+            for x in frame.scale.iter().chain(&frame.translate) {
+                buf.extend_from_slice(&x.to_le_bytes());
+            }
+            buf.extend_from_slice(&frame.unknown.to_le_bytes());
+            for vertex in &frame.vertices {
+                buf.extend_from_slice(vertex);
+            }
+            buf.extend_from_slice(&frame.face_data);
+            buf.resize(buf.len().next_multiple_of(DATA_ALIGNMENT), 0);
+        }
+
+        animation::write_animations(
+            &mut buf,
+            &self.animations,
+            layout.animations,
+            self.frames.len(),
+        )?;
+
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    /// The model entries; the first 12 are animated.
+    #[cfg(feature = "packfile")]
+    const MODEL_ENTRIES: std::ops::Range<usize> = 10..60;
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn model_new_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = pman[10].to_zlib()?.expect("models are compressed.");
+        let model = Model::new(&bytes)?;
+
+        assert_eq!(model.faces.len(), 230);
+        assert_eq!(model.vertex_count(), 118);
+        assert_eq!((model.texture_width, model.texture_height), (256, 256));
+        assert_eq!(model.frames.len(), 266);
+        assert_eq!(model.animations.len(), 19);
+        assert_eq!(model.animations[0].frame_range(), Some(0..30));
+        assert_eq!(model.faces[0].vertices, [17, 15, 18]);
+        assert_eq!(model.faces[0].uvs[0], [164, 9]);
+        assert_eq!(model.frames[0].positions().len(), 118);
+
+        Ok(())
+    }
+
+    #[test]
+    fn model_morph_targets_test() {
+        let frame = |x| Frame {
+            scale: [256 << 16; 3],
+            vertices: vec![[x, 0, 0, 0], [0, x, 0, 0]],
+            ..Default::default()
+        };
+        let model = Model {
+            frames: vec![frame(1), frame(3)],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            model.morph_targets(),
+            [
+                [[0.0; 3]; 2].to_vec(),
+                vec![[2.0, 0.0, 0.0], [0.0, 2.0, 0.0]]
+            ]
+        );
+        assert!(Model::default().morph_targets().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn model_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in MODEL_ENTRIES {
+            let bytes = pman[index].to_zlib()?.expect("models are compressed.");
+
+            assert_eq!(Model::new(&bytes)?.to_bytes()?, bytes, "entry {index}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn model_invalid_test() -> eyre::Result<()> {
+        let mut model = Model {
+            faces: vec![Face::default()],
+            texture_width: 2,
+            texture_height: 3,
+            texture: vec![0; 6],
+            frames: vec![Frame {
+                vertices: vec![[0; 4]; 3],
+                face_data: vec![0],
+                ..Default::default()
+            }],
+            animations: vec![Animation { frames: vec![0] }],
+        };
+        let bytes = model.to_bytes()?;
+
+        assert_eq!(Model::new(&bytes)?, model);
+        assert_eq!(
+            Model::new(&bytes[..bytes.len() - 1]).unwrap_err().kind(),
+            ErrorKind::InvalidFormat
+        );
+
+        model.frames[0].face_data.clear();
+
+        assert_eq!(
+            model.to_bytes().unwrap_err().kind(),
+            ErrorKind::InvalidModel
+        );
+
+        Ok(())
+    }
+}
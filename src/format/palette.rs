@@ -0,0 +1,165 @@
+use super::Result;
+use crate::error::ErrorKind;
+use nom::{multi::fill, number::complete::le_u16, Parser};
+use nom_supreme::ParserExt;
+
+pub const COLOR_COUNT: usize = 256;
+pub const PALETTE_SIZE: usize = COLOR_COUNT * 2;
+/// The bits used by a `0x0RGB` color.
+const RGB444_MASK: u16 = 0x0FFF;
+
+pub use super::color::{rgb444_to_rgba, rgba_to_rgb444};
+
+/// A table of 256 `0x0RGB` (4 bits per channel) colors, that indexed textures point into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_array"))]
+    pub colors: [u16; COLOR_COUNT],
+}
+
+/// Reads a palette that is embedded on another entry; e.g. the header of a texture.
+pub(super) fn read_palette(input: &[u8]) -> Result<'_, Palette> {
+    let mut colors = [0; COLOR_COUNT];
+    let (rest, ()) = fill(le_u16, &mut colors).context("palette").parse(input)?;
+
+    if let Some(index) = colors.iter().position(|c| c & !RGB444_MASK != 0) {
+        return super::fail(
+            &input[index * 2..],
+            ErrorKind::InvalidFormat,
+            format!("color {index} is not a `0x0RGB` color"),
+            2,
+        );
+    }
+
+    Ok((rest, Palette { colors }))
+}
+
+impl Palette {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid palette entry.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        use nom::combinator::eof;
+
+        super::parse_all(bytes, "palette", |input| {
+            let (input, palette) = read_palette(input)?;
+            let (input, _) = eof.context("end of data").parse(input)?;
+
+            Ok((input, palette))
+        })
+    }
+
+    /// Creates a palette from 8-bit RGBA colors; see [`rgba_to_rgb444`].
+    #[must_use]
+    pub fn from_rgba(colors: &[[u8; 4]; COLOR_COUNT]) -> Self {
+        Self {
+            colors: colors.map(rgba_to_rgb444),
+        }
+    }
+
+    #[must_use]
+    pub fn to_rgba(&self) -> [[u8; 4]; COLOR_COUNT] {
+        self.colors.map(rgb444_to_rgba)
+    }
+
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.colors.iter().flat_map(|c| c.to_le_bytes()).collect()
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            colors: [0; COLOR_COUNT],
+        }
+    }
+}
+
+/// The layout of a palette entry; see [`super::schema`].
+pub(super) fn schema() -> super::schema::Format {
+    use super::schema::{Field, Format, Size, TypeDef};
+
+    Format {
+        id: "palette",
+        title: "Ashen palette",
+        imports: vec![],
+        root: TypeDef {
+            id: "palette",
+            doc: None,
+            fields: vec![Field::primitive("colors", "u2")
+                .repeat(Size::Fixed(COLOR_COUNT))
+                .doc(format!(
+                    "`0x0RGB`; 4 bits per channel, masked by {RGB444_MASK:#06X}."
+                ))],
+        },
+        types: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    /// The standalone palette entries.
+    #[cfg(feature = "packfile")]
+    const PALETTE_ENTRIES: [usize; 6] = [66, 67, 68, 69, 70, 71];
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn palette_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in PALETTE_ENTRIES {
+            let bytes = pman[index].bytes();
+            let palette = Palette::new(bytes)?;
+
+            assert_eq!(palette.to_bytes(), bytes, "entry {index}");
+            assert_eq!(Palette::from_rgba(&palette.to_rgba()), palette);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn palette_invalid_test() {
+        let mut bytes = [0; PALETTE_SIZE];
+        bytes[3] = 0xF0;
+
+        let err = Palette::new(&bytes).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidFormat);
+        assert!(Palette::new(&bytes[..10]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn palette_serde_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let palette = Palette::new(pman[66].bytes())?;
+
+        let json = serde_json::to_string(&palette)?;
+        assert_eq!(serde_json::from_str::<Palette>(&json)?, palette);
+        assert!(serde_json::from_str::<Palette>("{\"colors\":[0,1]}").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn palette_snapshot_test() -> eyre::Result<()> {
+        // a few colors; the unused ones are black.
+        let mut colors = [0; COLOR_COUNT];
+        colors[..4].copy_from_slice(&[0x0FFF, 0x0F00, 0x00F0, 0x000F]);
+        let palette = Palette { colors };
+
+        crate::snapshot::assert_parsed_snapshot!("palette", Palette::new(&palette.to_bytes())?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,622 @@
+//! Delta patches between two archives; the format of `.rpatch` files.
+//!
+//! A patch only has what is needed to turn an archive into another: the entries that are added,
+//! and copies of (and inserts into) the ones that changed. Entries that are the same on both
+//! archives are not on the patch at all, so it doesn't carry any data of the original archive.
+
+use super::{
+    pman::{self, PmanFile, PmanFileData},
+    pool::BufferPool,
+    Result,
+};
+use crate::error::{Error, ErrorKind};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder};
+use nom::{
+    bytes::complete::take,
+    combinator::{eof, rest},
+    multi::count,
+    number::complete::{le_u16, le_u32, u8 as le_u8},
+    Parser,
+};
+use nom_supreme::{tag::complete::tag, ParserExt};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Read, Write},
+};
+
+/// The extension of patch files, without the leading dot.
+pub const PATCH_EXTENSION: &str = "rpatch";
+const HEADER_MAGIC_STRING: &[u8; 4] = b"RPCH";
+const VERSION: u16 = 1;
+/// The size of the blocks of the old entry that a delta looks for on the new one.
+const BLOCK_SIZE: usize = 16;
+
+/// An instruction of a delta; the new entry is built by running them in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeltaOp {
+    /// Copies `len` bytes of the old entry, from `offset`.
+    Copy { offset: u32, len: u32 },
+    /// Bytes that are not on the old entry.
+    Insert(Vec<u8>),
+}
+
+/// How an entry changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryChange {
+    /// The whole new entry; for the entries that are added, or that have nothing in common with
+    /// the old ones.
+    Replace(Vec<u8>),
+    /// A delta over the stored bytes of the old entry.
+    Delta(Vec<DeltaOp>),
+    /// A delta over the decompressed data of a `ZL` entry; the result is compressed again, see
+    /// [`pman::compress`].
+    ZlibDelta(Vec<DeltaOp>),
+}
+
+/// A change of a single entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPatch {
+    pub index: usize,
+    /// See [`PmanFileData::entry_type`].
+    pub entry_type: u32,
+    /// The CRC-32 of the old entry; `0` if it is added.
+    pub source_crc: u32,
+    /// The CRC-32 of the new entry, to verify that the patch was applied right.
+    pub target_crc: u32,
+    pub change: EntryChange,
+}
+
+/// The changes that turn an archive into another one.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Patch {
+    /// How many entries the old archive has.
+    pub source_count: usize,
+    /// How many entries the new archive has; entries after it are removed.
+    pub target_count: usize,
+    /// Ordered by index.
+    pub entries: Vec<EntryPatch>,
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format: "patch",
+        reason: reason.into(),
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(bytes);
+
+    crc.sum()
+}
+
+/// How many bytes `ops` take on a patch, before it is compressed.
+fn encoded_len(ops: &[DeltaOp]) -> usize {
+    ops.iter()
+        .map(|op| match op {
+            DeltaOp::Copy { .. } => 9,
+            DeltaOp::Insert(bytes) => 5 + bytes.len(),
+        })
+        .sum()
+}
+
+/// Finds the blocks of `source` on `target`; every byte that is not on one is inserted.
+#[allow(clippy::cast_possible_truncation)]
+fn delta(source: &[u8], target: &[u8]) -> Vec<DeltaOp> {
+    let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+    for (i, block) in source.chunks_exact(BLOCK_SIZE).enumerate() {
+        blocks.entry(block).or_insert(i * BLOCK_SIZE);
+    }
+
+    let mut ops = Vec::new();
+    let mut literal = Vec::new();
+    let mut position = 0;
+
+    while position < target.len() {
+        let Some(block) = target.get(position..position + BLOCK_SIZE) else {
+            literal.extend_from_slice(&target[position..]);
+            break;
+        };
+        // the same offset is the most likely match; e.g. if a few bytes were changed in place.
+        let found = (source.get(position..position + BLOCK_SIZE) == Some(block))
+            .then_some(position)
+            .or_else(|| blocks.get(block).copied());
+
+        let Some(mut offset) = found else {
+            literal.push(target[position]);
+            position += 1;
+            continue;
+        };
+
+        let forward = source[offset..]
+            .iter()
+            .zip(&target[position..])
+            .take_while(|(a, b)| a == b)
+            .count();
+        let mut len = forward;
+        // the bytes before the match might be on the old entry too.
+        while offset > 0 && literal.last() == Some(&source[offset - 1]) {
+            literal.pop();
+            offset -= 1;
+            len += 1;
+        }
+
+        if !literal.is_empty() {
+            ops.push(DeltaOp::Insert(std::mem::take(&mut literal)));
+        }
+        // entries can't be larger than `u32::MAX` bytes; see `PmanFile::into_bytes`.
+        ops.push(DeltaOp::Copy {
+            offset: offset as u32,
+            len: len as u32,
+        });
+        position += forward;
+    }
+
+    if !literal.is_empty() {
+        ops.push(DeltaOp::Insert(literal));
+    }
+
+    ops
+}
+
+/// Runs `ops` over `source`.
+fn apply_delta(source: &[u8], ops: &[DeltaOp]) -> crate::error::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(source.len());
+
+    for op in ops {
+        match op {
+            &DeltaOp::Copy { offset, len } => {
+                let (offset, len) = (offset as usize, len as usize);
+                let bytes = source.get(offset..offset + len).ok_or_else(|| {
+                    invalid(format!(
+                        "a copy of {len:#X} bytes at {offset:#X} is out of the old entry"
+                    ))
+                })?;
+
+                buf.extend_from_slice(bytes);
+            }
+            DeltaOp::Insert(bytes) => buf.extend_from_slice(bytes),
+        }
+    }
+
+    Ok(buf)
+}
+
+/// The smallest change that turns `source` into `target`; the scratch buffers are taken from
+/// `pool`.
+fn diff_entry(
+    source: &[u8],
+    target: &[u8],
+    pool: &BufferPool,
+) -> crate::error::Result<EntryChange> {
+    let mut best = EntryChange::Replace(target.to_vec());
+    let mut best_len = target.len();
+    let mut consider = |change: EntryChange, len: usize| {
+        if len < best_len {
+            best = change;
+            best_len = len;
+        }
+    };
+
+    let ops = delta(source, target);
+    let len = encoded_len(&ops);
+    consider(EntryChange::Delta(ops), len);
+
+    // deltas of compressed data are useless after the first change, so try the decompressed data;
+    // only if compressing it again gives the same bytes back.
+    if let (Ok(Some(source)), Ok(Some(target_zlib))) = (
+        pman::decompress_with(source, pool),
+        pman::decompress_with(target, pool),
+    ) {
+        let recompressed = pman::compress_with(&target_zlib, pool)?;
+        if recompressed == target {
+            let ops = delta(&source, &target_zlib);
+            let len = encoded_len(&ops);
+            consider(EntryChange::ZlibDelta(ops), len);
+        }
+
+        for buffer in [source, target_zlib, recompressed] {
+            pool.give(buffer);
+        }
+    }
+
+    Ok(best)
+}
+
+fn read_op(input: &[u8]) -> Result<'_, DeltaOp> {
+    let (input, tag) = le_u8.context("delta tag").parse(input)?;
+
+    match tag {
+        0 => {
+            let (input, (offset, len)) = le_u32.and(le_u32).context("copy").parse(input)?;
+            Ok((input, DeltaOp::Copy { offset, len }))
+        }
+        1 => {
+            let (input, len) = le_u32.context("insert size").parse(input)?;
+            let (input, bytes) = take(len).context("insert").parse(input)?;
+            Ok((input, DeltaOp::Insert(bytes.to_vec())))
+        }
+        _ => super::fail(
+            input,
+            ErrorKind::InvalidFormat,
+            format!("`{tag}` is not a delta operation"),
+            1,
+        ),
+    }
+}
+
+fn read_delta(input: &[u8]) -> Result<'_, Vec<DeltaOp>> {
+    let (input, op_count) = le_u32.context("operation count").parse(input)?;
+
+    count(read_op, op_count as usize).parse(input)
+}
+
+fn read_entry(input: &[u8]) -> Result<'_, EntryPatch> {
+    let (input, fields) = count(le_u32, 4).context("entry header").parse(input)?;
+    let (input, kind) = le_u8.context("change kind").parse(input)?;
+    let (input, change) = match kind {
+        0 => {
+            let (input, len) = le_u32.context("entry size").parse(input)?;
+            let (input, bytes) = take(len).context("entry").parse(input)?;
+            (input, EntryChange::Replace(bytes.to_vec()))
+        }
+        1 => {
+            let (input, ops) = read_delta(input)?;
+            (input, EntryChange::Delta(ops))
+        }
+        2 => {
+            let (input, ops) = read_delta(input)?;
+            (input, EntryChange::ZlibDelta(ops))
+        }
+        _ => {
+            return super::fail(
+                input,
+                ErrorKind::InvalidFormat,
+                format!("`{kind}` is not a kind of change"),
+                1,
+            )
+        }
+    };
+
+    Ok((
+        input,
+        EntryPatch {
+            index: fields[0] as usize,
+            entry_type: fields[1],
+            source_crc: fields[2],
+            target_crc: fields[3],
+            change,
+        },
+    ))
+}
+
+fn read_body(input: &[u8]) -> Result<'_, Patch> {
+    let (input, (source_count, target_count)) =
+        le_u32.and(le_u32).context("entry counts").parse(input)?;
+    let (input, entry_count) = le_u32.context("change count").parse(input)?;
+    let (input, entries) = count(read_entry, entry_count as usize)
+        .context("changes")
+        .parse(input)?;
+    let (input, _) = eof.context("end of data").parse(input)?;
+
+    Ok((
+        input,
+        Patch {
+            source_count: source_count as usize,
+            target_count: target_count as usize,
+            entries,
+        },
+    ))
+}
+
+fn push_delta(buf: &mut Vec<u8>, ops: &[DeltaOp]) -> crate::error::Result<()> {
+    buf.extend_from_slice(&super::count_u32("delta operations", ops.len())?.to_le_bytes());
+
+    for op in ops {
+        match op {
+            DeltaOp::Copy { offset, len } => {
+                buf.push(0);
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&len.to_le_bytes());
+            }
+            DeltaOp::Insert(bytes) => {
+                buf.push(1);
+                buf.extend_from_slice(
+                    &super::count_u32("inserted bytes", bytes.len())?.to_le_bytes(),
+                );
+                buf.extend_from_slice(bytes);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Patch {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid patch, or if it was made by a newer version.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        let (body, version) = super::parse_all(bytes, "patch header", |input| {
+            let (input, _) = super::with_kind(ErrorKind::BadMagic, tag(&HEADER_MAGIC_STRING[..]))
+                .context("magic string")
+                .parse(input)?;
+            let (input, version) = le_u16.context("version").parse(input)?;
+            let (input, body) = rest(input)?;
+
+            Ok((input, (body, version)))
+        })?;
+
+        if version != VERSION {
+            return Err(invalid(format!(
+                "version {version} is not supported; expected {VERSION}"
+            )));
+        }
+
+        let mut buf = Vec::new();
+        ZlibDecoder::new(body)
+            .read_to_end(&mut buf)
+            .map_err(|err| invalid(format!("the body is not a zlib stream; {err}")))?;
+
+        super::parse_all(&buf, "patch", read_body)
+    }
+
+    /// Finds the changes that turn `source` into `target`.
+    ///
+    /// # Errors
+    ///
+    /// If a `ZL` entry can't be compressed again.
+    pub fn diff(source: &PmanFile, target: &PmanFile) -> crate::error::Result<Self> {
+        let mut entries = Vec::new();
+        let pool = BufferPool::new();
+
+        for (index, file) in target.files().iter().enumerate() {
+            let old = source.files().get(index);
+            if old == Some(file) {
+                continue;
+            }
+
+            let change = match old {
+                Some(old) => diff_entry(old.bytes(), file.bytes(), &pool)?,
+                None => EntryChange::Replace(file.bytes().to_vec()),
+            };
+
+            entries.push(EntryPatch {
+                index,
+                entry_type: file.entry_type(),
+                source_crc: old.map_or(0, |old| crc32(old.bytes())),
+                target_crc: crc32(file.bytes()),
+                change,
+            });
+        }
+
+        Ok(Self {
+            source_count: source.files().len(),
+            target_count: target.files().len(),
+            entries,
+        })
+    }
+
+    /// Applies the patch on `pman`; returns the indices of the entries that were added or changed.
+    ///
+    /// Nothing is changed if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If `pman` is not the archive that the patch was made against, or if the patch is broken.
+    pub fn apply(&self, pman: &mut PmanFile) -> crate::error::Result<Vec<usize>> {
+        let files = pman.files();
+        if files.len() != self.source_count {
+            return Err(invalid(format!(
+                "the patch is for an archive of {} entries, but this one has {}",
+                self.source_count,
+                files.len()
+            )));
+        }
+
+        let mut patched = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let index = entry.index;
+            if index >= self.target_count {
+                return Err(invalid(format!("entry {index} is out of the new archive")));
+            }
+
+            let source = files.get(index).map_or(&[][..], PmanFileData::bytes);
+            if index < files.len() && crc32(source) != entry.source_crc {
+                return Err(invalid(format!(
+                    "entry {index} is not the one that the patch was made against"
+                )));
+            }
+
+            let bytes = match &entry.change {
+                EntryChange::Replace(bytes) => bytes.clone(),
+                EntryChange::Delta(ops) => apply_delta(source, ops)?,
+                EntryChange::ZlibDelta(ops) => {
+                    let source = pman::decompress(source)?
+                        .ok_or_else(|| invalid(format!("entry {index} is not compressed")))?;
+
+                    pman::compress(&apply_delta(&source, ops)?)?
+                }
+            };
+            if crc32(&bytes) != entry.target_crc {
+                return Err(invalid(format!(
+                    "entry {index} doesn't match after the patch"
+                )));
+            }
+
+            patched.push((index, entry.entry_type, bytes));
+        }
+
+        let files = pman.files_mut();
+        files.resize_with(self.target_count, PmanFileData::default);
+        let indices = patched.iter().map(|(index, ..)| *index).collect();
+        for (index, entry_type, bytes) in patched {
+            files[index].set_entry_type(entry_type);
+            files[index].set_bytes(bytes);
+        }
+
+        Ok(indices)
+    }
+
+    /// Encodes the patch; the body is zlib compressed.
+    ///
+    /// # Errors
+    ///
+    /// If a count doesn't fit on an `u32`, or if the body can't be compressed.
+    pub fn to_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&super::count_u32("entries", self.source_count)?.to_le_bytes());
+        body.extend_from_slice(&super::count_u32("entries", self.target_count)?.to_le_bytes());
+        body.extend_from_slice(&super::count_u32("changes", self.entries.len())?.to_le_bytes());
+
+        for entry in &self.entries {
+            let index = super::count_u32("entries", entry.index)?;
+            for field in [index, entry.entry_type, entry.source_crc, entry.target_crc] {
+                body.extend_from_slice(&field.to_le_bytes());
+            }
+
+            match &entry.change {
+                EntryChange::Replace(bytes) => {
+                    body.push(0);
+                    body.extend_from_slice(&super::count_u32("bytes", bytes.len())?.to_le_bytes());
+                    body.extend_from_slice(bytes);
+                }
+                EntryChange::Delta(ops) => {
+                    body.push(1);
+                    push_delta(&mut body, ops)?;
+                }
+                EntryChange::ZlibDelta(ops) => {
+                    body.push(2);
+                    push_delta(&mut body, ops)?;
+                }
+            }
+        }
+
+        let mut buf = HEADER_MAGIC_STRING.to_vec();
+        buf.extend_from_slice(&VERSION.to_le_bytes());
+
+        let mut encoder = ZlibEncoder::new(buf, flate2::Compression::best());
+        encoder.write_all(&body)?;
+
+        Ok(encoder.finish()?)
+    }
+}
+
+impl fmt::Display for EntryPatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let index = self.index;
+
+        match &self.change {
+            EntryChange::Replace(bytes) => write!(f, "entry {index}: {:#X} new bytes", bytes.len()),
+            EntryChange::Delta(ops) | EntryChange::ZlibDelta(ops) => {
+                let inserted: usize = ops
+                    .iter()
+                    .map(|op| match op {
+                        DeltaOp::Insert(bytes) => bytes.len(),
+                        DeltaOp::Copy { .. } => 0,
+                    })
+                    .sum();
+
+                write!(
+                    f,
+                    "entry {index}: {} operations, {inserted:#X} new bytes",
+                    ops.len()
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn delta_test() -> crate::error::Result<()> {
+        let source =
+            b"the quick brown fox jumps over the lazy dog, twice: the quick brown fox".to_vec();
+        let mut target = source.clone();
+        target.splice(10..15, *b"red");
+        target.extend_from_slice(b"!");
+
+        let ops = delta(&source, &target);
+        assert_eq!(apply_delta(&source, &ops)?, target);
+        assert!(encoded_len(&ops) < target.len());
+
+        assert_eq!(delta(&[], b"new"), [DeltaOp::Insert(b"new".to_vec())]);
+        assert!(apply_delta(&source, &delta(&source, &[]))?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn patch_round_trip_test() -> eyre::Result<()> {
+        let source = PmanFile::new(&fixture::archive())?;
+        let mut target = source.clone();
+        let (raw, zlib) = (fixture::RAW, fixture::ZLIB);
+
+        // a few bytes of a raw entry, a byte of a compressed one, and a new entry.
+        let mut bytes = target[raw].bytes().to_vec();
+        bytes[100..110].fill(7);
+        target.files_mut()[raw].set_bytes(bytes);
+        let mut text = target[zlib].to_zlib()?.expect("it is compressed.");
+        text[40] ^= 1;
+        target.files_mut()[zlib].set_bytes(pman::compress(&text)?);
+        target.files_mut().push(b"new entry".to_vec().into());
+
+        let patch = Patch::diff(&source, &target)?;
+        let indices: Vec<_> = patch.entries.iter().map(|entry| entry.index).collect();
+        assert_eq!(indices, [raw, zlib, source.files().len()]);
+        assert!(matches!(patch.entries[0].change, EntryChange::Delta(_)));
+        assert!(matches!(patch.entries[1].change, EntryChange::ZlibDelta(_)));
+
+        let bytes = patch.to_bytes()?;
+        assert!(bytes.len() < 0x400, "{:#X}", bytes.len());
+        assert_eq!(Patch::new(&bytes)?, patch);
+
+        let mut patched = source.clone();
+        assert_eq!(patch.apply(&mut patched)?, indices);
+        assert_eq!(patched, target);
+
+        // removed entries.
+        let patch = Patch::diff(&target, &source)?;
+        let mut patched = target.clone();
+        patch.apply(&mut patched)?;
+        assert_eq!(patched, source);
+
+        Ok(())
+    }
+
+    #[test]
+    fn patch_invalid_test() -> eyre::Result<()> {
+        let source = PmanFile::new(&fixture::archive())?;
+        let mut target = source.clone();
+        let mut bytes = target[fixture::RAW].bytes().to_vec();
+        bytes[0] ^= 1;
+        target.files_mut()[fixture::RAW].set_bytes(bytes);
+        let patch = Patch::diff(&source, &target)?;
+
+        // the patch was already applied, so the entry is not the one it expects.
+        let mut patched = target.clone();
+        let err = patch.apply(&mut patched).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidImport);
+        assert_eq!(patched, target);
+
+        let mut bytes = patch.to_bytes()?;
+        assert_eq!(
+            Patch::new(&bytes[..10]).unwrap_err().kind(),
+            ErrorKind::InvalidImport
+        );
+        bytes[4] = 9;
+        assert_eq!(
+            Patch::new(&bytes).unwrap_err().kind(),
+            ErrorKind::InvalidImport
+        );
+        assert_eq!(Patch::new(b"PMAN").unwrap_err().kind(), ErrorKind::BadMagic);
+
+        Ok(())
+    }
+}
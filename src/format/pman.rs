@@ -1,315 +1,4312 @@
-use super::{FileEntry, Result};
-use flate2::read::ZlibDecoder;
+use super::{
+    detect::{self, FileType},
+    pool::BufferPool,
+    with_kind, Endianness, FileEntry, Result,
+};
+use crate::error::{Error, ErrorKind, Violation};
+pub use bytes::Bytes;
+use bytes::BytesMut;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder};
 use nom::{
-    bytes::complete::{tag, take},
-    character::complete::char,
-    combinator::eof,
-    multi::{count, separated_list1},
-    number::complete::le_u32,
-    sequence::{preceded, terminated, Tuple},
+    bytes::complete::take, character::complete::char, combinator::eof, multi::separated_list1,
+    sequence::terminated, Parser,
 };
+use nom_supreme::{tag::complete::tag, ParserExt};
 use std::{
-    io::{self, Read, Write},
+    collections::BTreeMap,
+    fmt,
+    io::{self, Read, Seek, SeekFrom, Write},
     mem::size_of,
-    ops::Index,
+    ops::{Deref, Index, Range},
+    str::FromStr,
 };
 
 // TODO(Unavailable): Rename to symbols (functions/variables) from debug build.
 
-#[allow(dead_code)]
-enum PmanFileType {
-    Unknown,
-    Entity,
-    Skybox,
-    /// Information about the current map being played.
-    Level,
-    /// COLL
-    Collision,
-    /// TWPT
-    Waypoint,
-    /// The color palette used for textures.
-    Palette,
-    /// Mainly for language text banks. UTF-16
-    Text,
-}
-
+/// The data of a file, and its type; the data is shared, so cloning it doesn't copy the data.
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PmanFileData {
-    bytes: Vec<u8>,
-    // r#type: PmanFileType TODO(Unavailable): Data detection.
+    bytes: Bytes,
+    entry_type: u32,
 }
 
 impl PmanFileData {
+    /// The raw `type` column of this file's entry on the entry table.
+    #[must_use]
+    pub fn entry_type(&self) -> u32 {
+        self.entry_type
+    }
+
+    pub fn set_entry_type(&mut self, entry_type: u32) {
+        self.entry_type = entry_type;
+    }
+
+    #[must_use]
     pub fn bytes(&self) -> &[u8] {
         &self.bytes
     }
 
-    pub fn bytes_mut(&mut self) -> &mut Vec<u8> {
-        &mut self.bytes
+    /// The data, as a buffer that is shared with the archive that it was read from (and keeps it
+    /// alive); cloning or slicing it doesn't copy the data.
+    #[must_use]
+    pub fn shared(&self) -> &Bytes {
+        &self.bytes
     }
 
-    pub fn to_zlib(&self) -> Option<Vec<u8>> {
-        // TODO(Unavailable): use nom.
+    pub fn set_bytes(&mut self, bytes: impl Into<Bytes>) {
+        self.bytes = bytes.into();
+    }
 
-        (&self.bytes[..2] == b"ZL").then(|| {
-            let size = u32::from_le_bytes([self.bytes[2], self.bytes[3], self.bytes[4], 0]);
-            let mut decoder = ZlibDecoder::new(&self.bytes[5..]);
-            let mut zlib = Vec::<u8>::with_capacity(size as usize);
-            // FIX(Unavailable): Error handling:
-            // - Check if enough bytes were read.
-            // - Remove unwrap.
-            decoder.read_to_end(&mut zlib).unwrap();
+    /// Decompresses the file data, if it is `ZL` compressed.
+    ///
+    /// # Errors
+    ///
+    /// If the zlib stream is malformed, if it doesn't decompress to the size that its header says,
+    /// or if it decompresses to more than [`DEFAULT_MAX_ENTRY_SIZE`].
+    pub fn to_zlib(&self) -> crate::error::Result<Option<Vec<u8>>> {
+        decompress(&self.bytes)
+    }
 
-            zlib
-        })
+    /// Like [`Self::to_zlib`], but the data is decompressed into a buffer of `pool`; see
+    /// [`decompress_with`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::to_zlib`].
+    pub fn to_zlib_with(&self, pool: &BufferPool) -> crate::error::Result<Option<Vec<u8>>> {
+        decompress_with(&self.bytes, pool)
+    }
+
+    /// Like [`Self::to_zlib`], but the data is streamed to `writer`, instead of being kept in
+    /// memory; see [`decompress_to`].
+    ///
+    /// # Errors
+    ///
+    /// See [`decompress_to`].
+    pub fn decompress_to(&self, writer: impl Write) -> crate::error::Result<Option<usize>> {
+        decompress_to(&self.bytes, writer)
+    }
+
+    /// The (decompressed) file data, as a stream; `ZL` data is inflated as it is read, so it is
+    /// never kept in memory as a whole. See [`EntryReader`].
+    #[must_use]
+    pub fn reader(&self) -> EntryReader {
+        match ZlReader::new(self.bytes.clone()) {
+            Some(zlib) => EntryReader::Zlib(zlib),
+            None => EntryReader::Raw(io::Cursor::new(self.bytes.clone())),
+        }
+    }
+
+    /// What the (decompressed) file data most likely is; see [`detect`](super::detect::detect).
+    ///
+    /// Returns `None` if nothing matches, or if the file data can't be decompressed.
+    #[must_use]
+    pub fn detect_type(&self) -> Option<FileType> {
+        let zlib = self.to_zlib().ok()?;
+        let bytes = zlib.as_deref().unwrap_or(&self.bytes);
+
+        detect::detect(bytes).first().map(|guess| guess.file_type)
+    }
+
+    /// How the file data is compressed; see [`Compression::of`].
+    #[must_use]
+    pub fn compression(&self) -> Compression {
+        Compression::of(&self.bytes)
     }
 }
 
-const HEADER_SIZE: usize = 64;
-const HEADER_MAGIC_STRING: &[u8; 4] = b"PMAN";
-const COPYRIGHT_MAX_SIZE: usize = HEADER_SIZE - 9;
+impl AsRef<[u8]> for PmanFileData {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
 
-fn read_header(input: &[u8]) -> Result<(String, u32)> {
-    const NULL: char = '\0';
+impl Deref for PmanFileData {
+    type Target = [u8];
 
-    let (input, header) = take(HEADER_SIZE)(input)?;
-    let (header, _) = tag(HEADER_MAGIC_STRING)(header)?;
-    let (header, file_entry_count) = le_u32(header)?;
-    let (header, copyright) = terminated(take(COPYRIGHT_MAX_SIZE), char(NULL))(header)?;
-    _ = eof(header)?; // Not really needed, but having a guard doesn't hurt.
+    fn deref(&self) -> &Self::Target {
+        &self.bytes
+    }
+}
 
-    let copyright = String::from_utf8_lossy(copyright);
-    let copyright = copyright.trim_end_matches(NULL);
+/// An entry with `bytes` as its data, and no `type`.
+impl From<Vec<u8>> for PmanFileData {
+    fn from(bytes: Vec<u8>) -> Self {
+        Bytes::from(bytes).into()
+    }
+}
 
-    Ok((input, (copyright.into(), file_entry_count)))
+/// An entry with `bytes` as its data, and no `type`; the data is not copied.
+impl From<Bytes> for PmanFileData {
+    fn from(bytes: Bytes) -> Self {
+        Self {
+            bytes,
+            entry_type: 0,
+        }
+    }
 }
 
-fn entry_table_size(file_entry_count: u32) -> usize {
-    file_entry_count as usize * size_of::<u32>() * 4
+/// An entry with the contents of the file at a path as its data, and no `type`; e.g. one that was
+/// extracted before.
+impl TryFrom<&std::path::Path> for PmanFileData {
+    type Error = Error;
+
+    fn try_from(path: &std::path::Path) -> crate::error::Result<Self> {
+        Ok(std::fs::read(path)?.into())
+    }
 }
 
-fn read_file_entries(input: &[u8], file_entry_count: u32) -> Result<Vec<FileEntry>> {
-    let (input, table) = take(entry_table_size(file_entry_count))(input)?;
-    let (table, entries) = separated_list1(super::u32_zero, |table| {
-        // TODO(Unavailable): On the original source code this u32 should be the file type, but for
-        // some reason on the version `1.0.6` this value is always `0`.
-        //
-        // I could rewrite the `packfile.dat` to add this values by default.
-        let (table, _type) = super::u32_zero(table)?;
+/// Appends to the data; it is only copied if it is shared (e.g. with the archive that it was read
+/// from).
+impl Extend<u8> for PmanFileData {
+    fn extend<T: IntoIterator<Item = u8>>(&mut self, iter: T) {
+        let mut bytes = BytesMut::from(std::mem::take(&mut self.bytes));
 
-        FileEntry::from_bytes(table)
-    })(table)?;
-    _ = ((super::u32_zero, eof)).parse(table)?;
+        bytes.extend(iter);
+        self.bytes = bytes.freeze();
+    }
+}
 
-    Ok((input, entries))
+/// The CRC-32 and the size of the (decompressed) data of an entry; written as `1a2b3c4d-9658`.
+///
+/// The data is hashed after it is decompressed, so an entry that is compressed again (with other
+/// settings than the original ones) keeps its hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ContentHash {
+    pub crc: u32,
+    pub len: usize,
 }
 
-fn read_files<'a>(input: &'a [u8], file_entries: Vec<FileEntry>) -> Result<'a, Vec<PmanFileData>> {
-    let len = file_entries.len();
+/// Hashes what is written to it; so decompressed data is hashed as it is streamed.
+struct HashWriter(flate2::Crc);
 
-    // TODO(Unavailable): Can this be improved further?
-    let mut prev_entry = file_entries.first().unwrap().with_size(0);
-    let mut iter = file_entries.into_iter();
+impl Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
 
-    let (input, files) = count(
-        |input: &'a [u8]| {
-            let entry = iter.next().unwrap();
-            let FileEntry {
-                offset: prev_offset,
-                size: prev_size,
-            } = prev_entry;
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
 
-            let (input, data) = preceded(
-                // reads the extra NULL bytes from previous offset.
-                take(entry.offset - (prev_offset + prev_size)),
-                take(entry.size),
-            )(input)?;
+impl ContentHash {
+    #[must_use]
+    pub fn of(bytes: &[u8]) -> Self {
+        let mut crc = flate2::Crc::new();
+        crc.update(bytes);
 
-            prev_entry = entry;
+        Self {
+            crc: crc.sum(),
+            len: bytes.len(),
+        }
+    }
 
-            Ok((
-                input,
-                PmanFileData {
-                    bytes: data.to_vec(),
-                },
-            ))
-        },
-        len,
-    )(input)?;
+    /// The hash of the data of `file`, after it is decompressed; the data is streamed, so it is
+    /// never kept in memory as a whole.
+    ///
+    /// # Errors
+    ///
+    /// If the data can't be decompressed.
+    pub fn of_file(file: &PmanFileData) -> crate::error::Result<Self> {
+        let mut hasher = HashWriter(flate2::Crc::new());
 
-    Ok((input, files))
+        match file.decompress_to(&mut hasher)? {
+            Some(len) => Ok(Self {
+                crc: hasher.0.sum(),
+                len,
+            }),
+            None => Ok(Self::of(file.bytes())),
+        }
+    }
 }
 
-pub struct PmanFile {
-    copyright: String,
-    files: Vec<PmanFileData>,
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}-{}", self.crc, self.len)
+    }
 }
 
-impl PmanFile {
-    pub fn new(bytes: Vec<u8>) -> eyre::Result<PmanFile> {
-        // needed to infer the err case of `?`.
-        fn _new(bytes: &[u8]) -> Result<PmanFile> {
-            let (input, (copyright, entry_count)) = read_header(bytes)?;
-            let (input, entries) = read_file_entries(input, entry_count)?;
-            let (input, files) = read_files(input, entries)?;
-            // FIX(Unavailable): assert input is empty.
+impl FromStr for ContentHash {
+    type Err = Error;
+
+    fn from_str(s: &str) -> crate::error::Result<Self> {
+        s.split_once('-')
+            .and_then(|(crc, len)| {
+                Some(Self {
+                    crc: u32::from_str_radix(crc, 16).ok()?,
+                    len: len.parse().ok()?,
+                })
+            })
+            .ok_or_else(|| Error::InvalidImport {
+                format: "metadata",
+                reason: format!("`{s}` is not a content hash"),
+            })
+    }
+}
+
+/// Everything about an entry of an archive, at a glance; see [`PmanFile::metadata`].
+///
+/// [`Self::offset`] and [`Self::padding`] describe where the entry is on the file; every other
+/// field describes the data that it has now, which may have been replaced since.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntryMetadata {
+    pub index: usize,
+    /// Where its data is on the file that the archive was parsed from (or last saved to); or
+    /// where it would be packed, if it wasn't parsed; see [`PmanFile::entry_at_offset`].
+    pub offset: usize,
+    /// The size of its current data, as it is stored; it is not what the entry takes at
+    /// [`Self::offset`], if it was replaced since the archive was parsed.
+    pub stored_size: usize,
+    /// The size of its current data once it is decompressed; `None` if it is not `ZL` compressed.
+    pub decompressed_size: Option<usize>,
+    /// The `type` column of its row on the entry table; see [`PmanFileData::entry_type`].
+    pub entry_type: u32,
+    /// How many bytes there are between the end of the previous entry (or of the entry table)
+    /// and [`Self::offset`], on the same file; always `0` for an archive that wasn't parsed.
+    pub padding: usize,
+    /// The hash of its current (decompressed) data.
+    pub hash: ContentHash,
+}
+
+const ZL_MAGIC_STRING: &[u8; 2] = b"ZL";
+const ZL_HEADER_SIZE: usize = 5;
+
+/// The wrapper around the data of an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    None,
+    /// A `ZL` chunk; `declared_len` is the size of the decompressed data, from its header.
+    Zlib {
+        declared_len: usize,
+    },
+    /// A wrapper with the shape of `ZL` (a 2 bytes magic string, an `u24` size and a zlib
+    /// stream), but with another magic string; none of them are on the `1.0.6` release.
+    Unknown {
+        magic: [u8; 2],
+    },
+}
 
-            Ok((input, PmanFile { copyright, files }))
+impl Compression {
+    /// Finds out the compression of `bytes`, from their first bytes.
+    #[must_use]
+    pub fn of(bytes: &[u8]) -> Self {
+        let Some(&[m0, m1, s0, s1, s2, cmf, flg]) = bytes.first_chunk::<{ ZL_HEADER_SIZE + 2 }>()
+        else {
+            return match bytes.strip_prefix(ZL_MAGIC_STRING) {
+                // too short to be valid, but let `decompress` tell why.
+                Some(_) => Self::Zlib { declared_len: 0 },
+                None => Self::None,
+            };
+        };
+        let declared_len = u32::from_le_bytes([s0, s1, s2, 0]) as usize;
+
+        if [m0, m1] == *ZL_MAGIC_STRING {
+            return Self::Zlib { declared_len };
         }
 
-        Ok(_new(&bytes).map_err(|err| err.map_input(<[u8]>::to_vec))?.1)
+        // a zlib header; `deflate` with a 32K window, and a valid check.
+        let zlib = cmf == 0x78 && (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0;
+        if zlib && m0.is_ascii_uppercase() && m1.is_ascii_uppercase() {
+            Self::Unknown { magic: [m0, m1] }
+        } else {
+            Self::None
+        }
     }
+}
 
-    pub fn copyright(&self) -> &str {
-        &self.copyright
+/// Reads the header of a `ZL` chunk; the magic string, and the `u24` size of the decompressed data.
+fn read_zl_header(input: &[u8]) -> Result<'_, usize> {
+    use nom::number::complete::le_u24;
+
+    let (input, _) = tag(&ZL_MAGIC_STRING[..])
+        .context("magic string")
+        .parse(input)?;
+    let (input, size) = le_u24.context("size").parse(input)?;
+
+    Ok((input, size as usize))
+}
+
+/// Decompresses the `ZL` chunk at the start of `bytes`.
+///
+/// Returns the decompressed data, and how many bytes the chunk takes; or `None`, if `bytes` doesn't
+/// start with a `ZL` chunk.
+///
+/// # Errors
+///
+/// See [`PmanFileData::to_zlib`].
+pub fn decompress_chunk(bytes: &[u8]) -> crate::error::Result<Option<(Vec<u8>, usize)>> {
+    let Compression::Zlib { declared_len } = Compression::of(bytes) else {
+        return Ok(None);
+    };
+    let mut zlib = Vec::with_capacity(initial_capacity(bytes, declared_len));
+
+    Ok(decompress_chunk_to(bytes, &mut zlib, DEFAULT_MAX_ENTRY_SIZE)?.map(|(_, len)| (zlib, len)))
+}
+
+/// How much deflate can compress at best; a zlib stream can't inflate to more than this many
+/// times its size.
+const DEFLATE_MAX_RATIO: usize = 1032;
+
+/// The capacity to reserve for the data of `bytes`; the size on their header can't be trusted, so
+/// it is capped to what they could possibly inflate to.
+fn initial_capacity(bytes: &[u8], declared_len: usize) -> usize {
+    declared_len.min(bytes.len().saturating_mul(DEFLATE_MAX_RATIO))
+}
+
+/// Like [`decompress_chunk`], but the data is written to `writer`; returns how many bytes were
+/// written, and how many bytes the chunk takes.
+///
+/// It fails as soon as the data goes past the size on its header, or past `max_size` bytes.
+fn decompress_chunk_to(
+    bytes: &[u8],
+    writer: &mut impl Write,
+    max_size: usize,
+) -> crate::error::Result<Option<(usize, usize)>> {
+    if !matches!(Compression::of(bytes), Compression::Zlib { .. }) {
+        return Ok(None);
     }
+    let Ok((data, size)) = read_zl_header(bytes) else {
+        return Err(Error::InvalidZlib(io::ErrorKind::UnexpectedEof.into()));
+    };
 
-    /// Sets the copyright notice of the `PmanFile`.
-    ///
-    /// # Panics
-    ///
-    /// If the new copyright string length is `>` than 55. Do note that `length` != `# of chars`;
-    /// you can read [`String::len`] for more information.
-    pub fn set_copyright<S>(&mut self, copyright: S)
-    where
-        S: Into<String>,
+    let mut decoder = ZlibDecoder::new(data);
+    let mut buf = [0; 0x4000];
+    let mut written = 0;
+    loop {
+        let read = match decoder.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(Error::InvalidZlib(err)),
+        };
+        written += read;
+
+        // there is no point on inflating the rest of a stream that is already too large.
+        if written > max_size {
+            return Err(Error::DecompressionLimit {
+                limit: max_size,
+                what: "an entry",
+            });
+        }
+        if written > size {
+            return Err(Error::ZlibSizeMismatch {
+                expected: size,
+                actual: written,
+            });
+        }
+        writer.write_all(&buf[..read])?;
+    }
+
+    if written != size {
+        return Err(Error::ZlibSizeMismatch {
+            expected: size,
+            actual: written,
+        });
+    }
+
+    // `total_in` fits, because the decoder can't read more than `data`.
+    #[allow(clippy::cast_possible_truncation)]
+    let len = bytes.len() - data.len() + decoder.total_in() as usize;
+
+    Ok(Some((written, len)))
+}
+
+/// Decompresses `bytes`, if they are `ZL` compressed; e.g. the entries of a `TSND` bank.
+///
+/// Some entries are made of many `ZL` chunks, one after the other (e.g. the textures of entry
+/// `149`), which are stitched together; that is also the only way to store more than the 16 MiB
+/// that the `u24` size of a chunk allows.
+///
+/// Decompression stops at the first bytes that are not a `ZL` chunk; entry `150` mixes compressed
+/// and raw chunks, which can only be told apart with its table on entry `148`.
+///
+/// # Errors
+///
+/// See [`PmanFileData::to_zlib`].
+pub fn decompress(bytes: &[u8]) -> crate::error::Result<Option<Vec<u8>>> {
+    decompress_with(bytes, &BufferPool::new())
+}
+
+/// Like [`decompress`], but the data is decompressed into a buffer taken from `pool`; give it back
+/// once it is not needed, so the next entry reuses it.
+///
+/// # Errors
+///
+/// See [`PmanFileData::to_zlib`].
+pub fn decompress_with(bytes: &[u8], pool: &BufferPool) -> crate::error::Result<Option<Vec<u8>>> {
+    decompress_limited(bytes, pool, DEFAULT_MAX_ENTRY_SIZE)
+}
+
+/// Like [`decompress_with`], but the data can't take more than `max_size` bytes, instead of
+/// [`DEFAULT_MAX_ENTRY_SIZE`].
+///
+/// # Errors
+///
+/// See [`PmanFileData::to_zlib`]; [`Error::DecompressionLimit`] if the data goes past `max_size`.
+pub fn decompress_limited(
+    bytes: &[u8],
+    pool: &BufferPool,
+    max_size: usize,
+) -> crate::error::Result<Option<Vec<u8>>> {
+    let Compression::Zlib { declared_len } = Compression::of(bytes) else {
+        return Ok(None);
+    };
+    let mut zlib = pool.take(initial_capacity(bytes, declared_len).min(max_size));
+
+    match decompress_to_limited(bytes, &mut zlib, max_size) {
+        Ok(_) => Ok(Some(zlib)),
+        Err(err) => {
+            pool.give(zlib);
+            Err(err)
+        }
+    }
+}
+
+/// Like [`decompress`], but the data is streamed to `writer` as it is inflated, so it is never
+/// kept in memory as a whole; returns how many bytes were written, or `None` if `bytes` is not
+/// `ZL` compressed (and nothing was written).
+///
+/// # Errors
+///
+/// See [`PmanFileData::to_zlib`]; or, if `writer` fails, [`Error::Io`]. Whatever was inflated
+/// before an error is already written.
+pub fn decompress_to(bytes: &[u8], writer: impl Write) -> crate::error::Result<Option<usize>> {
+    decompress_to_limited(bytes, writer, DEFAULT_MAX_ENTRY_SIZE)
+}
+
+/// Like [`decompress_to`], but the data can't take more than `max_size` bytes, instead of
+/// [`DEFAULT_MAX_ENTRY_SIZE`]; nothing past it is written.
+///
+/// # Errors
+///
+/// See [`decompress_to`]; [`Error::DecompressionLimit`] if the data goes past `max_size`.
+pub fn decompress_to_limited(
+    bytes: &[u8],
+    mut writer: impl Write,
+    max_size: usize,
+) -> crate::error::Result<Option<usize>> {
+    let Some((mut written, mut offset)) = decompress_chunk_to(bytes, &mut writer, max_size)? else {
+        return Ok(None);
+    };
+
+    while let Some((chunk, len)) =
+        decompress_chunk_to(&bytes[offset..], &mut writer, max_size - written)?
     {
-        let copyright = copyright.into();
+        written += chunk;
+        offset += len;
+    }
 
-        assert!(
-            copyright.len() <= COPYRIGHT_MAX_SIZE,
-            "copyright notice should be less than {COPYRIGHT_MAX_SIZE} bytes long."
-        );
+    Ok(Some(written))
+}
 
-        self.copyright = copyright;
+/// A `ZL` chunk that is being inflated.
+struct ZlChunk {
+    /// Where the chunk starts.
+    start: usize,
+    declared_len: usize,
+    decoder: flate2::bufread::ZlibDecoder<io::Cursor<Bytes>>,
+}
+
+impl ZlChunk {
+    /// The `ZL` chunk at `start` of `bytes`; `None` if there is none.
+    fn at(bytes: &Bytes, start: usize) -> io::Result<Option<Self>> {
+        let rest = &bytes[start.min(bytes.len())..];
+        let Compression::Zlib { declared_len } = Compression::of(rest) else {
+            return Ok(None);
+        };
+        if rest.len() < ZL_HEADER_SIZE {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let data = bytes.slice(start + ZL_HEADER_SIZE..);
+
+        Ok(Some(Self {
+            start,
+            declared_len,
+            decoder: flate2::bufread::ZlibDecoder::new(io::Cursor::new(data)),
+        }))
     }
 
-    pub fn files(&self) -> &[PmanFileData] {
-        &self.files
+    /// Where the chunk after this one starts; only once this one is fully inflated.
+    fn end(&self) -> usize {
+        // `total_in` fits, because the decoder can't read more than the data.
+        #[allow(clippy::cast_possible_truncation)]
+        let len = self.decoder.total_in() as usize;
+
+        self.start + ZL_HEADER_SIZE + len
     }
+}
 
-    pub fn files_mut(&mut self) -> &mut Vec<PmanFileData> {
-        &mut self.files
+/// The data of `ZL` chunks (see [`decompress`]), inflated as it is read.
+///
+/// Seeking forward inflates (and drops) the data up to there, and seeking backward starts over
+/// from the first chunk; so it is cheap to read it in order, and to skip ahead, but not to jump
+/// around it. Seeking from the end inflates the data up to the end once, to learn its size.
+///
+/// Reads fail with [`io::ErrorKind::InvalidData`] where [`decompress`] would fail (e.g. at a chunk
+/// that inflates to another size than its header says), with the [`Error`] as the source.
+pub struct ZlReader {
+    bytes: Bytes,
+    /// The chunk being inflated; `None` once every chunk is.
+    chunk: Option<ZlChunk>,
+    /// How many bytes the current chunk inflated so far.
+    inflated: usize,
+    position: u64,
+    len: Option<u64>,
+}
+
+impl ZlReader {
+    /// A reader of the `ZL` chunks at the start of `bytes`; `None` if they are not `ZL`
+    /// compressed.
+    #[must_use]
+    pub fn new(bytes: impl Into<Bytes>) -> Option<Self> {
+        let bytes = bytes.into();
+        let Compression::Zlib { .. } = Compression::of(&bytes) else {
+            return None;
+        };
+        let mut reader = Self {
+            bytes,
+            chunk: None,
+            inflated: 0,
+            position: 0,
+            len: None,
+        };
+        reader.rewind_chunks();
+
+        Some(reader)
     }
 
-    pub fn size_upto_file_data(&self) -> usize {
-        HEADER_SIZE + entry_table_size(self.files.len() as u32)
+    /// Starts over from the first chunk.
+    fn rewind_chunks(&mut self) {
+        // a truncated header is only reported by the first read.
+        self.chunk = ZlChunk::at(&self.bytes, 0).ok().flatten();
+        self.inflated = 0;
+        self.position = 0;
     }
 
-    /// Turns this `PmanFile` back to its bytes representation.
-    pub fn into_bytes(self) -> io::Result<Vec<u8>> {
-        // TODO(Unavailable): I can probably remove all `?` with unwraps...
+    fn invalid(err: Error) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
 
-        let files_size = self.files.iter().map(|f| f.bytes().len()).sum::<usize>();
-        let size = self.size_upto_file_data();
+    /// The size of the data; the data is inflated up to the end the first time.
+    fn len(&mut self) -> io::Result<u64> {
+        if let Some(len) = self.len {
+            return Ok(len);
+        }
+        io::copy(self, &mut io::sink())?;
 
-        // FIX(Unavailable): Could potentially fail if size + files_size >= isize::MAX;
-        //
-        // Could be mitigated by converting `files_mut` into `set_files` and then check invalid
-        // state beforehand.
-        let mut buf = Vec::with_capacity(size + files_size);
+        Ok(self.len.unwrap_or(self.position))
+    }
+}
 
-        buf.write_all(HEADER_MAGIC_STRING)?;
-        buf.write_all(&(self.files.len() as u32).to_le_bytes())?;
-        buf.write_all(&self.copyright.as_bytes())?;
+impl Read for ZlReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
 
-        let zero_bytes = [0; 1];
-        // + 1 to include a null character.
-        (0..COPYRIGHT_MAX_SIZE - self.copyright.len() + 1)
-            .try_for_each(|_| buf.write_all(&zero_bytes))?;
-
-        let zero_bytes = [0; 4];
-        // FIX(Unavailable): `as u32` is not safe if size is bigger that u32::MAX.
-        //
-        // Realistically speaking that is unlikely to happen, but I should be more explicit with
-        // what an invalid file should look like.
-        self.files.iter().try_fold(size as u32, |offset, file| {
-            let size = file.bytes.len() as u32;
-
-            buf.write_all(&zero_bytes)?;
-            buf.write_all(&offset.to_le_bytes())?;
-            buf.write_all(&size.to_le_bytes())?;
-            buf.write_all(&zero_bytes)?;
-
-            Ok::<_, io::Error>(offset + size)
-        })?;
+        loop {
+            if self.position == 0 && self.chunk.is_none() {
+                // the header of the first chunk is truncated.
+                ZlChunk::at(&self.bytes, 0)
+                    .map_err(|err| Self::invalid(Error::InvalidZlib(err)))?;
+            }
+            let Some(chunk) = &mut self.chunk else {
+                // not the position, if it was sought past the end.
+                self.len.get_or_insert(self.position);
+                return Ok(0);
+            };
 
-        self.into_iter()
-            .try_for_each(|file| buf.write_all(file.bytes()))?;
+            let read = chunk
+                .decoder
+                .read(buf)
+                .map_err(|err| Self::invalid(Error::InvalidZlib(err)))?;
+            self.inflated += read;
+            if self.inflated > chunk.declared_len
+                || (read == 0 && self.inflated != chunk.declared_len)
+            {
+                return Err(Self::invalid(Error::ZlibSizeMismatch {
+                    expected: chunk.declared_len,
+                    actual: self.inflated,
+                }));
+            }
+            if self.position + read as u64 > DEFAULT_MAX_ENTRY_SIZE as u64 {
+                return Err(Self::invalid(Error::DecompressionLimit {
+                    limit: DEFAULT_MAX_ENTRY_SIZE,
+                    what: "an entry",
+                }));
+            }
+            if read > 0 {
+                self.position += read as u64;
+                return Ok(read);
+            }
 
-        Ok(buf)
+            let end = chunk.end();
+            self.chunk = ZlChunk::at(&self.bytes, end)
+                .map_err(|err| Self::invalid(Error::InvalidZlib(err)))?;
+            self.inflated = 0;
+        }
     }
 }
 
-impl IntoIterator for PmanFile {
-    type Item = PmanFileData;
-    type IntoIter = std::vec::IntoIter<Self::Item>;
+impl Seek for ZlReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.len()?.checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.files.into_iter()
+        if target < self.position {
+            self.rewind_chunks();
+        }
+        let skip = target.saturating_sub(self.position);
+        io::copy(&mut self.by_ref().take(skip), &mut io::sink())?;
+        // seeking past the end is fine; reads from there are empty.
+        self.position = target;
+
+        Ok(target)
     }
 }
 
-impl Index<usize> for PmanFile {
-    type Output = PmanFileData;
+impl fmt::Debug for ZlReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZlReader")
+            .field("bytes", &self.bytes.len())
+            .field("position", &self.position)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
 
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.files[index]
+/// The (decompressed) data of an entry, as a stream; see [`PmanFileData::reader`].
+#[derive(Debug)]
+pub enum EntryReader {
+    Raw(io::Cursor<Bytes>),
+    Zlib(ZlReader),
+}
+
+impl Read for EntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Raw(raw) => raw.read(buf),
+            Self::Zlib(zlib) => zlib.read(buf),
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl Seek for EntryReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::Raw(raw) => raw.seek(pos),
+            Self::Zlib(zlib) => zlib.seek(pos),
+        }
+    }
+}
 
-    const INPUT: &'static [u8] = include_bytes!("../../.res/packfile.dat");
-    const FILE_COUNT: u32 = 158;
-    const ENTRY_TABLE_START: usize = 0x40;
+/// The most bytes that the data of an entry can decompress to, unless it is asked otherwise; far
+/// more than any entry of the `1.0.6` release, but little enough that a corrupted (or crafted) one
+/// can't take all of the memory.
+pub const DEFAULT_MAX_ENTRY_SIZE: usize = 0x1000_0000;
 
-    #[test]
-    fn read_header_test() -> eyre::Result<()> {
-        let (_, (copyright, file_count)) = read_header(INPUT)?;
+/// Limits that `ZL` data is checked against while it is decompressed; see
+/// [`PmanFile::decompress_all_with`].
+///
+/// The size on the header of a `ZL` chunk can't be trusted, and a few bytes of zlib can inflate to
+/// many MiB; without them, a damaged archive can take all of the memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecompressLimits {
+    /// The most bytes that an entry can decompress to.
+    pub max_entry_size: usize,
+    /// The most bytes that every entry can decompress to, together.
+    pub max_total_size: usize,
+}
 
-        assert_eq!(copyright, "Copyright (c) 2004 Torus Games Pty. Ltd.");
-        assert_eq!(file_count, FILE_COUNT);
+impl DecompressLimits {
+    /// No limits at all; only for archives that are trusted.
+    pub const UNLIMITED: Self = Self {
+        max_entry_size: usize::MAX,
+        max_total_size: usize::MAX,
+    };
+}
 
-        Ok(())
+impl Default for DecompressLimits {
+    /// [`DEFAULT_MAX_ENTRY_SIZE`] per entry, and 1 GiB for the whole archive.
+    fn default() -> Self {
+        Self {
+            max_entry_size: DEFAULT_MAX_ENTRY_SIZE,
+            max_total_size: 0x4000_0000,
+        }
     }
+}
 
-    #[test]
-    fn read_entry_table_test() -> eyre::Result<()> {
-        let (_, entry_table) = read_file_entries(&INPUT[ENTRY_TABLE_START..], FILE_COUNT)?;
+/// Limits that an archive is checked against while it is read; see
+/// [`PmanFile::from_reader_with_limits`].
+///
+/// The header and the entry table of an archive can't be trusted; a few bytes can claim millions of
+/// entries, or entries of many GiB. The entry table is checked before the data of the entries is
+/// read; so an archive that goes past them is never read as a whole. The data is still compressed;
+/// see [`DecompressLimits`] for its decompression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// The most entries that the archive can have.
+    pub max_entries: usize,
+    /// The most bytes that the (stored) data of an entry can take.
+    pub max_entry_size: usize,
+    /// The most bytes that the whole archive can take.
+    pub max_total: usize,
+}
 
-        assert_eq!(*entry_table.first().unwrap(), FileEntry::new(0xA20, 0x6500));
+impl ParseLimits {
+    /// No limits at all; only for archives that are trusted.
+    pub const UNLIMITED: Self = Self {
+        max_entries: usize::MAX,
+        max_entry_size: usize::MAX,
+        max_total: usize::MAX,
+    };
+
+    fn check(limit: usize, value: usize, what: &'static str) -> crate::error::Result<()> {
+        if value > limit {
+            return Err(Error::ParseLimit { limit, what });
+        }
 
         Ok(())
     }
+}
 
-    #[test]
-    fn read_files_test() -> eyre::Result<()> {
-        let (input, entry_table) = read_file_entries(&INPUT[ENTRY_TABLE_START..], FILE_COUNT)?;
-        let (_, files) = read_files(input, entry_table)?;
-        let file = files[77].to_zlib().expect("zlib file data.");
+impl Default for ParseLimits {
+    /// 65536 entries, [`DEFAULT_MAX_ENTRY_SIZE`] per entry, and 256 MiB for the whole archive; the
+    /// archive of the `1.0.6` release has 158 entries, on 14 MiB.
+    fn default() -> Self {
+        Self {
+            max_entries: 0x1_0000,
+            max_entry_size: DEFAULT_MAX_ENTRY_SIZE,
+            max_total: 0x1000_0000,
+        }
+    }
+}
 
-        assert_eq!(&file[..4], b"COLL");
+/// The most bytes that a `ZL` chunk can hold; the size on its header is an `u24`.
+const ZL_MAX_SIZE: usize = 0xFF_FFFF;
 
-        Ok(())
+/// Compresses `bytes` into `ZL` chunks, the reverse of [`decompress`]; data larger than what the
+/// `u24` size of a chunk allows is split into many of them.
+///
+/// The zlib streams use the best compression, like every entry of the `1.0.6` release; they won't
+/// be byte for byte the same as the original ones, but they decompress into the same data.
+///
+/// The output only depends on `bytes`: the level is fixed, and so is the zlib implementation (the
+/// `rust_backend` of `flate2`; see `Cargo.toml`).
+///
+/// # Errors
+///
+/// If the zlib stream can't be written.
+pub fn compress(bytes: &[u8]) -> crate::error::Result<Vec<u8>> {
+    compress_with(bytes, &BufferPool::new())
+}
+
+/// [`compress`] of every item of `data`, in order; with the `rayon` feature, they are compressed
+/// across the threads of the current `rayon` pool.
+///
+/// Compression takes most of the time of a repack, so the data of every entry is better compressed
+/// up front with this, before the archive is put together.
+///
+/// # Errors
+///
+/// See [`compress`]; the first error, if there is more than one.
+pub fn compress_all<T>(data: &[T]) -> crate::error::Result<Vec<Vec<u8>>>
+where
+    T: AsRef<[u8]> + Sync,
+{
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        data.par_iter()
+            .map(|data| compress(data.as_ref()))
+            .collect()
     }
+    #[cfg(not(feature = "rayon"))]
+    data.iter().map(|data| compress(data.as_ref())).collect()
+}
 
-    #[test]
-    fn pman_new_test() -> eyre::Result<()> {
-        _ = PmanFile::new(INPUT.to_vec())?;
+/// Like [`compress`], but the data is compressed into a buffer taken from `pool`; see
+/// [`decompress_with`].
+///
+/// # Errors
+///
+/// See [`compress`].
+pub fn compress_with(bytes: &[u8], pool: &BufferPool) -> crate::error::Result<Vec<u8>> {
+    let mut buf = pool.take(bytes.len() / 2 + ZL_HEADER_SIZE);
+    // empty data is still a (single, empty) chunk.
+    let chunks = bytes
+        .chunks(ZL_MAX_SIZE)
+        .chain(bytes.is_empty().then_some(bytes));
 
-        Ok(())
+    for chunk in chunks {
+        // the size fits, because of `ZL_MAX_SIZE`.
+        #[allow(clippy::cast_possible_truncation)]
+        let size = (chunk.len() as u32).to_le_bytes();
+
+        buf.extend_from_slice(ZL_MAGIC_STRING);
+        buf.extend_from_slice(&size[..3]);
+
+        let mut encoder = ZlibEncoder::new(buf, flate2::Compression::best());
+        encoder.write_all(chunk)?;
+        buf = encoder.finish()?;
     }
 
-    #[test]
-    fn pman_into_bytes_test() -> eyre::Result<()> {
-        let pman = PmanFile::new(INPUT.to_vec())?;
-        let bytes = &pman.into_bytes()?;
+    Ok(buf)
+}
 
-        assert_eq!(bytes.len() + 170, INPUT.len());
+const HEADER_SIZE: usize = 64;
+const HEADER_MAGIC_STRING: &[u8; 4] = b"PMAN";
+const COPYRIGHT_MAX_SIZE: usize = HEADER_SIZE - 9;
+/// File data is aligned to this many bytes; any padding bigger than that is suspicious.
+const FILE_DATA_ALIGNMENT: usize = 4;
 
-        Ok(())
+/// A non-fatal observation made while parsing a `PmanFile`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// The `type` column of the entry table is not zero, on a layout where it should be; see
+    /// [`PmanVersion`].
+    NonZeroEntryType { index: usize, value: u32 },
+    /// The `type` column of a [`PmanVersion::Typed`] archive is not the
+    /// [`FileType::type_code`] of any type.
+    UnknownEntryType { index: usize, value: u32 },
+    /// The gap between the previous file and this one is bigger than the data alignment.
+    LargePadding {
+        index: usize,
+        offset: usize,
+        padding: usize,
+    },
+    /// The last file is shorter than what its entry says.
+    TruncatedEntry {
+        index: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// There are bytes left after the last file; see [`PmanFile::trailing_bytes`].
+    TrailingBytes { offset: usize, len: usize },
+    /// The data of this entry starts before the end of the previous one; it was read from its own
+    /// offset anyway. See [`Error::EntryOverlap`].
+    EntryOverlap {
+        index: usize,
+        offset: usize,
+        previous_end: usize,
+    },
+    /// This entry starts before the previous one; it was read from its own offset anyway. See
+    /// [`Error::EntryOutOfOrder`].
+    EntryOutOfOrder {
+        index: usize,
+        offset: usize,
+        previous_offset: usize,
+    },
+    /// The file ends before the data of its entries does; the entries from `first_missing_entry`
+    /// on were dropped. See [`Error::Truncated`].
+    TruncatedArchive {
+        first_missing_entry: usize,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonZeroEntryType { index, value } => {
+                write!(f, "entry {index} has a non-zero type `{value:#X}`")
+            }
+            Self::UnknownEntryType { index, value } => {
+                write!(f, "entry {index} has an unknown type `{value:#X}`")
+            }
+            Self::LargePadding {
+                index,
+                offset,
+                padding,
+            } => write!(
+                f,
+                "entry {index} at {offset:#X} is preceded by {padding} bytes of padding"
+            ),
+            Self::TruncatedEntry {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "entry {index} is truncated; expected {expected:#X} bytes, but got {actual:#X}"
+            ),
+            Self::TrailingBytes { offset, len } => {
+                write!(
+                    f,
+                    "{len} unparsed bytes at {offset:#X}, after the last entry"
+                )
+            }
+            Self::EntryOverlap {
+                index,
+                offset,
+                previous_end,
+            } => write!(
+                f,
+                "entry {index} at {offset:#X} overlaps the data before it, which ends at \
+                 {previous_end:#X}"
+            ),
+            Self::EntryOutOfOrder {
+                index,
+                offset,
+                previous_offset,
+            } => write!(
+                f,
+                "entry {index} at {offset:#X} is out of order; the previous entry is at \
+                 {previous_offset:#X}"
+            ),
+            Self::TruncatedArchive {
+                first_missing_entry,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "the archive is truncated; expected {expected:#X} bytes, but got {actual:#X}, so \
+                 the entries from {first_missing_entry} on were dropped"
+            ),
+        }
+    }
+}
+
+const V1_0_6_COPYRIGHT: &str = "Copyright (c) 2004 Torus Games Pty. Ltd.";
+const V1_0_6_FILE_COUNT: usize = 158;
+
+/// The layout of a `packfile.dat`, by the release of the game that it comes from.
+///
+/// It is detected out of the header (the copyright notice and the entry count) and the `type`
+/// column of the entry table, before the rows of the table are read; the rows are then read as
+/// the layout says. The byte order is detected on its own; see [`PmanFile::endianness`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PmanVersion {
+    /// The N-Gage release `1.0.6`; the game doesn't read the `type` column, and it is always zero.
+    V1_0_6,
+    /// Every entry has a `type`, which is what the original source code expects; no release is
+    /// known to be like this, but archives written with [`EntryTypes::Detected`] are. The column
+    /// is read as the [`FileType::type_code`] of every entry, and codes that are not known are
+    /// reported as [`ParseWarning::UnknownEntryType`].
+    Typed,
+    /// Anything else; e.g. a modded `1.0.6` archive, with entries added or removed. It is read
+    /// like the `1.0.6` release, so `type` columns that are not zero are reported as
+    /// [`ParseWarning::NonZeroEntryType`].
+    Unknown,
+}
+
+impl PmanVersion {
+    fn detect(copyright: &str, file_count: usize, typed_count: usize) -> Self {
+        match (copyright, file_count, typed_count) {
+            (V1_0_6_COPYRIGHT, V1_0_6_FILE_COUNT, 0) => Self::V1_0_6,
+            (_, count, typed) if count > 0 && count == typed => Self::Typed,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Detects the layout of an archive out of its header, and of the `type` column of the rows
+    /// of its entry `table` (as many of them as there are) that come after it.
+    fn detect_layout(
+        copyright: &str,
+        entry_count: u32,
+        table: &[u8],
+        endianness: Endianness,
+    ) -> Self {
+        let typed_count = table
+            .chunks_exact(FILE_ENTRY_SIZE)
+            .take(entry_count as usize)
+            .filter(|row| endianness.u32(row).is_ok_and(|(_, r#type)| r#type != 0))
+            .count();
+
+        Self::detect(copyright, entry_count as usize, typed_count)
+    }
+
+    /// The warning of an entry whose `type` column is `value`, if this layout doesn't expect it.
+    fn check_entry_type(self, index: usize, value: u32) -> Option<ParseWarning> {
+        match self {
+            Self::V1_0_6 | Self::Unknown if value != 0 => {
+                Some(ParseWarning::NonZeroEntryType { index, value })
+            }
+            Self::Typed if FileType::from_type_code(value).is_none() => {
+                Some(ParseWarning::UnknownEntryType { index, value })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PmanVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::V1_0_6 => "1.0.6",
+            Self::Typed => "typed",
+            Self::Unknown => "unknown",
+        })
+    }
+}
+
+/// Reads the header; the copyright notice, the number of entries, and the byte order that it is
+/// written in (see [`Endianness::detect`]).
+fn read_header(input: &[u8]) -> Result<'_, (String, u32, Endianness)> {
+    const NULL: char = '\0';
+
+    let (input, header) = with_kind(ErrorKind::TruncatedHeader, take(HEADER_SIZE))
+        .context("header")
+        .parse(input)?;
+    let (header, _) = with_kind(ErrorKind::BadMagic, tag(&HEADER_MAGIC_STRING[..]))
+        .context("magic string")
+        .parse(header)?;
+    let endianness = header
+        .first_chunk()
+        .map_or(Endianness::Little, |count| Endianness::detect(*count));
+    let (header, file_entry_count) =
+        with_kind(ErrorKind::TruncatedHeader, |input| endianness.u32(input))
+            .context("file entry count")
+            .parse(header)?;
+    let (header, copyright) = with_kind(
+        ErrorKind::InvalidCopyright,
+        terminated(take(COPYRIGHT_MAX_SIZE), char(NULL)),
+    )
+    .context("copyright")
+    .parse(header)?;
+    _ = eof(header)?; // Not really needed, but having a guard doesn't hurt.
+
+    let copyright = String::from_utf8_lossy(copyright);
+    let copyright = copyright.trim_end_matches(NULL);
+
+    Ok((input, (copyright.into(), file_entry_count, endianness)))
+}
+
+const FILE_ENTRY_SIZE: usize = size_of::<u32>() * 4;
+
+fn truncated_help(expected: usize) -> String {
+    format!("the file may be truncated; expected at least {expected:#X} bytes.")
+}
+
+fn entry_table_size(file_entry_count: u32) -> usize {
+    (file_entry_count as usize).saturating_mul(FILE_ENTRY_SIZE)
+}
+
+/// Reads the entry table; the `type` column is checked as `version` expects it.
+fn read_file_entries<'a>(
+    input: &'a [u8],
+    file_entry_count: u32,
+    endianness: Endianness,
+    version: PmanVersion,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<'a, Vec<FileEntry>> {
+    let table_size = entry_table_size(file_entry_count);
+    let (input, table) = (|input: &'a [u8]| {
+        if input.len() < table_size {
+            return super::fail_with(
+                input,
+                Violation::new(
+                    ErrorKind::TruncatedEntryTable,
+                    format!(
+                        "the entry table of {file_entry_count} entries needs {table_size:#X} \
+                         bytes, but only {:#X} are left",
+                        input.len()
+                    ),
+                    input.len(),
+                )
+                .with_help(truncated_help(HEADER_SIZE.saturating_add(table_size))),
+            );
+        }
+
+        with_kind(ErrorKind::TruncatedEntryTable, take(table_size))(input)
+    })
+    .context("entry table")
+    .parse(input)?;
+    // an empty archive has an empty entry table, without even the `0` that ends it.
+    if table.is_empty() {
+        return Ok((input, vec![]));
+    }
+    let mut index = 0;
+    let (table, entries) = separated_list1(
+        |table| endianness.u32_zero(table),
+        |table| {
+            // the first u32 of a row is the file type on the original source code, but it's
+            // always `0` on the version `1.0.6`; `version` decides which of both is expected.
+            //
+            // `EntryTypes::Detected` rewrites the `packfile.dat` with these values filled in.
+            let (table, entry) = with_kind(ErrorKind::InvalidEntryTable, |table| {
+                FileEntry::from_bytes(table, endianness)
+            })
+            .context("file entry")
+            .parse(table)?;
+
+            warnings.extend(version.check_entry_type(index, entry.r#type));
+            index += 1;
+
+            Ok((table, entry))
+        },
+    )(table)?;
+    _ = with_kind(
+        ErrorKind::InvalidEntryTable,
+        terminated(|table| endianness.u32_zero(table), eof),
+    )
+    .context("entry table end")
+    .parse(table)?;
+
+    Ok((input, entries))
+}
+
+/// Reads the data of every file; `input` is a slice of `source`, which the data is shared with.
+fn read_files<'a>(
+    input: &'a [u8],
+    source: &Bytes,
+    file_entries: &[FileEntry],
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<'a, Vec<PmanFileData>> {
+    let len = file_entries.len();
+    // the first file starts right after the entry table.
+    let mut prev_end = file_entries.first().map_or(0, |entry| entry.offset);
+    let mut files = Vec::with_capacity(len);
+    let mut input = input;
+
+    for (index, &entry) in file_entries.iter().enumerate() {
+        // the entries were checked by `layout_issues`, so they are in order.
+        let padding = entry.offset - prev_end;
+
+        if padding >= FILE_DATA_ALIGNMENT {
+            warnings.push(ParseWarning::LargePadding {
+                index,
+                offset: entry.offset,
+                padding,
+            });
+        }
+
+        // reads the extra NULL bytes from previous offset.
+        let (rest, _) = with_kind(ErrorKind::EntryOutOfBounds, take(padding))
+            .context("padding")
+            .parse(input)?;
+        let size = match rest.len() {
+            actual if actual >= entry.size => entry.size,
+            actual if index == len - 1 => {
+                warnings.push(ParseWarning::TruncatedEntry {
+                    index,
+                    expected: entry.size,
+                    actual,
+                });
+
+                actual
+            }
+            actual => {
+                let end = entry.offset.saturating_add(entry.size);
+
+                return super::fail_with(
+                    rest,
+                    Violation::new(
+                        ErrorKind::EntryOutOfBounds,
+                        format!(
+                            "entry {index} ends at {end:#X}, past the end of the file at {:#X}",
+                            entry.offset.saturating_add(actual),
+                        ),
+                        actual,
+                    )
+                    .with_help(truncated_help(end)),
+                );
+            }
+        };
+        let (rest, data) = with_kind(ErrorKind::EntryOutOfBounds, take(size))
+            .context("file data")
+            .parse(rest)?;
+
+        input = rest;
+        prev_end = entry.offset.saturating_add(entry.size);
+        files.push(PmanFileData {
+            bytes: source.slice_ref(data),
+            entry_type: entry.r#type,
+        });
+    }
+
+    Ok((input, files))
+}
+
+/// Reads the `index`th row of the entry table, where `input` is the whole file.
+fn read_file_entry_at(input: &[u8], index: usize, endianness: Endianness) -> Result<'_, FileEntry> {
+    let row = index
+        .saturating_mul(FILE_ENTRY_SIZE)
+        .saturating_add(HEADER_SIZE);
+    let rest = &input[row.min(input.len())..];
+
+    if rest.len() < FILE_ENTRY_SIZE {
+        return super::fail_with(
+            rest,
+            Violation::new(
+                ErrorKind::TruncatedEntryTable,
+                format!("entry {index} is past the end of the file"),
+                rest.len(),
+            )
+            .with_help(truncated_help(row.saturating_add(FILE_ENTRY_SIZE))),
+        );
+    }
+
+    with_kind(
+        ErrorKind::InvalidEntryTable,
+        terminated(
+            |rest| FileEntry::from_bytes(rest, endianness),
+            |rest| endianness.u32_zero(rest),
+        ),
+    )
+    .context("file entry")
+    .parse(rest)
+}
+
+/// Reads the data of the `index`th `entry`, where `input` is the whole file.
+fn read_file_data_at(input: &[u8], index: usize, entry: FileEntry) -> Result<'_, &[u8]> {
+    let rest = &input[entry.offset.min(input.len())..];
+
+    if rest.len() < entry.size {
+        let end = entry.offset.saturating_add(entry.size);
+
+        return super::fail_with(
+            rest,
+            Violation::new(
+                ErrorKind::EntryOutOfBounds,
+                format!(
+                    "entry {index} ends at {end:#X}, past the end of the file at {:#X}",
+                    input.len(),
+                ),
+                rest.len(),
+            )
+            .with_help(truncated_help(end)),
+        );
+    }
+
+    with_kind(ErrorKind::EntryOutOfBounds, take(entry.size))
+        .context("file data")
+        .parse(rest)
+}
+
+/// A damaged `packfile.dat` that was parsed as far as possible; see [`PmanFile::new_partial`].
+#[derive(Debug)]
+pub struct PartialPmanFile {
+    copyright: String,
+    file_count: u32,
+    files: Vec<(usize, PmanFileData)>,
+    errors: Vec<(usize, Error)>,
+}
+
+impl PartialPmanFile {
+    #[must_use]
+    pub fn copyright(&self) -> &str {
+        &self.copyright
+    }
+
+    /// How many files the header says the archive has.
+    #[must_use]
+    pub fn file_count(&self) -> u32 {
+        self.file_count
+    }
+
+    /// The files that could be read, next to their index on the entry table.
+    #[must_use]
+    pub fn files(&self) -> &[(usize, PmanFileData)] {
+        &self.files
+    }
+
+    /// Why the missing files couldn't be read, next to their index on the entry table.
+    ///
+    /// If the entry table itself is truncated, only the first missing row has an error.
+    #[must_use]
+    pub fn errors(&self) -> &[(usize, Error)] {
+        &self.errors
+    }
+
+    /// Whether every file of the archive could be read.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    #[must_use]
+    pub fn into_files(self) -> Vec<(usize, PmanFileData)> {
+        self.files
+    }
+}
+
+/// The first entry whose data doesn't fit on a file of `len` bytes, and how many bytes the data of
+/// every entry needs; the last entry can be cut short, as long as it starts inside of the file.
+fn find_truncation(entries: &[FileEntry], len: usize) -> Option<(usize, usize)> {
+    let last = entries.len().checked_sub(1)?;
+    let first_missing_entry = entries.iter().enumerate().position(|(index, entry)| {
+        let end = if index == last {
+            entry.offset
+        } else {
+            entry.offset.saturating_add(entry.size)
+        };
+
+        end > len
+    })?;
+    let expected = entries
+        .iter()
+        .map(|entry| entry.offset.saturating_add(entry.size))
+        .max()
+        .unwrap_or(len);
+
+    Some((first_missing_entry, expected))
+}
+
+/// Entries that don't start after the end of the one before them (or of the entry table, for the
+/// first one), as [`ParseWarning::EntryOverlap`] and [`ParseWarning::EntryOutOfOrder`].
+fn layout_issues(entries: &[FileEntry], table_end: usize) -> Vec<ParseWarning> {
+    let mut issues = vec![];
+    let mut previous: Option<FileEntry> = None;
+
+    for (index, &entry) in entries.iter().enumerate() {
+        let previous_end = previous.map_or(table_end, |p| p.offset.saturating_add(p.size));
+
+        match previous {
+            Some(previous) if entry.offset < previous.offset => {
+                issues.push(ParseWarning::EntryOutOfOrder {
+                    index,
+                    offset: entry.offset,
+                    previous_offset: previous.offset,
+                });
+            }
+            _ if entry.offset < previous_end => issues.push(ParseWarning::EntryOverlap {
+                index,
+                offset: entry.offset,
+                previous_end,
+            }),
+            _ => {}
+        }
+        previous = Some(entry);
+    }
+
+    issues
+}
+
+/// The error of an issue found by [`layout_issues`].
+fn layout_error(issue: ParseWarning) -> Error {
+    match issue {
+        ParseWarning::EntryOverlap {
+            index,
+            offset,
+            previous_end,
+        } => Error::EntryOverlap {
+            index,
+            offset,
+            previous_end,
+        },
+        ParseWarning::EntryOutOfOrder {
+            index,
+            offset,
+            previous_offset,
+        } => Error::EntryOutOfOrder {
+            index,
+            offset,
+            previous_offset,
+        },
+        _ => unreachable!("only layout issues are found."),
+    }
+}
+
+/// Reads the data of every file straight from the offset of its entry, in whatever order (or
+/// overlap) they are; data past the end of `source` is cut off. Returns the files, and the bytes
+/// after the end of the one that ends last.
+fn read_scattered_files(
+    source: &Bytes,
+    entries: &[FileEntry],
+    table_end: usize,
+    warnings: &mut Vec<ParseWarning>,
+) -> (Vec<PmanFileData>, Bytes) {
+    let len = source.len();
+    let files = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let start = entry.offset.min(len);
+            let end = entry.offset.saturating_add(entry.size).min(len);
+
+            if end - start < entry.size {
+                warnings.push(ParseWarning::TruncatedEntry {
+                    index,
+                    expected: entry.size,
+                    actual: end - start,
+                });
+            }
+
+            PmanFileData {
+                bytes: source.slice(start..end),
+                entry_type: entry.r#type,
+            }
+        })
+        .collect();
+    let end = entries
+        .iter()
+        .map(|entry| entry.offset.saturating_add(entry.size))
+        .fold(table_end, usize::max)
+        .min(len);
+
+    (files, source.slice(end..))
+}
+
+/// Reads a `packfile.dat` (see [`parse_pman`]) in a `parse` span; the warnings of the archive are
+/// logged as they are found.
+fn read_pman(source: &Bytes, lenient: bool) -> crate::error::Result<PmanFile> {
+    let _span = tracing::debug_span!("parse", len = source.len(), lenient).entered();
+
+    let pman = parse_pman(source, lenient);
+    match &pman {
+        Ok(pman) => {
+            for warning in pman.warnings() {
+                tracing::warn!("{warning}");
+            }
+            tracing::debug!(
+                entries = pman.files().len(),
+                version = %pman.version(),
+                endianness = ?pman.endianness(),
+                "parsed"
+            );
+        }
+        Err(err) => tracing::debug!("{err}"),
+    }
+
+    pman
+}
+
+/// Parses a `packfile.dat`; a truncated one fails with [`Error::Truncated`], and one whose entries
+/// overlap (or are out of order) with [`Error::EntryOverlap`] (or [`Error::EntryOutOfOrder`]),
+/// unless `lenient`.
+fn parse_pman<'a>(source: &'a Bytes, lenient: bool) -> crate::error::Result<PmanFile> {
+    let bytes = &source[..];
+    let mut warnings = vec![];
+    let (copyright, mut entries, endianness, version) =
+        super::parse_all(bytes, "packfile", |input| {
+            let (input, (copyright, entry_count, endianness)) = read_header(input)?;
+            let version = PmanVersion::detect_layout(&copyright, entry_count, input, endianness);
+            let (input, entries) =
+                read_file_entries(input, entry_count, endianness, version, &mut warnings)?;
+
+            Ok((input, (copyright, entries, endianness, version)))
+        })?;
+    let table_end = HEADER_SIZE + entries.len() * FILE_ENTRY_SIZE;
+
+    let layout = layout_issues(&entries, table_end);
+    match layout.first() {
+        Some(&issue) if !lenient => {
+            return Err(layout_error(issue));
+        }
+        // the files can't be read one after the other; the archive is laid out again when it is
+        // packed, so it is never patched in place either.
+        Some(_) => {
+            warnings.extend(layout);
+            let (files, trailing_bytes) =
+                read_scattered_files(source, &entries, table_end, &mut warnings);
+
+            if !trailing_bytes.is_empty() {
+                warnings.push(ParseWarning::TrailingBytes {
+                    offset: bytes.len() - trailing_bytes.len(),
+                    len: trailing_bytes.len(),
+                });
+            }
+
+            return Ok(PmanFile::parsed(
+                copyright,
+                endianness,
+                files,
+                version,
+                warnings,
+                trailing_bytes.to_vec(),
+            ));
+        }
+        None => {}
+    }
+
+    let mut end = bytes.len();
+
+    let truncation = find_truncation(&entries, bytes.len());
+    if let Some((first_missing_entry, expected)) = truncation {
+        if !lenient {
+            return Err(Error::Truncated {
+                expected,
+                actual: bytes.len(),
+                first_missing_entry,
+            });
+        }
+
+        warnings.push(ParseWarning::TruncatedArchive {
+            first_missing_entry,
+            expected,
+            actual: bytes.len(),
+        });
+        entries.truncate(first_missing_entry);
+        // whatever is left of the missing entries is dropped with them; it is not trailing data.
+        end = entries
+            .last()
+            .map_or(table_end, |entry| entry.offset + entry.size)
+            .max(table_end);
+    }
+
+    let offsets = entries.iter().map(|entry| entry.offset).collect();
+    // anything between the entry table and the first file is skipped.
+    let start = entries.first().map_or(table_end, |entry| entry.offset);
+    let (files, trailing_bytes) = super::parse_all(bytes, "packfile", |input: &'a [u8]| {
+        let (rest, files) = read_files(&input[start..end], source, &entries, &mut warnings)?;
+
+        if !rest.is_empty() && !lenient {
+            return super::fail(
+                rest,
+                ErrorKind::TrailingBytes,
+                format!(
+                    "{} unparsed bytes after the {}",
+                    rest.len(),
+                    if files.is_empty() {
+                        "header"
+                    } else {
+                        "last entry"
+                    }
+                ),
+                rest.len(),
+            );
+        }
+
+        Ok((rest, (files, rest)))
+    })?;
+
+    if !trailing_bytes.is_empty() {
+        warnings.push(ParseWarning::TrailingBytes {
+            offset: end - trailing_bytes.len(),
+            len: trailing_bytes.len(),
+        });
+    }
+
+    let mut pman = PmanFile::parsed(
+        copyright,
+        endianness,
+        files,
+        version,
+        warnings,
+        trailing_bytes.to_vec(),
+    );
+    // the entry table of the file has rows for the dropped entries, so it is never patched.
+    if truncation.is_none() {
+        pman.origin = Some(Origin::new(&pman, offsets, bytes.len()));
+    }
+
+    Ok(pman)
+}
+
+/// What is written on the `type` column of the entry table; see [`PackOptions::entry_types`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntryTypes {
+    /// The [`PmanFileData::entry_type`] of every entry, as it is.
+    #[default]
+    Stored,
+    /// Zeros, like the `1.0.6` release has; the game doesn't read them.
+    Zeros,
+    /// The stored type of the entries that have one, and the [`FileType::type_code`] of the
+    /// detected type of the others; entries of an unknown type are left at `0`. An archive whose
+    /// entries are all known is read back as [`PmanVersion::Typed`].
+    Detected,
+}
+
+/// Size limits that a rebuilt archive is checked against; see [`PmanFile::into_bytes_with`].
+///
+/// The game ran on phones with a few MB of storage and RAM; an entry (or an archive) that is larger
+/// than the original might not load, even if it is valid.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PackOptions {
+    /// The most bytes that the whole archive can take; there is no limit (other than the `u32`
+    /// offsets of the entry table) if `None`.
+    pub max_archive_size: Option<usize>,
+    /// The most bytes that an entry can take, by index; entries without one have no limit.
+    pub entry_budgets: BTreeMap<usize, usize>,
+    /// Fails if a limit is exceeded; otherwise, the violations are only returned.
+    pub strict: bool,
+    /// Drops the [`PmanFile::trailing_bytes`]; so the archive only depends on its copyright
+    /// notice, its byte order and the data of its entries, and the same inputs always give the
+    /// same bytes (e.g. to check a mod build against a known hash).
+    pub reproducible: bool,
+    /// What is written on the `type` column of the entry table.
+    pub entry_types: EntryTypes,
+}
+
+impl PackOptions {
+    /// Budgets every entry (and the whole archive) at the size that it has on `pman`; so nothing
+    /// can grow past the original archive.
+    #[must_use]
+    pub fn with_original_sizes(pman: &PmanFile) -> Self {
+        Self {
+            max_archive_size: Some(pman.packed_size()),
+            entry_budgets: pman
+                .files
+                .iter()
+                .map(|file| file.bytes.len())
+                .enumerate()
+                .collect(),
+            strict: false,
+            reproducible: false,
+            entry_types: EntryTypes::default(),
+        }
+    }
+
+    /// Every limit that `pman` exceeds.
+    #[must_use]
+    pub fn check(&self, pman: &PmanFile) -> Vec<BudgetViolation> {
+        let mut violations: Vec<_> = self
+            .entry_budgets
+            .iter()
+            .filter_map(|(&index, &budget)| {
+                let size = pman.files.get(index)?.bytes.len();
+
+                (size > budget).then_some(BudgetViolation::Entry {
+                    index,
+                    size,
+                    budget,
+                })
+            })
+            .collect();
+
+        let size = pman.packed_size();
+        if let Some(max) = self.max_archive_size.filter(|&max| size > max) {
+            violations.push(BudgetViolation::Archive { size, max });
+        }
+
+        violations
+    }
+
+    /// Like [`Self::check`], but it fails with [`Self::strict`].
+    ///
+    /// # Errors
+    ///
+    /// With [`Self::strict`], if `pman` exceeds a limit.
+    pub fn validate(&self, pman: &PmanFile) -> crate::error::Result<Vec<BudgetViolation>> {
+        let violations = self.check(pman);
+
+        if self.strict && !violations.is_empty() {
+            return Err(Error::OverBudget { violations });
+        }
+
+        Ok(violations)
+    }
+}
+
+/// A limit of [`PackOptions`] that an archive exceeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetViolation {
+    Archive {
+        size: usize,
+        max: usize,
+    },
+    Entry {
+        index: usize,
+        size: usize,
+        budget: usize,
+    },
+}
+
+impl fmt::Display for BudgetViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Archive { size, max } => {
+                write!(
+                    f,
+                    "the archive takes {size:#X} bytes, but the limit is {max:#X}"
+                )
+            }
+            Self::Entry {
+                index,
+                size,
+                budget,
+            } => write!(
+                f,
+                "entry {index} takes {size:#X} bytes, but its budget is {budget:#X}"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PmanFile {
+    copyright: String,
+    files: Vec<PmanFileData>,
+    version: PmanVersion,
+    endianness: Endianness,
+    warnings: Vec<ParseWarning>,
+    trailing_bytes: Vec<u8>,
+    /// What the archive was parsed from; see [`Self::save_incremental`].
+    origin: Option<Origin>,
+}
+
+// where an archive was read from doesn't change what it is.
+impl PartialEq for PmanFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.copyright == other.copyright
+            && self.files == other.files
+            && self.version == other.version
+            && self.endianness == other.endianness
+            && self.warnings == other.warnings
+            && self.trailing_bytes == other.trailing_bytes
+    }
+}
+
+impl Eq for PmanFile {}
+
+/// The layout of the `packfile.dat` that a [`PmanFile`] was parsed from, or last saved to.
+#[derive(Clone)]
+struct Origin {
+    copyright: String,
+    endianness: Endianness,
+    /// Every file, next to the offset of its data.
+    files: Vec<(usize, PmanFileData)>,
+    trailing_bytes: Vec<u8>,
+    /// The size of the whole `packfile.dat`.
+    len: usize,
+}
+
+impl Origin {
+    fn new(pman: &PmanFile, offsets: Vec<usize>, len: usize) -> Self {
+        Self {
+            copyright: pman.copyright.clone(),
+            endianness: pman.endianness,
+            files: offsets
+                .into_iter()
+                .zip(pman.files.iter().cloned())
+                .collect(),
+            trailing_bytes: pman.trailing_bytes.clone(),
+            len,
+        }
+    }
+
+    fn is_dirty(&self, index: usize, file: &PmanFileData) -> bool {
+        self.files.get(index).is_none_or(|(_, original)| {
+            // files that are still shared with the original data didn't change.
+            let shared = original.bytes.as_ptr() == file.bytes.as_ptr()
+                && original.bytes.len() == file.bytes.len();
+
+            original.entry_type != file.entry_type || !shared && original.bytes != file.bytes
+        })
+    }
+}
+
+impl fmt::Debug for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Origin")
+            .field("files", &self.files.len())
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PmanFile {
+    /// A parsed `PmanFile`, without an [`Origin`]; `version` is the layout it was read as.
+    fn parsed(
+        copyright: String,
+        endianness: Endianness,
+        files: Vec<PmanFileData>,
+        version: PmanVersion,
+        warnings: Vec<ParseWarning>,
+        trailing_bytes: Vec<u8>,
+    ) -> Self {
+        Self {
+            copyright,
+            files,
+            version,
+            endianness,
+            warnings,
+            trailing_bytes,
+            origin: None,
+        }
+    }
+
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid `packfile.dat`, if it is truncated (see [`Error::Truncated`]), if
+    /// its entries overlap or are out of order (see [`Error::EntryOverlap`] and
+    /// [`Error::EntryOutOfOrder`]), or if there are bytes left after the last file.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<PmanFile> {
+        Self::from_shared(&Bytes::copy_from_slice(bytes))
+    }
+
+    /// Like [`Self::new`], but the data of the files is sliced out of `bytes`, instead of being
+    /// copied; see [`PmanFileData::shared`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::new`].
+    pub fn from_shared(bytes: &Bytes) -> crate::error::Result<PmanFile> {
+        read_pman(bytes, false)
+    }
+
+    /// Parses the `packfile.dat` at `path`, which is mapped into memory instead of being read; the
+    /// data of the files is read from the disk as it is accessed, and untouched files are copied
+    /// straight from the mapping by [`Self::write_to`].
+    ///
+    /// The file must not be changed while the `PmanFile` (or any data of its files) is alive; write
+    /// a new archive somewhere else, and move it over `path` afterwards.
+    ///
+    /// # Errors
+    ///
+    /// If `path` can't be read; or see [`Self::new`].
+    #[cfg(feature = "mmap")]
+    pub fn open(path: impl AsRef<std::path::Path>) -> crate::error::Result<PmanFile> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the mapping is read-only; the file changing under it is documented above.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+
+        Self::from_shared(&Bytes::from_owner(mmap))
+    }
+
+    /// Reads an archive out of `reader`, that can't be trusted (e.g. an upload), checked against
+    /// `limits`: the header and the entry table are read first, and the data of the entries only
+    /// if they are within them; no more than [`ParseLimits::max_total`] bytes are read, whatever
+    /// `reader` has.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::ParseLimit`] if the archive goes past `limits`; if `reader` fails; or see
+    /// [`Self::new`].
+    pub fn from_reader_with_limits(
+        reader: impl Read,
+        limits: &ParseLimits,
+    ) -> crate::error::Result<PmanFile> {
+        let as_u64 = |len: usize| u64::try_from(len).unwrap_or(u64::MAX);
+        // one byte past the limit; so an archive that goes past it is told apart.
+        let mut reader = reader.take(as_u64(limits.max_total).saturating_add(1));
+        let mut bytes = Vec::new();
+
+        (&mut reader)
+            .take(as_u64(HEADER_SIZE))
+            .read_to_end(&mut bytes)?;
+        // a header that can't be read is left to the parser, to tell why.
+        if let Ok((copyright, entry_count, endianness)) =
+            super::parse_all(&bytes, "packfile", read_header)
+        {
+            ParseLimits::check(
+                limits.max_entries,
+                entry_count as usize,
+                "the number of entries",
+            )?;
+
+            (&mut reader)
+                .take(as_u64(entry_table_size(entry_count)))
+                .read_to_end(&mut bytes)?;
+            let table = &bytes[HEADER_SIZE..];
+            let version = PmanVersion::detect_layout(&copyright, entry_count, table, endianness);
+            let entries = super::parse_all(table, "packfile", |input| {
+                read_file_entries(input, entry_count, endianness, version, &mut vec![])
+            });
+
+            for entry in entries.iter().flatten() {
+                ParseLimits::check(limits.max_entry_size, entry.size, "the size of an entry")?;
+                ParseLimits::check(
+                    limits.max_total,
+                    entry.offset.saturating_add(entry.size),
+                    "the size of the archive",
+                )?;
+            }
+        }
+
+        reader.read_to_end(&mut bytes)?;
+        ParseLimits::check(limits.max_total, bytes.len(), "the size of the archive")?;
+
+        Self::from_shared(&Bytes::from(bytes))
+    }
+
+    /// Like [`Self::new`], but bytes left after the last file are kept, and the entries of a
+    /// truncated archive that fit are read, instead of failing.
+    ///
+    /// Entries that overlap (or are out of order) are read straight from their own offsets, and
+    /// cut off at the end of the file; [`Self::into_bytes`] lays them out one after the other.
+    ///
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid `packfile.dat`.
+    pub fn new_lenient(bytes: &[u8]) -> crate::error::Result<PmanFile> {
+        read_pman(&Bytes::copy_from_slice(bytes), true)
+    }
+
+    /// Parses a damaged `packfile.dat`, keeping every file that can be read.
+    ///
+    /// Unlike [`Self::new`], every file is read straight from the offset of its entry, so one bad
+    /// entry doesn't take down the rest of the archive with it.
+    ///
+    /// # Errors
+    ///
+    /// If the header of the archive can't be read.
+    pub fn new_partial(bytes: &[u8]) -> crate::error::Result<PartialPmanFile> {
+        let source = Bytes::copy_from_slice(bytes);
+        let bytes = &source[..];
+        let (copyright, file_count, endianness) = super::parse_all(bytes, "packfile", read_header)?;
+        let mut files = vec![];
+        let mut errors = vec![];
+
+        for index in 0..file_count as usize {
+            let entry = match super::parse_all(bytes, "packfile", |i| {
+                read_file_entry_at(i, index, endianness)
+            }) {
+                Ok(entry) => entry,
+                Err(err) if err.kind() == ErrorKind::TruncatedEntryTable => {
+                    errors.push((index, err));
+                    break;
+                }
+                Err(err) => {
+                    errors.push((index, err));
+                    continue;
+                }
+            };
+
+            match super::parse_all(bytes, "packfile", |i| read_file_data_at(i, index, entry)) {
+                Ok(data) => files.push((
+                    index,
+                    PmanFileData {
+                        bytes: source.slice_ref(data),
+                        entry_type: entry.r#type,
+                    },
+                )),
+                Err(err) => errors.push((index, err)),
+            }
+        }
+
+        Ok(PartialPmanFile {
+            copyright,
+            file_count,
+            files,
+            errors,
+        })
+    }
+
+    #[must_use]
+    pub fn copyright(&self) -> &str {
+        &self.copyright
+    }
+
+    /// Sets the copyright notice of the `PmanFile`.
+    ///
+    /// # Errors
+    ///
+    /// If the new copyright string length is `>` than 55. Do note that `length` != `# of chars`;
+    /// you can read [`String::len`] for more information.
+    pub fn set_copyright<S>(&mut self, copyright: S) -> crate::error::Result<()>
+    where
+        S: Into<String>,
+    {
+        let copyright = copyright.into();
+
+        if copyright.len() > COPYRIGHT_MAX_SIZE {
+            return Err(Error::CopyrightTooLong {
+                len: copyright.len(),
+                max: COPYRIGHT_MAX_SIZE,
+            });
+        }
+
+        self.copyright = copyright;
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn files(&self) -> &[PmanFileData] {
+        &self.files
+    }
+
+    pub fn files_mut(&mut self) -> &mut Vec<PmanFileData> {
+        &mut self.files
+    }
+
+    /// The entries, in order; the same as `files().iter()`.
+    pub fn iter(&self) -> std::slice::Iter<'_, PmanFileData> {
+        self.files.iter()
+    }
+
+    /// The entries, in order; the same as `files_mut().iter_mut()`.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, PmanFileData> {
+        self.files.iter_mut()
+    }
+
+    /// The entries, without the archive around them; like `into_iter().collect()`, but without
+    /// moving them.
+    #[must_use]
+    pub fn into_files(self) -> Vec<PmanFileData> {
+        self.files
+    }
+
+    /// [`PmanFileData::to_zlib`] of every file, in order; they are decompressed across the threads
+    /// of the current `rayon` pool.
+    #[cfg(feature = "rayon")]
+    #[must_use]
+    pub fn par_decompress_all(&self) -> Vec<crate::error::Result<Option<Vec<u8>>>> {
+        use rayon::prelude::*;
+
+        self.par_iter().map(PmanFileData::to_zlib).collect()
+    }
+
+    /// [`PmanFileData::to_zlib`] of every file, in order, but checked against `limits`; a file that
+    /// would take the decompressed data past [`DecompressLimits::max_total_size`] fails, and it
+    /// doesn't count towards it.
+    #[must_use]
+    pub fn decompress_all_with(
+        &self,
+        limits: &DecompressLimits,
+    ) -> Vec<crate::error::Result<Option<Vec<u8>>>> {
+        let pool = BufferPool::new();
+        let mut total = 0;
+
+        self.files
+            .iter()
+            .map(|file| {
+                let remaining = limits.max_total_size.saturating_sub(total);
+                let max_size = limits.max_entry_size.min(remaining);
+                let zlib =
+                    decompress_limited(&file.bytes, &pool, max_size).map_err(|err| match err {
+                        Error::DecompressionLimit { .. } if remaining < limits.max_entry_size => {
+                            Error::DecompressionLimit {
+                                limit: limits.max_total_size,
+                                what: "the whole archive",
+                            }
+                        }
+                        err => err,
+                    })?;
+                total += zlib.as_ref().map_or(0, Vec::len);
+
+                Ok(zlib)
+            })
+            .collect()
+    }
+
+    /// Checks that the `ZL` chunks of every entry decompress, into the size that they declare;
+    /// returns the entries that don't, with why. The data is streamed, so it is never kept in
+    /// memory as a whole.
+    #[must_use]
+    pub fn validate(&self) -> Vec<(usize, Error)> {
+        self.files
+            .iter()
+            .enumerate()
+            .filter_map(|(index, file)| Some((index, file.decompress_to(io::sink()).err()?)))
+            .collect()
+    }
+
+    /// The release of the game this `PmanFile` looks like it comes from; see [`PmanVersion`].
+    #[must_use]
+    pub fn version(&self) -> PmanVersion {
+        self.version
+    }
+
+    /// The byte order of the header and the entry table; it is written back the same way.
+    #[must_use]
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Changes the byte order that the archive is written in; e.g. to convert an archive of a
+    /// big-endian build into one for the N-Gage.
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.endianness = endianness;
+    }
+
+    /// Non-fatal observations made while parsing this `PmanFile`.
+    #[must_use]
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// Bytes found after the last file by [`Self::new_lenient`]; they are written back as they
+    /// are by [`Self::into_bytes`].
+    #[must_use]
+    pub fn trailing_bytes(&self) -> &[u8] {
+        &self.trailing_bytes
+    }
+
+    /// Sets the [`PmanFileData::entry_type`] of every entry, as `entry_types` says.
+    pub fn fill_entry_types(&mut self, entry_types: EntryTypes) {
+        match entry_types {
+            EntryTypes::Stored => {}
+            EntryTypes::Zeros => self.files.iter_mut().for_each(|file| file.entry_type = 0),
+            EntryTypes::Detected => {
+                for file in self.files.iter_mut().filter(|file| file.entry_type == 0) {
+                    file.entry_type = file.detect_type().map_or(0, FileType::type_code);
+                }
+            }
+        }
+    }
+
+    /// Drops the [`Self::trailing_bytes`], so they are not written back.
+    pub fn clear_trailing_bytes(&mut self) {
+        self.trailing_bytes.clear();
+    }
+
+    /// The index of the entry whose data is at `offset`; e.g. an address on a hex editor, or the
+    /// name of an extracted file.
+    ///
+    /// The offsets are the ones of the file that the archive was parsed from (or last saved to,
+    /// with [`Self::save_incremental`]); or the ones that it would be packed with, if it wasn't
+    /// parsed (or if entries were added or removed since). Extracted files are named after the
+    /// latter, so they only match if the parsed file had no padding between its entries. Empty
+    /// entries are found at the offset that they point to, unless another entry starts there first.
+    #[must_use]
+    pub fn entry_at_offset(&self, offset: usize) -> Option<usize> {
+        self.layout()
+            .into_iter()
+            .position(|(start, len)| offset == start || (start..start + len).contains(&offset))
+    }
+
+    /// The offset and the size of the data of every entry; on the file that the archive was parsed
+    /// from, or as it would be packed (if entries were added or removed since). See
+    /// [`Self::entry_at_offset`].
+    fn layout(&self) -> Vec<(usize, usize)> {
+        match &self.origin {
+            Some(origin) if origin.files.len() == self.files.len() => origin
+                .files
+                .iter()
+                .map(|(start, file)| (*start, file.bytes.len()))
+                .collect(),
+            _ => self
+                .files
+                .iter()
+                .scan(self.size_upto_file_data(), |start, file| {
+                    let current = *start;
+                    *start += file.bytes.len();
+
+                    Some((current, file.bytes.len()))
+                })
+                .collect(),
+        }
+    }
+
+    /// Everything about the entry at `index`; `None` if there is no such entry. Listings, diffs
+    /// and manifests of archives are built out of it.
+    ///
+    /// # Errors
+    ///
+    /// If the data of the entry can't be decompressed; see [`ContentHash::of_file`].
+    pub fn metadata(&self, index: usize) -> crate::error::Result<Option<EntryMetadata>> {
+        if index >= self.files.len() {
+            return Ok(None);
+        }
+
+        self.entry_metadata(index, &self.layout()).map(Some)
+    }
+
+    /// [`Self::metadata`] of every entry, in order; the layout of the archive is worked out once,
+    /// instead of once per entry.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::metadata`].
+    pub fn metadata_all(&self) -> crate::error::Result<Vec<EntryMetadata>> {
+        let layout = self.layout();
+
+        (0..self.files.len())
+            .map(|index| self.entry_metadata(index, &layout))
+            .collect()
+    }
+
+    /// [`Self::metadata`] of the entry at `index`, on `layout` (see [`Self::layout`]).
+    fn entry_metadata(
+        &self,
+        index: usize,
+        layout: &[(usize, usize)],
+    ) -> crate::error::Result<EntryMetadata> {
+        let file = &self.files[index];
+        let (offset, _) = layout[index];
+        let previous_end = match index.checked_sub(1) {
+            Some(previous) => layout[previous].0 + layout[previous].1,
+            None => self.size_upto_file_data(),
+        };
+        let hash = ContentHash::of_file(file)?;
+
+        Ok(EntryMetadata {
+            index,
+            offset,
+            stored_size: file.bytes.len(),
+            decompressed_size: matches!(file.compression(), Compression::Zlib { .. })
+                .then_some(hash.len),
+            entry_type: file.entry_type,
+            padding: offset.saturating_sub(previous_end),
+            hash,
+        })
+    }
+
+    #[must_use]
+    pub fn size_upto_file_data(&self) -> usize {
+        HEADER_SIZE.saturating_add(self.files.len().saturating_mul(FILE_ENTRY_SIZE))
+    }
+
+    /// How many bytes [`Self::into_bytes`] takes; it is counted on an `u64`, so it is exact even
+    /// for archives that don't fit on an `usize` (e.g. on 32 bits targets, like WebAssembly).
+    #[must_use]
+    pub fn total_size(&self) -> u64 {
+        let files_size: u64 = self.files.iter().map(|f| f.bytes.len() as u64).sum();
+
+        (HEADER_SIZE as u64)
+            + self.files.len() as u64 * FILE_ENTRY_SIZE as u64
+            + files_size
+            + self.trailing_bytes.len() as u64
+    }
+
+    /// Whether the archive can be packed: every offset and size of the entry table (and the count
+    /// of entries on the header) is an `u32`, so the whole archive has to fit on one. An archive
+    /// that doesn't is rejected by [`Self::into_bytes`], with [`Error::ArchiveTooLarge`].
+    #[must_use]
+    pub fn fits_in_u32_offsets(&self) -> bool {
+        fits_in_u32_offsets(self.total_size())
+    }
+
+    /// [`Self::total_size`], on an `usize`; or `usize::MAX`, if it doesn't fit.
+    fn packed_size(&self) -> usize {
+        usize::try_from(self.total_size()).unwrap_or(usize::MAX)
+    }
+
+    /// Like [`Self::into_bytes`], but the archive is checked against the limits of `options`
+    /// first; returns the limits that it exceeds too.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::into_bytes`]; or, with [`PackOptions::strict`], if a limit is exceeded.
+    pub fn into_bytes_with(
+        mut self,
+        options: &PackOptions,
+    ) -> crate::error::Result<(Vec<u8>, Vec<BudgetViolation>)> {
+        if options.reproducible {
+            self.clear_trailing_bytes();
+        }
+        self.fill_entry_types(options.entry_types);
+        let violations = options.validate(&self)?;
+
+        Ok((self.into_bytes()?, violations))
+    }
+
+    /// Turns this `PmanFile` back to its bytes representation.
+    ///
+    /// The entries are written in order, right after each other; the header is padded with zeros,
+    /// and the data is compressed with fixed settings (see [`compress`]). So the same entries, with
+    /// the same copyright notice and byte order, always give the same bytes.
+    ///
+    /// # Errors
+    ///
+    /// If the copyright notice doesn't fit on the header (it can happen when the parsed copyright
+    /// had invalid UTF-8), or if the archive is too big for the `u32` offsets of the entry table.
+    pub fn into_bytes(self) -> crate::error::Result<Vec<u8>> {
+        // before anything is allocated for it.
+        self.check_packable(self.total_size())?;
+        let mut buf = Vec::with_capacity(self.packed_size());
+        self.write_to(&mut buf)?;
+
+        Ok(buf)
+    }
+
+    /// Like [`Self::into_bytes`], but the archive is written straight to `writer`; the data of
+    /// every file is copied from where it is (e.g. the memory map of `PmanFile::open`), without
+    /// building the whole archive in memory first. The header and the entry table are written in
+    /// small pieces, so files should be wrapped in a [`BufWriter`](io::BufWriter).
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::into_bytes`]; or, if `writer` fails, [`Error::Io`]. Nothing is written if the
+    /// archive can't be packed.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write_to(&self, mut writer: impl Write) -> crate::error::Result<()> {
+        self.check_packable(self.total_size())?;
+
+        self.write_header(&mut writer)?;
+        write_rows(
+            &mut writer,
+            &self.files,
+            self.size_upto_file_data(),
+            self.endianness,
+        )?;
+        self.files
+            .iter()
+            .try_for_each(|file| writer.write_all(file.bytes()))?;
+        writer.write_all(&self.trailing_bytes)?;
+
+        Ok(())
+    }
+
+    /// Writes the archive into `path`; see [`Self::write_to`].
+    ///
+    /// It is written next to `path` first, and moved over it once it is complete; so `path` is
+    /// never left half written, and it can be the file that the archive was opened from (see
+    /// `PmanFile::open`).
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::write_to`].
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> crate::error::Result<()> {
+        save_with(path.as_ref(), |file| {
+            let mut writer = io::BufWriter::new(file);
+            self.write_to(&mut writer)?;
+            writer.flush()?;
+
+            Ok(())
+        })
+    }
+
+    /// Like [`Self::save`], but the file is sized up front, and the archive is written straight
+    /// into a writable memory map of it; the data isn't buffered twice, on a [`io::BufWriter`] and
+    /// on the page cache.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::save`]; or if the file can't be mapped.
+    #[cfg(feature = "mmap")]
+    pub fn save_mapped(&self, path: impl AsRef<std::path::Path>) -> crate::error::Result<()> {
+        self.check_packable(self.total_size())?;
+        let size = self.packed_size();
+
+        save_with(path.as_ref(), |file| {
+            file.set_len(size as u64)?;
+            if size == 0 {
+                return Ok(());
+            }
+
+            // SAFETY: the file was just created by this process, and nothing else knows about it.
+            let mut mmap = unsafe { memmap2::MmapMut::map_mut(&file)? };
+            self.write_to(&mut mmap[..])?;
+            mmap.flush()?;
+
+            Ok(())
+        })
+    }
+
+    /// Whether an archive of `total_size` bytes, with this copyright notice, can be written.
+    fn check_packable(&self, total_size: u64) -> crate::error::Result<()> {
+        if self.copyright.len() > COPYRIGHT_MAX_SIZE {
+            return Err(Error::CopyrightTooLong {
+                len: self.copyright.len(),
+                max: COPYRIGHT_MAX_SIZE,
+            });
+        }
+        if !fits_in_u32_offsets(total_size) {
+            return Err(Error::ArchiveTooLarge {
+                size: usize::try_from(total_size).unwrap_or(usize::MAX),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Writes the magic string, the file count and the copyright notice.
+    #[allow(clippy::cast_possible_truncation)]
+    fn write_header(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(HEADER_MAGIC_STRING)?;
+        writer.write_all(&self.endianness.u32_bytes(self.files.len() as u32))?;
+        writer.write_all(self.copyright.as_bytes())?;
+
+        let zero_bytes = [0; 1];
+        // + 1 to include a null character.
+        (0..=COPYRIGHT_MAX_SIZE - self.copyright.len())
+            .try_for_each(|_| writer.write_all(&zero_bytes))
+    }
+
+    /// Whether the file at `index` changed since the archive was parsed (or last saved with
+    /// [`Self::save_incremental`]); every file of an archive that wasn't parsed is.
+    #[must_use]
+    pub fn is_dirty(&self, index: usize) -> bool {
+        self.origin
+            .as_ref()
+            .is_none_or(|origin| origin.is_dirty(index, &self.files[index]))
+    }
+
+    /// The indices of the files that [`Self::is_dirty`].
+    #[must_use]
+    pub fn dirty_files(&self) -> Vec<usize> {
+        (0..self.files.len())
+            .filter(|&index| self.is_dirty(index))
+            .collect()
+    }
+
+    /// Saves the archive over the `packfile.dat` at `path`, which it was parsed from (or last
+    /// saved to); only the entries from the first one that [`Self::is_dirty`] onward are written,
+    /// with their rows of the entry table. Entries before it, and their padding, are kept as they
+    /// are on the disk.
+    ///
+    /// The whole archive is written if the number of files changed, if the archive wasn't parsed,
+    /// or if the file at `path` doesn't have the size that it had; but not if it has the same size
+    /// with different contents, so it must not be changed by anything else in between.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::into_bytes`]; or if `path` can't be written. The archive on the disk might be
+    /// broken if it fails halfway.
+    pub fn save_incremental(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> crate::error::Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let len = file.metadata()?.len();
+        let origin = self.origin.as_ref().filter(|origin| {
+            origin.files.len() == self.files.len()
+                && origin.len as u64 == len
+                && origin.endianness == self.endianness
+        });
+
+        // the first file that moves, and the offset that its data starts at.
+        let (first, start) = match origin {
+            Some(origin) => {
+                let first = (0..self.files.len())
+                    .find(|&index| origin.is_dirty(index, &self.files[index]))
+                    .unwrap_or(self.files.len());
+                let start = origin
+                    .files
+                    .get(first)
+                    .map_or(origin.len - origin.trailing_bytes.len(), |(offset, _)| {
+                        *offset
+                    });
+
+                (first, start)
+            }
+            None => (0, self.size_upto_file_data()),
+        };
+        let header = origin.is_none_or(|origin| origin.copyright != self.copyright);
+        let unchanged = origin.is_some_and(|origin| origin.trailing_bytes == self.trailing_bytes);
+        if first == self.files.len() && !header && unchanged {
+            return Ok(());
+        }
+
+        let mut tail = self.files[first..]
+            .iter()
+            .map(PmanFileData::bytes)
+            .chain([&self.trailing_bytes[..]])
+            .fold(vec![], |mut tail, bytes| {
+                tail.extend_from_slice(bytes);
+                tail
+            });
+        let total_size = start.saturating_add(tail.len());
+        self.check_packable(total_size as u64)?;
+
+        let mut head = vec![];
+        if header {
+            self.write_header(&mut head)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&head)?;
+            head.clear();
+        }
+        write_rows(&mut head, &self.files[first..], start, self.endianness)?;
+        file.seek(SeekFrom::Start(
+            (HEADER_SIZE + first * FILE_ENTRY_SIZE) as u64,
+        ))?;
+        file.write_all(&head)?;
+        file.seek(SeekFrom::Start(start as u64))?;
+        file.write_all(&tail)?;
+        file.set_len(total_size as u64)?;
+        file.flush()?;
+
+        // the written files are shared with `tail` now, so they don't depend on what was on the
+        // disk (e.g. the mapping of `open`) anymore.
+        tail.truncate(tail.len() - self.trailing_bytes.len());
+        let tail = Bytes::from(tail);
+        let mut offset = 0;
+        for file in &mut self.files[first..] {
+            let len = file.bytes.len();
+            file.bytes = tail.slice(offset..offset + len);
+            offset += len;
+        }
+
+        let mut offsets: Vec<_> = origin
+            .map(|origin| {
+                origin.files[..first]
+                    .iter()
+                    .map(|(offset, _)| *offset)
+                    .collect()
+            })
+            .unwrap_or_default();
+        offsets.extend(self.files[first..].iter().scan(start, |offset, file| {
+            let current = *offset;
+            *offset += file.bytes.len();
+
+            Some(current)
+        }));
+        self.origin = Some(Origin::new(self, offsets, total_size));
+
+        Ok(())
+    }
+}
+
+/// Writes a file next to `path` with `write`, and moves it over `path`; the file is removed if
+/// `write` fails.
+fn save_with<W>(path: &std::path::Path, write: W) -> crate::error::Result<()>
+where
+    W: FnOnce(std::fs::File) -> crate::error::Result<()>,
+{
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".tmp");
+    let temp = path.with_file_name(name);
+
+    // readable too, so it can be mapped.
+    let result = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp)
+        .map_err(Error::from)
+        .and_then(write)
+        .and_then(|()| Ok(std::fs::rename(&temp, path)?));
+    if result.is_err() {
+        // it might not have been created.
+        _ = std::fs::remove_file(&temp);
+    }
+
+    result
+}
+
+/// Whether an archive of `total_size` bytes can be addressed by the `u32` offsets of the entry
+/// table.
+fn fits_in_u32_offsets(total_size: u64) -> bool {
+    u32::try_from(total_size).is_ok()
+}
+
+/// Writes the rows of the entry table of `files`; their data is expected one after the other, from
+/// `offset`.
+#[allow(clippy::cast_possible_truncation)]
+fn write_rows(
+    writer: &mut impl Write,
+    files: &[PmanFileData],
+    offset: usize,
+    endianness: Endianness,
+) -> io::Result<()> {
+    let zero_bytes = [0; 4];
+    // every `as u32` is fine, because the whole archive fits on a `u32`.
+    files.iter().try_fold(offset as u32, |offset, file| {
+        let size = file.bytes.len() as u32;
+
+        writer.write_all(&endianness.u32_bytes(file.entry_type))?;
+        writer.write_all(&endianness.u32_bytes(offset))?;
+        writer.write_all(&endianness.u32_bytes(size))?;
+        writer.write_all(&zero_bytes)?;
+
+        Ok::<_, io::Error>(offset + size)
+    })?;
+
+    Ok(())
+}
+
+/// The bytes of the `packfile.dat` in `file` that the entry at `index` can take, without moving any
+/// other entry; from its offset to the offset of the next entry, so the padding after it counts
+/// too.
+///
+/// The last entry can't grow, nor shrink; the file can't be truncated through [`Write`].
+///
+/// # Errors
+///
+/// If the header, or the entry table, of `file` is not valid; if there is no entry at `index`; or
+/// if `file` can't be read.
+pub fn entry_slot<F>(file: &mut F, index: usize) -> crate::error::Result<Range<usize>>
+where
+    F: Read + Seek,
+{
+    read_slot(file, index).map(|(slot, ..)| slot)
+}
+
+/// See [`entry_slot`]; and if the entry is the last one, and the byte order of the archive.
+fn read_slot<F>(
+    file: &mut F,
+    index: usize,
+) -> crate::error::Result<(Range<usize>, bool, Endianness)>
+where
+    F: Read + Seek,
+{
+    let mut table = vec![0; HEADER_SIZE];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut table)?;
+
+    let (_, file_count, endianness) = super::parse_all(&table, "packfile", read_header)?;
+    let file_count = file_count as usize;
+    if index >= file_count {
+        return Err(Error::InvalidImport {
+            format: "packfile",
+            reason: format!("there is no entry {index}; the archive has {file_count} of them"),
+        });
+    }
+
+    // only the rows up to the next entry are needed.
+    let rows = (index + 2).min(file_count);
+    table.resize(HEADER_SIZE + rows * FILE_ENTRY_SIZE, 0);
+    file.read_exact(&mut table[HEADER_SIZE..])?;
+
+    let entry = super::parse_all(&table, "packfile", |i| {
+        read_file_entry_at(i, index, endianness)
+    })?;
+    let is_last = index + 1 == file_count;
+    let end = if is_last {
+        entry.offset.saturating_add(entry.size)
+    } else {
+        super::parse_all(&table, "packfile", |i| {
+            read_file_entry_at(i, index + 1, endianness)
+        })?
+        .offset
+    };
+
+    Ok((entry.offset..end.max(entry.offset), is_last, endianness))
+}
+
+/// Replaces the data of the entry at `index` of the `packfile.dat` in `file` with `bytes`, without
+/// rewriting the rest of the archive; only the data, and the size on the entry table, are written.
+/// The rest of the slot of the entry is zeroed.
+///
+/// Returns `false`, without writing anything, if `bytes` doesn't fit on the slot of the entry (see
+/// [`entry_slot`]); the archive has to be rebuilt then, see [`PmanFile::into_bytes`].
+///
+/// # Errors
+///
+/// See [`entry_slot`]; or if `file` can't be written.
+pub fn patch_in_place<F>(file: &mut F, index: usize, bytes: &[u8]) -> crate::error::Result<bool>
+where
+    F: Read + Write + Seek,
+{
+    let (slot, is_last, endianness) = read_slot(file, index)?;
+
+    if bytes.len() > slot.len() || (is_last && bytes.len() != slot.len()) {
+        return Ok(false);
+    }
+    // the slot is inside of the archive, so its size fits on an `u32`.
+    let Ok(size) = u32::try_from(bytes.len()) else {
+        return Ok(false);
+    };
+
+    file.seek(SeekFrom::Start(slot.start as u64))?;
+    file.write_all(bytes)?;
+    io::copy(
+        &mut io::repeat(0).take((slot.len() - bytes.len()) as u64),
+        file,
+    )?;
+
+    // the size is the third `u32` of the row.
+    let row = HEADER_SIZE + index * FILE_ENTRY_SIZE + 8;
+    file.seek(SeekFrom::Start(row as u64))?;
+    file.write_all(&endianness.u32_bytes(size))?;
+    file.flush()?;
+
+    Ok(true)
+}
+
+impl IntoIterator for PmanFile {
+    type Item = PmanFileData;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PmanFile {
+    type Item = &'a PmanFileData;
+    type IntoIter = std::slice::Iter<'a, PmanFileData>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut PmanFile {
+    type Item = &'a mut PmanFileData;
+    type IntoIter = std::slice::IterMut<'a, PmanFileData>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.iter_mut()
+    }
+}
+
+/// The entries, across the threads of the current `rayon` pool; e.g.
+/// `pman.par_iter().map(ContentHash::of_file)` hashes every entry in parallel. `par_iter` (and
+/// `par_iter_mut`) come from the impls for `&PmanFile` (and `&mut PmanFile`).
+#[cfg(feature = "rayon")]
+impl rayon::iter::IntoParallelIterator for PmanFile {
+    type Item = PmanFileData;
+    type Iter = rayon::vec::IntoIter<Self::Item>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.files.into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> rayon::iter::IntoParallelIterator for &'a PmanFile {
+    type Item = &'a PmanFileData;
+    type Iter = rayon::slice::Iter<'a, PmanFileData>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        rayon::iter::IntoParallelIterator::into_par_iter(&self.files)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> rayon::iter::IntoParallelIterator for &'a mut PmanFile {
+    type Item = &'a mut PmanFileData;
+    type Iter = rayon::slice::IterMut<'a, PmanFileData>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        rayon::iter::IntoParallelIterator::into_par_iter(&mut self.files)
+    }
+}
+
+impl Index<usize> for PmanFile {
+    type Output = PmanFileData;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.files[index]
+    }
+}
+
+/// The layout of a `packfile.dat`; see [`super::schema`].
+pub(super) fn schema() -> super::schema::Format {
+    use super::schema::{Field, Format, Kind, Size, TypeDef};
+
+    Format {
+        id: "pman",
+        title: "Ashen packfile",
+        imports: vec![],
+        root: TypeDef {
+            id: "pman",
+            doc: Some("The `packfile.dat` archive; entries can be `ZL` compressed.".into()),
+            fields: vec![
+                Field::magic("magic", HEADER_MAGIC_STRING),
+                Field::primitive("file_count", "u4"),
+                Field::new(
+                    "copyright",
+                    Kind::Str {
+                        size: COPYRIGHT_MAX_SIZE,
+                        encoding: "ASCII",
+                    },
+                ),
+                Field::magic("copyright_end", &[0]),
+                Field::new("entries", Kind::Type("file_entry"))
+                    .repeat(Size::Expr("file_count".into())),
+                Field::magic("entries_end", &[0; 4]),
+            ],
+        },
+        types: vec![TypeDef {
+            id: "file_entry",
+            doc: None,
+            fields: vec![
+                Field::primitive("entry_type", "u4").doc("Always `0` on the `1.0.6` release."),
+                Field::primitive("offset", "u4"),
+                Field::primitive("size", "u4"),
+                Field::magic("reserved", &[0; 4]),
+                Field::new("data", Kind::Bytes(Size::Expr("size".into())))
+                    .at("offset")
+                    .doc(format!(
+                        "Aligned to {FILE_DATA_ALIGNMENT} bytes; the padding is garbage."
+                    )),
+            ],
+        }],
+    }
+}
+
+/// The layout of the `ZL` wrapper of an entry; see [`super::schema`].
+pub(super) fn zl_schema() -> super::schema::Format {
+    use super::schema::{Field, Format, Kind, Size, TypeDef};
+
+    Format {
+        id: "zl",
+        title: "Ashen ZL compressed entry",
+        imports: vec![],
+        root: TypeDef {
+            id: "zl",
+            doc: Some(format!(
+                "A single chunk; data bigger than {ZL_MAX_SIZE:#X} bytes is split into many of \
+                 them, one after the other."
+            )),
+            fields: vec![
+                Field::magic("magic", ZL_MAGIC_STRING),
+                Field::new("size", Kind::Bytes(Size::Fixed(ZL_HEADER_SIZE - 2)))
+                    .doc("The size of the decompressed data; a little-endian `u24`."),
+                Field::new("data", Kind::Bytes(Size::Eos)).process("zlib"),
+            ],
+        },
+        types: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    #[cfg(feature = "packfile")]
+    const FILE_COUNT: u32 = 158;
+    const ENTRY_TABLE_START: usize = 0x40;
+
+    #[test]
+    fn read_header_test() {
+        let input = fixture::archive();
+        let (_, (copyright, file_count, endianness)) =
+            read_header(&input).expect("the header is valid.");
+
+        assert_eq!(copyright, "Copyright (c) 2004 Torus Games Pty. Ltd.");
+        assert_eq!(file_count as usize, fixture::FILE_COUNT);
+        assert_eq!(endianness, Endianness::Little);
+    }
+
+    #[test]
+    fn read_header_context_test() {
+        use nom_supreme::error::{GenericErrorTree, StackContext};
+
+        let mut input = fixture::archive()[..HEADER_SIZE].to_vec();
+        input[0] = b'X';
+
+        let Err(nom::Err::Error(GenericErrorTree::Stack { contexts, .. })) = read_header(&input)
+        else {
+            panic!("expected a context stack.");
+        };
+
+        assert_eq!(contexts[0].1, StackContext::Context("magic string"));
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn pman_new_invalid_format_test() {
+        let mut input = INPUT.to_vec();
+        input[0] = b'X';
+
+        let Err(Error::InvalidFormat(err)) = PmanFile::new(&input) else {
+            panic!("expected an invalid format error.");
+        };
+
+        assert_eq!(err.kind(), ErrorKind::BadMagic);
+        assert_eq!(err.span(), (0, 4).into());
+        assert_eq!(err.reason(), "expected `PMAN`");
+        assert_eq!(err.contexts(), [(0, "magic string"), (0, "packfile")]);
+
+        let Err(Error::InvalidFormat(err)) = PmanFile::new(&INPUT[..0x100]) else {
+            panic!("expected an invalid format error.");
+        };
+
+        assert_eq!(err.kind(), ErrorKind::TruncatedEntryTable);
+        assert_eq!(err.offset(), ENTRY_TABLE_START);
+        assert_eq!(err.contexts()[0], (ENTRY_TABLE_START, "entry table"));
+
+        // cuts the file in the middle of the 11th entry.
+        let Err(Error::Truncated {
+            expected,
+            actual,
+            first_missing_entry,
+        }) = PmanFile::new(&INPUT[..0x50000])
+        else {
+            panic!("expected a truncated error.");
+        };
+
+        assert_eq!((expected, actual), (INPUT.len(), 0x50000));
+        assert_eq!(first_missing_entry, 10);
+    }
+
+    #[test]
+    fn pman_new_error_kind_test() {
+        let kind = |input: &[u8]| PmanFile::new(input).err().map(|err| err.kind());
+        let archive = fixture::archive();
+
+        assert_eq!(kind(&archive[..0x20]), Some(ErrorKind::TruncatedHeader));
+
+        let mut input = archive.clone();
+        input[HEADER_SIZE - 1] = b'X';
+        assert_eq!(kind(&input), Some(ErrorKind::InvalidCopyright));
+
+        let mut input = archive;
+        // zero column of the first entry.
+        input[ENTRY_TABLE_START + 12] = 1;
+        assert_eq!(kind(&input), Some(ErrorKind::InvalidEntryTable));
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn pman_new_help_test() {
+        use miette::Diagnostic;
+
+        let help = |bytes: &[u8]| {
+            PmanFile::new(bytes)
+                .unwrap_err()
+                .help()
+                .unwrap()
+                .to_string()
+        };
+
+        assert!(help(&[b"SIS\0", &INPUT[4..]].concat()).contains("PMAN archive"));
+        assert!(help(&INPUT[..0x50000]).contains("PmanFile::new_lenient"));
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn pman_new_partial_test() -> eyre::Result<()> {
+        let pman = PmanFile::new_partial(INPUT)?;
+
+        assert!(pman.is_complete());
+        assert_eq!(pman.files().len(), FILE_COUNT as usize);
+
+        // cuts the file in the middle of the 11th entry.
+        let pman = PmanFile::new_partial(&INPUT[..0x50000])?;
+
+        assert_eq!(pman.files().len(), 10);
+        assert_eq!(pman.errors().len(), FILE_COUNT as usize - 10);
+        assert_eq!(pman.errors()[0].0, 10);
+        assert!(pman
+            .errors()
+            .iter()
+            .all(|(_, err)| err.kind() == ErrorKind::EntryOutOfBounds));
+
+        // only the first 12 rows of the entry table are there.
+        let pman = PmanFile::new_partial(&INPUT[..0x100])?;
+        let (index, err) = pman.errors().last().unwrap();
+
+        assert!(pman.files().is_empty());
+        assert_eq!(pman.errors().len(), 13);
+        assert_eq!((*index, err.kind()), (12, ErrorKind::TruncatedEntryTable));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn read_entry_table_test() -> eyre::Result<()> {
+        let (_, entry_table) = read_file_entries(
+            &INPUT[ENTRY_TABLE_START..],
+            FILE_COUNT,
+            Endianness::Little,
+            PmanVersion::V1_0_6,
+            &mut vec![],
+        )?;
+
+        assert_eq!(*entry_table.first().unwrap(), FileEntry::new(0xA20, 0x6500));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn read_files_test() -> eyre::Result<()> {
+        let mut warnings = vec![];
+        let (input, entry_table) = read_file_entries(
+            &INPUT[ENTRY_TABLE_START..],
+            FILE_COUNT,
+            Endianness::Little,
+            PmanVersion::V1_0_6,
+            &mut warnings,
+        )?;
+        let (_, files) = read_files(
+            input,
+            &Bytes::from_static(INPUT),
+            &entry_table,
+            &mut warnings,
+        )?;
+        let file = files[77].to_zlib()?.expect("zlib file data.");
+
+        assert_eq!(&file[..4], b"COLL");
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn pman_new_test() -> eyre::Result<()> {
+        _ = PmanFile::new(INPUT)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn pman_version_test() -> eyre::Result<()> {
+        assert_eq!(PmanFile::new(INPUT)?.version(), PmanVersion::V1_0_6);
+
+        let mut input = INPUT.to_vec();
+        (0..FILE_COUNT as usize).for_each(|i| input[ENTRY_TABLE_START + i * 16] = 1);
+        let pman = PmanFile::new(&input)?;
+
+        assert_eq!(pman.version(), PmanVersion::Typed);
+        assert!(pman.warnings().is_empty());
+        assert_eq!(pman[0].entry_type(), 1);
+
+        // a typed archive reads the column as type codes, not as something that should be zero.
+        input[ENTRY_TABLE_START + 16] = 0xFF;
+        let pman = PmanFile::new(&input)?;
+
+        assert_eq!(pman.version(), PmanVersion::Typed);
+        assert_eq!(
+            pman.warnings(),
+            [ParseWarning::UnknownEntryType {
+                index: 1,
+                value: 0xFF
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn pman_warnings_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        assert!(pman.warnings().is_empty());
+
+        let mut input = INPUT.to_vec();
+        // type column of the second entry.
+        input[ENTRY_TABLE_START + 16] = 1;
+        input.truncate(INPUT.len() - 0x10);
+        let pman = PmanFile::new(&input)?;
+
+        assert_eq!(
+            pman.warnings(),
+            [
+                ParseWarning::NonZeroEntryType { index: 1, value: 1 },
+                ParseWarning::TruncatedEntry {
+                    index: FILE_COUNT as usize - 1,
+                    expected: 0x2959,
+                    actual: 0x2949
+                }
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_into_bytes_entry_type_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(&fixture::archive())?;
+        pman.files_mut()[3].set_entry_type(0xFF);
+        let pman = PmanFile::new(&pman.into_bytes()?)?;
+
+        assert_eq!(pman[3].entry_type(), 0xFF);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_set_copyright_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(&fixture::archive())?;
+
+        pman.set_copyright("Copyright (c) 2023")?;
+        assert_eq!(pman.copyright(), "Copyright (c) 2023");
+
+        let err = pman.set_copyright("a".repeat(COPYRIGHT_MAX_SIZE + 1));
+        assert!(matches!(err, Err(Error::CopyrightTooLong { len: 56, .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_zlib_test() {
+        let zlib = |bytes: &[u8]| {
+            PmanFileData {
+                bytes: Bytes::copy_from_slice(bytes),
+                ..Default::default()
+            }
+            .to_zlib()
+            .map_err(|err| err.kind())
+        };
+
+        assert_eq!(zlib(b""), Ok(None));
+        assert_eq!(zlib(b"Z"), Ok(None));
+        assert_eq!(zlib(b"ZL\x01"), Err(ErrorKind::InvalidZlib));
+        assert_eq!(zlib(b"ZL\x01\x00\x00garbage"), Err(ErrorKind::InvalidZlib));
+        // `ZL` + 2 + an empty zlib stream.
+        assert_eq!(
+            zlib(b"ZL\x02\x00\x00\x78\x9C\x03\x00\x00\x00\x00\x01"),
+            Err(ErrorKind::ZlibSizeMismatch)
+        );
+        // two chunks of `ZL` + 0 + an empty zlib stream, and then raw bytes.
+        let empty = b"ZL\x00\x00\x00\x78\x9C\x03\x00\x00\x00\x00\x01";
+        assert_eq!(
+            zlib(&[&empty[..], empty, b"raw"].concat()),
+            Ok(Some(vec![]))
+        );
+        assert_eq!(
+            zlib(&[&empty[..], b"ZL\x01"].concat()),
+            Err(ErrorKind::InvalidZlib)
+        );
+    }
+
+    #[test]
+    fn compression_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(&fixture::archive())?;
+
+        assert_eq!(pman[fixture::RAW].compression(), Compression::None);
+        assert_eq!(pman[fixture::EMPTY].compression(), Compression::None);
+        assert_eq!(
+            pman[fixture::ZLIB].compression(),
+            Compression::Zlib {
+                declared_len: fixture::TEXT.len()
+            }
+        );
+        assert_eq!(
+            Compression::of(b"ZL"),
+            Compression::Zlib { declared_len: 0 }
+        );
+        assert_eq!(
+            Compression::of(b"LZ\x01\x00\x00\x78\xDA"),
+            Compression::Unknown { magic: *b"LZ" }
+        );
+        assert_eq!(
+            Compression::of(b"LZ\x01\x00\x00\x78\xDB"),
+            Compression::None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn compress_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(&fixture::archive())?;
+        let text = pman[fixture::ZLIB].to_zlib()?.expect("it is compressed.");
+        let compressed = compress(&text)?;
+
+        assert_eq!(compressed[..5], pman[fixture::ZLIB].bytes()[..5]);
+        assert_eq!(decompress(&compressed)?, Some(text));
+        assert_eq!(decompress(&compress(&[])?)?, Some(Vec::new()));
+
+        // larger than a single chunk.
+        let large = vec![7; ZL_MAX_SIZE + 2];
+        let compressed = compress(&large)?;
+
+        assert_eq!(
+            decompress_chunk(&compressed)?.map(|(c, _)| c.len()),
+            Some(ZL_MAX_SIZE)
+        );
+        assert_eq!(decompress(&compressed)?, Some(large));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reader_test() -> eyre::Result<()> {
+        let mut files = fixture::files();
+        // a `ZL` header that declares one byte more than its data.
+        files.push(files[fixture::ZLIB].clone());
+        files[fixture::FILE_COUNT][2] += 1;
+        let pman = PmanFile::new(&fixture::pack(fixture::COPYRIGHT, &files))?;
+        let chunks = pman[fixture::CHUNKS].to_zlib()?.expect("it is compressed.");
+
+        let mut reader = pman[fixture::CHUNKS].reader();
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all)?;
+        assert_eq!(all, chunks);
+
+        // across the end of the first chunk, after going back to the start.
+        let middle = fixture::TEXT.len() - 4;
+        let mut window = [0; 8];
+        assert_eq!(reader.seek(SeekFrom::Start(middle as u64))?, middle as u64);
+        reader.read_exact(&mut window)?;
+        assert_eq!(window[..], chunks[middle..][..8]);
+        assert_eq!(reader.seek(SeekFrom::End(-3))?, chunks.len() as u64 - 3);
+        assert_eq!(reader.seek(SeekFrom::Current(-5))?, chunks.len() as u64 - 8);
+        reader.read_exact(&mut window)?;
+        assert_eq!(window[..], chunks[chunks.len() - 8..]);
+        assert!(reader.seek(SeekFrom::Current(-0x1000)).is_err());
+        // past the end.
+        assert_eq!(reader.seek(SeekFrom::End(4))?, chunks.len() as u64 + 4);
+        assert_eq!(reader.read(&mut window)?, 0);
+
+        let mut raw = pman[fixture::RAW].reader();
+        assert!(matches!(raw, EntryReader::Raw(_)));
+        all.clear();
+        raw.read_to_end(&mut all)?;
+        assert_eq!(all, pman[fixture::RAW].bytes());
+
+        let err = pman[fixture::FILE_COUNT]
+            .reader()
+            .read_to_end(&mut all)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = err
+            .into_inner()
+            .and_then(|err| err.downcast::<Error>().ok())
+            .expect("the source is an `Error`.");
+        assert_eq!(err.kind(), ErrorKind::ZlibSizeMismatch);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn to_zlib_chunks_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        // the sum of the sizes on the table of entry `147`.
+        let zlib = pman[149].to_zlib()?.expect("it is compressed.");
+        assert_eq!(zlib.len(), 2_297_380);
+
+        let (chunk, len) = decompress_chunk(pman[150].bytes())?.expect("it is compressed.");
+        assert_eq!((chunk.len(), len), (0x7B4, 0x3C5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_to_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(&fixture::archive())?;
+        let chunks = &pman[fixture::CHUNKS];
+
+        let mut zlib = vec![];
+        assert_eq!(
+            chunks.decompress_to(&mut zlib)?,
+            Some(fixture::TEXT.len() * 2)
+        );
+        assert_eq!(Some(zlib), chunks.to_zlib()?);
+
+        let mut raw = vec![];
+        assert_eq!(decompress_to(b"raw", &mut raw)?, None);
+        assert!(raw.is_empty());
+
+        // a writer with no room left.
+        let mut full = [0; 0x10];
+        let err = chunks.decompress_to(&mut full[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_from_shared_test() -> eyre::Result<()> {
+        let source = Bytes::from(fixture::archive());
+        let pman = PmanFile::from_shared(&source)?;
+        assert_eq!(pman, PmanFile::new(&source)?);
+
+        // the data is a view of `source`, and so are its clones.
+        let data = pman[fixture::RAW].clone();
+        assert!(source.as_ptr_range().contains(&data.shared().as_ptr()));
+        assert_eq!(data.shared().as_ptr(), pman[fixture::RAW].bytes().as_ptr());
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_from_reader_with_limits_test() -> eyre::Result<()> {
+        let archive = fixture::archive();
+        let pman = PmanFile::new(&archive)?;
+        let table_end = pman.size_upto_file_data();
+        let largest = pman.files().iter().map(|file| file.bytes().len()).max();
+        let largest = largest.expect("the fixture has entries.");
+        let parse = |limits: ParseLimits| {
+            let mut reader = io::Cursor::new(&archive);
+            let pman = PmanFile::from_reader_with_limits(&mut reader, &limits);
+
+            (pman, reader.position())
+        };
+
+        let (within, _) = parse(ParseLimits {
+            max_entries: fixture::FILE_COUNT,
+            max_entry_size: largest,
+            max_total: archive.len(),
+        });
+        assert_eq!(within?, pman);
+        assert_eq!(parse(ParseLimits::UNLIMITED).0?, pman);
+
+        // the entry table is checked before the data is read.
+        for (limits, limit, what) in [
+            (
+                ParseLimits {
+                    max_entries: fixture::FILE_COUNT - 1,
+                    ..ParseLimits::UNLIMITED
+                },
+                fixture::FILE_COUNT - 1,
+                "the number of entries",
+            ),
+            (
+                ParseLimits {
+                    max_entry_size: largest - 1,
+                    ..ParseLimits::UNLIMITED
+                },
+                largest - 1,
+                "the size of an entry",
+            ),
+            (
+                ParseLimits {
+                    max_total: archive.len() - 1,
+                    ..ParseLimits::UNLIMITED
+                },
+                archive.len() - 1,
+                "the size of the archive",
+            ),
+        ] {
+            let (err, read) = parse(limits);
+            assert!(
+                matches!(err, Err(Error::ParseLimit { limit: l, what: w }) if l == limit && w == what),
+                "{what}"
+            );
+            assert!(
+                read <= table_end as u64,
+                "{what}: {read:#X} bytes were read"
+            );
+        }
+
+        // no more than the limit is read, whatever the reader has.
+        let mut endless = io::repeat(0);
+        let err = PmanFile::from_reader_with_limits(
+            &mut endless,
+            &ParseLimits {
+                max_total: 0x1000,
+                ..ParseLimits::UNLIMITED
+            },
+        );
+        assert_eq!(err.map_err(|err| err.kind()), Err(ErrorKind::ParseLimit));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_metadata_test() -> eyre::Result<()> {
+        let files = fixture::files();
+        let archive = fixture::pack(fixture::COPYRIGHT, &files);
+        let mut pman = PmanFile::new(&archive)?;
+        let table_end = pman.size_upto_file_data();
+
+        let raw = pman.metadata(fixture::RAW)?.expect("the entry exists.");
+        assert_eq!(
+            raw,
+            EntryMetadata {
+                index: fixture::RAW,
+                offset: table_end,
+                stored_size: files[fixture::RAW].len(),
+                decompressed_size: None,
+                entry_type: 0,
+                padding: 0,
+                hash: ContentHash::of(&files[fixture::RAW]),
+            }
+        );
+        let zlib = pman.metadata(fixture::ZLIB)?.expect("the entry exists.");
+        let raw_len = files[fixture::RAW].len();
+        assert_eq!(zlib.padding, raw_len.next_multiple_of(4) - raw_len);
+        assert_eq!(zlib.offset, raw.offset + raw.stored_size + zlib.padding);
+        assert_eq!(zlib.decompressed_size, Some(fixture::TEXT.len()));
+        assert_eq!(zlib.hash, ContentHash::of(fixture::TEXT));
+        assert_eq!(pman.entry_at_offset(zlib.offset), Some(fixture::ZLIB));
+        assert_eq!(pman.metadata(fixture::FILE_COUNT)?, None);
+        assert_eq!(pman.metadata_all()?[fixture::ZLIB], zlib);
+        assert_eq!(pman.metadata_all()?.len(), fixture::FILE_COUNT);
+
+        // the hash is streamed, but it is the one of the decompressed data.
+        let chunks = pman.metadata(fixture::CHUNKS)?.expect("the entry exists.");
+        let data = pman[fixture::CHUNKS]
+            .to_zlib()?
+            .expect("the entry is compressed.");
+        assert_eq!(chunks.hash, ContentHash::of(&data));
+        assert_eq!(chunks.decompressed_size, Some(data.len()));
+
+        // an archive that wasn't parsed has no padding.
+        pman.files_mut().push(PmanFileData::default());
+        let zlib = pman.metadata(fixture::ZLIB)?.expect("the entry exists.");
+        assert_eq!((zlib.offset, zlib.padding), (table_end + 0x10 + raw_len, 0));
+        assert_eq!(
+            ContentHash::of(b"").to_string().parse::<ContentHash>()?,
+            ContentHash::of(b"")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_limits_test() -> eyre::Result<()> {
+        let pool = BufferPool::new();
+        let bomb = compress(&vec![0; 0x10000])?;
+
+        assert_eq!(
+            decompress_limited(&bomb, &pool, 0x10000)?.map(|z| z.len()),
+            Some(0x10000)
+        );
+        assert!(matches!(
+            decompress_limited(&bomb, &pool, 0x1000),
+            Err(Error::DecompressionLimit {
+                limit: 0x1000,
+                what: "an entry"
+            })
+        ));
+        // nothing past the limit is written.
+        let mut writer = vec![];
+        assert!(decompress_to_limited(&bomb, &mut writer, 0x1000).is_err());
+        assert!(writer.len() <= 0x1000);
+
+        // the header says less than what the stream inflates to.
+        let mut lying = bomb.clone();
+        lying[2..5].copy_from_slice(&[0x10, 0, 0]);
+        assert!(matches!(
+            decompress(&lying),
+            Err(Error::ZlibSizeMismatch { expected: 0x10, .. })
+        ));
+        // ... or much more than what it possibly could.
+        assert_eq!(
+            initial_capacity(b"ZL\xFF\xFF\xFF", 0xFF_FFFF),
+            5 * DEFLATE_MAX_RATIO
+        );
+
+        let pman = PmanFile::new(&fixture::archive())?;
+        let all = pman.decompress_all_with(&DecompressLimits {
+            max_entry_size: fixture::TEXT.len(),
+            ..DecompressLimits::default()
+        });
+
+        assert!(matches!(all[fixture::RAW], Ok(None)));
+        assert_eq!(
+            all[fixture::ZLIB].as_ref().ok(),
+            Some(&Some(fixture::TEXT.to_vec()))
+        );
+        assert!(matches!(
+            all[fixture::CHUNKS],
+            Err(Error::DecompressionLimit {
+                what: "an entry",
+                ..
+            })
+        ));
+
+        // `CHUNKS` doesn't fit after `ZLIB`, but `LAST` does.
+        let all = pman.decompress_all_with(&DecompressLimits {
+            max_entry_size: usize::MAX,
+            max_total_size: fixture::TEXT.len() + 4,
+        });
+        assert!(matches!(
+            all[fixture::CHUNKS],
+            Err(Error::DecompressionLimit {
+                what: "the whole archive",
+                ..
+            })
+        ));
+        assert_eq!(
+            all[fixture::LAST].as_ref().ok(),
+            Some(&Some(b"last".to_vec()))
+        );
+        assert!(pman
+            .decompress_all_with(&DecompressLimits::UNLIMITED)
+            .into_iter()
+            .all(|zlib| zlib.is_ok()));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn par_decompress_all_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(&fixture::archive())?;
+        let all = pman.par_decompress_all();
+        assert_eq!(all.len(), pman.files().len());
+
+        for (file, zlib) in pman.files().iter().zip(all) {
+            assert_eq!(file.to_zlib()?, zlib?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_iter_test() -> eyre::Result<()> {
+        fn assert_iterator<I>(iter: I) -> I
+        where
+            I: ExactSizeIterator + DoubleEndedIterator + std::iter::FusedIterator,
+        {
+            iter
+        }
+
+        let mut pman = PmanFile::new(&fixture::archive())?;
+        let files = pman.files().to_vec();
+
+        let mut borrowed = assert_iterator(pman.iter());
+        assert_eq!(borrowed.len(), fixture::FILE_COUNT);
+        assert_eq!(borrowed.next_back(), files.last());
+        assert_eq!(borrowed.rev().last(), files.first());
+
+        for file in assert_iterator(pman.iter_mut()).rev().take(1) {
+            file.set_entry_type(7);
+        }
+        assert_eq!(pman[fixture::LAST].entry_type(), 7);
+        pman.files_mut()[fixture::LAST].set_entry_type(0);
+
+        assert_eq!((&pman).into_iter().len(), fixture::FILE_COUNT);
+        let owned = assert_iterator(pman.clone().into_iter());
+        assert_eq!(owned.len(), fixture::FILE_COUNT);
+        assert_eq!(
+            owned.rev().collect::<Vec<_>>(),
+            files.iter().rev().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(pman.into_files(), files);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_file_data_traits_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(&fixture::archive())?;
+        let mut file = pman[fixture::RAW].clone();
+
+        assert_eq!(&*file, pman[fixture::RAW].bytes());
+        assert_eq!(file.as_ref(), &file[..]);
+        assert_eq!(file.len(), 257);
+
+        // shared with the archive; so it is copied, and the archive keeps its data.
+        file.extend([1, 2]);
+        assert_eq!(file[257..], [1, 2]);
+        assert_eq!(pman[fixture::RAW].len(), 257);
+
+        let owned = PmanFileData::from(b"data".to_vec());
+        assert_eq!((&owned[..], owned.entry_type()), (&b"data"[..], 0));
+
+        let path = std::env::temp_dir().join(format!("rashen-{}-entry.dat", std::process::id()));
+        std::fs::write(&path, fixture::TEXT)?;
+        let read = PmanFileData::try_from(path.as_path());
+        std::fs::remove_file(&path)?;
+        assert_eq!(read?, PmanFileData::from(fixture::TEXT.to_vec()));
+        assert_eq!(
+            PmanFileData::try_from(path.as_path()).unwrap_err().kind(),
+            ErrorKind::Io
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn pman_par_iter_test() -> eyre::Result<()> {
+        use rayon::prelude::*;
+
+        let mut pman = PmanFile::new(&fixture::archive())?;
+        let hashes: Vec<_> = pman
+            .par_iter()
+            .map(ContentHash::of_file)
+            .collect::<crate::error::Result<_>>()?;
+        let expected: Vec<_> = pman
+            .files()
+            .iter()
+            .map(ContentHash::of_file)
+            .collect::<crate::error::Result<_>>()?;
+        assert_eq!(hashes, expected);
+
+        pman.par_iter_mut().for_each(|file| file.set_entry_type(7));
+        let files: Vec<_> = pman.clone().into_par_iter().collect();
+        assert_eq!(files, pman.files());
+        assert!(files.iter().all(|file| file.entry_type() == 7));
+
+        Ok(())
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn pman_new_never_panics_test() {
+        // xorshift; good enough to get some deterministic garbage.
+        let mut state = 0x2545_F491_4F6C_DD1D_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+        let archive = fixture::archive();
+        let table_end = HEADER_SIZE + fixture::FILE_COUNT * FILE_ENTRY_SIZE;
+
+        for _ in 0..256 {
+            let mut input = archive[..next() % archive.len()].to_vec();
+            for _ in 0..next() % 8 {
+                if let Some(byte) = input.get_mut(next() % table_end) {
+                    *byte = next() as u8;
+                }
+            }
+
+            if let Ok(pman) = PmanFile::new(&input) {
+                pman.files().iter().for_each(|file| _ = file.to_zlib());
+                _ = pman.into_bytes();
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn pman_into_bytes_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = &pman.into_bytes()?;
+
+        assert_eq!(bytes.len() + 170, INPUT.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_into_bytes_padding_test() -> eyre::Result<()> {
+        let input = fixture::archive();
+        let bytes = PmanFile::new(&input)?.into_bytes()?;
+
+        // the padding between the entries is dropped.
+        assert_eq!(
+            bytes.len() + fixture::padding(&fixture::files()),
+            input.len()
+        );
+        assert_eq!(PmanFile::new(&bytes)?, PmanFile::new(&input)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_write_to_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(&fixture::archive())?;
+        let mut bytes = vec![];
+        pman.write_to(&mut bytes)?;
+        assert_eq!(bytes, pman.clone().into_bytes()?);
+
+        // nothing is written if the archive can't be packed.
+        let mut pman = pman;
+        pman.copyright = "c".repeat(COPYRIGHT_MAX_SIZE + 1);
+        let mut bytes = vec![];
+        assert!(pman.write_to(&mut bytes).is_err());
+        assert!(bytes.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn save_incremental_test() -> eyre::Result<()> {
+        let path = std::env::temp_dir().join(format!("rashen-save-{}.dat", std::process::id()));
+        let input = fixture::archive();
+        std::fs::write(&path, &input)?;
+
+        let mut pman = PmanFile::new(&input)?;
+        assert!(pman.dirty_files().is_empty());
+        pman.save_incremental(&path)?;
+        assert_eq!(std::fs::read(&path)?, input);
+
+        let index = fixture::CHUNKS;
+        pman.files_mut()[index].set_bytes(compress(&[b'A'; 0x4000])?);
+        assert_eq!(pman.dirty_files(), [index]);
+        pman.save_incremental(&path)?;
+        assert!(pman.dirty_files().is_empty());
+
+        // the rows, and the data (with its padding), of the entries before it are untouched.
+        let bytes = std::fs::read(&path)?;
+        let rows = HEADER_SIZE + index * FILE_ENTRY_SIZE;
+        let (_, entry) =
+            read_file_entry_at(&input, index, Endianness::Little).expect("the row is valid.");
+        let data = entry.offset;
+        assert_eq!(bytes[..rows], input[..rows]);
+        let table_end = HEADER_SIZE + fixture::FILE_COUNT * FILE_ENTRY_SIZE;
+        assert_eq!(bytes[table_end..data], input[table_end..data]);
+        assert_eq!(PmanFile::new(&bytes)?, pman);
+
+        // a new entry moves every other one.
+        pman.files_mut().push(PmanFileData::default());
+        pman.save_incremental(&path)?;
+        let bytes = std::fs::read(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(bytes, pman.clone().into_bytes()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_save_test() -> eyre::Result<()> {
+        let path = std::env::temp_dir().join(format!("rashen-save-as-{}.dat", std::process::id()));
+        let pman = PmanFile::new(&fixture::archive())?;
+        let bytes = pman.clone().into_bytes()?;
+
+        pman.save(&path)?;
+        assert_eq!(std::fs::read(&path)?, bytes);
+        #[cfg(feature = "mmap")]
+        {
+            pman.save_mapped(&path)?;
+            assert_eq!(std::fs::read(&path)?, bytes);
+        }
+        std::fs::remove_file(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn pman_open_test() -> eyre::Result<()> {
+        let path = std::env::temp_dir().join(format!("rashen-open-{}.dat", std::process::id()));
+        let input = fixture::archive();
+        std::fs::write(&path, &input)?;
+
+        let mut pman = PmanFile::open(&path)?;
+        assert_eq!(pman, PmanFile::new(&input)?);
+        pman.files_mut()[fixture::ZLIB].set_bytes(compress(b"replaced")?);
+        let mut bytes = vec![];
+        pman.write_to(&mut bytes)?;
+        drop(pman);
+        std::fs::remove_file(&path)?;
+
+        let pman = PmanFile::new(&bytes)?;
+        assert_eq!(
+            pman[fixture::ZLIB].to_zlib()?.as_deref(),
+            Some(&b"replaced"[..])
+        );
+        assert_eq!(pman[fixture::RAW], PmanFile::new(&input)?[fixture::RAW]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_into_bytes_with_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(&fixture::archive())?;
+        let mut options = PackOptions::with_original_sizes(&pman);
+
+        let (bytes, violations) = pman.clone().into_bytes_with(&options)?;
+        assert_eq!(bytes, pman.clone().into_bytes()?);
+        assert_eq!(violations, []);
+
+        let budget = pman[fixture::RAW].bytes().len();
+        let grown = [pman[fixture::RAW].bytes(), &[0]].concat();
+        pman.files_mut()[fixture::RAW].set_bytes(grown);
+        let size = pman.packed_size();
+        let (_, violations) = pman.clone().into_bytes_with(&options)?;
+        assert_eq!(
+            violations,
+            [
+                BudgetViolation::Entry {
+                    index: fixture::RAW,
+                    size: budget + 1,
+                    budget
+                },
+                BudgetViolation::Archive {
+                    size,
+                    max: size - 1
+                }
+            ]
+        );
+
+        options.strict = true;
+        let err = pman.into_bytes_with(&options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::OverBudget);
+
+        Ok(())
+    }
+
+    #[test]
+    fn patch_in_place_test() -> eyre::Result<()> {
+        let input = fixture::archive();
+        let original = PmanFile::new(&input)?;
+        let mut file = io::Cursor::new(input);
+        let (raw, last) = (fixture::RAW, fixture::LAST);
+
+        assert!(entry_slot(&mut file, raw)?.len() >= original[raw].bytes().len());
+        assert!(patch_in_place(&mut file, raw, b"smaller")?);
+
+        let patched = PmanFile::new_lenient(file.get_ref())?;
+        assert_eq!(patched[raw].bytes(), b"smaller");
+        for (index, (a, b)) in original.files().iter().zip(patched.files()).enumerate() {
+            assert!(index == raw || a == b, "entry {index}");
+        }
+
+        // larger than the slot, and the last entry, which can't shrink.
+        let larger = vec![0; entry_slot(&mut file, raw)?.len() + 1];
+        assert!(!patch_in_place(&mut file, raw, &larger)?);
+        assert!(!patch_in_place(&mut file, last, b"s")?);
+        let same = original[last].bytes().to_vec();
+        assert!(patch_in_place(&mut file, last, &same)?);
+        assert_eq!(PmanFile::new_lenient(file.get_ref())?, patched);
+
+        let err = patch_in_place(&mut file, fixture::FILE_COUNT, b"").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidImport);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_empty_test() -> eyre::Result<()> {
+        let input = fixture::pack(fixture::COPYRIGHT, &[]);
+        let pman = PmanFile::new(&input)?;
+
+        assert!(pman.files().is_empty());
+        assert_eq!(pman.version(), PmanVersion::Unknown);
+        assert_eq!(pman.clone().into_iter().count(), 0);
+        assert_eq!(pman.clone().into_bytes()?, input);
+        assert!(PmanFile::new_partial(&input)?.is_complete());
+
+        let mut file = io::Cursor::new(input.clone());
+        let err = entry_slot(&mut file, 0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidImport);
+
+        // anything after the header is not an entry table.
+        let err = PmanFile::new(&[&input[..], &[0; 0x10]].concat()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TrailingBytes);
+        assert!(err.to_string().contains("after the header"), "{err}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_single_entry_test() -> eyre::Result<()> {
+        let input = fixture::pack(fixture::COPYRIGHT, &[compress(fixture::TEXT)?]);
+        let mut pman = PmanFile::new(&input)?;
+
+        assert_eq!(pman.files().len(), 1);
+        assert_eq!(pman[0].to_zlib()?.as_deref(), Some(fixture::TEXT));
+        assert_eq!(pman.clone().into_bytes()?, input);
+
+        // the only entry is the last one too, so it can't shrink.
+        let mut file = io::Cursor::new(input.clone());
+        assert_eq!(
+            entry_slot(&mut file, 0)?,
+            HEADER_SIZE + FILE_ENTRY_SIZE..input.len()
+        );
+        assert!(!patch_in_place(&mut file, 0, b"smaller")?);
+
+        // down to no entries, and back.
+        pman.files_mut().clear();
+        let empty = PmanFile::new(&pman.clone().into_bytes()?)?;
+        assert_eq!(empty, pman);
+        pman.files_mut().push(PmanFile::new(&input)?[0].clone());
+        assert_eq!(pman.into_bytes()?, input);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_truncated_test() -> eyre::Result<()> {
+        let archive = fixture::archive();
+        let pman = PmanFile::new(&archive)?;
+        // cuts the file in the middle of the data of `CHUNKS`.
+        let row = ENTRY_TABLE_START + fixture::CHUNKS * FILE_ENTRY_SIZE;
+        let (_, entry) =
+            FileEntry::from_bytes(&archive[row..], Endianness::Little).expect("a valid entry.");
+        let chunks = entry.offset;
+        let input = &archive[..chunks + 0x10];
+
+        let err = PmanFile::new(input).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Truncated);
+        assert!(matches!(
+            err,
+            Error::Truncated {
+                expected,
+                actual,
+                first_missing_entry: fixture::CHUNKS,
+            } if expected == archive.len() && actual == input.len()
+        ));
+
+        let lenient = PmanFile::new_lenient(input)?;
+        assert_eq!(lenient.files(), &pman.files()[..fixture::CHUNKS]);
+        assert!(lenient.trailing_bytes().is_empty());
+        assert_eq!(
+            lenient.warnings(),
+            [ParseWarning::TruncatedArchive {
+                first_missing_entry: fixture::CHUNKS,
+                expected: archive.len(),
+                actual: input.len(),
+            }]
+        );
+        assert_eq!(PmanFile::new(&lenient.into_bytes()?)?.files().len(), 3);
+
+        // only the last entry is cut short; that is not enough to drop it.
+        let pman = PmanFile::new(&archive[..archive.len() - 1])?;
+        assert_eq!(pman.files().len(), fixture::FILE_COUNT);
+        assert!(matches!(
+            pman.warnings(),
+            [ParseWarning::TruncatedEntry {
+                index: fixture::LAST,
+                ..
+            }]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_big_endian_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(&fixture::archive())?;
+        let archive = pman.clone().into_bytes()?;
+        pman.set_endianness(Endianness::Big);
+        let input = pman.clone().into_bytes()?;
+
+        // the header and the entry table are swapped; the data is not.
+        assert_eq!(input.len(), archive.len());
+        assert_eq!(input[4..8], [0, 0, 0, 5]);
+        let offset = HEADER_SIZE + 4..HEADER_SIZE + 8;
+        assert_eq!(
+            u32::from_be_bytes(input[offset.clone()].try_into()?),
+            u32::from_le_bytes(archive[offset].try_into()?)
+        );
+        assert_eq!(
+            input[pman.size_upto_file_data()..],
+            archive[pman.size_upto_file_data()..]
+        );
+
+        let big = PmanFile::new(&input)?;
+        assert_eq!(big.endianness(), Endianness::Big);
+        assert_eq!(big, pman);
+
+        let mut file = io::Cursor::new(input);
+        assert!(patch_in_place(&mut file, fixture::RAW, b"patched")?);
+        let patched = PmanFile::new(file.get_ref())?;
+        assert_eq!(patched[fixture::RAW].bytes(), b"patched");
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_entry_layout_test() -> eyre::Result<()> {
+        let archive = fixture::archive();
+        let pman = PmanFile::new(&archive)?;
+        let row = |index: usize| ENTRY_TABLE_START + index * FILE_ENTRY_SIZE;
+        let offset = |input: &[u8], index: usize| {
+            let (_, entry) = FileEntry::from_bytes(&input[row(index)..], Endianness::Little)
+                .expect("a valid entry.");
+            entry.offset
+        };
+        let set_offset = |input: &mut [u8], index: usize, offset: usize| {
+            let offset = u32::try_from(offset).expect("the offset fits.");
+            input[row(index) + 4..row(index) + 8].copy_from_slice(&offset.to_le_bytes());
+        };
+        let raw = offset(&archive, fixture::RAW);
+
+        // `ZLIB` starts in the middle of `RAW`.
+        let mut input = archive.clone();
+        set_offset(&mut input, fixture::ZLIB, raw + 0x10);
+        let err = PmanFile::new(&input).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::EntryOverlap);
+        assert!(matches!(
+            err,
+            Error::EntryOverlap {
+                index: fixture::ZLIB,
+                offset,
+                previous_end,
+            } if offset == raw + 0x10 && previous_end == raw + 0x101
+        ));
+
+        let lenient = PmanFile::new_lenient(&input)?;
+        let size = pman[fixture::ZLIB].bytes().len();
+        assert_eq!(
+            lenient[fixture::ZLIB].bytes(),
+            &archive[raw + 0x10..raw + 0x10 + size]
+        );
+        assert_eq!(lenient[fixture::CHUNKS], pman[fixture::CHUNKS]);
+        assert!(matches!(
+            lenient.warnings(),
+            [ParseWarning::EntryOverlap {
+                index: fixture::ZLIB,
+                ..
+            }]
+        ));
+        // laid out one after the other again.
+        let packed = PmanFile::new(&lenient.clone().into_bytes()?)?;
+        assert_eq!(packed.files(), lenient.files());
+
+        // the first entry can't start on the entry table.
+        let mut input = archive.clone();
+        set_offset(&mut input, fixture::RAW, ENTRY_TABLE_START);
+        assert!(matches!(
+            PmanFile::new(&input),
+            Err(Error::EntryOverlap {
+                index: fixture::RAW,
+                previous_end,
+                ..
+            }) if previous_end == raw
+        ));
+
+        // `ZLIB` and `LAST` swap their rows, so `EMPTY` starts before the entry before it.
+        let mut input = archive.clone();
+        let (zlib, last) = (row(fixture::ZLIB), row(fixture::LAST));
+        let zlib_row = input[zlib..zlib + FILE_ENTRY_SIZE].to_vec();
+        input.copy_within(last..last + FILE_ENTRY_SIZE, zlib);
+        input[last..last + FILE_ENTRY_SIZE].copy_from_slice(&zlib_row);
+        let err = PmanFile::new(&input).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::EntryOutOfOrder);
+        assert!(matches!(
+            err,
+            Error::EntryOutOfOrder {
+                index: fixture::EMPTY,
+                ..
+            }
+        ));
+
+        let lenient = PmanFile::new_lenient(&input)?;
+        assert_eq!(lenient[fixture::ZLIB], pman[fixture::LAST]);
+        assert_eq!(lenient[fixture::LAST], pman[fixture::ZLIB]);
+        assert!(lenient.trailing_bytes().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_entry_at_offset_test() -> eyre::Result<()> {
+        let files = fixture::files();
+        let archive = fixture::archive();
+        let pman = PmanFile::new(&archive)?;
+        let start = pman.size_upto_file_data();
+        let zlib = start + files[fixture::RAW].len().next_multiple_of(4);
+
+        assert_eq!(pman.entry_at_offset(0), None);
+        assert_eq!(pman.entry_at_offset(start), Some(fixture::RAW));
+        assert_eq!(pman.entry_at_offset(start + 0x100), Some(fixture::RAW));
+        // the padding after it.
+        assert_eq!(pman.entry_at_offset(start + 0x101), None);
+        assert_eq!(pman.entry_at_offset(zlib + 1), Some(fixture::ZLIB));
+        assert_eq!(pman.entry_at_offset(archive.len() - 1), Some(fixture::LAST));
+        assert_eq!(pman.entry_at_offset(archive.len()), None);
+
+        // a truncated archive is not patched, so the offsets are the ones that it is packed with;
+        // without padding.
+        let end = archive.len() - files[fixture::LAST].len() - 1;
+        let truncated = PmanFile::new_lenient(&archive[..end])?;
+        assert_eq!(
+            truncated.entry_at_offset(start + 0x101),
+            Some(fixture::ZLIB)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_trailing_bytes_test() -> eyre::Result<()> {
+        let archive = fixture::archive();
+        let input = [&archive[..], b"trailing"].concat();
+        let err = PmanFile::new(&input).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::TrailingBytes);
+
+        let Error::InvalidFormat(err) = err else {
+            unreachable!("trailing bytes are a format error.");
+        };
+
+        assert_eq!(err.offset(), archive.len());
+
+        let pman = PmanFile::new_lenient(&input)?;
+
+        assert_eq!(pman.trailing_bytes(), b"trailing");
+        assert_eq!(
+            pman.warnings().last(),
+            Some(&ParseWarning::TrailingBytes {
+                offset: archive.len(),
+                len: 8
+            })
+        );
+        assert!(pman.into_bytes()?.ends_with(b"trailing"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_total_size_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(&fixture::archive())?;
+        assert_eq!(pman.total_size(), pman.clone().into_bytes()?.len() as u64);
+        assert!(pman.fits_in_u32_offsets());
+
+        // 4 GiB of entries, that share the same 1 MiB; so nothing is allocated for them.
+        let shared = Bytes::from(vec![0; 1 << 20]);
+        for _ in 0..0x1000 {
+            pman.files_mut().push(shared.clone().into());
+        }
+        assert!(pman.total_size() > u64::from(u32::MAX));
+        assert!(!pman.fits_in_u32_offsets());
+        assert_eq!(
+            pman.into_bytes().map_err(|err| err.kind()),
+            Err(ErrorKind::ArchiveTooLarge)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn pman_entry_types_test() -> eyre::Result<()> {
+        use crate::format::texture::TEXTURE_SIZE;
+
+        // every entry of a known type, so the archive is read back as typed.
+        let files = [
+            b"PMAN".to_vec(),
+            vec![1; (TEXTURE_SIZE * TEXTURE_SIZE) as usize],
+        ];
+        let pman = PmanFile::new(&fixture::pack(fixture::COPYRIGHT, &files))?;
+        let pack = |entry_types| {
+            let options = PackOptions {
+                entry_types,
+                ..PackOptions::default()
+            };
+            let (bytes, _) = pman.clone().into_bytes_with(&options)?;
+
+            PmanFile::new(&bytes)
+        };
+
+        let typed = pack(EntryTypes::Detected)?;
+        let codes: Vec<_> = typed.files().iter().map(PmanFileData::entry_type).collect();
+        assert_eq!(
+            codes,
+            [
+                FileType::Packfile.type_code(),
+                FileType::Texture.type_code()
+            ]
+        );
+        assert_eq!(typed.version(), PmanVersion::Typed);
+        assert_eq!(
+            codes
+                .into_iter()
+                .map(FileType::from_type_code)
+                .collect::<Vec<_>>(),
+            [Some(FileType::Packfile), Some(FileType::Texture)]
+        );
+
+        // the stored codes are kept, unless zeros are asked for.
+        let mut stored = typed.clone();
+        stored.files_mut()[0].set_entry_type(0x1234);
+        let stored = PmanFile::new(&stored.into_bytes()?)?;
+        assert_eq!(stored[0].entry_type(), 0x1234);
+        let mut again = stored.clone();
+        again.fill_entry_types(EntryTypes::Detected);
+        assert_eq!(again, stored);
+        again.fill_entry_types(EntryTypes::Zeros);
+        assert!(again.files().iter().all(|file| file.entry_type() == 0));
+        assert_eq!(pack(EntryTypes::Zeros)?, pman);
+        assert_eq!(pack(EntryTypes::Stored)?, pman);
+
+        Ok(())
+    }
+
+    /// [`compress`] of `b"ashen"`.
+    const PINNED: &[u8] = &[
+        0x5A, 0x4C, 0x05, 0x00, 0x00, 0x78, 0xDA, 0x4B, 0x2C, 0xCE, 0x48, 0xCD, 0x03, 0x00, 0x06,
+        0x26, 0x02, 0x10,
+    ];
+
+    #[test]
+    fn pman_reproducible_test() -> eyre::Result<()> {
+        // the zlib settings are fixed; a change of them (or of the backend) shows up here.
+        assert_eq!(compress(b"ashen")?, PINNED);
+        let data: Vec<_> = fixture::files()
+            .into_iter()
+            .map(|file| file.repeat(3))
+            .collect();
+        let compressed = compress_all(&data)?;
+        assert_eq!(compressed, compress_all(&data)?);
+
+        let pack = |trailing: &[u8]| -> eyre::Result<Vec<u8>> {
+            let input = [&fixture::archive()[..], trailing].concat();
+            let mut pman = PmanFile::new_lenient(&input)?;
+            for (file, bytes) in pman.files_mut().iter_mut().zip(&compressed) {
+                file.set_bytes(bytes.clone());
+            }
+            let options = PackOptions {
+                reproducible: true,
+                ..PackOptions::default()
+            };
+
+            Ok(pman.into_bytes_with(&options)?.0)
+        };
+        let packed = pack(b"")?;
+
+        assert_eq!(packed, pack(b"")?);
+        // only the inputs matter; not what the original archive had after its last entry.
+        assert_eq!(packed, pack(b"trailing")?);
+        // and packing it again gives it back.
+        assert_eq!(packed, PmanFile::new(&packed)?.into_bytes()?);
+
+        Ok(())
+    }
+
+    mod round_trip {
+        use super::*;
+        use proptest::{collection::vec, prelude::*};
+
+        /// A copyright notice that fits on the header; a NULL would end it early.
+        fn copyright() -> impl Strategy<Value = String> {
+            vec(any::<char>(), 0..=COPYRIGHT_MAX_SIZE).prop_map(|chars| {
+                let mut copyright = String::new();
+                for c in chars.into_iter().filter(|&c| c != '\0') {
+                    if copyright.len() + c.len_utf8() > COPYRIGHT_MAX_SIZE {
+                        break;
+                    }
+                    copyright.push(c);
+                }
+
+                copyright
+            })
+        }
+
+        /// The data of every file; either every entry type is `0` (like `1.0.6`), or every entry
+        /// has the code of a known type (like [`PmanVersion::Typed`]).
+        fn files() -> impl Strategy<Value = Vec<PmanFileData>> {
+            let file_type = prop::sample::select(FileType::ALL.as_slice());
+
+            (
+                any::<bool>(),
+                vec((vec(any::<u8>(), 0..0x100), file_type), 0..32),
+            )
+                .prop_map(|(typed, files)| {
+                    files
+                        .into_iter()
+                        .map(|(bytes, file_type)| PmanFileData {
+                            bytes: bytes.into(),
+                            entry_type: if typed { file_type.type_code() } else { 0 },
+                        })
+                        .collect()
+                })
+        }
+
+        /// An archive that was built in memory, instead of being parsed.
+        fn archive() -> impl Strategy<Value = PmanFile> {
+            (copyright(), files(), any::<bool>()).prop_map(|(copyright, files, big)| {
+                let typed_count = files.iter().filter(|file| file.entry_type != 0).count();
+
+                PmanFile {
+                    version: PmanVersion::detect(&copyright, files.len(), typed_count),
+                    // the count of an empty archive reads the same both ways.
+                    endianness: if big && !files.is_empty() {
+                        Endianness::Big
+                    } else {
+                        Endianness::Little
+                    },
+                    copyright,
+                    files,
+                    warnings: vec![],
+                    trailing_bytes: vec![],
+                    origin: None,
+                }
+            })
+        }
+
+        /// Packs `pman` like [`PmanFile::write_to`], but with `paddings[i]` between the files `i`
+        /// and `i + 1`; the padding after the last file is trailing bytes.
+        #[allow(clippy::cast_possible_truncation)]
+        fn pack_with_padding(pman: &PmanFile, paddings: &[Vec<u8>]) -> Vec<u8> {
+            let mut bytes = vec![];
+            pman.write_header(&mut bytes).expect("`Vec` can't fail.");
+
+            let mut offset = pman.size_upto_file_data();
+            for (file, padding) in pman.files.iter().zip(paddings) {
+                for value in [file.entry_type, offset as u32, file.bytes.len() as u32, 0] {
+                    bytes.extend_from_slice(&pman.endianness.u32_bytes(value));
+                }
+                offset += file.bytes.len() + padding.len();
+            }
+            for (file, padding) in pman.files.iter().zip(paddings) {
+                bytes.extend_from_slice(&file.bytes);
+                bytes.extend_from_slice(padding);
+            }
+
+            bytes
+        }
+
+        proptest! {
+            #[test]
+            fn pman_round_trip_test(pman in archive()) {
+                let bytes = pman.clone().into_bytes()?;
+
+                prop_assert_eq!(bytes.len(), pman.packed_size());
+                prop_assert_eq!(PmanFile::new(&bytes)?, pman);
+            }
+
+            #[test]
+            fn pman_padding_round_trip_test(
+                pman in archive(),
+                paddings in vec(vec(any::<u8>(), 0..8), 32),
+            ) {
+                let bytes = pack_with_padding(&pman, &paddings);
+                let parsed = PmanFile::new_lenient(&bytes)?;
+                prop_assert_eq!(&parsed.copyright, &pman.copyright);
+                prop_assert_eq!(&parsed.files, &pman.files);
+
+                // the padding is dropped, but nothing else.
+                let padding = paddings[..pman.files.len().saturating_sub(1)]
+                    .iter()
+                    .map(Vec::len)
+                    .sum::<usize>();
+                let packed = parsed.clone().into_bytes()?;
+                prop_assert_eq!(packed.len(), bytes.len() - padding);
+
+                let repacked = PmanFile::new_lenient(&packed)?;
+                prop_assert_eq!(&repacked.copyright, &parsed.copyright);
+                prop_assert_eq!(&repacked.files, &parsed.files);
+                prop_assert_eq!(&repacked.trailing_bytes, &parsed.trailing_bytes);
+                prop_assert_eq!(repacked.version, parsed.version);
+                prop_assert_eq!(repacked.into_bytes()?, packed);
+            }
+        }
     }
 }
@@ -0,0 +1,79 @@
+//! Scratch buffers that are reused across the entries of an archive; see [`BufferPool`].
+
+use std::sync::{Mutex, PoisonError};
+
+/// How many buffers a [`BufferPool`] keeps; the rest are freed when they are given back.
+const MAX_BUFFERS: usize = 16;
+
+/// Buffers that are given back after use, so the next entry takes one of them instead of
+/// allocating its own; e.g. for [`decompress_with`](super::pman::decompress_with) and
+/// [`compress_with`](super::pman::compress_with) in a loop over every entry.
+///
+/// It can be shared between threads; buffers that are not given back are simply not reused.
+#[derive(Debug, Default)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buffers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// An empty buffer, with room for at least `capacity` bytes.
+    #[must_use]
+    pub fn take(&self, capacity: usize) -> Vec<u8> {
+        let buffer = self
+            .buffers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+
+        match buffer {
+            Some(mut buffer) => {
+                buffer.reserve(capacity);
+                buffer
+            }
+            None => Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Gives `buffer` back, to be taken again; its data is cleared.
+    pub fn give(&self, mut buffer: Vec<u8>) {
+        buffer.clear();
+
+        let mut buffers = self.buffers.lock().unwrap_or_else(PoisonError::into_inner);
+        if buffers.len() < MAX_BUFFERS && buffer.capacity() > 0 {
+            buffers.push(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_pool_test() {
+        let pool = BufferPool::new();
+
+        let mut buffer = pool.take(0x100);
+        assert!(buffer.capacity() >= 0x100);
+        buffer.extend_from_slice(b"data");
+        let ptr = buffer.as_ptr();
+        pool.give(buffer);
+
+        // the same allocation, but empty.
+        let buffer = pool.take(0x10);
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert!(buffer.is_empty());
+
+        for _ in 0..MAX_BUFFERS * 2 {
+            pool.give(vec![0; 1]);
+        }
+        assert_eq!(pool.buffers.lock().unwrap().len(), MAX_BUFFERS);
+    }
+}
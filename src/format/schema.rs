@@ -0,0 +1,310 @@
+//! Machine-readable descriptions of the formats, for external reverse-engineering tools.
+//!
+//! Every format module describes its own layout (see [`formats`]), with the same constants that
+//! its parser reads; they are written as Kaitai Struct files with [`Format::to_ksy`].
+
+use std::fmt::Write;
+
+/// How many items, or bytes, a [`Field`] has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Size {
+    Fixed(usize),
+    /// An expression over the previous fields; e.g. `width * height`.
+    Expr(String),
+    /// Up to the end of the data.
+    Eos,
+}
+
+/// What a [`Field`] is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kind {
+    /// Bytes that must match.
+    Magic(Vec<u8>),
+    /// A primitive type, by its Kaitai name; e.g. `u4` or `s2`.
+    Primitive(&'static str),
+    Bytes(Size),
+    /// A string with a fixed size, padded with NULs.
+    Str {
+        size: usize,
+        encoding: &'static str,
+    },
+    /// A NUL terminated string.
+    StrZ {
+        encoding: &'static str,
+    },
+    /// Another type, by its id; of this format, or of an imported one.
+    Type(&'static str),
+}
+
+/// A field of a [`TypeDef`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub id: &'static str,
+    pub kind: Kind,
+    /// How many times it repeats, if it does.
+    pub repeat: Option<Size>,
+    /// The offset that it is at, if it is not right after the previous field; an expression.
+    pub pos: Option<String>,
+    /// The stream that [`Self::pos`] is relative to, if it is not the one of the type; e.g.
+    /// `_root._io`.
+    pub io: Option<&'static str>,
+    /// The size that it takes, for types that don't know their own.
+    pub size: Option<Size>,
+    /// How the bytes are processed before they are read; e.g. `zlib`.
+    pub process: Option<&'static str>,
+    pub doc: Option<String>,
+}
+
+impl Field {
+    #[must_use]
+    pub fn new(id: &'static str, kind: Kind) -> Self {
+        Self {
+            id,
+            kind,
+            repeat: None,
+            pos: None,
+            io: None,
+            size: None,
+            process: None,
+            doc: None,
+        }
+    }
+
+    #[must_use]
+    pub fn magic(id: &'static str, bytes: &[u8]) -> Self {
+        Self::new(id, Kind::Magic(bytes.to_vec()))
+    }
+
+    #[must_use]
+    pub fn primitive(id: &'static str, name: &'static str) -> Self {
+        Self::new(id, Kind::Primitive(name))
+    }
+
+    #[must_use]
+    pub fn repeat(mut self, count: Size) -> Self {
+        self.repeat = Some(count);
+        self
+    }
+
+    #[must_use]
+    pub fn at(mut self, pos: impl Into<String>) -> Self {
+        self.pos = Some(pos.into());
+        self
+    }
+
+    #[must_use]
+    pub fn io(mut self, io: &'static str) -> Self {
+        self.io = Some(io);
+        self
+    }
+
+    #[must_use]
+    pub fn sized(mut self, size: Size) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    #[must_use]
+    pub fn process(mut self, process: &'static str) -> Self {
+        self.process = Some(process);
+        self
+    }
+
+    #[must_use]
+    pub fn doc(mut self, doc: impl Into<String>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
+}
+
+/// A sequence of fields; fields with a position are read lazily, when they are accessed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDef {
+    pub id: &'static str,
+    pub doc: Option<String>,
+    pub fields: Vec<Field>,
+}
+
+/// A top-level format; e.g. the `packfile.dat`, or one of the types of its entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Format {
+    pub id: &'static str,
+    pub title: &'static str,
+    /// The formats whose types this one uses.
+    pub imports: Vec<&'static str>,
+    /// The layout of the format itself.
+    pub root: TypeDef,
+    /// Types that the root (or each other) use.
+    pub types: Vec<TypeDef>,
+}
+
+/// Every format that is described; see [`Format::to_ksy`].
+#[must_use]
+pub fn formats() -> Vec<Format> {
+    vec![
+        super::pman::schema(),
+        super::pman::zl_schema(),
+        super::palette::schema(),
+        super::texture::schema(),
+        super::skybox::schema(),
+        super::text::schema(),
+        super::audio::schema(),
+        super::audio::clip_schema(),
+    ]
+}
+
+fn size_key(size: &Size) -> String {
+    match size {
+        Size::Fixed(size) => format!("size: {size}"),
+        Size::Expr(expr) => format!("size: {expr}"),
+        Size::Eos => "size-eos: true".into(),
+    }
+}
+
+fn write_field(ksy: &mut String, field: &Field, indent: &str) {
+    let mut keys = Vec::new();
+
+    if let Some(io) = field.io {
+        keys.push(format!("io: {io}"));
+    }
+    if let Some(pos) = &field.pos {
+        keys.push(format!("pos: {pos}"));
+    }
+    match &field.kind {
+        Kind::Magic(bytes) => {
+            let bytes: Vec<_> = bytes.iter().map(|b| format!("{b:#04x}")).collect();
+            keys.push(format!("contents: [{}]", bytes.join(", ")));
+        }
+        Kind::Primitive(name) => keys.push(format!("type: {name}")),
+        Kind::Bytes(size) => keys.push(size_key(size)),
+        Kind::Str { size, encoding } => {
+            keys.push("type: str".into());
+            keys.push(format!("size: {size}"));
+            keys.push(format!("encoding: {encoding}"));
+            keys.push("terminator: 0".into());
+        }
+        Kind::StrZ { encoding } => {
+            keys.push("type: strz".into());
+            keys.push(format!("encoding: {encoding}"));
+        }
+        Kind::Type(id) => keys.push(format!("type: {id}")),
+    }
+    if let Some(size) = &field.size {
+        keys.push(size_key(size));
+    }
+    if let Some(process) = field.process {
+        keys.push(format!("process: {process}"));
+    }
+    let count = match &field.repeat {
+        Some(Size::Fixed(count)) => Some(count.to_string()),
+        Some(Size::Expr(expr)) => Some(expr.clone()),
+        Some(Size::Eos) => {
+            keys.push("repeat: eos".into());
+            None
+        }
+        None => None,
+    };
+    if let Some(count) = count {
+        keys.push("repeat: expr".into());
+        keys.push(format!("repeat-expr: {count}"));
+    }
+    if let Some(doc) = &field.doc {
+        keys.push(format!("doc: {doc:?}"));
+    }
+
+    for key in keys {
+        _ = writeln!(ksy, "{indent}{key}");
+    }
+}
+
+fn write_type(ksy: &mut String, ty: &TypeDef, indent: &str) {
+    let inner = format!("{indent}    ");
+
+    if let Some(doc) = &ty.doc {
+        _ = writeln!(ksy, "{indent}doc: {doc:?}");
+    }
+
+    let (instances, seq): (Vec<_>, Vec<_>) = ty.fields.iter().partition(|f| f.pos.is_some());
+    if !seq.is_empty() {
+        _ = writeln!(ksy, "{indent}seq:");
+        for field in seq {
+            _ = writeln!(ksy, "{indent}  - id: {}", field.id);
+            write_field(ksy, field, &inner);
+        }
+    }
+    if !instances.is_empty() {
+        _ = writeln!(ksy, "{indent}instances:");
+        for field in instances {
+            _ = writeln!(ksy, "{indent}  {}:", field.id);
+            write_field(ksy, field, &inner);
+        }
+    }
+}
+
+impl Format {
+    /// Writes the format as a Kaitai Struct (`.ksy`) file; its imports are expected next to it.
+    #[must_use]
+    pub fn to_ksy(&self) -> String {
+        let mut ksy = String::new();
+
+        _ = writeln!(
+            ksy,
+            "# Generated by `rashen schema`; don't edit it by hand."
+        );
+        _ = writeln!(ksy, "meta:");
+        _ = writeln!(ksy, "  id: {}", self.id);
+        _ = writeln!(ksy, "  title: {:?}", self.title);
+        _ = writeln!(ksy, "  endian: le");
+        if !self.imports.is_empty() {
+            _ = writeln!(ksy, "  imports:");
+            for import in &self.imports {
+                _ = writeln!(ksy, "    - {import}");
+            }
+        }
+        write_type(&mut ksy, &self.root, "");
+
+        if !self.types.is_empty() {
+            _ = writeln!(ksy, "types:");
+            for ty in &self.types {
+                _ = writeln!(ksy, "  {}:", ty.id);
+                write_type(&mut ksy, ty, "    ");
+            }
+        }
+
+        ksy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_test() {
+        let formats = formats();
+
+        for format in &formats {
+            // every import, and every type that is used, is defined.
+            for import in &format.imports {
+                assert!(formats.iter().any(|f| f.id == *import), "{import}");
+            }
+            let fields = format
+                .types
+                .iter()
+                .chain([&format.root])
+                .flat_map(|t| &t.fields);
+            for field in fields {
+                if let Kind::Type(id) = field.kind {
+                    let known =
+                        format.types.iter().any(|t| t.id == id) || format.imports.contains(&id);
+                    assert!(known, "{}: {id}", format.id);
+                }
+            }
+        }
+
+        let pman = formats[0].to_ksy();
+        assert!(pman.contains("  id: pman\n"));
+        assert!(pman.contains("contents: [0x50, 0x4d, 0x41, 0x4e]"));
+        assert!(pman.contains("repeat-expr: file_count"));
+    }
+}
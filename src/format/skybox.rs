@@ -0,0 +1,351 @@
+use super::{
+    color,
+    palette::{self, Palette},
+    Result,
+};
+use crate::error::{Error, ErrorKind, Violation};
+use nom::{bytes::complete::take, combinator::eof, number::complete::le_u32, Parser};
+use nom_supreme::ParserExt;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// A face of a cube map, in the `Y` up space of OpenGL (and of most engines); the camera looks at
+/// `-Z`, so `+X` is to its right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    /// Every face, in the order of the layers of an OpenGL cube map.
+    pub const ALL: [Self; 6] = [
+        Self::PositiveX,
+        Self::NegativeX,
+        Self::PositiveY,
+        Self::NegativeY,
+        Self::PositiveZ,
+        Self::NegativeZ,
+    ];
+
+    /// The short name of the face; e.g. `px` for [`Self::PositiveX`].
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::PositiveX => "px",
+            Self::NegativeX => "nx",
+            Self::PositiveY => "py",
+            Self::NegativeY => "ny",
+            Self::PositiveZ => "pz",
+            Self::NegativeZ => "nz",
+        }
+    }
+
+    /// The direction (not normalized) of the point of the face at `s` (to the right) and `t`
+    /// (down), both from `-1` to `1`.
+    fn direction(self, s: f32, t: f32) -> [f32; 3] {
+        match self {
+            Self::PositiveX => [1.0, -t, -s],
+            Self::NegativeX => [-1.0, -t, s],
+            Self::PositiveY => [s, 1.0, t],
+            Self::NegativeY => [s, -1.0, -t],
+            Self::PositiveZ => [s, -t, 1.0],
+            Self::NegativeZ => [-s, -t, -1.0],
+        }
+    }
+}
+
+/// The skyboxes of the game; a panorama of the horizon that wraps around the level.
+///
+/// Each one is a single indexed image, with its own palette.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Skybox {
+    pub width: u32,
+    pub height: u32,
+    pub palette: Palette,
+    /// Indices into [`Self::palette`], row by row.
+    pub pixels: Vec<u8>,
+}
+
+fn read_skybox(input: &[u8]) -> Result<'_, Skybox> {
+    let (input, width) = le_u32.context("width").parse(input)?;
+    let (input, height) = le_u32.context("height").parse(input)?;
+    let (input, palette) = palette::read_palette(input)?;
+
+    let size = (width as usize).saturating_mul(height as usize);
+    if input.len() != size {
+        return super::fail_with(
+            input,
+            Violation::new(
+                ErrorKind::InvalidFormat,
+                format!(
+                    "a {width}x{height} image needs {size:#X} bytes, but there are {:#X}",
+                    input.len()
+                ),
+                input.len(),
+            ),
+        );
+    }
+
+    let (input, pixels) = take(size).context("pixels").parse(input)?;
+    let (input, _) = eof(input)?;
+
+    Ok((
+        input,
+        Skybox {
+            width,
+            height,
+            palette,
+            pixels: pixels.to_vec(),
+        },
+    ))
+}
+
+impl Skybox {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid skybox entry.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "skybox", read_skybox)
+    }
+
+    /// The pixels as 8-bit RGBA, row by row.
+    #[must_use]
+    pub fn to_rgba(&self) -> Vec<[u8; 4]> {
+        color::apply_palette(&self.pixels, &self.palette.to_rgba())
+    }
+
+    /// The palette index at `azimuth` (from `0` to `1`, a full turn to the right, from behind the
+    /// camera) and `elevation` (in radians, up from the horizon).
+    ///
+    /// The panorama wraps once around the horizon, with its middle column straight ahead (`-Z`);
+    /// its bottom row is the horizon, and its top row is right above the camera. Below the
+    /// horizon, it is the bottom row.
+    // TODO(Unavailable): the wrapping and the elevations are guesses; figure out how the game
+    // draws it.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn sample_at(&self, azimuth: f32, elevation: f32) -> u8 {
+        if self.width == 0 || self.height == 0 {
+            return 0;
+        }
+
+        let x = ((azimuth.rem_euclid(1.0) * self.width as f32) as u32).min(self.width - 1);
+        let up = (elevation / FRAC_PI_2).clamp(0.0, 1.0);
+        let y = (((1.0 - up) * self.height as f32) as u32).min(self.height - 1);
+
+        self.pixels
+            .get(y as usize * self.width as usize + x as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The palette index that is seen when looking at `direction`, in the `Y` up space of
+    /// [`CubeFace`]; see [`Self::to_equirectangular`] for how the panorama is laid out.
+    #[must_use]
+    pub fn sample(&self, direction: [f32; 3]) -> u8 {
+        let [x, y, z] = direction;
+        let azimuth = x.atan2(-z) / TAU + 0.5;
+
+        self.sample_at(azimuth, y.atan2(x.hypot(z)))
+    }
+
+    /// Stitches the panorama into an equirectangular one, that covers every direction; it is twice
+    /// as tall as the skybox, so the sky keeps every row, and twice as wide as it is tall.
+    ///
+    /// Straight ahead (`-Z`) is the middle of both panoramas; the skybox wraps once around the
+    /// horizon, from its bottom row (the horizon) to its top one (right above the camera). Below
+    /// the horizon, the bottom row is repeated.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_equirectangular(&self) -> Self {
+        let height = self.height.saturating_mul(2);
+        let width = height.saturating_mul(2);
+        let pixels = (0..height)
+            .flat_map(|y| {
+                let elevation = FRAC_PI_2 - (y as f32 + 0.5) / height as f32 * PI;
+
+                (0..width).map(move |x| ((x as f32 + 0.5) / width as f32, elevation))
+            })
+            .map(|(azimuth, elevation)| self.sample_at(azimuth, elevation))
+            .collect();
+
+        Self {
+            width,
+            height,
+            palette: self.palette.clone(),
+            pixels,
+        }
+    }
+
+    /// Renders a `size` by `size` face of a cube map of the panorama; see [`Self::sample`].
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_cube_face(&self, face: CubeFace, size: u32) -> Self {
+        let coordinate = |i: u32| (i as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+        let pixels = (0..size)
+            .flat_map(|t| (0..size).map(move |s| face.direction(coordinate(s), coordinate(t))))
+            .map(|direction| self.sample(direction))
+            .collect();
+
+        Self {
+            width: size,
+            height: size,
+            palette: self.palette.clone(),
+            pixels,
+        }
+    }
+
+    /// # Errors
+    ///
+    /// If [`Self::pixels`] doesn't have `width * height` pixels.
+    pub fn to_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        let size = (self.width as usize).saturating_mul(self.height as usize);
+
+        if self.pixels.len() != size {
+            return Err(Error::InvalidImageSize {
+                width: self.width,
+                height: self.height,
+                len: self.pixels.len(),
+            });
+        }
+
+        let mut buf = Vec::with_capacity(8 + palette::PALETTE_SIZE + size);
+
+        buf.extend_from_slice(&self.width.to_le_bytes());
+        buf.extend_from_slice(&self.height.to_le_bytes());
+        buf.extend_from_slice(&self.palette.to_bytes());
+        buf.extend_from_slice(&self.pixels);
+
+        Ok(buf)
+    }
+}
+
+/// The layout of a skybox entry; see [`super::schema`].
+pub(super) fn schema() -> super::schema::Format {
+    use super::schema::{Field, Format, Kind, Size, TypeDef};
+
+    Format {
+        id: "skybox",
+        title: "Ashen skybox",
+        imports: vec!["palette"],
+        root: TypeDef {
+            id: "skybox",
+            doc: None,
+            fields: vec![
+                Field::primitive("width", "u4"),
+                Field::primitive("height", "u4"),
+                Field::new("palette", Kind::Type("palette")),
+                Field::new("pixels", Kind::Bytes(Size::Expr("width * height".into())))
+                    .doc("Indices into `palette`, row by row."),
+            ],
+        },
+        types: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    #[cfg(feature = "packfile")]
+    const SKYBOX_ENTRIES: [usize; 6] = [60, 61, 62, 63, 64, 65];
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn skybox_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in SKYBOX_ENTRIES {
+            let bytes = pman[index].bytes();
+            let skybox = Skybox::new(bytes)?;
+
+            assert_eq!((skybox.width, skybox.height), (256, 256));
+            assert_eq!(skybox.to_rgba().len(), 256 * 256);
+            assert_eq!(skybox.to_bytes()?, bytes, "entry {index}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn skybox_invalid_test() -> eyre::Result<()> {
+        let mut skybox = Skybox {
+            width: 2,
+            height: 2,
+            pixels: vec![0; 4],
+            ..Default::default()
+        };
+        let bytes = skybox.to_bytes()?;
+
+        assert_eq!(Skybox::new(&bytes)?, skybox);
+        assert_eq!(
+            Skybox::new(&bytes[..bytes.len() - 1]).unwrap_err().kind(),
+            ErrorKind::InvalidFormat
+        );
+
+        skybox.pixels.pop();
+
+        assert_eq!(
+            skybox.to_bytes().unwrap_err().kind(),
+            ErrorKind::InvalidImageSize
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn skybox_projection_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let skybox = Skybox::new(pman[60].bytes())?;
+        let (width, height) = (skybox.width as usize, skybox.height as usize);
+        let row = |image: &Skybox, y: usize| {
+            let width = image.width as usize;
+            image.pixels[y * width..(y + 1) * width].to_vec()
+        };
+
+        let equirect = skybox.to_equirectangular();
+        assert_eq!((equirect.width, equirect.height), (1024, 512));
+        assert_eq!(equirect.pixels.len(), 1024 * 512);
+        // the horizon is the bottom row, and the top of the sky is the top row.
+        let horizon: Vec<_> = row(&equirect, 256).into_iter().step_by(4).collect();
+        assert_eq!(horizon, row(&skybox, height - 1));
+        assert!(row(&equirect, 0)
+            .iter()
+            .all(|&i| row(&skybox, 0).contains(&i)));
+
+        // straight ahead is the middle of the panorama, and to the right is 3 quarters of it.
+        let at_horizon = |x: usize| skybox.pixels[(height - 1) * width + x];
+        assert_eq!(skybox.sample([0.0, 0.0, -1.0]), at_horizon(width / 2));
+        assert_eq!(skybox.sample([1.0, 0.0, -0.01]), at_horizon(width * 3 / 4));
+        assert!(row(&skybox, 0).contains(&skybox.sample([0.0, 1.0, 0.0])));
+
+        for face in CubeFace::ALL {
+            let image = skybox.to_cube_face(face, 64);
+
+            assert_eq!(
+                (image.width, image.height, image.pixels.len()),
+                (64, 64, 64 * 64)
+            );
+        }
+
+        let ground = skybox.to_cube_face(CubeFace::NegativeY, 8);
+        let bottom = row(&skybox, height - 1);
+        assert!(ground.pixels.iter().all(|i| bottom.contains(i)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,455 @@
+use super::Result;
+use crate::error::{Error, ErrorKind};
+use nom::{
+    combinator::{eof, verify},
+    multi::many0,
+    number::{complete::u16 as nom_u16, Endianness},
+    sequence::terminated,
+    Parser,
+};
+use nom_supreme::ParserExt;
+use std::fmt;
+
+const BOM: u16 = 0xFEFF;
+const NULL: u16 = 0;
+/// The characters that the game uses to lay out a string; e.g. `|` switches the speaker of a
+/// dialog. `\r` is left out, because the translations of the `1.0.6` release break lines freely.
+pub const CONTROL_CHARACTERS: [char; 3] = ['†', '‡', '|'];
+/// The text bank entries of the archive, next to the ISO 639-1 code of their language.
+pub const TEXT_BANKS: [(usize, &str); 6] = [
+    (152, "en"),
+    (153, "en"),
+    (154, "fr"),
+    (155, "it"),
+    (156, "de"),
+    (157, "es"),
+];
+/// The english text bank; the one that the others are translated from.
+pub const ENGLISH_TEXT_BANK: usize = 152;
+
+/// How the strings of a [`TextBank`] are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextEncoding {
+    /// Used by every bank of the `1.0.6` release.
+    #[default]
+    Utf16Le,
+    Utf16Be,
+}
+
+impl TextEncoding {
+    fn endianness(self) -> Endianness {
+        match self {
+            Self::Utf16Le => Endianness::Little,
+            Self::Utf16Be => Endianness::Big,
+        }
+    }
+
+    fn encode(self, unit: u16) -> [u8; 2] {
+        match self {
+            Self::Utf16Le => unit.to_le_bytes(),
+            Self::Utf16Be => unit.to_be_bytes(),
+        }
+    }
+}
+
+/// The language text banks; every UI string of the game, in a single language.
+///
+/// Strings may have control characters that the game uses for formatting, e.g. `†`, `‡`, `|`, and
+/// `\r`; they are kept as they are.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextBank {
+    /// The id of every string (its position on the bank), next to the string itself.
+    pub entries: Vec<(u32, String)>,
+    pub encoding: TextEncoding,
+    /// Whether the strings start with a byte order mark.
+    pub bom: bool,
+}
+
+/// A string that would break the layout of the game; see [`TextBank::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextIssue {
+    /// The string has a different amount of a [`CONTROL_CHARACTERS`] than the reference one.
+    ControlMismatch {
+        id: u32,
+        control: char,
+        expected: usize,
+        actual: usize,
+    },
+    /// The string, once encoded, takes more bytes than its budget.
+    OverBudget { id: u32, len: usize, budget: usize },
+    /// The reference bank doesn't have a string with this id.
+    UnknownId { id: u32 },
+}
+
+impl fmt::Display for TextIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ControlMismatch {
+                id,
+                control,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "string {id} has {actual} `{control}`, but the original has {expected}"
+            ),
+            Self::OverBudget { id, len, budget } => write!(
+                f,
+                "string {id} takes {len} bytes, but it can't take more than {budget}"
+            ),
+            Self::UnknownId { id } => write!(f, "string {id} is not on the original bank"),
+        }
+    }
+}
+
+/// Reads the `u32` string count, and finds out the encoding from the byte order mark, if any.
+fn read_header(input: &[u8]) -> Result<'_, (u32, TextEncoding, bool)> {
+    use nom::number::complete::{be_u16, le_u32};
+
+    let (input, count) = le_u32.context("string count").parse(input)?;
+
+    match be_u16::<_, ()>(input) {
+        Ok((rest, 0xFFFE)) => Ok((rest, (count, TextEncoding::Utf16Le, true))),
+        Ok((rest, BOM)) => Ok((rest, (count, TextEncoding::Utf16Be, true))),
+        _ => Ok((input, (count, TextEncoding::Utf16Le, false))),
+    }
+}
+
+fn read_string(input: &[u8], index: usize, encoding: TextEncoding) -> Result<'_, String> {
+    let unit = || nom_u16(encoding.endianness());
+    let (rest, units) = terminated(
+        many0(verify(unit(), |&u| u != NULL)),
+        verify(unit(), |&u| u == NULL),
+    )
+    .context("string")
+    .parse(input)?;
+
+    match String::from_utf16(&units) {
+        Ok(string) => Ok((rest, string)),
+        Err(_) => super::fail(
+            input,
+            ErrorKind::InvalidFormat,
+            format!("string {index} is not valid UTF-16"),
+            units.len() * 2,
+        ),
+    }
+}
+
+fn read_text(input: &[u8]) -> Result<'_, TextBank> {
+    let (mut input, (count, encoding, bom)) = read_header(input)?;
+    // every string takes at least 2 bytes, so don't trust `count` for the capacity.
+    let mut entries = Vec::with_capacity((count as usize).min(input.len() / 2));
+
+    for id in 0..count {
+        let (rest, string) = read_string(input, id as usize, encoding)?;
+        entries.push((id, string));
+        input = rest;
+    }
+
+    let (input, _) = eof.context("end of data").parse(input)?;
+
+    Ok((
+        input,
+        TextBank {
+            entries,
+            encoding,
+            bom,
+        },
+    ))
+}
+
+impl TextBank {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid (decompressed) text bank.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "text bank", read_text)
+    }
+
+    /// The string with the given `id`.
+    #[must_use]
+    pub fn get(&self, id: u32) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(i, _)| *i == id)
+            .map(|(_, string)| string.as_str())
+    }
+
+    /// Compares every string against the one with the same id on `reference` (e.g. the english
+    /// bank, when checking a translation), and against a `budget` of bytes, if any.
+    ///
+    /// The budget counts the encoded string with its null character; the engine doesn't have a
+    /// documented limit, but the longest string of the `1.0.6` release takes `2928` bytes.
+    #[must_use]
+    pub fn validate(&self, reference: &TextBank, budget: Option<usize>) -> Vec<TextIssue> {
+        let mut issues = Vec::new();
+
+        for (id, string) in &self.entries {
+            let id = *id;
+            let Some(original) = reference.get(id) else {
+                issues.push(TextIssue::UnknownId { id });
+                continue;
+            };
+
+            for control in CONTROL_CHARACTERS {
+                let count = |s: &str| s.chars().filter(|&c| c == control).count();
+                let (expected, actual) = (count(original), count(string));
+
+                if expected != actual {
+                    issues.push(TextIssue::ControlMismatch {
+                        id,
+                        control,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+
+            let len = (string.encode_utf16().count() + 1) * 2;
+            if let Some(budget) = budget.filter(|&budget| len > budget) {
+                issues.push(TextIssue::OverBudget { id, len, budget });
+            }
+        }
+
+        issues
+    }
+
+    /// Like [`Self::to_bytes`], but fails on the first issue that [`Self::validate`] finds.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::validate`] and [`Self::to_bytes`].
+    pub fn to_bytes_checked(
+        &self,
+        reference: &TextBank,
+        budget: Option<usize>,
+    ) -> crate::error::Result<Vec<u8>> {
+        if let Some(issue) = self.validate(reference, budget).into_iter().next() {
+            let id = match issue {
+                TextIssue::ControlMismatch { id, .. }
+                | TextIssue::OverBudget { id, .. }
+                | TextIssue::UnknownId { id } => id,
+            };
+            let index = self.entries.iter().position(|(i, _)| *i == id);
+
+            return Err(Error::InvalidText {
+                index: index.unwrap_or_default(),
+                reason: issue.to_string(),
+            });
+        }
+
+        self.to_bytes()
+    }
+
+    /// Encodes the strings back, in the order of [`Self::entries`].
+    ///
+    /// # Errors
+    ///
+    /// If a string has a null character, or if there are more than `u32::MAX` strings.
+    pub fn to_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        let count = super::count_u32("strings", self.entries.len())?;
+        let mut buf = Vec::with_capacity(
+            4 + self
+                .entries
+                .iter()
+                .map(|(_, s)| (s.len() + 1) * 2)
+                .sum::<usize>(),
+        );
+
+        buf.extend_from_slice(&count.to_le_bytes());
+        if self.bom {
+            buf.extend_from_slice(&self.encoding.encode(BOM));
+        }
+
+        for (index, (_, string)) in self.entries.iter().enumerate() {
+            if string.contains('\0') {
+                return Err(Error::InvalidText {
+                    index,
+                    reason: "it has a null character, which would end it early".into(),
+                });
+            }
+
+            string
+                .encode_utf16()
+                .chain([NULL])
+                .for_each(|unit| buf.extend_from_slice(&self.encoding.encode(unit)));
+        }
+
+        Ok(buf)
+    }
+}
+
+/// The layout of a text bank; see [`super::schema`].
+///
+/// Only the little-endian encoding, without a BOM, is described, which is the one that the game
+/// uses.
+pub(super) fn schema() -> super::schema::Format {
+    use super::schema::{Field, Format, Kind, Size, TypeDef};
+
+    Format {
+        id: "text",
+        title: "Ashen text bank",
+        imports: vec![],
+        root: TypeDef {
+            id: "text",
+            doc: None,
+            fields: vec![
+                Field::primitive("string_count", "u4"),
+                Field::new(
+                    "strings",
+                    Kind::StrZ {
+                        encoding: "UTF-16LE",
+                    },
+                )
+                .repeat(Size::Expr("string_count".into())),
+            ],
+        },
+        types: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    /// The text banks; english (twice), french, italian, german and spanish.
+    #[cfg(feature = "packfile")]
+    const TEXT_ENTRIES: [usize; 6] = [152, 153, 154, 155, 156, 157];
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn text_bank_new_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = pman[156].to_zlib()?.expect("text banks are compressed.");
+        let bank = TextBank::new(&bytes)?;
+
+        assert_eq!(bank.entries.len(), 411);
+        assert_eq!(bank.get(1), Some("START drücken"));
+        assert_eq!(bank.encoding, TextEncoding::Utf16Le);
+        assert!(!bank.bom);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn text_bank_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in TEXT_ENTRIES {
+            let bytes = pman[index].to_zlib()?.expect("text banks are compressed.");
+
+            assert_eq!(TextBank::new(&bytes)?.to_bytes()?, bytes, "entry {index}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn text_bank_encoding_test() -> eyre::Result<()> {
+        let mut bank = TextBank {
+            entries: vec![(0, "Ashen".into()), (1, "ñ†".into())],
+            encoding: TextEncoding::Utf16Be,
+            bom: true,
+        };
+        let bytes = bank.to_bytes()?;
+
+        assert_eq!(&bytes[4..8], [0xFE, 0xFF, 0x00, b'A']);
+        assert_eq!(TextBank::new(&bytes)?, bank);
+
+        bank.entries[0].1.push('\0');
+
+        assert_eq!(bank.to_bytes().unwrap_err().kind(), ErrorKind::InvalidText);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn text_bank_validate_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bank = |index: usize| -> eyre::Result<TextBank> {
+            let bytes = pman[index].to_zlib()?.expect("text banks are compressed.");
+            Ok(TextBank::new(&bytes)?)
+        };
+        let (english, german) = (bank(152)?, bank(156)?);
+
+        assert_eq!(bank(153)?.validate(&english, Some(2928)), []);
+
+        let issues = german.validate(&english, None);
+        let ids = issues.iter().map(|issue| match issue {
+            TextIssue::ControlMismatch { id, .. } => *id,
+            _ => unreachable!("every id is on both banks, and there is no budget."),
+        });
+
+        assert_eq!(
+            ids.collect::<Vec<_>>(),
+            [142, 147, 151, 172, 172, 172, 182, 182]
+        );
+        assert!(german.to_bytes_checked(&english, None).is_err());
+
+        let original = TextBank {
+            entries: vec![(0, "Ashen".into()), (1, "START†".into())],
+            ..Default::default()
+        };
+        let mut edited = original.clone();
+        edited.entries[1].1 = "Press START†".into();
+
+        assert_eq!(edited.validate(&original, Some(26)), []);
+        assert_eq!(
+            edited.validate(&original, Some(20)),
+            [TextIssue::OverBudget {
+                id: 1,
+                len: 26,
+                budget: 20
+            }]
+        );
+
+        edited.entries[1].1.push('|');
+        edited.entries.push((2, String::new()));
+
+        assert_eq!(edited.validate(&original, None).len(), 2);
+        assert_eq!(
+            edited.to_bytes_checked(&original, None).unwrap_err().kind(),
+            ErrorKind::InvalidText
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn text_bank_invalid_test() {
+        // an unpaired surrogate.
+        let bytes = [1, 0, 0, 0, 0x00, 0xD8, 0, 0];
+
+        assert_eq!(
+            TextBank::new(&bytes).unwrap_err().kind(),
+            ErrorKind::InvalidFormat
+        );
+        assert!(TextBank::new(&[2, 0, 0, 0, b'A', 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn text_bank_snapshot_test() -> eyre::Result<()> {
+        let bank = TextBank {
+            entries: vec![
+                (0, "Ashen".into()),
+                (1, "START drücken".into()),
+                (2, String::new()),
+                (3, "Press START†".into()),
+            ],
+            encoding: TextEncoding::Utf16Le,
+            bom: false,
+        };
+
+        crate::snapshot::assert_parsed_snapshot!("text_bank", TextBank::new(&bank.to_bytes()?)?);
+
+        Ok(())
+    }
+}
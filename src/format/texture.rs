@@ -0,0 +1,348 @@
+use super::{color, palette::Palette, Result};
+use crate::error::{ErrorKind, Violation};
+use nom::{
+    bytes::complete::take,
+    combinator::eof,
+    multi::{fill, many0},
+    number::complete::{le_u16, le_u32},
+    Parser,
+};
+use nom_supreme::ParserExt;
+use std::ops::Range;
+
+/// The width and the height of every texture entry.
+pub const TEXTURE_SIZE: u32 = 256;
+/// The standalone texture entries of the archive.
+// TODO(Unavailable): which palette entry each texture uses is not known yet; pairing them by the
+// order of the entries gives colors that don't look right, so every consumer (exports, imports,
+// the bindings) works with indices, or with a palette that its caller picks.
+pub const TEXTURE_ENTRIES: Range<usize> = 72..76;
+/// The table of the textures of the levels; their pixels are the chunks of entry `149`.
+pub const LEVEL_TEXTURE_TABLE: usize = 147;
+/// The table of the sprites; their pixels are the chunks of entry `150`.
+pub const SPRITE_TABLE: usize = 148;
+/// The pixels of the sprites; the chunks that the rows of [`SPRITE_TABLE`] point into.
+pub const SPRITE_STORE: usize = 150;
+/// Every texture (and sprite) is made of 8-bit indices into a palette.
+pub const BITS_PER_PIXEL: u32 = 8;
+const TEXTURE_INFO_SIZE: usize = 24;
+
+/// The standalone textures of the game; e.g. the alternative skins of some enemies.
+///
+/// Entries have no header, just `256 * 256` indices into a palette; there are no mip levels on the
+/// `1.0.6` release.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    /// Indices into a [`Palette`], row by row.
+    pub pixels: Vec<u8>,
+}
+
+fn read_texture(input: &[u8]) -> Result<'_, Texture> {
+    let size = (TEXTURE_SIZE * TEXTURE_SIZE) as usize;
+
+    if input.len() != size {
+        return super::fail_with(
+            input,
+            Violation::new(
+                ErrorKind::InvalidFormat,
+                format!(
+                    "a texture needs {size:#X} bytes, but there are {:#X}",
+                    input.len()
+                ),
+                input.len(),
+            ),
+        );
+    }
+
+    let (input, pixels) = take(size).context("pixels").parse(input)?;
+    let (input, _) = eof(input)?;
+
+    Ok((
+        input,
+        Texture {
+            width: TEXTURE_SIZE,
+            height: TEXTURE_SIZE,
+            pixels: pixels.to_vec(),
+        },
+    ))
+}
+
+impl Texture {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid texture entry.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "texture", read_texture)
+    }
+
+    /// The pixels as 8-bit RGBA, row by row, with the colors of `palette`.
+    #[must_use]
+    pub fn to_rgba(&self, palette: &Palette) -> Vec<[u8; 4]> {
+        color::apply_palette(&self.pixels, &palette.to_rgba())
+    }
+
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.pixels.clone()
+    }
+}
+
+/// A row of a texture table; everything that is known about a texture, without decoding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureInfo {
+    pub width: u16,
+    pub height: u16,
+    /// The offset of the pixels on the store entry; see [`Self::chunk`].
+    pub offset: u32,
+    /// The size of the pixels on the store entry.
+    pub size: u32,
+    /// The size of the pixels once they are decompressed; if [`Self::size`] is the same, they are
+    /// not compressed at all.
+    pub unpacked_size: u32,
+    /// The frame count of the animation that the texture is part of; `0` if it is not animated.
+    pub animation: u32,
+    /// The index of the texture that replaces this one on animated textures; e.g. the frames of a
+    /// 9 frames animation point into each other, and the last one into the first one.
+    pub next: u32,
+}
+
+impl TextureInfo {
+    /// The size of every mip level, from the largest to the smallest.
+    fn level_sizes(&self) -> impl Iterator<Item = u64> {
+        let (width, height) = (u64::from(self.width), u64::from(self.height));
+
+        (0..u16::BITS)
+            .map(move |level| (width >> level) * (height >> level))
+            .take_while(|&size| size != 0)
+    }
+
+    /// How many mip levels the pixels have, counting the full size one.
+    ///
+    /// Returns `None` if [`Self::unpacked_size`] is not the size of a mip chain; e.g. on sprites,
+    /// which have their own layout.
+    #[must_use]
+    pub fn mip_count(&self) -> Option<u32> {
+        // on `u64`; the rows can't be trusted, and the levels of a `0xFFFF` square don't fit.
+        let mut total = 0;
+
+        for (count, size) in (1..).zip(self.level_sizes()) {
+            total += size;
+
+            if total == u64::from(self.unpacked_size) {
+                return Some(count);
+            }
+        }
+
+        None
+    }
+
+    /// The range of the pixels on the store entry.
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        let start = self.offset as usize;
+
+        start..start.saturating_add(self.size as usize)
+    }
+
+    /// The (maybe `ZL` compressed) pixels of the texture, out of the `store` entry of its table.
+    #[must_use]
+    pub fn chunk<'a>(&self, store: &'a [u8]) -> Option<&'a [u8]> {
+        store.get(self.range())
+    }
+}
+
+/// The textures (or sprites) of a texture table entry.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureTable {
+    pub textures: Vec<TextureInfo>,
+}
+
+fn read_texture_info(input: &[u8]) -> Result<'_, TextureInfo> {
+    let (input, width) = le_u16.context("width").parse(input)?;
+    let (input, height) = le_u16.context("height").parse(input)?;
+    let mut fields = [0; 5];
+    let (input, ()) = fill(le_u32, &mut fields)
+        .context("texture info")
+        .parse(input)?;
+
+    Ok((
+        input,
+        TextureInfo {
+            width,
+            height,
+            offset: fields[0],
+            size: fields[1],
+            unpacked_size: fields[2],
+            animation: fields[3],
+            next: fields[4],
+        },
+    ))
+}
+
+fn read_texture_table(input: &[u8]) -> Result<'_, TextureTable> {
+    let rest = input.len() % TEXTURE_INFO_SIZE;
+
+    if rest != 0 {
+        return super::fail(
+            &input[input.len() - rest..],
+            ErrorKind::InvalidFormat,
+            format!("the rows of a texture table take {TEXTURE_INFO_SIZE:#X} bytes each"),
+            rest,
+        );
+    }
+
+    let (input, textures) = many0(read_texture_info).parse(input)?;
+    let (input, _) = eof(input)?;
+
+    Ok((input, TextureTable { textures }))
+}
+
+impl TextureTable {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid texture table entry.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "texture table", read_texture_table)
+    }
+
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.textures.len() * TEXTURE_INFO_SIZE);
+
+        for info in &self.textures {
+            buf.extend_from_slice(&info.width.to_le_bytes());
+            buf.extend_from_slice(&info.height.to_le_bytes());
+            for field in [
+                info.offset,
+                info.size,
+                info.unpacked_size,
+                info.animation,
+                info.next,
+            ] {
+                buf.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+
+        buf
+    }
+}
+
+/// The layout of a texture entry; see [`super::schema`].
+pub(super) fn schema() -> super::schema::Format {
+    use super::schema::{Field, Format, Kind, Size, TypeDef};
+
+    Format {
+        id: "texture",
+        title: "Ashen texture",
+        imports: vec![],
+        root: TypeDef {
+            id: "texture",
+            doc: Some(format!(
+                "A {TEXTURE_SIZE}x{TEXTURE_SIZE} indexed image; its palette is another entry."
+            )),
+            fields: vec![Field::new(
+                "pixels",
+                Kind::Bytes(Size::Fixed((TEXTURE_SIZE * TEXTURE_SIZE) as usize)),
+            )
+            .doc("Palette indices, row by row.")],
+        },
+        types: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::{self, PmanFile};
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn texture_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        let palette = Palette::new(pman[66].bytes())?;
+
+        for index in TEXTURE_ENTRIES {
+            let bytes = pman[index].bytes();
+            let texture = Texture::new(bytes)?;
+
+            assert_eq!(texture.to_rgba(&palette).len(), bytes.len());
+            assert_eq!(texture.to_bytes(), bytes, "entry {index}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn texture_table_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for (index, len) in [(LEVEL_TEXTURE_TABLE, 267), (SPRITE_TABLE, 785)] {
+            let bytes = pman[index].bytes();
+            let table = TextureTable::new(bytes)?;
+
+            assert_eq!(table.textures.len(), len);
+            assert_eq!(table.to_bytes(), bytes, "entry {index}");
+        }
+
+        let table = TextureTable::new(pman[LEVEL_TEXTURE_TABLE].bytes())?;
+        let first = table.textures[0];
+
+        assert_eq!((first.width, first.height), (64, 64));
+        assert_eq!(first.mip_count(), Some(4));
+        assert!(table.textures.iter().all(|t| t.mip_count() == Some(4)));
+        assert_eq!(
+            (table.textures[50].animation, table.textures[50].next),
+            (9, 42)
+        );
+
+        let chunk = first
+            .chunk(pman[LEVEL_TEXTURE_TABLE + 2].bytes())
+            .expect("it is inside of the store entry.");
+        let (pixels, len) = pman::decompress_chunk(chunk)?.expect("it is compressed.");
+
+        assert_eq!(len, first.size as usize);
+        assert_eq!(pixels.len(), first.unpacked_size as usize);
+
+        Ok(())
+    }
+
+    #[test]
+    fn texture_mip_count_test() {
+        let info = |width, height, unpacked_size| TextureInfo {
+            width,
+            height,
+            unpacked_size,
+            ..TextureInfo::default()
+        };
+
+        assert_eq!(info(64, 64, 0x1000).mip_count(), Some(1));
+        assert_eq!(info(64, 64, 0x1000 + 0x400 + 0x100).mip_count(), Some(3));
+        assert_eq!(info(64, 64, 0x1001).mip_count(), None);
+        // the levels add up to more than an `u32`.
+        assert_eq!(info(0xFFFF, 0xFFFF, u32::MAX).mip_count(), None);
+        assert_eq!(info(0, 64, 0).mip_count(), None);
+    }
+
+    #[test]
+    fn texture_invalid_test() {
+        assert_eq!(
+            Texture::new(&[0; 16]).unwrap_err().kind(),
+            ErrorKind::InvalidFormat
+        );
+        assert_eq!(
+            TextureTable::new(&[0; 25]).unwrap_err().kind(),
+            ErrorKind::InvalidFormat
+        );
+    }
+}
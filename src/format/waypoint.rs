@@ -0,0 +1,302 @@
+use super::Result;
+use crate::error::ErrorKind;
+use nom::{
+    bytes::complete::{take, take_while_m_n},
+    combinator::{eof, verify},
+    multi::{count, fill},
+    number::complete::{le_i32, le_u16, le_u32},
+    Parser,
+};
+use nom_supreme::{tag::complete::tag, ParserExt};
+
+const HEADER_SIZE: usize = 16;
+const HEADER_MAGIC_STRING: &[u8; 4] = b"TWPT";
+const VERSION: u32 = 3;
+const CELL_SIZE: usize = 25;
+const DATA_ALIGNMENT: usize = 4;
+/// The scale of the 16.16 fixed point components of [`Waypoint::position`].
+const POSITION_SCALE: f32 = 65_536.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Waypoint {
+    /// As 16.16 fixed point numbers.
+    pub position: [i32; 3],
+    /// Always `0` on the `1.0.6` release.
+    pub flags: u32,
+}
+
+impl Waypoint {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn position_f32(&self) -> [f32; 3] {
+        self.position.map(|x| x as f32 / POSITION_SCALE)
+    }
+}
+
+/// A row of the next-hop table of a waypoint: to reach `target`, go to `next` first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Route {
+    pub target: u16,
+    pub next: u16,
+}
+
+/// The `TWPT` entries of the archive; the waypoints that enemies use to move around a level.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WaypointGraph {
+    pub waypoints: Vec<Waypoint>,
+    /// The routes of every waypoint, in the same order as [`Self::waypoints`].
+    pub routes: Vec<Vec<Route>>,
+    // TODO(Unavailable): figure out what these are; their bytes look like waypoint indices.
+    pub cells: Vec<[u8; CELL_SIZE]>,
+}
+
+fn read_header(input: &[u8]) -> Result<'_, (usize, usize)> {
+    let (input, header) = super::with_kind(ErrorKind::TruncatedHeader, take(HEADER_SIZE))
+        .context("header")
+        .parse(input)?;
+    let (header, _) = super::with_kind(ErrorKind::BadMagic, tag(&HEADER_MAGIC_STRING[..]))
+        .context("magic string")
+        .parse(header)?;
+    let (header, _) = verify(le_u32, |v| *v == VERSION)
+        .context("version")
+        .parse(header)?;
+    let (header, waypoint_count) = le_u32.context("waypoint count").parse(header)?;
+    let (header, cell_count) = le_u32.context("cell count").parse(header)?;
+    _ = eof(header)?;
+
+    Ok((input, (waypoint_count as usize, cell_count as usize)))
+}
+
+fn read_waypoint(input: &[u8]) -> Result<'_, Waypoint> {
+    let mut position = [0; 3];
+    let (input, ()) = fill(le_i32, &mut position)(input)?;
+    let (input, flags) = le_u32(input)?;
+
+    Ok((input, Waypoint { position, flags }))
+}
+
+fn read_route(input: &[u8]) -> Result<'_, Route> {
+    let (input, target) = le_u16(input)?;
+    let (input, next) = le_u16(input)?;
+
+    Ok((input, Route { target, next }))
+}
+
+fn read_cell(input: &[u8]) -> Result<'_, [u8; CELL_SIZE]> {
+    let (input, cell) = take(CELL_SIZE)(input)?;
+    let mut buf = [0; CELL_SIZE];
+    buf.copy_from_slice(cell);
+
+    Ok((input, buf))
+}
+
+fn read_waypoints(input: &[u8]) -> Result<'_, WaypointGraph> {
+    let (input, (waypoint_count, cell_count)) = read_header(input)?;
+    let (input, waypoints) = count(read_waypoint, waypoint_count)
+        .context("waypoints")
+        .parse(input)?;
+    let (input, ranges) = count(le_u32.and(le_u32), waypoint_count)
+        .context("route ranges")
+        .parse(input)?;
+    let (input, route_count) = le_u32.context("route count").parse(input)?;
+    let (input, flat_routes) = count(read_route, route_count as usize)
+        .context("routes")
+        .parse(input)?;
+    let (input, cells) = count(read_cell, cell_count).context("cells").parse(input)?;
+    let (input, _) = take_while_m_n(0, DATA_ALIGNMENT - 1, |b| b == 0)
+        .context("padding")
+        .parse(input)?;
+    let (input, _) = eof.context("end of data").parse(input)?;
+
+    let mut routes = Vec::with_capacity(ranges.len());
+    for (index, (start, len)) in ranges.into_iter().enumerate() {
+        let (start, len) = (start as usize, len as usize);
+        let Some(slice) = start
+            .checked_add(len)
+            .and_then(|end| flat_routes.get(start..end))
+        else {
+            return super::fail(
+                input,
+                ErrorKind::InvalidFormat,
+                format!("the routes of waypoint {index} are out of the {route_count} routes"),
+                0,
+            );
+        };
+
+        routes.push(slice.to_vec());
+    }
+
+    Ok((
+        input,
+        WaypointGraph {
+            waypoints,
+            routes,
+            cells,
+        },
+    ))
+}
+
+impl WaypointGraph {
+    /// # Errors
+    ///
+    /// If `bytes` is not a valid (decompressed) `TWPT` entry.
+    pub fn new(bytes: &[u8]) -> crate::error::Result<Self> {
+        super::parse_all(bytes, "TWPT", read_waypoints)
+    }
+
+    /// The waypoints directly linked to the waypoint at `index`.
+    pub fn links(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.routes
+            .get(index)
+            .into_iter()
+            .flatten()
+            .filter(|route| route.target == route.next)
+            .map(|route| route.target as usize)
+    }
+
+    /// # Errors
+    ///
+    /// If there are more than `u32::MAX` waypoints, routes or cells.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn to_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        use super::count_u32;
+
+        let waypoint_count = count_u32("waypoints", self.waypoints.len())?;
+        let cell_count = count_u32("cells", self.cells.len())?;
+        let route_count = self.routes.iter().map(Vec::len).sum::<usize>();
+        let route_count = count_u32("routes", route_count)?;
+        let mut buf = Vec::with_capacity(
+            HEADER_SIZE
+                + self.waypoints.len() * 16
+                + self.routes.len() * 8
+                + 4
+                + route_count as usize * 4
+                + self.cells.len() * CELL_SIZE
+                + DATA_ALIGNMENT,
+        );
+
+        buf.extend_from_slice(HEADER_MAGIC_STRING);
+        buf.extend_from_slice(&VERSION.to_le_bytes());
+        buf.extend_from_slice(&waypoint_count.to_le_bytes());
+        buf.extend_from_slice(&cell_count.to_le_bytes());
+
+        for waypoint in &self.waypoints {
+            for x in waypoint.position {
+                buf.extend_from_slice(&x.to_le_bytes());
+            }
+            buf.extend_from_slice(&waypoint.flags.to_le_bytes());
+        }
+
+        // every `as u32` is fine, because there are less than `route_count` routes.
+        let mut start: u32 = 0;
+        for routes in &self.routes {
+            buf.extend_from_slice(&start.to_le_bytes());
+            buf.extend_from_slice(&(routes.len() as u32).to_le_bytes());
+            start += routes.len() as u32;
+        }
+        buf.extend_from_slice(&route_count.to_le_bytes());
+        for route in self.routes.iter().flatten() {
+            buf.extend_from_slice(&route.target.to_le_bytes());
+            buf.extend_from_slice(&route.next.to_le_bytes());
+        }
+
+        for cell in &self.cells {
+            buf.extend_from_slice(cell);
+        }
+        buf.resize(buf.len().next_multiple_of(DATA_ALIGNMENT), 0);
+
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::format::pman::PmanFile;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    /// The `TWPT` entries of every level.
+    #[cfg(feature = "packfile")]
+    const TWPT_ENTRIES: [usize; 15] = [
+        78, 82, 86, 90, 94, 98, 102, 106, 110, 114, 118, 122, 126, 142, 145,
+    ];
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn waypoint_new_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let bytes = pman[78].to_zlib()?.expect("`TWPT` entries are compressed.");
+        let graph = WaypointGraph::new(&bytes)?;
+
+        assert_eq!(graph.waypoints.len(), 85);
+        assert_eq!(graph.routes.iter().map(Vec::len).sum::<usize>(), 3026);
+        assert_eq!(graph.cells.len(), 1548);
+        assert_eq!(graph.waypoints[0].position, [0x5_0000, 0x8_F000, -0x3700]);
+        assert!(graph.links(0).any(|link| link == 39));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn waypoint_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in TWPT_ENTRIES {
+            let bytes = pman[index]
+                .to_zlib()?
+                .expect("`TWPT` entries are compressed.");
+
+            assert_eq!(
+                WaypointGraph::new(&bytes)?.to_bytes()?,
+                bytes,
+                "entry {index}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn waypoint_invalid_test() {
+        assert_eq!(
+            WaypointGraph::new(b"COLL").unwrap_err().kind(),
+            ErrorKind::TruncatedHeader
+        );
+    }
+
+    #[test]
+    fn waypoint_snapshot_test() -> eyre::Result<()> {
+        let graph = WaypointGraph {
+            waypoints: vec![
+                Waypoint {
+                    position: [0x5_0000, 0x8_F000, -0x3700],
+                    flags: 0,
+                },
+                Waypoint {
+                    position: [0x6_8000, 0x8_F000, -0x3700],
+                    flags: 0,
+                },
+            ],
+            routes: vec![
+                vec![Route { target: 1, next: 1 }],
+                vec![Route { target: 0, next: 0 }],
+            ],
+            cells: vec![[
+                0, 1, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ]],
+        };
+
+        crate::snapshot::assert_parsed_snapshot!(
+            "waypoint",
+            WaypointGraph::new(&graph.to_bytes()?)?
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+//! Translations of the messages of the command line tool; see [`message`] and [`tr!`].
+//!
+//! Every language is a catalog on `src/i18n/`, on a small subset of the
+//! [Fluent](https://projectfluent.org) syntax: one `id = text` a line, `#` comments, and
+//! `{ $name }` for the values that are put into the text. English is the reference; a message that
+//! a catalog doesn't have yet is shown in English.
+//!
+//! The language is taken from `RASHEN_LANG`, or from the locale (`LC_ALL`, `LC_MESSAGES` or
+//! `LANG`); e.g. `RASHEN_LANG=de rashen list packfile.dat`.
+
+use std::{collections::HashMap, env, fmt::Display, sync::OnceLock};
+
+/// The catalog of every language, by its code; the first one is the reference.
+pub const LANGUAGES: [(&str, &str); 4] = [
+    ("en", include_str!("i18n/en.ftl")),
+    ("de", include_str!("i18n/de.ftl")),
+    ("es", include_str!("i18n/es.ftl")),
+    ("fr", include_str!("i18n/fr.ftl")),
+];
+
+/// The messages of a language, by id.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Reads the messages of a catalog; lines that are not a message (nor a comment) are skipped.
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let messages = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (id, message) = line.split_once('=')?;
+
+                Some((id.trim().to_owned(), message.trim().to_owned()))
+            })
+            .collect();
+
+        Self { messages }
+    }
+
+    /// The catalog of `language` (e.g. `de`); `None` if there isn't one.
+    #[must_use]
+    pub fn of(language: &str) -> Option<Self> {
+        LANGUAGES
+            .iter()
+            .find(|(code, _)| *code == language)
+            .map(|(_, text)| Self::parse(text))
+    }
+
+    /// The ids of every message.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.messages.keys().map(String::as_str)
+    }
+
+    /// The message `id`, with every `{ $name }` replaced by the value of `name` on `args`; `None`
+    /// if the catalog doesn't have it.
+    #[must_use]
+    pub fn format(&self, id: &str, args: &[(&str, &dyn Display)]) -> Option<String> {
+        let mut message = self.messages.get(id)?.clone();
+
+        for (name, value) in args {
+            message = message.replace(&format!("{{ ${name} }}"), &value.to_string());
+        }
+
+        Some(message)
+    }
+}
+
+/// The language of the locale; the `de` of `de_DE.UTF-8`.
+fn language_from_env() -> Option<String> {
+    let locale = ["RASHEN_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())?;
+    let language = locale.split(['_', '-', '.', '@']).next()?;
+
+    Some(language.to_ascii_lowercase())
+}
+
+/// The catalog of the language of the locale, and the English one, to fall back on.
+fn catalogs() -> &'static (Option<Catalog>, Catalog) {
+    static CATALOGS: OnceLock<(Option<Catalog>, Catalog)> = OnceLock::new();
+
+    CATALOGS.get_or_init(|| {
+        let english = Catalog::parse(LANGUAGES[0].1);
+        let local = language_from_env()
+            .filter(|language| language != LANGUAGES[0].0)
+            .and_then(|language| Catalog::of(&language));
+
+        (local, english)
+    })
+}
+
+/// The message `id` on the language of the locale; see [`Catalog::format`]. It is the English
+/// one if the language has no catalog, or if its catalog doesn't have it; or `id` itself, if not
+/// even the English catalog has it.
+#[must_use]
+pub fn message(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    let (local, english) = catalogs();
+
+    local
+        .as_ref()
+        .and_then(|local| local.format(id, args))
+        .or_else(|| english.format(id, args))
+        .unwrap_or_else(|| id.to_owned())
+}
+
+/// [`message`], with the values given by name; e.g. `tr!("wrote", path = output.display())`.
+#[macro_export]
+macro_rules! tr {
+    ($id:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::message(
+            $id,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// The names of the values that `message` takes.
+    fn placeholders(message: &str) -> BTreeSet<&str> {
+        message
+            .split("{ $")
+            .skip(1)
+            .filter_map(|rest| rest.split_once(" }"))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn catalog_test() {
+        let catalog = Catalog::parse("# a comment\n\nwrote = wrote { $path }\nnot a message\n");
+
+        assert_eq!(catalog.ids().collect::<Vec<_>>(), ["wrote"]);
+        assert_eq!(
+            catalog.format("wrote", &[("path", &"packfile.dat")]),
+            Some("wrote packfile.dat".to_owned())
+        );
+        assert_eq!(catalog.format("missing", &[]), None);
+        assert_eq!(message("no-such-message", &[]), "no-such-message");
+    }
+
+    #[test]
+    fn languages_test() {
+        let english = Catalog::parse(LANGUAGES[0].1);
+        let ids: BTreeSet<_> = english.ids().collect();
+
+        for (code, text) in &LANGUAGES[1..] {
+            let catalog = Catalog::parse(text);
+
+            // every line is a message, or a comment.
+            for line in text.lines().map(str::trim) {
+                assert!(
+                    line.is_empty() || line.starts_with('#') || line.contains(" = "),
+                    "{code}: `{line}`"
+                );
+            }
+            assert_eq!(catalog.ids().collect::<BTreeSet<_>>(), ids, "{code}");
+            // and every message takes the same values as the English one.
+            for id in &ids {
+                assert_eq!(
+                    placeholders(&catalog.messages[*id]),
+                    placeholders(&english.messages[*id]),
+                    "{code}: {id}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+# Deutsch.
+
+warning = Warnung: { $message }
+wrote = { $path } geschrieben
+found-archive = Archiv in { $path } gefunden
+needs-feature = `{ $what }` benötigt das Feature `{ $feature }`
+
+# Argumente.
+unknown-export-format = unbekanntes Format `{ $format }`; erwartet wird `gltf`, `obj`, `csv`, `json` oder `po`
+jobs-not-a-number = `--jobs` erwartet eine Anzahl von Threads, nicht `{ $jobs }`
+not-a-size = `{ $value }` ist keine Größe
+unknown-entry-types = unbekannter Wert `{ $entry_types }` für `--entry-types`; erwartet `stored`, `zeros` oder `detected`
+expected-entry-budget = erwartet wird `--entry-budget <index>=<größe>`
+expected-output = es fehlt, wohin das Archiv geschrieben werden soll, z. B. `-o packfile.dat`
+not-a-hex-offset = `{ $offset }` ist kein hexadezimaler Offset
+not-an-index = `{ $index }` ist kein Index
+in-place-reproducible = `--in-place` kann nicht mit `--reproducible` kombiniert werden
+
+# Importe.
+expected-import-file = es fehlt die zu importierende Datei
+expected-source-packfile = es fehlt das Archiv, in das importiert werden soll, z. B. `game/packfile.dat`
+expected-text-language = es fehlt die Sprache der Datei, z. B. `--lang de`
+unknown-text-format = unbekanntes Format `{ $extension }`; erwartet wird `csv`, `json` oder `po`
+expected-import-format = es fehlt das Format der Datei, z. B. `--from wav` oder `--from gpl`
+expected-clip = es fehlt der zu ersetzende Clip, z. B. `--clip 3`
+expected-palette = es fehlt die zu ersetzende Palette, z. B. `--entry 66`
+expected-texture = es fehlt der Index eines Textur-Eintrags, z. B. `--texture 72`
+unknown-import-format = unbekanntes Format `{ $format }`; erwartet wird `wav`, `gpl`, `act` oder `pal`
+replaced-entry = Eintrag { $index } ersetzt
+
+# Mods und Patches.
+expected-mods = es fehlen die Mod-Verzeichnisse und das Archiv, auf das sie angewendet werden
+mod-conflict = Eintrag { $index } wird von { $mods } ersetzt; { $winner } gewinnt
+rewrote-from = Archiv ab Eintrag { $index } neu geschrieben
+patched-in-place = Eintrag { $index } direkt gepatcht
+expected-diff = es fehlen das ursprüngliche und das geänderte Archiv
+entries-removed = Einträge { $from }..{ $to } entfernt
+expected-patch = es fehlen der Patch und das Archiv, auf das er angewendet wird
+patched-entry = Eintrag { $index } gepatcht
+
+# Archive ansehen.
+expected-mount = es fehlen das Archiv und das Verzeichnis, in das es eingehängt wird
+mounting = { $archive } wird in { $mountpoint } eingehängt; zum Beenden aushängen
+expected-serve = es fehlt das bereitzustellende Archiv
+serving = { $archive } wird auf http://{ $addr }/entries bereitgestellt
+expected-tree = es fehlt das anzuzeigende Archiv
+expected-cat = es fehlt das Archiv, aus dem der Eintrag gelesen wird
+expected-offset = es fehlt der Offset des Eintrags, z. B. `--offset 0xA20`
+no-entry-at = bei { $offset } gibt es keinen Eintrag
+entry = Eintrag { $index }
+expected-peek = es fehlen das Archiv und der Index des anzuzeigenden Eintrags
+no-entry = es gibt keinen Eintrag { $index }; das Archiv hat { $count }
+expected-list = es fehlt das aufzulistende Archiv
+expected-doctor = es fehlt das zu prüfende Archiv
+expected-bench = es fehlt das zu messende Archiv
+runs-not-a-number = `--runs` erwartet eine Anzahl von Durchläufen, nicht `{ $runs }`
+expected-extract = es fehlen die zu entpackenden Archive
+extracting = entpacke { $path }
+extracted = { $path }: { $entries } Einträge, { $version }, { $warnings } Warnungen, { $skipped } schon entpackt; nach { $output }
+extract-failed = { $path }: fehlgeschlagen; { $error }
+extract-summary = { $count } Archive:
+resumed = { $count } Einträge waren schon entpackt, und wurden übersprungen
+expected-log-file = nach `--log-file` fehlt die Datei für das Protokoll
+cache-cold = kalter
+cache-warm = warmer
+bench-header = { $path }: { $size } Bytes, { $runs } Durchläufe, { $cache } Cache
+bench-stage = { $stage } bestes { $best }, Median { $median }, { $throughput }
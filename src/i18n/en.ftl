@@ -0,0 +1,67 @@
+# The messages of the command line tool; the reference catalog. See `src/i18n.rs`.
+
+warning = warning: { $message }
+wrote = wrote { $path }
+found-archive = found the archive on { $path }
+needs-feature = `{ $what }` needs the `{ $feature }` feature
+
+# Arguments.
+unknown-export-format = unknown format `{ $format }`; expected `gltf`, `obj`, `csv`, `json` or `po`
+jobs-not-a-number = `--jobs` expects a number of threads, not `{ $jobs }`
+not-a-size = `{ $value }` is not a size
+unknown-entry-types = unknown `--entry-types` `{ $entry_types }`; expected `stored`, `zeros` or `detected`
+expected-entry-budget = expected `--entry-budget <index>=<size>`
+expected-output = expected where to write the archive, e.g. `-o packfile.dat`
+not-a-hex-offset = `{ $offset }` is not a hex offset
+not-an-index = `{ $index }` is not an index
+in-place-reproducible = `--in-place` can't be combined with `--reproducible`
+
+# Imports.
+expected-import-file = expected the file to import
+expected-source-packfile = expected the archive to import into, e.g. `game/packfile.dat`
+expected-text-language = expected the language of the file, e.g. `--lang de`
+unknown-text-format = unknown format `{ $extension }`; expected `csv`, `json` or `po`
+expected-import-format = expected the format of the file, e.g. `--from wav` or `--from gpl`
+expected-clip = expected the clip to replace, e.g. `--clip 3`
+expected-palette = expected the palette to replace, e.g. `--entry 66`
+expected-texture = expected the index of a texture entry, e.g. `--texture 72`
+unknown-import-format = unknown format `{ $format }`; expected `wav`, `gpl`, `act` or `pal`
+replaced-entry = replaced entry { $index }
+
+# Mods and patches.
+expected-mods = expected the mod directories, and the archive to apply them on
+mod-conflict = entry { $index } is replaced by { $mods }; { $winner } wins
+rewrote-from = rewrote the archive from entry { $index } onward
+patched-in-place = patched entry { $index } in place
+expected-diff = expected the original archive, and the modified one
+entries-removed = entries { $from }..{ $to } removed
+expected-patch = expected the patch, and the archive to apply it on
+patched-entry = patched entry { $index }
+
+# Viewing archives.
+expected-mount = expected the archive, and the directory to mount it on
+mounting = mounting { $archive } on { $mountpoint }; unmount it to stop
+expected-serve = expected the archive to serve
+serving = serving { $archive } on http://{ $addr }/entries
+expected-tree = expected the archive to show
+expected-cat = expected the archive to read the entry from
+expected-offset = expected the offset of the entry, e.g. `--offset 0xA20`
+no-entry-at = there is no entry at { $offset }
+entry = entry { $index }
+expected-peek = expected the archive, and the index of the entry to preview
+no-entry = there is no entry { $index }; the archive has { $count } of them
+expected-list = expected the archive to list
+expected-doctor = expected the archive to check
+expected-bench = expected the archive to time
+runs-not-a-number = `--runs` expects a number of runs, not `{ $runs }`
+expected-extract = expected the archives to extract
+extracting = extracting { $path }
+extracted = { $path }: { $entries } entries, { $version }, { $warnings } warnings, { $skipped } already extracted; into { $output }
+extract-failed = { $path }: failed; { $error }
+extract-summary = { $count } archives:
+resumed = { $count } entries were already extracted, and were skipped
+expected-log-file = expected the file to log to, after `--log-file`
+cache-cold = cold
+cache-warm = warm
+bench-header = { $path }: { $size } bytes, { $runs } runs, { $cache } cache
+bench-stage = { $stage } best { $best }, median { $median }, { $throughput }
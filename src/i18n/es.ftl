@@ -0,0 +1,67 @@
+# Español.
+
+warning = aviso: { $message }
+wrote = se escribió { $path }
+found-archive = se encontró el archivo en { $path }
+needs-feature = `{ $what }` necesita la característica `{ $feature }`
+
+# Argumentos.
+unknown-export-format = formato `{ $format }` desconocido; se esperaba `gltf`, `obj`, `csv`, `json` o `po`
+jobs-not-a-number = `--jobs` espera un número de hilos, no `{ $jobs }`
+not-a-size = `{ $value }` no es un tamaño
+unknown-entry-types = valor desconocido `{ $entry_types }` de `--entry-types`; se esperaba `stored`, `zeros` o `detected`
+expected-entry-budget = se esperaba `--entry-budget <índice>=<tamaño>`
+expected-output = falta dónde escribir el archivo, p. ej. `-o packfile.dat`
+not-a-hex-offset = `{ $offset }` no es un desplazamiento hexadecimal
+not-an-index = `{ $index }` no es un índice
+in-place-reproducible = `--in-place` no se puede combinar con `--reproducible`
+
+# Importaciones.
+expected-import-file = falta el fichero a importar
+expected-source-packfile = falta el archivo en el que importar, p. ej. `game/packfile.dat`
+expected-text-language = falta el idioma del fichero, p. ej. `--lang de`
+unknown-text-format = formato `{ $extension }` desconocido; se esperaba `csv`, `json` o `po`
+expected-import-format = falta el formato del fichero, p. ej. `--from wav` o `--from gpl`
+expected-clip = falta el clip a reemplazar, p. ej. `--clip 3`
+expected-palette = falta la paleta a reemplazar, p. ej. `--entry 66`
+expected-texture = falta el índice de una entrada de textura, p. ej. `--texture 72`
+unknown-import-format = formato `{ $format }` desconocido; se esperaba `wav`, `gpl`, `act` o `pal`
+replaced-entry = se reemplazó la entrada { $index }
+
+# Mods y parches.
+expected-mods = faltan los directorios de los mods, y el archivo sobre el que aplicarlos
+mod-conflict = la entrada { $index } la reemplazan { $mods }; gana { $winner }
+rewrote-from = se reescribió el archivo a partir de la entrada { $index }
+patched-in-place = se parcheó la entrada { $index } en su sitio
+expected-diff = faltan el archivo original y el modificado
+entries-removed = se eliminaron las entradas { $from }..{ $to }
+expected-patch = faltan el parche y el archivo sobre el que aplicarlo
+patched-entry = se parcheó la entrada { $index }
+
+# Ver archivos.
+expected-mount = faltan el archivo y el directorio donde montarlo
+mounting = montando { $archive } en { $mountpoint }; desmóntalo para terminar
+expected-serve = falta el archivo a servir
+serving = sirviendo { $archive } en http://{ $addr }/entries
+expected-tree = falta el archivo a mostrar
+expected-cat = falta el archivo del que leer la entrada
+expected-offset = falta el desplazamiento de la entrada, p. ej. `--offset 0xA20`
+no-entry-at = no hay ninguna entrada en { $offset }
+entry = entrada { $index }
+expected-peek = faltan el archivo y el índice de la entrada a previsualizar
+no-entry = no existe la entrada { $index }; el archivo tiene { $count }
+expected-list = falta el archivo a listar
+expected-doctor = falta el archivo a comprobar
+expected-bench = falta el archivo a medir
+runs-not-a-number = `--runs` espera un número de pasadas, no `{ $runs }`
+expected-extract = faltan los archivos a extraer
+extracting = extrayendo { $path }
+extracted = { $path }: { $entries } entradas, { $version }, { $warnings } avisos, { $skipped } ya extraídas; en { $output }
+extract-failed = { $path }: falló; { $error }
+extract-summary = { $count } archivos:
+resumed = { $count } entradas ya estaban extraídas, y se saltaron
+expected-log-file = falta el archivo del registro, tras `--log-file`
+cache-cold = fría
+cache-warm = caliente
+bench-header = { $path }: { $size } bytes, { $runs } pasadas, caché { $cache }
+bench-stage = { $stage } mejor { $best }, mediana { $median }, { $throughput }
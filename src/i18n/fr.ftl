@@ -0,0 +1,67 @@
+# Français.
+
+warning = avertissement : { $message }
+wrote = { $path } écrit
+found-archive = archive trouvée dans { $path }
+needs-feature = `{ $what }` nécessite la fonctionnalité `{ $feature }`
+
+# Arguments.
+unknown-export-format = format `{ $format }` inconnu ; attendu : `gltf`, `obj`, `csv`, `json` ou `po`
+jobs-not-a-number = `--jobs` attend un nombre de threads, pas `{ $jobs }`
+not-a-size = `{ $value }` n'est pas une taille
+unknown-entry-types = valeur inconnue `{ $entry_types }` pour `--entry-types` ; attendu `stored`, `zeros` ou `detected`
+expected-entry-budget = attendu : `--entry-budget <index>=<taille>`
+expected-output = il manque où écrire l'archive, p. ex. `-o packfile.dat`
+not-a-hex-offset = `{ $offset }` n'est pas un décalage hexadécimal
+not-an-index = `{ $index }` n'est pas un index
+in-place-reproducible = `--in-place` ne peut pas être combiné avec `--reproducible`
+
+# Importations.
+expected-import-file = il manque le fichier à importer
+expected-source-packfile = il manque l'archive dans laquelle importer, p. ex. `game/packfile.dat`
+expected-text-language = il manque la langue du fichier, p. ex. `--lang de`
+unknown-text-format = format `{ $extension }` inconnu ; attendu : `csv`, `json` ou `po`
+expected-import-format = il manque le format du fichier, p. ex. `--from wav` ou `--from gpl`
+expected-clip = il manque le clip à remplacer, p. ex. `--clip 3`
+expected-palette = il manque la palette à remplacer, p. ex. `--entry 66`
+expected-texture = il manque l'index d'une entrée de texture, p. ex. `--texture 72`
+unknown-import-format = format `{ $format }` inconnu ; attendu : `wav`, `gpl`, `act` ou `pal`
+replaced-entry = entrée { $index } remplacée
+
+# Mods et correctifs.
+expected-mods = il manque les répertoires des mods, et l'archive sur laquelle les appliquer
+mod-conflict = l'entrée { $index } est remplacée par { $mods } ; { $winner } l'emporte
+rewrote-from = archive réécrite à partir de l'entrée { $index }
+patched-in-place = entrée { $index } corrigée sur place
+expected-diff = il manque l'archive d'origine, et celle qui a été modifiée
+entries-removed = entrées { $from }..{ $to } supprimées
+expected-patch = il manque le correctif, et l'archive sur laquelle l'appliquer
+patched-entry = entrée { $index } corrigée
+
+# Consultation des archives.
+expected-mount = il manque l'archive, et le répertoire où la monter
+mounting = montage de { $archive } sur { $mountpoint } ; démontez-la pour arrêter
+expected-serve = il manque l'archive à servir
+serving = { $archive } servie sur http://{ $addr }/entries
+expected-tree = il manque l'archive à afficher
+expected-cat = il manque l'archive dont lire l'entrée
+expected-offset = il manque le décalage de l'entrée, p. ex. `--offset 0xA20`
+no-entry-at = il n'y a aucune entrée à { $offset }
+entry = entrée { $index }
+expected-peek = il manque l'archive, et l'index de l'entrée à prévisualiser
+no-entry = il n'y a pas d'entrée { $index } ; l'archive en a { $count }
+expected-list = il manque l'archive à lister
+expected-doctor = il manque l'archive à vérifier
+expected-bench = il manque l'archive à mesurer
+runs-not-a-number = `--runs` attend un nombre de passes, pas `{ $runs }`
+expected-extract = il manque les archives à extraire
+extracting = extraction de { $path }
+extracted = { $path } : { $entries } entrées, { $version }, { $warnings } avertissements, { $skipped } déjà extraites ; dans { $output }
+extract-failed = { $path } : échec ; { $error }
+extract-summary = { $count } archives :
+resumed = { $count } entrées étaient déjà extraites, et ont été sautées
+expected-log-file = il manque le fichier du journal, après `--log-file`
+cache-cold = froid
+cache-warm = chaud
+bench-header = { $path } : { $size } octets, { $runs } passes, cache { $cache }
+bench-stage = { $stage } meilleur { $best }, médiane { $median }, { $throughput }
@@ -0,0 +1,291 @@
+//! PNG import, behind the `import-image` feature.
+
+use crate::{
+    error::{Error, Result},
+    format::{
+        color,
+        palette::Palette,
+        pman::PmanFile,
+        texture::{Texture, TEXTURE_SIZE},
+    },
+};
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format: "PNG",
+        reason: reason.into(),
+    }
+}
+
+/// Decodes a PNG file into its width, its height, and its pixels as 8-bit RGBA, row by row.
+///
+/// Every color type is supported; 16-bit channels are reduced to 8 bits, and images without alpha
+/// are opaque.
+///
+/// # Errors
+///
+/// If `bytes` is not a valid PNG file.
+pub fn decode_png(bytes: &[u8]) -> Result<(u32, u32, Vec<[u8; 4]>)> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+    let mut reader = decoder
+        .read_info()
+        .map_err(|err| invalid(err.to_string()))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|err| invalid(err.to_string()))?;
+    let bytes = &buf[..info.buffer_size()];
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => bytes
+            .chunks_exact(4)
+            .map(|p| [p[0], p[1], p[2], p[3]])
+            .collect(),
+        png::ColorType::Rgb => bytes
+            .chunks_exact(3)
+            .map(|p| [p[0], p[1], p[2], u8::MAX])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => bytes
+            .chunks_exact(2)
+            .map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => bytes.iter().map(|&g| [g, g, g, u8::MAX]).collect(),
+        // `EXPAND` turns indexed images into RGB(A) ones.
+        png::ColorType::Indexed => return Err(invalid("indexed images can't be expanded")),
+    };
+
+    Ok((info.width, info.height, pixels))
+}
+
+/// Converts 8-bit RGBA `pixels` into a texture, with the colors of `palette`; or, if there is no
+/// `palette`, with a new one that is generated out of them (see [`color::generate_palette`]).
+///
+/// Returns the texture, and the palette that it uses. With `dither`, see [`color::quantize`].
+///
+/// # Errors
+///
+/// If the image is not as large as a texture entry (`256x256`), or if there are not
+/// `width * height` pixels.
+pub fn rgba_to_texture(
+    width: u32,
+    height: u32,
+    pixels: &[[u8; 4]],
+    palette: Option<&Palette>,
+    dither: bool,
+) -> Result<(Texture, Palette)> {
+    if (width, height) != (TEXTURE_SIZE, TEXTURE_SIZE) {
+        return Err(invalid(format!(
+            "a texture must be {TEXTURE_SIZE}x{TEXTURE_SIZE}, but the image is {width}x{height}"
+        )));
+    }
+    if pixels.len() != (width as usize).saturating_mul(height as usize) {
+        return Err(Error::InvalidImageSize {
+            width,
+            height,
+            len: pixels.len(),
+        });
+    }
+
+    let palette = palette.cloned().unwrap_or_else(|| {
+        // the palette can only hold `0x0RGB` colors, so quantize against those.
+        Palette::from_rgba(&color::generate_palette(pixels))
+    });
+    let indices = color::quantize(pixels, width as usize, &palette.to_rgba(), dither);
+
+    Ok((
+        Texture {
+            width,
+            height,
+            pixels: indices,
+        },
+        palette,
+    ))
+}
+
+/// Converts a PNG file into a texture; see [`rgba_to_texture`].
+///
+/// # Errors
+///
+/// See [`decode_png`] and [`rgba_to_texture`].
+pub fn png_to_texture(
+    bytes: &[u8],
+    palette: Option<&Palette>,
+    dither: bool,
+) -> Result<(Texture, Palette)> {
+    let (width, height, pixels) = decode_png(bytes)?;
+
+    rgba_to_texture(width, height, &pixels, palette, dither)
+}
+
+/// Converts 8-bit RGBA `pixels` into a texture, where the gray level of each pixel is its index
+/// into the palette; the PNG files of textures are exported like that.
+///
+/// # Errors
+///
+/// If the image is not as large as a texture entry (`256x256`), if there are not `width * height`
+/// pixels, or if a pixel is not an opaque gray.
+pub fn gray_to_texture(width: u32, height: u32, pixels: &[[u8; 4]]) -> Result<Texture> {
+    if (width, height) != (TEXTURE_SIZE, TEXTURE_SIZE) {
+        return Err(invalid(format!(
+            "a texture must be {TEXTURE_SIZE}x{TEXTURE_SIZE}, but the image is {width}x{height}"
+        )));
+    }
+    if pixels.len() != (width as usize).saturating_mul(height as usize) {
+        return Err(Error::InvalidImageSize {
+            width,
+            height,
+            len: pixels.len(),
+        });
+    }
+
+    let pixels = pixels
+        .iter()
+        .enumerate()
+        .map(|(i, &[r, g, b, a])| {
+            if r == g && g == b && a == u8::MAX {
+                Ok(r)
+            } else {
+                let (x, y) = (i % width as usize, i / width as usize);
+
+                Err(invalid(format!(
+                    "the pixel at ({x}, {y}) is not an opaque gray, so it is not a palette index; \
+                     pass a palette to quantize colors against"
+                )))
+            }
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Texture {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Replaces the texture entry at `index` of `pman` with a PNG file.
+///
+/// Without a `palette`, the gray level of each pixel is read as its index (see
+/// [`gray_to_texture`]); with one, the image is quantized against it (see [`rgba_to_texture`]).
+/// The palettes of the textures are not known for sure yet, so none is looked up on `pman`.
+///
+/// # Errors
+///
+/// If `index` is not a texture entry, if the image can't be converted, or if the new texture
+/// doesn't take the same bytes as the entry it replaces.
+pub fn import_texture(
+    pman: &mut PmanFile,
+    index: usize,
+    png: &[u8],
+    palette: Option<&Palette>,
+    dither: bool,
+) -> Result<()> {
+    let is_texture = pman
+        .files()
+        .get(index)
+        .is_some_and(|file| Texture::new(file.bytes()).is_ok());
+    if !is_texture {
+        return Err(invalid(format!(
+            "entry {index} is not a texture that can be replaced"
+        )));
+    }
+
+    let (width, height, pixels) = decode_png(png)?;
+    let texture = match palette {
+        Some(palette) => rgba_to_texture(width, height, &pixels, Some(palette), dither)?.0,
+        None => gray_to_texture(width, height, &pixels)?,
+    };
+    let bytes = texture.to_bytes();
+
+    // textures are not compressed, so they must take as many bytes as the entry they replace.
+    let budget = pman.files().get(index).map_or(0, |file| file.bytes().len());
+    if bytes.len() != budget {
+        return Err(invalid(format!(
+            "the texture takes {:#X} bytes, but entry {index} takes {budget:#X}",
+            bytes.len()
+        )));
+    }
+
+    pman.files_mut()[index].set_bytes(bytes);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    fn encode(width: u32, height: u32, color_type: png::ColorType, bytes: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut encoder = png::Encoder::new(&mut buf, width, height);
+        encoder.set_color(color_type);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(bytes))
+            .expect("the image is valid.");
+
+        buf
+    }
+
+    #[test]
+    fn decode_png_test() -> eyre::Result<()> {
+        let rgb = encode(2, 1, png::ColorType::Rgb, &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            decode_png(&rgb)?,
+            (2, 1, vec![[1, 2, 3, 0xFF], [4, 5, 6, 0xFF]])
+        );
+
+        let grey = encode(1, 1, png::ColorType::GrayscaleAlpha, &[7, 8]);
+        assert_eq!(decode_png(&grey)?, (1, 1, vec![[7, 7, 7, 8]]));
+
+        assert_eq!(
+            decode_png(b"PNG").unwrap_err().kind(),
+            ErrorKind::InvalidImport
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn import_texture_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(INPUT)?;
+        let texture = Texture::new(pman[72].bytes())?;
+        let gray: Vec<_> = texture.pixels.iter().map(|&i| [i, i, i, 0xFF]).collect();
+        let png = encode(256, 256, png::ColorType::Rgba, gray.as_flattened());
+
+        // an exported texture comes back as it is.
+        import_texture(&mut pman, 72, &png, None, false)?;
+        assert_eq!(Texture::new(pman[72].bytes())?, texture);
+
+        // the same colors come back, even if a duplicated color of the palette is picked instead.
+        let palette = Palette::new(pman[66].bytes())?;
+        let rgba = texture.to_rgba(&palette);
+        let png = encode(256, 256, png::ColorType::Rgba, rgba.as_flattened());
+        import_texture(&mut pman, 72, &png, Some(&palette), false)?;
+        assert_eq!(Texture::new(pman[72].bytes())?.to_rgba(&palette), rgba);
+
+        let small = encode(1, 1, png::ColorType::Rgba, &[0; 4]);
+        for (index, png, palette) in [
+            (72, &small, Some(&palette)),
+            (0, &png, Some(&palette)),
+            (72, &png, None),
+        ] {
+            assert_eq!(
+                import_texture(&mut pman, index, png, palette, false)
+                    .unwrap_err()
+                    .kind(),
+                ErrorKind::InvalidImport
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,748 @@
+//! Finds the `packfile.dat` in what the game is distributed (or installed) as; see [`locate`].
+//!
+//! The game was sold on an N-Gage game card (an MMC), whose files are installed under
+//! `E:\system\apps\Ashen\`; dumps of it are either a copy of that folder, a raw image of the whole
+//! card, or a `.sis` installer.
+
+use crate::error::{Error, Result};
+use flate2::read::ZlibDecoder;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// The name of the archive, which is matched without regard to case.
+pub const PACKFILE_NAME: &str = "packfile.dat";
+
+/// What [`locate`] found the archive in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// The archive itself.
+    Archive,
+    /// A file of a directory; e.g. a copy of the installation folder.
+    Directory,
+    /// A file of a `.sis` installer.
+    Sis,
+    /// A file of a raw FAT image; e.g. a dump of the game card.
+    Image,
+}
+
+fn invalid(format: &'static str, reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format,
+        reason: reason.into(),
+    }
+}
+
+fn is_packfile(name: &str) -> bool {
+    name.eq_ignore_ascii_case(PACKFILE_NAME)
+}
+
+/// Reads the archive out of `path`; the archive itself, a directory that has it (at any depth), a
+/// `.sis` installer, or a raw FAT image (with, or without, a partition table).
+///
+/// # Errors
+///
+/// If `path` can't be read, or if there is no archive on it.
+pub fn locate(path: impl AsRef<Path>) -> Result<(Vec<u8>, Source)> {
+    let path = path.as_ref();
+
+    if path.is_dir() {
+        let file = find_in_dir(path)?
+            .ok_or_else(|| invalid("install folder", format!("there is no `{PACKFILE_NAME}`")))?;
+        return Ok((fs::read(file)?, Source::Directory));
+    }
+
+    let mut file = BufReader::new(File::open(path)?);
+    let mut magic = [0; 16];
+    let len = file.read(&mut magic)?;
+    file.rewind()?;
+
+    match &magic[..len] {
+        [b'P', b'M', b'A', b'N', ..] => {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            Ok((bytes, Source::Archive))
+        }
+        magic if is_sis(magic) => {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            Ok((from_sis(&bytes)?, Source::Sis))
+        }
+        _ => Ok((from_image(file)?, Source::Image)),
+    }
+}
+
+/// The first `packfile.dat` under `dir`, searched breadth first.
+fn find_in_dir(dir: &Path) -> Result<Option<PathBuf>> {
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while !dirs.is_empty() {
+        let mut next = Vec::new();
+
+        for dir in dirs {
+            let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+            entries.sort_by_key(fs::DirEntry::file_name);
+
+            for entry in entries {
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    next.push(path);
+                } else if entry.file_name().to_str().is_some_and(is_packfile) {
+                    return Ok(Some(path));
+                }
+            }
+        }
+        dirs = next;
+    }
+
+    Ok(None)
+}
+
+/// The third UID of every `.sis` file.
+const SIS_UID3: u32 = 0x1000_0419;
+/// The second UID of the installers for EPOC release 6 (e.g. the N-Gage); their file records are
+/// longer than the ones of older releases.
+const SIS_UID2_ER6: u32 = 0x1000_3A12;
+const SIS_UNICODE: u16 = 0x0001;
+const SIS_NO_COMPRESS: u16 = 0x0008;
+
+fn is_sis(bytes: &[u8]) -> bool {
+    bytes.get(8..12) == Some(&SIS_UID3.to_le_bytes()[..])
+}
+
+/// A little-endian reader over a `.sis` file, which fails on reads past its end.
+struct SisReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl SisReader<'_> {
+    fn slice(&self, offset: usize, len: usize) -> Result<&[u8]> {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or_else(|| {
+                invalid(
+                    "SIS",
+                    format!("{len:#X} bytes at {offset:#X} are past its end"),
+                )
+            })
+    }
+
+    fn u16_at(&self, offset: usize) -> Result<u16> {
+        let bytes = self.slice(offset, 2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32_at(&self, offset: usize) -> Result<usize> {
+        let bytes = self.slice(offset, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn u32(&mut self) -> Result<usize> {
+        let value = self.u32_at(self.pos)?;
+        self.pos += 4;
+        Ok(value)
+    }
+
+    /// Reads `count` fields, and keeps the first one.
+    fn first_of(&mut self, count: usize) -> Result<usize> {
+        let first = self.u32()?;
+        self.pos += count.saturating_sub(1) * 4;
+        Ok(first)
+    }
+}
+
+/// Reads the `packfile.dat` out of a `.sis` installer; the first language of it, if it has many.
+///
+/// # Errors
+///
+/// If `bytes` is not a valid `.sis` file, or if it doesn't install the archive.
+pub fn from_sis(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut sis = SisReader { bytes, pos: 0 };
+
+    if !is_sis(bytes) {
+        return Err(invalid("SIS", "its third UID is not `0x10000419`"));
+    }
+    let er6 = sis.u32_at(4)? == SIS_UID2_ER6 as usize;
+    let languages = usize::from(sis.u16_at(0x12)?);
+    let records = sis.u16_at(0x14)?;
+    let options = sis.u16_at(0x24)?;
+    sis.pos = sis.u32_at(0x34)?;
+
+    let name = |sis: &SisReader, len, ptr| -> Result<String> {
+        let name = sis.slice(ptr, len)?;
+        if options & SIS_UNICODE == 0 {
+            return Ok(name.iter().copied().map(char::from).collect());
+        }
+        let units: Vec<_> = name
+            .chunks_exact(2)
+            .map(|u| u16::from_le_bytes([u[0], u[1]]))
+            .collect();
+        Ok(String::from_utf16_lossy(&units))
+    };
+
+    for _ in 0..records {
+        match sis.u32()? {
+            // a file, or a file for each language.
+            record @ (0 | 1) => {
+                let files = if record == 0 { 1 } else { languages };
+                let (_file_type, _details) = (sis.u32()?, sis.u32()?);
+                let source = (sis.u32()?, sis.u32()?);
+                let destination = (sis.u32()?, sis.u32()?);
+                let len = sis.first_of(files)?;
+                let ptr = sis.first_of(files)?;
+                if er6 {
+                    sis.first_of(files)?;
+                    sis.pos += 8;
+                }
+
+                let destination = name(&sis, destination.0, destination.1)?;
+                let source = name(&sis, source.0, source.1)?;
+                let found = [destination, source]
+                    .iter()
+                    .any(|path| path.rsplit(['\\', '/']).next().is_some_and(is_packfile));
+                if !found {
+                    continue;
+                }
+
+                let data = sis.slice(ptr, len)?;
+                if options & SIS_NO_COMPRESS != 0 {
+                    return Ok(data.to_vec());
+                }
+                let mut bytes = Vec::new();
+                ZlibDecoder::new(data)
+                    .read_to_end(&mut bytes)
+                    .map_err(Error::InvalidZlib)?;
+                return Ok(bytes);
+            }
+            // the options of the installer; a caption for each language, and the 128-bit set of
+            // the selected ones.
+            2 => {
+                let count = sis.u32()?;
+                sis.pos += count * languages * 8 + 16;
+            }
+            // `if` and `else if`; the size of its expression, and the expression.
+            3 | 4 => {
+                let size = sis.u32()?;
+                sis.pos += size;
+            }
+            // `else` and `endif`.
+            5 | 6 => {}
+            record => return Err(invalid("SIS", format!("unknown file record {record}"))),
+        }
+    }
+
+    Err(invalid(
+        "SIS",
+        format!("it doesn't install a `{PACKFILE_NAME}`"),
+    ))
+}
+
+const SECTOR_SIZE: usize = 512;
+/// The partition types of a master boot record that hold a FAT volume.
+const FAT_PARTITIONS: [u8; 6] = [0x01, 0x04, 0x06, 0x0B, 0x0C, 0x0E];
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_LONG_NAME: u8 = 0x0F;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FatType {
+    Fat12,
+    Fat16,
+    Fat32,
+}
+
+/// A FAT volume; only what is needed to read files out of it.
+struct FatVolume<R> {
+    reader: R,
+    fat_type: FatType,
+    /// Where the volume starts on the image, in bytes.
+    start: u64,
+    cluster_size: usize,
+    /// Where the first FAT starts, from the start of the volume.
+    fat_offset: u64,
+    /// Where the root directory starts, from the start of the volume; FAT12 and FAT16 only.
+    root_offset: u64,
+    root_size: usize,
+    /// The first cluster of the root directory; FAT32 only.
+    root_cluster: u32,
+    /// Where cluster `2` starts, from the start of the volume.
+    data_offset: u64,
+    cluster_count: u32,
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().expect("4 bytes."))
+}
+
+/// If `sector` is the boot sector of a FAT volume.
+fn is_boot_sector(sector: &[u8]) -> bool {
+    let bytes_per_sector = u16_at(sector, 11);
+    let sectors_per_cluster = sector[13];
+
+    matches!(sector[0], 0xEB | 0xE9)
+        && matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096)
+        && sectors_per_cluster.is_power_of_two()
+        && u16_at(sector, 14) != 0
+        && sector[16] != 0
+}
+
+impl<R: Read + Seek> FatVolume<R> {
+    fn new(mut reader: R) -> Result<Self> {
+        let mut sector = [0; SECTOR_SIZE];
+        reader.read_exact(&mut sector)?;
+
+        let mut start = 0;
+        if !is_boot_sector(&sector) {
+            if sector[510..] != [0x55, 0xAA] {
+                return Err(invalid(
+                    "image",
+                    "it is neither a FAT volume, nor a partitioned disk",
+                ));
+            }
+            let lba = (0..4)
+                .map(|i| &sector[446 + i * 16..462 + i * 16])
+                .find(|entry| FAT_PARTITIONS.contains(&entry[4]))
+                .map(|entry| u32_at(entry, 8))
+                .ok_or_else(|| invalid("image", "none of its partitions is a FAT volume"))?;
+
+            start = u64::from(lba) * SECTOR_SIZE as u64;
+            reader.seek(SeekFrom::Start(start))?;
+            reader.read_exact(&mut sector)?;
+            if !is_boot_sector(&sector) {
+                return Err(invalid("image", "its FAT partition has no boot sector"));
+            }
+        }
+
+        let bytes_per_sector = u16_at(&sector, 11);
+        let sectors_per_cluster = sector[13];
+        let root_entries = u16_at(&sector, 17);
+        let sector_size = u64::from(bytes_per_sector);
+        let reserved = u64::from(u16_at(&sector, 14));
+        let fats = u64::from(sector[16]);
+        let fat_size = match u16_at(&sector, 22) {
+            0 => u64::from(u32_at(&sector, 36)),
+            size => u64::from(size),
+        };
+        let total = match u16_at(&sector, 19) {
+            0 => u64::from(u32_at(&sector, 32)),
+            total => u64::from(total),
+        };
+
+        let root_sectors = (u64::from(root_entries) * 32).div_ceil(sector_size);
+        let data_sector = reserved + fats * fat_size + root_sectors;
+        let clusters = total.saturating_sub(data_sector) / u64::from(sectors_per_cluster);
+        let cluster_count =
+            u32::try_from(clusters).map_err(|_| invalid("image", "it has too many clusters"))?;
+        let fat_type = match cluster_count {
+            0..4085 => FatType::Fat12,
+            4085..65525 => FatType::Fat16,
+            _ => FatType::Fat32,
+        };
+
+        Ok(Self {
+            reader,
+            fat_type,
+            start,
+            cluster_size: usize::from(bytes_per_sector) * usize::from(sectors_per_cluster),
+            fat_offset: reserved * sector_size,
+            root_offset: (reserved + fats * fat_size) * sector_size,
+            root_size: usize::from(root_entries) * 32,
+            root_cluster: u32_at(&sector, 44),
+            data_offset: data_sector * sector_size,
+            cluster_count,
+        })
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        self.reader.seek(SeekFrom::Start(self.start + offset))?;
+        self.reader.read_exact(buf)?;
+
+        Ok(())
+    }
+
+    /// The cluster after `cluster`, or `None` if it is the last one of its chain.
+    fn next_cluster(&mut self, cluster: u32) -> Result<Option<u32>> {
+        let mut entry = [0; 4];
+        let (offset, end) = match self.fat_type {
+            FatType::Fat12 => (u64::from(cluster) * 3 / 2, 0xFF8),
+            FatType::Fat16 => (u64::from(cluster) * 2, 0xFFF8),
+            FatType::Fat32 => (u64::from(cluster) * 4, 0x0FFF_FFF8),
+        };
+        let len = if self.fat_type == FatType::Fat32 {
+            4
+        } else {
+            2
+        };
+        self.read_at(self.fat_offset + offset, &mut entry[..len])?;
+
+        let next = match self.fat_type {
+            FatType::Fat12 if cluster % 2 == 1 => u32::from(u16_at(&entry, 0) >> 4),
+            FatType::Fat12 => u32::from(u16_at(&entry, 0) & 0x0FFF),
+            FatType::Fat16 => u32::from(u16_at(&entry, 0)),
+            FatType::Fat32 => u32_at(&entry, 0) & 0x0FFF_FFFF,
+        };
+
+        Ok((2..end).contains(&next).then_some(next))
+    }
+
+    /// The clusters of the chain that starts at `cluster`.
+    fn chain(&mut self, cluster: u32) -> Result<Vec<u32>> {
+        let mut chain = Vec::new();
+        let mut next = Some(cluster).filter(|&c| c >= 2);
+
+        while let Some(cluster) = next {
+            // a chain can't be longer than the volume; if it is, it loops.
+            if chain.len() > self.cluster_count as usize {
+                return Err(invalid("image", format!("cluster {cluster} is on a loop")));
+            }
+            chain.push(cluster);
+            next = self.next_cluster(cluster)?;
+        }
+
+        Ok(chain)
+    }
+
+    /// The bytes of the clusters of the chain that starts at `cluster`, up to `len`.
+    fn read_chain(&mut self, cluster: u32, len: Option<usize>) -> Result<Vec<u8>> {
+        let chain = self.chain(cluster)?;
+        let size = chain.len() * self.cluster_size;
+        let len = len.unwrap_or(size);
+        if len > size {
+            return Err(invalid(
+                "image",
+                format!(
+                    "a file of {len:#X} bytes doesn't fit on {} clusters",
+                    chain.len()
+                ),
+            ));
+        }
+
+        let mut bytes = vec![0; size];
+        for (cluster, buf) in chain.into_iter().zip(bytes.chunks_mut(self.cluster_size)) {
+            let offset = self.data_offset + u64::from(cluster - 2) * self.cluster_size as u64;
+            self.read_at(offset, buf)?;
+        }
+        bytes.truncate(len);
+
+        Ok(bytes)
+    }
+
+    /// The entries of a directory; the root one if `cluster` is `None`.
+    fn read_dir(&mut self, cluster: Option<u32>) -> Result<Vec<DirEntry>> {
+        let bytes = match cluster {
+            Some(cluster) => self.read_chain(cluster, None)?,
+            None if self.fat_type == FatType::Fat32 => self.read_chain(self.root_cluster, None)?,
+            None => {
+                let mut bytes = vec![0; self.root_size];
+                self.read_at(self.root_offset, &mut bytes)?;
+                bytes
+            }
+        };
+
+        Ok(parse_dir(&bytes))
+    }
+
+    /// The bytes of the first `packfile.dat` of the volume, searched breadth first.
+    fn find_packfile(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut dirs = vec![None];
+        let mut visited = 0;
+
+        while !dirs.is_empty() {
+            let mut next = Vec::new();
+
+            for dir in dirs {
+                for entry in self.read_dir(dir)? {
+                    if entry.name == "." || entry.name == ".." || entry.cluster < 2 {
+                        continue;
+                    }
+                    if entry.is_dir {
+                        next.push(Some(entry.cluster));
+                    } else if is_packfile(&entry.name) {
+                        let bytes = self.read_chain(entry.cluster, Some(entry.size as usize))?;
+                        return Ok(Some(bytes));
+                    }
+                }
+
+                // every directory takes at least a cluster; more than that is a loop.
+                visited += 1;
+                if visited > self.cluster_count {
+                    return Err(invalid("image", "its directories are on a loop"));
+                }
+            }
+            dirs = next;
+        }
+
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DirEntry {
+    /// The long name, if it has one; the short (`8.3`) one, otherwise.
+    name: String,
+    is_dir: bool,
+    cluster: u32,
+    size: u32,
+}
+
+fn parse_dir(bytes: &[u8]) -> Vec<DirEntry> {
+    let mut entries = Vec::new();
+    let mut long_name = Vec::new();
+
+    for raw in bytes.chunks_exact(32) {
+        let attributes = raw[11];
+
+        match raw[0] {
+            0x00 => break,
+            0xE5 => {
+                long_name.clear();
+                continue;
+            }
+            _ => {}
+        }
+        if attributes & ATTR_LONG_NAME == ATTR_LONG_NAME {
+            // the parts of a long name come in reverse order, before its short entry.
+            let units = [1..11, 14..26, 28..32]
+                .into_iter()
+                .flat_map(|range| raw[range].chunks_exact(2).map(|u| u16_at(u, 0)))
+                .take_while(|&u| u != 0 && u != 0xFFFF);
+            let mut part: Vec<_> = units.collect();
+            part.append(&mut long_name);
+            long_name = part;
+            continue;
+        }
+        if attributes & ATTR_VOLUME_ID != 0 {
+            long_name.clear();
+            continue;
+        }
+
+        let name = if long_name.is_empty() {
+            let base = String::from_utf8_lossy(&raw[..8]).trim_end().to_owned();
+            let extension = String::from_utf8_lossy(&raw[8..11]).trim_end().to_owned();
+            if extension.is_empty() {
+                base
+            } else {
+                format!("{base}.{extension}")
+            }
+        } else {
+            String::from_utf16_lossy(&long_name)
+        };
+        long_name.clear();
+
+        entries.push(DirEntry {
+            name,
+            is_dir: attributes & ATTR_DIRECTORY != 0,
+            cluster: u32::from(u16_at(raw, 20)) << 16 | u32::from(u16_at(raw, 26)),
+            size: u32_at(raw, 28),
+        });
+    }
+
+    entries
+}
+
+/// Reads the `packfile.dat` out of a raw FAT image; e.g. a dump of the game card. The image can
+/// be a single volume, or a disk with a master boot record, whose first FAT partition is read.
+///
+/// # Errors
+///
+/// If `reader` is not a FAT image, or if there is no archive on it.
+pub fn from_image<R: Read + Seek>(reader: R) -> Result<Vec<u8>> {
+    FatVolume::new(reader)?
+        .find_packfile()?
+        .ok_or_else(|| invalid("image", format!("there is no `{PACKFILE_NAME}` on it")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::{Cursor, Write};
+
+    /// An archive that takes three clusters; it is only looked for, not parsed.
+    fn packfile() -> Vec<u8> {
+        let mut bytes = b"PMAN".to_vec();
+        bytes.extend((0..1200u32).map(|i| (i % 251) as u8));
+        bytes
+    }
+
+    /// A `.sis` file, for EPOC release 6, that installs `README.txt` and `packfile.dat`.
+    fn sis() -> eyre::Result<Vec<u8>> {
+        let names = ["README.txt", "!:\\system\\apps\\Ashen\\packfile.dat"];
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&packfile())?;
+        let files = [b"read me".to_vec(), encoder.finish()?];
+
+        let mut header = vec![0; 0x60];
+        header[4..8].copy_from_slice(&SIS_UID2_ER6.to_le_bytes());
+        header[8..12].copy_from_slice(&SIS_UID3.to_le_bytes());
+        header[0x12..0x14].copy_from_slice(&1u16.to_le_bytes());
+        header[0x14..0x16].copy_from_slice(&3u16.to_le_bytes());
+        header[0x34..0x38].copy_from_slice(&0x60u32.to_le_bytes());
+
+        // an `endif`, and a record for each file.
+        let mut records = 6u32.to_le_bytes().to_vec();
+        let mut data = Vec::new();
+        let data_offset = 0x60 + 4 + 2 * 48;
+        for (name, file) in names.iter().zip(&files) {
+            let name_offset = data_offset + data.len();
+            data.extend_from_slice(name.as_bytes());
+            let file_offset = data_offset + data.len();
+            data.extend_from_slice(file);
+
+            let fields = [
+                0,
+                0,
+                0,
+                name.len(),
+                name_offset,
+                name.len(),
+                name_offset,
+                file.len(),
+                file_offset,
+                0,
+                0,
+                0,
+            ];
+            for field in fields {
+                records.extend_from_slice(&u32::try_from(field)?.to_le_bytes());
+            }
+        }
+
+        Ok([header, records, data].concat())
+    }
+
+    /// A FAT12 volume, with `SYSTEM\APPS\Ashen\packfile.dat`; the archive takes three clusters,
+    /// out of order.
+    fn fat_image() -> Vec<u8> {
+        const SECTOR: usize = 512;
+        let mut image = vec![0; 64 * SECTOR];
+
+        // 1 reserved sector, 1 FAT, 16 root entries, 1 sector per cluster.
+        image[0] = 0xEB;
+        image[11..13].copy_from_slice(&512u16.to_le_bytes());
+        image[13] = 1;
+        image[14..16].copy_from_slice(&1u16.to_le_bytes());
+        image[16] = 1;
+        image[17..19].copy_from_slice(&16u16.to_le_bytes());
+        image[19..21].copy_from_slice(&64u16.to_le_bytes());
+        image[22..24].copy_from_slice(&1u16.to_le_bytes());
+
+        let set_fat = |image: &mut Vec<u8>, cluster: usize, value: u16| {
+            let offset = SECTOR + cluster * 3 / 2;
+            let mut entry = u16_at(image, offset);
+            entry = if cluster % 2 == 1 {
+                entry & 0x000F | value << 4
+            } else {
+                entry & 0xF000 | value
+            };
+            image[offset..offset + 2].copy_from_slice(&entry.to_le_bytes());
+        };
+        let entry = |name: &[u8; 11], attributes: u8, cluster: u16, size: u32| {
+            let mut entry = [0; 32];
+            entry[..11].copy_from_slice(name);
+            entry[11] = attributes;
+            entry[26..28].copy_from_slice(&cluster.to_le_bytes());
+            entry[28..32].copy_from_slice(&size.to_le_bytes());
+            entry
+        };
+        let long_name = |name: &str| {
+            let mut entry = [0xFF; 32];
+            let units: Vec<_> = name.encode_utf16().chain([0]).collect();
+            let ranges = [1..11, 14..26, 28..32];
+            let mut units = units.iter();
+            for range in ranges {
+                for unit in entry[range].chunks_exact_mut(2) {
+                    if let Some(u) = units.next() {
+                        unit.copy_from_slice(&u.to_le_bytes());
+                    }
+                }
+            }
+            entry[0] = 0x41;
+            entry[11] = ATTR_LONG_NAME;
+            entry[12..14].fill(0);
+            entry[26..28].fill(0);
+            entry
+        };
+        // the root directory is on sector 2, and cluster 2 on sector 3.
+        let cluster = |cluster: usize| (cluster + 1) * SECTOR;
+
+        let root = 2 * SECTOR;
+        image[root..root + 32].copy_from_slice(&entry(b"CARD       ", ATTR_VOLUME_ID, 0, 0));
+        image[root + 32..root + 64].copy_from_slice(&entry(b"README  TXT", 0, 0, 0));
+        image[root + 64..root + 96].copy_from_slice(&entry(b"SYSTEM     ", ATTR_DIRECTORY, 2, 0));
+        let dir = cluster(2);
+        image[dir..dir + 32].copy_from_slice(&entry(b".          ", ATTR_DIRECTORY, 2, 0));
+        image[dir + 32..dir + 64].copy_from_slice(&entry(b"APPS       ", ATTR_DIRECTORY, 3, 0));
+        let dir = cluster(3);
+        image[dir..dir + 32].copy_from_slice(&long_name("Ashen"));
+        image[dir + 32..dir + 64].copy_from_slice(&entry(b"ASHEN      ", ATTR_DIRECTORY, 4, 0));
+        let dir = cluster(4);
+        let packfile = packfile();
+        let size = u32::try_from(packfile.len()).expect("it is small.");
+        image[dir..dir + 32].copy_from_slice(&long_name("packfile.dat"));
+        image[dir + 32..dir + 64].copy_from_slice(&entry(b"PACKFILEDAT", 0, 7, size));
+
+        for (cluster, next) in [
+            (2, 0xFFF),
+            (3, 0xFFF),
+            (4, 0xFFF),
+            (7, 5),
+            (5, 6),
+            (6, 0xFFF),
+        ] {
+            set_fat(&mut image, cluster, next);
+        }
+        for (at, chunk) in [7, 5, 6].into_iter().zip(packfile.chunks(SECTOR)) {
+            image[cluster(at)..cluster(at) + chunk.len()].copy_from_slice(chunk);
+        }
+
+        image
+    }
+
+    #[test]
+    fn from_sis_test() -> eyre::Result<()> {
+        let sis = sis()?;
+        assert_eq!(from_sis(&sis)?, packfile());
+
+        let mut broken = sis.clone();
+        broken[8] = 0;
+        assert!(from_sis(&broken).is_err());
+        // only `README.txt` is left.
+        broken = sis;
+        broken[0x14] = 2;
+        assert!(from_sis(&broken).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_image_test() -> eyre::Result<()> {
+        let image = fat_image();
+        let mut volume = FatVolume::new(Cursor::new(&image))?;
+        assert_eq!(volume.fat_type, FatType::Fat12);
+        assert_eq!(volume.chain(7)?, [7, 5, 6]);
+
+        let bytes = from_image(Cursor::new(&image))?;
+        assert_eq!(bytes, packfile());
+
+        // the same volume, on the first partition of a disk.
+        let mut disk = vec![0; 8 * 512];
+        disk[446 + 4] = 0x01;
+        disk[446 + 8..446 + 12].copy_from_slice(&8u32.to_le_bytes());
+        disk[510..512].copy_from_slice(&[0x55, 0xAA]);
+        disk.extend_from_slice(&image);
+        assert_eq!(from_image(Cursor::new(&disk))?, packfile());
+
+        assert!(from_image(Cursor::new(vec![0; 1024])).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+//! Annotations of the entries of an archive, that are kept next to it on a sidecar file; see
+//! [`Meta`].
+//!
+//! ```toml
+//! [1a2b3c4d-36540]
+//! name = "english text"
+//! type = "text"
+//! notes = "the strings of the menus come first"
+//! ```
+//!
+//! Entries are keyed by the hash of their data (see [`ContentHash`]), not by their index; so the
+//! annotations follow an entry across archives (e.g. the releases of the game), and across repacks.
+
+pub use crate::format::pman::ContentHash;
+use crate::{
+    error::{Error, Result},
+    format::{detect::FileType, pman::PmanFile},
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The name of the sidecar file; it is next to the archive, or inside of an extraction.
+pub const SIDECAR: &str = ".rashen-meta.toml";
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format: "metadata",
+        reason: reason.into(),
+    }
+}
+
+/// What is known about an entry.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Annotation {
+    pub name: Option<String>,
+    pub notes: Option<String>,
+    /// The type that the entry was confirmed to be; it wins over the detected one (see
+    /// [`PmanFileData::detect_type`]).
+    pub file_type: Option<FileType>,
+}
+
+impl Annotation {
+    fn from_toml(value: &toml::Value) -> Result<Self> {
+        let table = value
+            .as_table()
+            .ok_or_else(|| invalid("an entry is not a table"))?;
+        let string = |key: &str| match table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(invalid(format!("`{key}` is not a string"))),
+        };
+
+        if let Some(key) = table
+            .keys()
+            .find(|key| !["name", "notes", "type"].contains(&key.as_str()))
+        {
+            return Err(invalid(format!("unknown key `{key}`")));
+        }
+        let file_type = string("type")?
+            .map(|file_type| {
+                FileType::from_extension(&file_type)
+                    .ok_or_else(|| invalid(format!("unknown type `{file_type}`")))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            name: string("name")?,
+            notes: string("notes")?,
+            file_type,
+        })
+    }
+
+    fn to_toml(&self) -> toml::Table {
+        [
+            ("name", self.name.as_deref()),
+            ("type", self.file_type.map(FileType::extension)),
+            ("notes", self.notes.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_owned(), value?.to_owned().into())))
+        .collect()
+    }
+}
+
+/// The annotations of a sidecar file, by the hash of the entry that they are about.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Meta {
+    pub entries: BTreeMap<ContentHash, Annotation>,
+}
+
+impl Meta {
+    /// Where the sidecar of the archive at `archive` is; next to it.
+    #[must_use]
+    pub fn sidecar(archive: &Path) -> PathBuf {
+        archive.with_file_name(SIDECAR)
+    }
+
+    /// Parses the contents of a sidecar file.
+    ///
+    /// # Errors
+    ///
+    /// If `text` is not valid TOML, or if an entry has an invalid key, or unknown fields.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let table: toml::Table = text
+            .parse()
+            .map_err(|err| invalid(format!("not valid TOML; {err}")))?;
+        let entries = table
+            .iter()
+            .map(|(hash, value)| Ok((hash.parse()?, Annotation::from_toml(value)?)))
+            .collect::<Result<_>>()?;
+
+        Ok(Self { entries })
+    }
+
+    /// The contents of a sidecar file; entries are sorted by their hash.
+    #[must_use]
+    pub fn to_toml(&self) -> String {
+        let table: toml::Table = self
+            .entries
+            .iter()
+            .map(|(hash, annotation)| (hash.to_string(), annotation.to_toml().into()))
+            .collect();
+
+        table.to_string()
+    }
+
+    /// Reads the sidecar file at `path`; it is empty if there is no such file.
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read, or if it is not valid; see [`Self::from_toml`].
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::from_toml(&text),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes the sidecar file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be written.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(fs::write(path, self.to_toml())?)
+    }
+
+    /// The annotation of every entry of `pman`, by index.
+    ///
+    /// # Errors
+    ///
+    /// If an entry can't be decompressed; see [`ContentHash::of_file`].
+    pub fn annotations(&self, pman: &PmanFile) -> Result<Vec<Option<&Annotation>>> {
+        pman.files()
+            .iter()
+            .map(|file| Ok(self.entries.get(&ContentHash::of_file(file)?)))
+            .collect()
+    }
+
+    /// Only the annotations of the entries of `pman`; e.g. for an extraction of it.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::annotations`].
+    pub fn subset(&self, pman: &PmanFile) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+        for file in pman.files() {
+            let hash = ContentHash::of_file(file)?;
+
+            if let Some(annotation) = self.entries.get(&hash) {
+                entries.insert(hash, annotation.clone());
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Keeps the annotations of the entries that changed from `before` to `after` (e.g. that a mod
+    /// replaced); they are copied to the hash of the new data, unless it already has its own. The
+    /// ones of the old data are kept too, for the archives that still have it.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::annotations`].
+    pub fn preserve(&mut self, before: &PmanFile, after: &PmanFile) -> Result<()> {
+        for (old, new) in before.files().iter().zip(after.files()) {
+            if old.bytes() == new.bytes() {
+                continue;
+            }
+            let Some(annotation) = self.entries.get(&ContentHash::of_file(old)?) else {
+                continue;
+            };
+
+            let annotation = annotation.clone();
+            self.entries
+                .entry(ContentHash::of_file(new)?)
+                .or_insert(annotation);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, fixture, format::pman};
+
+    #[test]
+    fn meta_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(&fixture::archive())?;
+        let text = ContentHash::of(fixture::TEXT);
+        let meta = Meta::from_toml(&format!(
+            "[{text}]\nname = \"intro\"\ntype = \"text\"\nnotes = \"\\\"quoted\\\"\"\n"
+        ))?;
+        let intro = Annotation {
+            name: Some("intro".to_owned()),
+            notes: Some("\"quoted\"".to_owned()),
+            file_type: Some(FileType::Text),
+        };
+
+        assert_eq!(meta.entries.get(&text), Some(&intro));
+        assert_eq!(Meta::from_toml(&meta.to_toml())?, meta);
+        // by the decompressed data; so it doesn't matter how it is compressed.
+        assert_eq!(ContentHash::of_file(&pman[fixture::ZLIB])?, text);
+
+        let annotations = meta.annotations(&pman)?;
+        assert_eq!(annotations[fixture::ZLIB], Some(&intro));
+        assert_eq!(annotations[fixture::RAW], None);
+        assert_eq!(meta.subset(&pman)?, meta);
+
+        // a mod replaces the entry; the annotation follows it.
+        let before = pman.clone();
+        pman.files_mut()[fixture::ZLIB].set_bytes(pman::compress(b"modded")?);
+        let mut preserved = meta.clone();
+        preserved.preserve(&before, &pman)?;
+        assert_eq!(
+            preserved.entries.get(&ContentHash::of(b"modded")),
+            Some(&intro)
+        );
+        assert_eq!(preserved.entries.get(&text), Some(&intro));
+
+        for invalid in [
+            "[nothex-5]\nname = \"bad hash\"",
+            &format!("[{text}]\ntype = \"mp3\""),
+            &format!("[{text}]\nauthor = \"unknown key\""),
+            "not toml",
+        ] {
+            let err = Meta::from_toml(invalid).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidImport, "{invalid}");
+        }
+        let missing = std::env::temp_dir().join(format!("rashen-{}-{SIDECAR}", std::process::id()));
+        assert_eq!(Meta::read(missing)?, Meta::default());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+//! Converters from the formats that other tools understand back into the formats of the game; the
+//! reverse of [`export`](crate::export).
+
+#[cfg(feature = "import-image")]
+pub mod image;
+pub mod install;
+pub mod meta;
+pub mod model;
+pub mod overlay;
+pub mod palette;
+pub mod text;
+pub mod wav;
@@ -0,0 +1,758 @@
+//! OBJ and binary glTF import for models; the reverse of [`export::obj`](crate::export::obj) and
+//! [`export::gltf`](crate::export::gltf).
+//!
+//! Meshes are read in the space of the game (`Z` up, like the exports); node transforms of glTF
+//! files are ignored. The texture of the model that is replaced is kept, so texture coordinates
+//! must be inside of it.
+
+use crate::{
+    error::{Error, Result},
+    format::{
+        animation::Animation,
+        model::{Face, Frame, Model},
+        pman::{self, PmanFile},
+    },
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The scale of the 16.16 fixed point components of [`Frame::scale`] and [`Frame::translate`].
+const FIXED_SCALE: f32 = 65_536.0;
+/// How far out of `0..=1` a texture coordinate can be, and still be clamped into it.
+const UV_EPSILON: f32 = 1.0 / 1024.0;
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const JSON_CHUNK: &[u8; 4] = b"JSON";
+const BIN_CHUNK: &[u8; 4] = b"BIN\0";
+const TRIANGLES: u64 = 4;
+
+/// A mesh that was read out of another format, before it is fitted into a [`Model`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mesh {
+    /// The position of every vertex, on every frame; static meshes have a single frame.
+    pub frames: Vec<Vec<[f32; 3]>>,
+    /// The vertices of every triangle, and their texture coordinates; from `0` to `1`, with the
+    /// origin on the top left corner of the texture.
+    pub faces: Vec<([usize; 3], [[f32; 2]; 3])>,
+    /// The frames that every animation plays; see [`Animation`].
+    pub animations: Vec<Vec<u32>>,
+}
+
+fn invalid(format: &'static str, reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format,
+        reason: reason.into(),
+    }
+}
+
+/// Resolves a (1-based, or negative and relative to the end) OBJ index into a list of `len` items.
+fn obj_index(index: &str, len: usize, line: usize) -> Result<usize> {
+    let error = || {
+        invalid(
+            "OBJ",
+            format!("line {}: `{index}` is not an index", line + 1),
+        )
+    };
+    let index: isize = index.parse().map_err(|_| error())?;
+
+    let resolved = match index {
+        1.. => index.unsigned_abs() - 1,
+        ..=-1 => len.checked_sub(index.unsigned_abs()).ok_or_else(error)?,
+        0 => return Err(error()),
+    };
+
+    if resolved < len {
+        Ok(resolved)
+    } else {
+        Err(error())
+    }
+}
+
+/// Reads the numbers of an OBJ statement; e.g. `v 1 2 3`.
+fn obj_numbers<const N: usize>(args: &[&str], line: usize) -> Result<[f32; N]> {
+    let numbers = args
+        .iter()
+        .take(N)
+        .map(|x| x.parse().ok())
+        .collect::<Option<Vec<_>>>()
+        .and_then(|numbers| numbers.try_into().ok());
+
+    numbers.ok_or_else(|| invalid("OBJ", format!("line {}: expected {N} numbers", line + 1)))
+}
+
+/// Reads the vertices, the texture coordinates and the faces of an OBJ file; polygons are split
+/// into triangles.
+///
+/// # Errors
+///
+/// If a statement can't be read, if a face doesn't have texture coordinates, or if the faces use
+/// more than one material; models only have one texture.
+pub fn obj_to_mesh(obj: &str) -> Result<Mesh> {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut faces = Vec::new();
+    let mut material = None;
+
+    for (line, statement) in obj.lines().enumerate() {
+        let mut args = statement.split_whitespace();
+        let Some(keyword) = args.next() else {
+            continue;
+        };
+        let args: Vec<_> = args.collect();
+
+        match keyword {
+            "v" => positions.push(obj_numbers::<3>(&args, line)?),
+            "vt" => {
+                let [u, v] = obj_numbers::<2>(&args, line)?;
+                // OBJ puts the origin of the texture on its bottom left corner.
+                uvs.push([u, 1.0 - v]);
+            }
+            "f" => {
+                let corners = args
+                    .iter()
+                    .map(|corner| {
+                        let mut parts = corner.split('/');
+                        let vertex =
+                            obj_index(parts.next().unwrap_or_default(), positions.len(), line)?;
+                        let uv = match parts.next() {
+                            Some(uv) if !uv.is_empty() => obj_index(uv, uvs.len(), line)?,
+                            _ => {
+                                return Err(invalid(
+                                    "OBJ",
+                                    format!(
+                                        "line {}: a face doesn't have texture coordinates",
+                                        line + 1
+                                    ),
+                                ))
+                            }
+                        };
+
+                        Ok((vertex, uvs[uv]))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                if corners.len() < 3 {
+                    return Err(invalid(
+                        "OBJ",
+                        format!("line {}: a face needs 3 vertices", line + 1),
+                    ));
+                }
+                for i in 1..corners.len() - 1 {
+                    let [a, b, c] = [corners[0], corners[i], corners[i + 1]];
+                    faces.push(([a.0, b.0, c.0], [a.1, b.1, c.1]));
+                }
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                if material.as_ref().is_some_and(|material| *material != name) {
+                    return Err(invalid(
+                        "OBJ",
+                        "models only have one texture, but it uses many materials",
+                    ));
+                }
+                material = Some(name);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Mesh {
+        frames: vec![positions],
+        faces,
+        animations: Vec::new(),
+    })
+}
+
+/// The JSON and the binary chunks of a `.glb` file.
+fn glb_chunks(glb: &[u8]) -> Result<(Value, &[u8])> {
+    let u32_at = |offset: usize| {
+        glb.get(offset..offset + 4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(|bytes| u32::from_le_bytes(bytes) as usize)
+    };
+    let chunk = |offset: usize, kind: &[u8; 4]| {
+        let len = u32_at(offset)?;
+        (glb.get(offset + 4..offset + 8)? == kind)
+            .then(|| glb.get(offset + 8..offset + 8 + len))
+            .flatten()
+    };
+
+    if !glb.starts_with(GLB_MAGIC) {
+        return Err(invalid("glTF", "it is not a binary glTF (`.glb`) file"));
+    }
+
+    let json_chunk =
+        chunk(12, JSON_CHUNK).ok_or_else(|| invalid("glTF", "there is no JSON chunk"))?;
+    let json =
+        serde_json::from_slice(json_chunk).map_err(|err| invalid("glTF", err.to_string()))?;
+    // the binary chunk is optional, and it is right after the JSON one.
+    let bin = chunk(12 + 8 + json_chunk.len(), BIN_CHUNK).unwrap_or_default();
+
+    Ok((json, bin))
+}
+
+/// A JSON number as an `usize`, if it is one.
+fn json_usize(value: &Value) -> Option<usize> {
+    value.as_u64().and_then(|x| usize::try_from(x).ok())
+}
+
+/// Reads the items of an accessor, as floats; integer components are not normalized.
+fn read_accessor(json: &Value, bin: &[u8], index: &Value) -> Result<Vec<Vec<f32>>> {
+    let error = |reason: String| invalid("glTF", reason);
+    let accessor = &json["accessors"][json_usize(index).unwrap_or(usize::MAX)];
+    let view = &json["bufferViews"][json_usize(&accessor["bufferView"]).unwrap_or(usize::MAX)];
+
+    if view.is_null() || accessor["sparse"].is_object() {
+        return Err(error(format!(
+            "accessor {index} must have a buffer view, and no sparse values"
+        )));
+    }
+    if view["buffer"].as_u64().unwrap_or_default() != 0 {
+        return Err(error(
+            "only the buffer of the `.glb` file can be read".into(),
+        ));
+    }
+
+    let components = match accessor["type"].as_str() {
+        Some("SCALAR") => 1,
+        Some("VEC2") => 2,
+        Some("VEC3") => 3,
+        Some("VEC4") => 4,
+        other => {
+            return Err(error(format!(
+                "accessors of type {other:?} are not supported"
+            )))
+        }
+    };
+    let size = match accessor["componentType"].as_u64() {
+        Some(5121) => 1,
+        Some(5123) => 2,
+        Some(5125 | 5126) => 4,
+        other => {
+            return Err(error(format!(
+                "components of type {other:?} are not supported"
+            )))
+        }
+    };
+    let count = json_usize(&accessor["count"]).unwrap_or_default();
+    let offset = json_usize(&view["byteOffset"]).unwrap_or_default()
+        + json_usize(&accessor["byteOffset"]).unwrap_or_default();
+    let stride = json_usize(&view["byteStride"]).unwrap_or(components * size);
+
+    (0..count)
+        .map(|item| {
+            (0..components)
+                .map(|component| {
+                    let start = offset + item * stride + component * size;
+                    let bytes = bin
+                        .get(start..start + size)
+                        .ok_or_else(|| error(format!("accessor {index} is out of the buffer")))?;
+
+                    #[allow(clippy::cast_precision_loss)]
+                    Ok(match accessor["componentType"].as_u64() {
+                        Some(5121) => f32::from(bytes[0]),
+                        Some(5123) => f32::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+                        Some(5125) => {
+                            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32
+                        }
+                        _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Reads the first mesh of a `.glb` file; the layout of
+/// [`model_to_glb`](crate::export::gltf::model_to_glb).
+///
+/// The mesh must have a single primitive of triangles, with texture coordinates. Every morph
+/// target is a frame (with the base pose if there are none), and every animation of the morph
+/// weights is an animation that plays, at each keyframe, the target with the highest weight.
+/// Corners with the same position on every frame are merged into a single vertex.
+///
+/// # Errors
+///
+/// If `glb` is not a valid `.glb` file, or if its mesh doesn't have the layout above.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn glb_to_mesh(glb: &[u8]) -> Result<Mesh> {
+    let (json, bin) = glb_chunks(glb)?;
+    let primitives = json["meshes"][0]["primitives"]
+        .as_array()
+        .ok_or_else(|| invalid("glTF", "there is no mesh"))?;
+    let [primitive] = primitives.as_slice() else {
+        return Err(invalid(
+            "glTF",
+            "models only have one texture, but the mesh has many primitives",
+        ));
+    };
+    if primitive["mode"].as_u64().unwrap_or(TRIANGLES) != TRIANGLES {
+        return Err(invalid("glTF", "the mesh is not made of triangles"));
+    }
+
+    let attributes = &primitive["attributes"];
+    let to_vec3 = |items: Vec<Vec<f32>>| -> Vec<[f32; 3]> {
+        items
+            .iter()
+            .map(|v| std::array::from_fn(|i| v.get(i).copied().unwrap_or_default()))
+            .collect()
+    };
+    let base = to_vec3(read_accessor(&json, bin, &attributes["POSITION"])?);
+    if attributes["TEXCOORD_0"].is_null() {
+        return Err(invalid("glTF", "the mesh doesn't have texture coordinates"));
+    }
+    let uvs: Vec<[f32; 2]> = read_accessor(&json, bin, &attributes["TEXCOORD_0"])?
+        .iter()
+        .map(|uv| {
+            [
+                uv.first().copied().unwrap_or_default(),
+                uv.get(1).copied().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let corners: Vec<usize> = if primitive["indices"].is_null() {
+        (0..base.len()).collect()
+    } else {
+        read_accessor(&json, bin, &primitive["indices"])?
+            .iter()
+            .map(|i| i[0] as usize)
+            .collect()
+    };
+
+    let targets = primitive["targets"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let frames = if targets.is_empty() {
+        vec![base.clone()]
+    } else {
+        targets
+            .iter()
+            .map(|target| {
+                let delta = to_vec3(read_accessor(&json, bin, &target["POSITION"])?);
+
+                Ok(base
+                    .iter()
+                    .zip(delta.iter().chain(std::iter::repeat(&[0.0; 3])))
+                    .map(|(b, d)| std::array::from_fn(|i| b[i] + d[i]))
+                    .collect())
+            })
+            .collect::<Result<Vec<Vec<[f32; 3]>>>>()?
+    };
+
+    let (frames, faces) = weld(&frames, &corners, &uvs)?;
+
+    Ok(Mesh {
+        frames,
+        faces,
+        animations: read_animations(&json, bin, targets.len()),
+    })
+}
+
+/// The triangles of the `corners` of a glTF mesh, with their texture coordinates, and the frames
+/// of the vertices that they use; corners with the same position on every frame are merged into a
+/// single vertex.
+#[allow(clippy::type_complexity)]
+fn weld(
+    frames: &[Vec<[f32; 3]>],
+    corners: &[usize],
+    uvs: &[[f32; 2]],
+) -> Result<(Vec<Vec<[f32; 3]>>, Vec<([usize; 3], [[f32; 2]; 3])>)> {
+    let len = frames.first().map_or(0, Vec::len).min(uvs.len());
+    let mut welded = HashMap::new();
+    let mut vertices = Vec::new();
+    let mut vertex_of = |corner: usize| {
+        let key: Vec<_> = frames
+            .iter()
+            .flat_map(|frame| frame[corner].map(f32::to_bits))
+            .collect();
+
+        *welded.entry(key).or_insert_with(|| {
+            vertices.push(corner);
+            vertices.len() - 1
+        })
+    };
+
+    let mut faces = Vec::with_capacity(corners.len() / 3);
+    for triangle in corners.chunks_exact(3) {
+        if let Some(&corner) = triangle.iter().find(|&&c| c >= len) {
+            return Err(invalid(
+                "glTF",
+                format!("index {corner} is out of the vertices"),
+            ));
+        }
+
+        faces.push((
+            [0, 1, 2].map(|i| vertex_of(triangle[i])),
+            [0, 1, 2].map(|i| uvs[triangle[i]]),
+        ));
+    }
+
+    let frames = frames
+        .iter()
+        .map(|frame| vertices.iter().map(|&corner| frame[corner]).collect())
+        .collect();
+
+    Ok((frames, faces))
+}
+
+/// The frames that every animation of the morph weights of a glTF file plays; at each keyframe,
+/// the target with the highest weight. Animations that can't be read are skipped.
+fn read_animations(json: &Value, bin: &[u8], target_count: usize) -> Vec<Vec<u32>> {
+    json["animations"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|animation| {
+            let channel = animation["channels"]
+                .as_array()?
+                .iter()
+                .find(|channel| channel["target"]["path"] == "weights")?;
+            let sampler = &animation["samplers"][json_usize(&channel["sampler"])?];
+            let weights = read_accessor(json, bin, &sampler["output"]).ok()?;
+            let weights: Vec<_> = weights.iter().map(|w| w[0]).collect();
+
+            let frames = weights
+                .chunks(target_count.max(1))
+                .filter_map(|weights| {
+                    let (frame, _) = weights
+                        .iter()
+                        .enumerate()
+                        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+                    u32::try_from(frame).ok()
+                })
+                .collect();
+
+            Some(frames)
+        })
+        .collect()
+}
+
+/// Quantizes `positions` into a frame, with the scale and the translation that fit its bounds.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn quantize_frame(positions: &[[f32; 3]], unknown: u32, face_count: usize) -> Frame {
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for position in positions {
+        for i in 0..3 {
+            min[i] = min[i].min(position[i]);
+            max[i] = max[i].max(position[i]);
+        }
+    }
+
+    // the largest byte, `255`, is `255 / 256` of the scale away from the translation.
+    let scale: [f32; 3] = std::array::from_fn(|i| (max[i] - min[i]) * 256.0 / 255.0);
+    let vertices = positions
+        .iter()
+        .map(|position| {
+            let mut vertex = [0; 4];
+            for i in 0..3 {
+                if scale[i] > 0.0 {
+                    let byte = (position[i] - min[i]) / scale[i] * 256.0;
+                    vertex[i] = byte.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+            vertex
+        })
+        .collect();
+
+    Frame {
+        scale: scale.map(|x| (x * FIXED_SCALE).round() as i32),
+        translate: min.map(|x| (x * FIXED_SCALE).round() as i32),
+        unknown,
+        vertices,
+        face_data: vec![0; face_count],
+    }
+}
+
+/// Fits `mesh` into a model that replaces `original`; the texture of `original` is kept.
+///
+/// Every frame is quantized into the bytes of [`Frame::vertices`], with the scale and the
+/// translation that fit its bounds. Meshes without animations get a single one, that plays every
+/// frame. The unknown fields of the frames are copied from `original`, or zeroed if it doesn't
+/// have as many frames.
+///
+/// # Errors
+///
+/// If the mesh has no frames, if its frames don't have the same vertex count, if it has more than
+/// `u16::MAX` vertices, if a face points out of the vertices, if a texture coordinate is out of
+/// the texture, or if an animation plays a frame that doesn't exist.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub fn mesh_to_model(mesh: &Mesh, original: &Model) -> Result<Model> {
+    let error = |reason: String| Error::InvalidModel { reason };
+    let vertex_count = mesh.frames.first().map_or(0, Vec::len);
+
+    if mesh.frames.is_empty() || vertex_count == 0 {
+        return Err(error("the mesh doesn't have any vertices".into()));
+    }
+    if vertex_count > usize::from(u16::MAX) {
+        return Err(error(format!(
+            "the mesh has {vertex_count} vertices, but a model can't have more than {}",
+            u16::MAX
+        )));
+    }
+    if let Some(index) = mesh.frames.iter().position(|f| f.len() != vertex_count) {
+        return Err(error(format!(
+            "frame {index} doesn't have {vertex_count} vertices, like frame 0"
+        )));
+    }
+
+    let (width, height) = (original.texture_width, original.texture_height);
+    let out_of_texture = |x: &f32| !(-UV_EPSILON..=1.0 + UV_EPSILON).contains(x);
+    let faces = mesh
+        .faces
+        .iter()
+        .enumerate()
+        .map(|(index, (vertices, uvs))| {
+            if let Some(vertex) = vertices.iter().find(|&&v| v >= vertex_count) {
+                let reason = format!("face {index} uses vertex {vertex}, of {vertex_count}");
+                return Err(error(reason));
+            }
+            if let Some([u, v]) = uvs.iter().find(|uv| uv.iter().any(out_of_texture)) {
+                let reason = format!("face {index} has the texture coordinates ({u}, {v})");
+                return Err(error(format!("{reason}, which are out of the texture")));
+            }
+
+            let texel = |x: f32, size: u32| (x.clamp(0.0, 1.0) * size as f32).round() as u16;
+
+            Ok(Face {
+                vertices: vertices.map(|v| v as u16),
+                uvs: uvs.map(|[u, v]| [texel(u, width), texel(v, height)]),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let frames: Vec<_> = mesh
+        .frames
+        .iter()
+        .enumerate()
+        .map(|(index, positions)| {
+            let unknown = original.frames.get(index).map_or(0, |frame| frame.unknown);
+
+            quantize_frame(positions, unknown, faces.len())
+        })
+        .collect();
+
+    let animations = if mesh.animations.is_empty() {
+        vec![Animation {
+            frames: (0..frames.len() as u32).collect(),
+        }]
+    } else {
+        mesh.animations
+            .iter()
+            .map(|frames| Animation {
+                frames: frames.clone(),
+            })
+            .collect()
+    };
+    if let Some((index, frame)) = animations
+        .iter()
+        .enumerate()
+        .find_map(|(index, animation)| {
+            animation
+                .frames
+                .iter()
+                .find(|&&f| f as usize >= frames.len())
+                .map(|f| (index, f))
+        })
+    {
+        return Err(error(format!(
+            "animation {index} plays frame {frame}, but there are {}",
+            frames.len()
+        )));
+    }
+
+    Ok(Model {
+        faces,
+        texture_width: width,
+        texture_height: height,
+        texture: original.texture.clone(),
+        frames,
+        animations,
+    })
+}
+
+/// Replaces the model entry at `index` of `pman` with `mesh`; see [`mesh_to_model`].
+///
+/// The entry is compressed again if it was.
+///
+/// # Errors
+///
+/// If there is no entry at `index`, if it is not a valid model, or if the mesh can't be fitted
+/// into one; see [`mesh_to_model`].
+pub fn import_model(pman: &mut PmanFile, index: usize, mesh: &Mesh) -> Result<()> {
+    let file = pman
+        .files()
+        .get(index)
+        .ok_or_else(|| Error::InvalidImport {
+            format: "model",
+            reason: format!("there is no entry {index}"),
+        })?;
+    let zlib = file.to_zlib()?;
+    let original = Model::new(zlib.as_deref().unwrap_or(file.bytes()))?;
+
+    let bytes = mesh_to_model(mesh, &original)?.to_bytes()?;
+    let bytes = if zlib.is_some() {
+        pman::compress(&bytes)?
+    } else {
+        bytes
+    };
+
+    pman.files_mut()[index].set_bytes(bytes);
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+    use crate::{
+        error::ErrorKind,
+        export::{gltf::model_to_glb, obj::model_to_obj},
+    };
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    fn model(pman: &PmanFile, index: usize) -> eyre::Result<Model> {
+        let bytes = pman[index].to_zlib()?.expect("models are compressed.");
+
+        Ok(Model::new(&bytes)?)
+    }
+
+    /// The position of every corner of every face, on `frame`.
+    fn corners(model: &Model, frame: usize) -> Vec<[f32; 3]> {
+        let positions = model.frames[frame].positions();
+
+        model
+            .faces
+            .iter()
+            .flat_map(|face| face.vertices.map(|v| positions[usize::from(v)]))
+            .collect()
+    }
+
+    /// The distance between 2 neighbouring bytes of `frame`, on its longest axis.
+    #[allow(clippy::cast_precision_loss)]
+    fn step(model: &Model, frame: usize) -> f32 {
+        let scale = model.frames[frame]
+            .scale
+            .into_iter()
+            .max()
+            .unwrap_or_default();
+
+        scale as f32 / FIXED_SCALE / 256.0
+    }
+
+    fn assert_close(a: &[[f32; 3]], b: &[[f32; 3]], tolerance: f32) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            for i in 0..3 {
+                assert!((a[i] - b[i]).abs() <= tolerance, "{a:?} {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn import_glb_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(INPUT)?;
+        let original = model(&pman, 10)?;
+        let mesh = glb_to_mesh(&model_to_glb(&original, "model"))?;
+
+        assert_eq!(mesh.frames.len(), original.frames.len());
+        assert_eq!(mesh.faces.len(), original.faces.len());
+        assert_eq!(mesh.animations.len(), original.animations.len());
+
+        import_model(&mut pman, 10, &mesh)?;
+        let imported = model(&pman, 10)?;
+
+        assert_eq!(imported.animations, original.animations);
+        assert_eq!(imported.texture, original.texture);
+        for (a, b) in imported.faces.iter().zip(&original.faces) {
+            assert_eq!(a.uvs, b.uvs);
+        }
+        for frame in [0, 100, 265] {
+            // both are quantized into bytes, but maybe with other bounds.
+            let tolerance = step(&original, frame);
+            assert_close(
+                &corners(&imported, frame),
+                &corners(&original, frame),
+                tolerance,
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_obj_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let original = model(&pman, 40)?;
+        let obj = model_to_obj(&original, 0, "model").expect("the model has a frame.");
+        let imported = mesh_to_model(&obj_to_mesh(&obj.obj)?, &original)?;
+
+        assert_eq!(imported.frames.len(), 1);
+        assert_eq!(imported.animations, [Animation { frames: vec![0] }]);
+        assert_eq!(imported.vertex_count(), original.vertex_count());
+        assert_eq!(imported.faces, original.faces);
+        assert_close(
+            &corners(&imported, 0),
+            &corners(&original, 0),
+            step(&original, 0),
+        );
+
+        // a quad is split into 2 triangles.
+        let quad = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 1\nf 1/1 2/1 3/2 4/2\n";
+        let mesh = obj_to_mesh(quad)?;
+        assert_eq!(mesh.faces.len(), 2);
+        assert_eq!(mesh.faces[1].0, [0, 2, 3]);
+        // the origin of the texture moves into its top left corner.
+        assert!(mesh.faces[0].1[0][1] > 0.99);
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_model_invalid_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+        let original = model(&pman, 40)?;
+
+        for obj in [
+            "v 0 0 0\nf 1 1 1\n",
+            "v 0 0 0\nvt 0 0\nf 1/1 2/1 1/1\n",
+            "v 0 0 0\nvt 0 0\nusemtl a\nf 1/1 1/1 1/1\nusemtl b\n",
+            "v 0 0\n",
+        ] {
+            assert_eq!(
+                obj_to_mesh(obj).unwrap_err().kind(),
+                ErrorKind::InvalidImport,
+                "{obj}"
+            );
+        }
+
+        let mesh = obj_to_mesh("v 0 0 0\nvt 1.5 0\nf 1/1 1/1 1/1\n")?;
+        assert_eq!(
+            mesh_to_model(&mesh, &original).unwrap_err().kind(),
+            ErrorKind::InvalidModel
+        );
+
+        let mesh = Mesh {
+            frames: vec![vec![[0.0; 3]; 3]],
+            animations: vec![vec![1]],
+            ..Default::default()
+        };
+        assert_eq!(
+            mesh_to_model(&mesh, &original).unwrap_err().kind(),
+            ErrorKind::InvalidModel
+        );
+
+        assert_eq!(
+            glb_to_mesh(b"OBJ").unwrap_err().kind(),
+            ErrorKind::InvalidImport
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,358 @@
+//! Mod overlays; a set of replacement entries that is applied on top of an archive.
+//!
+//! On disk, an overlay is a directory that mirrors the layout of an extraction: every file is named
+//! after the offset of the entry that it replaces (e.g. `0001A2B4.zlib`), or after its index (e.g.
+//! `152.zlib`). Other names can be given an index by a name map; see [`NAME_MAP`].
+//!
+//! Many overlays can be layered into one; see [`Overlay::merge`].
+
+use crate::{
+    error::{Error, Result},
+    format::pman::{self, PmanFile},
+    import::meta,
+};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+/// The file of an overlay directory that maps the names of its other files into entry indices; a
+/// JSON object, e.g. `{ "german.zlib": 156 }`.
+pub const NAME_MAP: &str = "overlay.json";
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format: "overlay",
+        reason: reason.into(),
+    }
+}
+
+/// An entry that more than one of the merged overlays replace, with different bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub index: usize,
+    /// The positions of the overlays that replace the entry, from the lowest to the highest
+    /// priority.
+    pub overlays: Vec<usize>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let overlays: Vec<_> = self.overlays.iter().map(ToString::to_string).collect();
+
+        write!(
+            f,
+            "entry {} is replaced by overlays {}",
+            self.index,
+            overlays.join(", ")
+        )
+    }
+}
+
+/// A set of replacement entries, by index.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Overlay {
+    /// The new (stored) bytes of every replaced entry.
+    pub entries: BTreeMap<usize, Vec<u8>>,
+}
+
+/// The offsets that the entries of `pman` are extracted with; the size of the header and of the
+/// entry table, and the size of every entry before them.
+fn extraction_offsets(pman: &PmanFile) -> Vec<usize> {
+    pman.files()
+        .iter()
+        .scan(pman.size_upto_file_data(), |offset, file| {
+            let current = *offset;
+            *offset += file.bytes().len();
+
+            Some(current)
+        })
+        .collect()
+}
+
+impl Overlay {
+    /// Reads an overlay directory, against the archive that it is going to be applied on.
+    ///
+    /// Files with the `zlib` extension are decompressed data, like the ones of an extraction, and
+    /// they are compressed again; any other file is stored as it is. Subdirectories (e.g. the ones
+    /// of a deep extraction) are ignored, and so is the sidecar of an extraction (see
+    /// [`meta::SIDECAR`]).
+    ///
+    /// # Errors
+    ///
+    /// If the directory can't be read, if the name map is not valid, or if a file doesn't name an
+    /// entry of `pman`.
+    pub fn from_dir(dir: impl AsRef<Path>, pman: &PmanFile) -> Result<Self> {
+        let dir = dir.as_ref();
+        let names = match fs::read(dir.join(NAME_MAP)) {
+            Ok(bytes) => read_name_map(&bytes)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        let offsets = extraction_offsets(pman);
+
+        let mut raw = vec![];
+        let mut zlib = vec![];
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            if entry.file_type()?.is_dir() || name == NAME_MAP || name == meta::SIDECAR {
+                continue;
+            }
+
+            let path = entry.path();
+            let stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default();
+            let index = names
+                .get(&name)
+                .copied()
+                .or_else(|| {
+                    // extracted entries are named after their offset, with 8 hex digits.
+                    (stem.len() == 8)
+                        .then(|| usize::from_str_radix(stem, 16).ok())
+                        .flatten()
+                        .and_then(|offset| offsets.binary_search(&offset).ok())
+                })
+                .or_else(|| stem.parse().ok())
+                .ok_or_else(|| invalid(format!("`{name}` doesn't name an entry")))?;
+
+            let bytes = fs::read(&path)?;
+            if path.extension().is_some_and(|e| e == "zlib") {
+                zlib.push((index, bytes));
+            } else {
+                raw.push((index, bytes));
+            }
+        }
+
+        // every file is compressed at once, so they can be compressed in parallel.
+        let data: Vec<_> = zlib.iter().map(|(_, bytes)| bytes).collect();
+        let compressed = pman::compress_all(&data)?;
+        let zlib = zlib.iter().map(|(index, _)| *index).zip(compressed);
+
+        let mut overlay = Self::default();
+        for (index, bytes) in raw.into_iter().chain(zlib) {
+            if overlay.insert(index, bytes).is_some() {
+                return Err(invalid(format!("entry {index} is replaced more than once")));
+            }
+        }
+
+        if let Some(&index) = overlay.entries.keys().find(|&&i| i >= pman.files().len()) {
+            return Err(invalid(format!(
+                "there is no entry {index}; the archive has {} of them",
+                pman.files().len()
+            )));
+        }
+
+        Ok(overlay)
+    }
+
+    /// Replaces the entry at `index` with `bytes`, as they are stored on the archive; returns the
+    /// bytes that it replaced, if any.
+    pub fn insert(&mut self, index: usize, bytes: Vec<u8>) -> Option<Vec<u8>> {
+        self.entries.insert(index, bytes)
+    }
+
+    /// Layers `overlays` into a single one, from the lowest to the highest priority.
+    ///
+    /// Returns the merged overlay, and the entries that more than one overlay replace with
+    /// different bytes; with `resolve`, the overlay with the highest priority wins them.
+    ///
+    /// # Errors
+    ///
+    /// If there are conflicts, and they are not resolved.
+    pub fn merge(overlays: &[Self], resolve: bool) -> Result<(Self, Vec<Conflict>)> {
+        let mut merged = Self::default();
+        let mut owners: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+        for (position, overlay) in overlays.iter().enumerate() {
+            for (&index, bytes) in &overlay.entries {
+                let owners = owners.entry(index).or_default();
+
+                // the same replacement is not a conflict.
+                if merged.entries.get(&index) != Some(bytes) {
+                    merged.insert(index, bytes.clone());
+                    owners.push(position);
+                }
+            }
+        }
+
+        let conflicts: Vec<_> = owners
+            .into_iter()
+            .filter(|(_, overlays)| overlays.len() > 1)
+            .map(|(index, overlays)| Conflict { index, overlays })
+            .collect();
+
+        if !resolve && !conflicts.is_empty() {
+            let conflicts: Vec<_> = conflicts.iter().map(ToString::to_string).collect();
+            return Err(invalid(conflicts.join("; ")));
+        }
+
+        Ok((merged, conflicts))
+    }
+
+    /// Applies the overlay on `pman`; returns the indices of the entries that were replaced.
+    ///
+    /// # Errors
+    ///
+    /// If the overlay replaces an entry that `pman` doesn't have; nothing is replaced then.
+    pub fn apply(&self, pman: &mut PmanFile) -> Result<Vec<usize>> {
+        let len = pman.files().len();
+
+        if let Some(&index) = self.entries.keys().find(|&&i| i >= len) {
+            return Err(invalid(format!(
+                "there is no entry {index}; the archive has {len} of them"
+            )));
+        }
+
+        let files = pman.files_mut();
+        for (&index, bytes) in &self.entries {
+            files[index].set_bytes(bytes.clone());
+        }
+
+        Ok(self.entries.keys().copied().collect())
+    }
+}
+
+/// Reads a name map; see [`NAME_MAP`].
+fn read_name_map(bytes: &[u8]) -> Result<BTreeMap<String, usize>> {
+    let map: Value = serde_json::from_slice(bytes)
+        .map_err(|err| invalid(format!("`{NAME_MAP}` is not valid JSON; {err}")))?;
+    let map = map
+        .as_object()
+        .ok_or_else(|| invalid(format!("`{NAME_MAP}` is not an object")))?;
+
+    map.iter()
+        .map(|(name, index)| {
+            let index = index
+                .as_u64()
+                .and_then(|index| usize::try_from(index).ok())
+                .ok_or_else(|| invalid(format!("`{name}` is not mapped into an index")))?;
+
+            Ok((name.clone(), index))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, fixture};
+
+    /// An empty directory, that is unique to `name`.
+    fn temp_dir(name: &str) -> eyre::Result<std::path::PathBuf> {
+        let dir = std::env::temp_dir().join(format!("rashen-{name}-{}", std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+
+        Ok(dir)
+    }
+
+    #[test]
+    fn overlay_from_dir_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(&fixture::archive())?;
+        let dir = temp_dir("overlay")?;
+        let offsets = extraction_offsets(&pman);
+        let (raw, zlib, empty) = (fixture::RAW, fixture::ZLIB, fixture::EMPTY);
+
+        // by offset, by index and by name; decompressed, and as it is stored.
+        let text = pman[zlib].to_zlib()?.expect("it is compressed.");
+        fs::write(dir.join(format!("{:08X}.zlib", offsets[zlib])), &text)?;
+        fs::write(
+            dir.join(format!("{raw}.dat")),
+            vec![1; pman[raw].bytes().len()],
+        )?;
+        fs::write(dir.join("last.dat"), pman[fixture::LAST].bytes())?;
+        fs::write(dir.join(NAME_MAP), format!(r#"{{ "last.dat": {empty} }}"#))?;
+        fs::create_dir(dir.join("ignored"))?;
+
+        let overlay = Overlay::from_dir(&dir, &pman)?;
+        assert_eq!(overlay.apply(&mut pman)?, [raw, zlib, empty]);
+
+        let pman = PmanFile::new(&pman.into_bytes()?)?;
+        assert_eq!(pman[zlib].to_zlib()?, Some(text));
+        assert_eq!(pman[raw].bytes()[0], 1);
+        assert_eq!(pman[empty].bytes(), pman[fixture::LAST].bytes());
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn overlay_merge_test() -> eyre::Result<()> {
+        let overlay = |entries: &[(usize, u8)]| Overlay {
+            entries: entries
+                .iter()
+                .map(|&(index, byte)| (index, vec![byte]))
+                .collect(),
+        };
+        let overlays = [
+            overlay(&[(10, 1), (11, 1)]),
+            overlay(&[(11, 1), (12, 2)]),
+            overlay(&[(10, 3), (12, 3), (13, 3)]),
+        ];
+
+        let err = Overlay::merge(&overlays, false).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidImport);
+
+        let (merged, conflicts) = Overlay::merge(&overlays, true)?;
+        assert_eq!(merged, overlay(&[(10, 3), (11, 1), (12, 3), (13, 3)]));
+        assert_eq!(
+            conflicts,
+            [
+                Conflict {
+                    index: 10,
+                    overlays: vec![0, 2]
+                },
+                Conflict {
+                    index: 12,
+                    overlays: vec![1, 2]
+                },
+            ]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "entry 10 is replaced by overlays 0, 2"
+        );
+
+        let (merged, conflicts) = Overlay::merge(&overlays[..2], false)?;
+        assert_eq!(merged.entries.len(), 3);
+        assert!(conflicts.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn overlay_invalid_test() -> eyre::Result<()> {
+        let input = fixture::archive();
+        let mut pman = PmanFile::new(&input)?;
+
+        for (name, map) in [
+            ("readme.txt", "{}"),
+            ("100000.dat", "{}"),
+            ("a.dat", r#"{ "a.dat": "ten" }"#),
+            ("a.dat", "["),
+        ] {
+            let dir = temp_dir("overlay-invalid")?;
+            fs::write(dir.join(name), b"")?;
+            fs::write(dir.join(NAME_MAP), map)?;
+
+            let err = Overlay::from_dir(&dir, &pman).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidImport, "{name}");
+
+            fs::remove_dir_all(dir)?;
+        }
+
+        let mut overlay = Overlay::default();
+        overlay.insert(fixture::RAW, Vec::new());
+        overlay.insert(100_000, Vec::new());
+        assert!(overlay.apply(&mut pman).is_err());
+        assert_eq!(
+            pman.files()[fixture::RAW].bytes(),
+            PmanFile::new(&input)?[fixture::RAW].bytes()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,394 @@
+//! Palette import from the formats of image editors; the reverse of
+//! [`export::palette`](crate::export::palette).
+//!
+//! The colors of the game only have 4 bits per channel, so imported colors are rounded to them.
+
+use crate::{
+    error::{Error, Result},
+    export::palette::PaletteFormat,
+    format::{
+        color,
+        palette::{Palette, COLOR_COUNT},
+        pman::PmanFile,
+        texture::Texture,
+    },
+};
+use std::fmt;
+
+fn invalid(format: &'static str, reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format,
+        reason: reason.into(),
+    }
+}
+
+/// Fills a palette with `colors`; the missing ones are black.
+fn palette_of(
+    format: &'static str,
+    colors: impl IntoIterator<Item = Result<[u8; 3]>>,
+) -> Result<Palette> {
+    let mut rgba = [[0, 0, 0, u8::MAX]; COLOR_COUNT];
+
+    for (index, color) in colors.into_iter().enumerate() {
+        let [r, g, b] = color?;
+        let slot = rgba.get_mut(index).ok_or_else(|| {
+            invalid(
+                format,
+                format!("a palette can't have more than {COLOR_COUNT} colors"),
+            )
+        })?;
+
+        *slot = [r, g, b, u8::MAX];
+    }
+
+    Ok(Palette::from_rgba(&rgba))
+}
+
+/// Parses the `r g b` channels at the start of `line`; anything after them (e.g. the name of the
+/// color) is ignored.
+fn read_rgb(format: &'static str, line: &str, number: usize) -> Result<[u8; 3]> {
+    let mut channels = line.split_whitespace().map(str::parse::<u8>);
+    let mut channel = || {
+        channels
+            .next()
+            .and_then(std::result::Result::ok)
+            .ok_or_else(|| invalid(format, format!("line {number}: `{line}` is not a color")))
+    };
+
+    Ok([channel()?, channel()?, channel()?])
+}
+
+/// Parses a GIMP palette; see [`export::palette::to_gpl`](crate::export::palette::to_gpl).
+///
+/// # Errors
+///
+/// If the header is missing, if a line is not a color, or if there are more than
+/// [`COLOR_COUNT`] colors.
+pub fn from_gpl(gpl: &str) -> Result<Palette> {
+    let mut lines = gpl.lines().enumerate();
+
+    if lines.next().map(|(_, line)| line.trim()) != Some("GIMP Palette") {
+        return Err(invalid("GPL", "there is no `GIMP Palette` header"));
+    }
+
+    let colors = lines
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| {
+            // the `Name:` and `Columns:` fields are optional.
+            !line.is_empty()
+                && !line.starts_with('#')
+                && !line.starts_with("Name:")
+                && !line.starts_with("Columns:")
+        })
+        .map(|(number, line)| read_rgb("GPL", line, number));
+
+    palette_of("GPL", colors)
+}
+
+/// Parses an Adobe color table; see [`export::palette::to_act`](crate::export::palette::to_act).
+///
+/// The 4 bytes footer that some editors write (the color count, and the transparent color) is
+/// ignored.
+///
+/// # Errors
+///
+/// If `act` is not `256` colors long.
+pub fn from_act(act: &[u8]) -> Result<Palette> {
+    let colors = act.get(..COLOR_COUNT * 3).ok_or_else(|| {
+        invalid(
+            "ACT",
+            format!("{COLOR_COUNT} colors take {:#X} bytes", COLOR_COUNT * 3),
+        )
+    })?;
+
+    palette_of(
+        "ACT",
+        colors
+            .chunks_exact(3)
+            .map(|rgb| Ok([rgb[0], rgb[1], rgb[2]])),
+    )
+}
+
+/// Parses a JASC palette; see
+/// [`export::palette::to_jasc_pal`](crate::export::palette::to_jasc_pal).
+///
+/// # Errors
+///
+/// If the header is missing, if a line is not a color, or if there are more than
+/// [`COLOR_COUNT`] colors.
+pub fn from_jasc_pal(pal: &str) -> Result<Palette> {
+    let mut lines = pal.lines().map(str::trim);
+
+    if lines.next() != Some("JASC-PAL") || lines.next() != Some("0100") {
+        return Err(invalid("PAL", "there is no `JASC-PAL` header"));
+    }
+
+    // the colors are counted as they are read, so the count is only checked to be there.
+    if lines
+        .next()
+        .and_then(|count| count.parse::<usize>().ok())
+        .is_none()
+    {
+        return Err(invalid("PAL", "there is no color count"));
+    }
+
+    let colors = lines
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(index, line)| read_rgb("PAL", line, index + 4));
+
+    palette_of("PAL", colors)
+}
+
+/// Parses a palette file of `format`.
+///
+/// # Errors
+///
+/// See [`from_gpl`], [`from_act`] and [`from_jasc_pal`]; text formats must be UTF-8 too.
+pub fn decode_palette(format: PaletteFormat, bytes: &[u8]) -> Result<Palette> {
+    let text =
+        |name| std::str::from_utf8(bytes).map_err(|err| invalid(name, format!("not UTF-8; {err}")));
+
+    match format {
+        PaletteFormat::Gimp => from_gpl(text("GPL")?),
+        PaletteFormat::Adobe => from_act(bytes),
+        PaletteFormat::Jasc => from_jasc_pal(text("PAL")?),
+    }
+}
+
+/// How far the colors of a texture moved when its palette was replaced.
+///
+/// Errors are the distance between the RGB colors that a pixel had, and the ones that it has now;
+/// from `0` to `441` (the distance from black to white).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RemapReport {
+    /// The index of the texture entry.
+    pub texture: usize,
+    /// The error of the average pixel.
+    pub mean_error: f64,
+    /// The error of the worst pixel.
+    pub max_error: f64,
+    /// If the texture was remapped into the new palette, or if it was left as it was.
+    pub remapped: bool,
+}
+
+impl fmt::Display for RemapReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            texture,
+            mean_error,
+            max_error,
+            remapped,
+        } = self;
+        let action = if *remapped { "remapped" } else { "kept" };
+
+        write!(
+            f,
+            "texture {texture} ({action}): mean error {mean_error:.2}, max error {max_error:.2}"
+        )
+    }
+}
+
+/// The distance between two colors, on 8-bit RGB.
+fn distance(a: [u8; 4], b: [u8; 4]) -> f64 {
+    a.iter()
+        .zip(b)
+        .take(3)
+        .map(|(&a, b)| (f64::from(a) - f64::from(b)).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Maps every color of `old` to the nearest color of `new`; see [`color::quantize`].
+#[must_use]
+pub fn remap_table(old: &Palette, new: &Palette) -> [u8; COLOR_COUNT] {
+    let indices = color::quantize(&old.to_rgba(), COLOR_COUNT, &new.to_rgba(), false);
+    let mut table = [0; COLOR_COUNT];
+    table.copy_from_slice(&indices);
+
+    table
+}
+
+/// Moves `texture` from the `old` palette into the `new` one, by picking the nearest color of every
+/// pixel; returns the new texture, and how far its colors moved (see [`RemapReport`]).
+#[must_use]
+pub fn remap_texture(texture: &Texture, old: &Palette, new: &Palette) -> (Texture, [f64; 2]) {
+    let table = remap_table(old, new);
+    let pixels: Vec<_> = texture.pixels.iter().map(|&i| table[i as usize]).collect();
+    let remapped = Texture {
+        pixels,
+        ..texture.clone()
+    };
+
+    let error = color_error(texture, old, &remapped, new);
+    (remapped, error)
+}
+
+/// The mean, and the max, of the distance between the colors of the pixels of two textures.
+#[allow(clippy::cast_precision_loss)]
+fn color_error(a: &Texture, a_palette: &Palette, b: &Texture, b_palette: &Palette) -> [f64; 2] {
+    let (a_colors, b_colors) = (a_palette.to_rgba(), b_palette.to_rgba());
+    let (sum, max) = a
+        .pixels
+        .iter()
+        .zip(&b.pixels)
+        .map(|(&a, &b)| distance(a_colors[a as usize], b_colors[b as usize]))
+        .fold((0.0, 0.0_f64), |(sum, max), error| {
+            (sum + error, max.max(error))
+        });
+
+    [sum / a.pixels.len().max(1) as f64, max]
+}
+
+/// Replaces the palette entry at `index` of `pman` with `palette`.
+///
+/// `textures` are the texture entries that use the palette; the archive doesn't say which ones
+/// they are, so it is up to the caller. With `remap`, each of them is remapped into the new palette
+/// (see [`remap_texture`]), so it keeps its colors as well as the new palette allows; otherwise,
+/// the textures keep their indices, and their colors change with the palette. Either way, returns
+/// how far the colors of every texture moved.
+///
+/// # Errors
+///
+/// If there is no entry at `index`, if it is not a valid palette, or if an entry of `textures` is
+/// missing or not a valid texture.
+pub fn import_palette(
+    pman: &mut PmanFile,
+    index: usize,
+    palette: &Palette,
+    textures: &[usize],
+    remap: bool,
+) -> Result<Vec<RemapReport>> {
+    let file = pman
+        .files()
+        .get(index)
+        .ok_or_else(|| invalid("palette", format!("there is no entry {index}")))?;
+    let old = Palette::new(file.bytes())?;
+
+    let mut reports = Vec::new();
+    for &texture in textures {
+        let file = pman
+            .files()
+            .get(texture)
+            .ok_or_else(|| invalid("palette", format!("there is no entry {texture}")))?;
+        let original = Texture::new(file.bytes())?;
+
+        let (remapped, [mean_error, max_error]) = if remap {
+            remap_texture(&original, &old, palette)
+        } else {
+            let error = color_error(&original, &old, &original, palette);
+            (original, error)
+        };
+
+        pman.files_mut()[texture].set_bytes(remapped.to_bytes());
+        reports.push(RemapReport {
+            texture,
+            mean_error,
+            max_error,
+            remapped: remap,
+        });
+    }
+
+    pman.files_mut()[index].set_bytes(palette.to_bytes());
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    #[cfg(feature = "packfile")]
+    use crate::export::palette::{to_act, to_gpl, to_jasc_pal};
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn decode_palette_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in 66..70 {
+            let palette = Palette::new(pman[index].bytes())?;
+
+            assert_eq!(from_gpl(&to_gpl(&palette, "test"))?, palette);
+            assert_eq!(from_act(&to_act(&palette))?, palette);
+            assert_eq!(from_jasc_pal(&to_jasc_pal(&palette))?, palette);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn decode_palette_invalid_test() {
+        let short_gpl = "GIMP Palette\n255 0 0 red\n";
+        let palette = from_gpl(short_gpl).expect("colors are optional.");
+        assert_eq!(palette.colors[..2], [0x0F00, 0]);
+
+        for (format, bytes) in [
+            (PaletteFormat::Gimp, &b"255 0 0\n"[..]),
+            (PaletteFormat::Gimp, &b"GIMP Palette\n255 0\n"[..]),
+            (PaletteFormat::Adobe, &[0; 10][..]),
+            (PaletteFormat::Jasc, &b"JASC-PAL\r\n0100\r\n"[..]),
+            (
+                PaletteFormat::Jasc,
+                &b"JASC-PAL\r\n0100\r\n1\r\n256 0 0\r\n"[..],
+            ),
+        ] {
+            let err = decode_palette(format, bytes).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidImport, "{format:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn import_palette_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(INPUT)?;
+        let original = Texture::new(pman[72].bytes())?;
+        let old = Palette::new(pman[66].bytes())?;
+
+        // the same colors, in the opposite order.
+        let mut reversed = old.clone();
+        reversed.colors.reverse();
+
+        let reports = import_palette(&mut pman, 66, &reversed, &[72], true)?;
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].texture, 72);
+        assert!(reports[0].max_error < f64::EPSILON, "{}", reports[0]);
+
+        let remapped = Texture::new(pman[72].bytes())?;
+        assert_eq!(remapped.to_rgba(&reversed), original.to_rgba(&old));
+        assert_eq!(Palette::new(pman[66].bytes())?, reversed);
+
+        // without remapping, the indices are kept and the colors change.
+        let mut pman = PmanFile::new(INPUT)?;
+        let reports = import_palette(&mut pman, 66, &reversed, &[72], false)?;
+        assert!(!reports[0].remapped);
+        assert_eq!(Texture::new(pman[72].bytes())?, original);
+
+        Ok(())
+    }
+
+    #[test]
+    fn remap_texture_error_test() {
+        let old = Palette {
+            colors: std::array::from_fn(|i| if i == 1 { 0x0FFF } else { 0 }),
+        };
+        let new = Palette {
+            colors: std::array::from_fn(|i| if i == 7 { 0x0EEE } else { 0 }),
+        };
+        let texture = Texture {
+            width: 2,
+            height: 1,
+            pixels: vec![0, 1],
+        };
+
+        let (remapped, [mean, max]) = remap_texture(&texture, &old, &new);
+        assert_eq!(remapped.pixels, [0, 7]);
+        // `0x0E` is `0xEE`, so white moved by `0x11` on every channel.
+        let expected = (3.0 * 17.0_f64.powi(2)).sqrt();
+        assert!((max - expected).abs() < 1e-9);
+        assert!((mean - expected / 2.0).abs() < 1e-9);
+    }
+}
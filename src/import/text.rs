@@ -0,0 +1,124 @@
+//! Text bank import; the reverse of [`export::text`](crate::export::text).
+
+use crate::{
+    error::{Error, Result},
+    export::text::TextFormat,
+    format::{
+        pman::{self, PmanFile},
+        text::{TextBank, TEXT_BANKS},
+    },
+};
+
+/// Replaces the text banks of the ISO 639-1 `language` (e.g. `de`) of `pman` with the strings of
+/// a file of `format`; see [`TextFormat::decode`].
+///
+/// The new banks keep the encoding of the ones they replace, and they are compressed again if those
+/// were. Returns the indices of the entries that were replaced; the english banks are on two
+/// entries.
+///
+/// # Errors
+///
+/// If there is no bank of `language`, if the file can't be decoded, if the original bank can't be
+/// parsed, or if a string would break the layout of the game; see
+/// [`TextBank::to_bytes_checked`], which compares every string against the one it replaces.
+pub fn import_text(
+    pman: &mut PmanFile,
+    language: &str,
+    format: TextFormat,
+    bytes: &[u8],
+) -> Result<Vec<usize>> {
+    let mut imported = format.decode(bytes)?;
+    let indices: Vec<_> = TEXT_BANKS
+        .iter()
+        .filter(|(_, lang)| lang.eq_ignore_ascii_case(language))
+        .map(|&(index, _)| index)
+        .filter(|&index| index < pman.files().len())
+        .collect();
+
+    if indices.is_empty() {
+        return Err(Error::InvalidImport {
+            format: "text bank",
+            reason: format!("there is no text bank for the `{language}` language"),
+        });
+    }
+
+    for &index in &indices {
+        let file = &pman.files()[index];
+        let zlib = file.to_zlib()?;
+        let original = TextBank::new(zlib.as_deref().unwrap_or(file.bytes()))?;
+
+        imported.encoding = original.encoding;
+        imported.bom = original.bom;
+
+        let bytes = imported.to_bytes_checked(&original, None)?;
+        let bytes = if zlib.is_some() {
+            pman::compress(&bytes)?
+        } else {
+            bytes
+        };
+
+        pman.files_mut()[index].set_bytes(bytes);
+    }
+
+    Ok(indices)
+}
+
+#[cfg(all(test, feature = "packfile"))]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, export::text::bank_to_csv};
+
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+
+    fn bank(pman: &PmanFile, index: usize) -> eyre::Result<TextBank> {
+        let bytes = pman[index].to_zlib()?.expect("text banks are compressed.");
+
+        Ok(TextBank::new(&bytes)?)
+    }
+
+    #[test]
+    fn import_text_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(INPUT)?;
+        let mut german = bank(&pman, 156)?;
+        german.entries[1].1 = "START drücken!".into();
+
+        let csv = bank_to_csv(&german);
+        assert_eq!(
+            import_text(&mut pman, "de", TextFormat::Csv, csv.as_bytes())?,
+            [156]
+        );
+        assert_eq!(bank(&pman, 156)?, german);
+
+        // the archive is rebuilt with the new entry.
+        let pman = PmanFile::new(&pman.into_bytes()?)?;
+        assert_eq!(bank(&pman, 156)?.get(1), Some("START drücken!"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_text_invalid_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(INPUT)?;
+        let mut german = bank(&pman, 156)?;
+
+        let csv = bank_to_csv(&german);
+        let err = import_text(&mut pman, "la", TextFormat::Csv, csv.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidImport);
+
+        // a control character went missing.
+        let (id, string) = german
+            .entries
+            .iter_mut()
+            .find(|(_, string)| string.contains('|'))
+            .expect("some strings switch the speaker.");
+        *string = string.replace('|', " ");
+        let id = *id;
+
+        let csv = bank_to_csv(&german);
+        let err = import_text(&mut pman, "de", TextFormat::Csv, csv.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidText);
+        assert_ne!(bank(&pman, 156)?.get(id), german.get(id));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,419 @@
+//! RIFF WAVE import for the instruments of the sound archive; the reverse of
+//! [`export::wav`](crate::export::wav).
+//!
+//! Any 8 or 16-bit PCM file is accepted; it is mixed down to mono, resampled to [`SAMPLE_RATE`],
+//! and quantized to signed 8-bit samples.
+
+use crate::{
+    error::{Error, Result},
+    format::{
+        audio::{AudioClip, SoundArchive, LOOP_FLAG, SAMPLE_RATE},
+        pman::{self, PmanFile},
+    },
+};
+use std::fmt;
+
+const PCM_FORMAT: u16 = 1;
+const EXTENSIBLE_FORMAT: u16 = 0xFFFE;
+/// The bank of the [`SoundArchive`] with the instruments.
+const INSTRUMENT_BANK: usize = 1;
+
+/// An imported clip that takes more bytes than the one it replaced.
+///
+/// The game might not have room for it; it is not an error, because nothing says that it won't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverBudget {
+    /// The index of the clip on the instrument bank.
+    pub clip: usize,
+    /// The (compressed) size of the new clip.
+    pub size: usize,
+    /// The (compressed) size of the clip that it replaced.
+    pub budget: usize,
+}
+
+impl fmt::Display for OverBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { clip, size, budget } = self;
+
+        write!(
+            f,
+            "clip {clip} takes {size:#X} bytes, but the one it replaced took {budget:#X}"
+        )
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format: "WAV",
+        reason: reason.into(),
+    }
+}
+
+/// The `fmt ` chunk of a WAV file; only what is needed to read its samples.
+struct WavFormat {
+    channels: u16,
+    rate: u32,
+    bits: u16,
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(*bytes.get(offset..)?.first_chunk()?))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(*bytes.get(offset..)?.first_chunk()?))
+}
+
+/// The chunks of a RIFF WAVE file, as `(id, data)` pairs.
+fn wav_chunks(wav: &[u8]) -> Result<Vec<([u8; 4], &[u8])>> {
+    if wav.get(..4) != Some(b"RIFF") || wav.get(8..12) != Some(b"WAVE") {
+        return Err(invalid("not a RIFF WAVE file"));
+    }
+
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+
+    while offset + 8 <= wav.len() {
+        let id = *wav[offset..].first_chunk().unwrap_or(&[0; 4]);
+        let size = u32_at(wav, offset + 4).unwrap_or_default() as usize;
+        let data = wav
+            .get(offset + 8..offset + 8 + size)
+            .ok_or_else(|| invalid(format!("the `{}` chunk is truncated", id.escape_ascii())))?;
+
+        chunks.push((id, data));
+        // chunks are padded to an even size.
+        offset += 8 + size + size % 2;
+    }
+
+    Ok(chunks)
+}
+
+fn read_format(fmt: &[u8]) -> Result<WavFormat> {
+    let truncated = || invalid("the `fmt ` chunk is truncated");
+    let mut tag = u16_at(fmt, 0).ok_or_else(truncated)?;
+    let channels = u16_at(fmt, 2).ok_or_else(truncated)?;
+    let rate = u32_at(fmt, 4).ok_or_else(truncated)?;
+    let bits = u16_at(fmt, 14).ok_or_else(truncated)?;
+
+    if tag == EXTENSIBLE_FORMAT {
+        // the first 2 bytes of the sub-format GUID are the format tag.
+        tag = u16_at(fmt, 24).ok_or_else(truncated)?;
+    }
+    if tag != PCM_FORMAT || !matches!(bits, 8 | 16) {
+        return Err(invalid(format!(
+            "only 8 and 16-bit PCM is supported; got format {tag:#X} with {bits} bits"
+        )));
+    }
+    if channels == 0 || rate == 0 {
+        return Err(invalid("there are no channels, or the sample rate is zero"));
+    }
+
+    Ok(WavFormat {
+        channels,
+        rate,
+        bits,
+    })
+}
+
+/// The samples of `data`, mixed down to mono, from `-1` to `1`.
+fn read_samples(data: &[u8], format: &WavFormat) -> Vec<f32> {
+    let width = usize::from(format.bits / 8);
+    let frame = width * usize::from(format.channels);
+
+    data.chunks_exact(frame)
+        .map(|frame| {
+            let sum: f32 = frame
+                .chunks_exact(width)
+                .map(|sample| match *sample {
+                    // 8-bit WAV samples are unsigned.
+                    [s] => f32::from((s ^ 0x80).cast_signed()) / 128.0,
+                    [lo, hi] => f32::from(i16::from_le_bytes([lo, hi])) / 32_768.0,
+                    _ => unreachable!("only 8 and 16-bit samples are read"),
+                })
+                .sum();
+
+            sum / f32::from(format.channels)
+        })
+        .collect()
+}
+
+/// Resamples `samples` from `from` to `to` Hz, with linear interpolation.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let step = f64::from(from) / f64::from(to);
+    let len = (samples.len() as f64 / step).round().max(1.0) as usize;
+
+    (0..len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let t = (position - index as f64) as f32;
+            let a = samples[index.min(samples.len() - 1)];
+            let b = samples[(index + 1).min(samples.len() - 1)];
+
+            a + (b - a) * t
+        })
+        .collect()
+}
+
+/// The loop of the `smpl` chunk, if it has one, in samples; `end` is inclusive.
+fn read_loop(smpl: &[u8]) -> Option<(u32, u32)> {
+    // the loop count is after the 7 fields of the header, and the first loop after the 9 of them.
+    let loops = u32_at(smpl, 28)?;
+    let start = u32_at(smpl, 36 + 8)?;
+    let end = u32_at(smpl, 36 + 12)?;
+
+    (loops > 0 && start <= end).then_some((start, end))
+}
+
+/// Converts a WAV file into a clip of the game, at [`SAMPLE_RATE`].
+///
+/// The header fields of `original` (the instrument, the volume, and the pitch) are kept; the loop
+/// is read from the `smpl` chunk, if there is one, and the clip doesn't loop otherwise.
+///
+/// # Errors
+///
+/// If `wav` is not a RIFF WAVE file, or if it is not 8 or 16-bit PCM.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn wav_to_clip(wav: &[u8], original: &AudioClip) -> Result<AudioClip> {
+    let chunks = wav_chunks(wav)?;
+    let chunk = |name: &[u8; 4]| chunks.iter().find(|(id, _)| id == name).map(|(_, d)| *d);
+
+    let format = read_format(chunk(b"fmt ").ok_or_else(|| invalid("there is no `fmt ` chunk"))?)?;
+    let data = chunk(b"data").ok_or_else(|| invalid("there is no `data` chunk"))?;
+    let samples = resample(&read_samples(data, &format), format.rate, SAMPLE_RATE);
+    let samples: Vec<_> = samples
+        .iter()
+        .map(|&s| (s * 128.0).round().clamp(-128.0, 127.0) as i8)
+        .collect();
+
+    let ratio = f64::from(SAMPLE_RATE) / f64::from(format.rate);
+    let scale = |x: u32| (f64::from(x) * ratio).round() as u32;
+    let looped = chunk(b"smpl").and_then(read_loop).map(|(start, end)| {
+        let last = u32::try_from(samples.len().saturating_sub(1)).unwrap_or(u32::MAX);
+        (scale(start).min(last), scale(end).min(last))
+    });
+
+    let mut clip = AudioClip {
+        samples,
+        loop_start: 0,
+        loop_end: 0,
+        flags: original.flags & !LOOP_FLAG,
+        ..original.clone()
+    };
+    // the loop of a clip counts half samples; see `export::wav::clip_to_wav`.
+    if let Some((start, end)) = looped {
+        clip.flags |= LOOP_FLAG;
+        clip.loop_start = start * 2;
+        clip.loop_end = (end + 1) * 2;
+    }
+
+    Ok(clip)
+}
+
+/// Replaces the clip `clip` of the instrument bank of the sound archive of `pman` with a WAV file;
+/// see [`wav_to_clip`].
+///
+/// The clip, and the archive, are compressed again if they were. Returns a warning if the new clip
+/// takes more bytes than the one it replaced.
+///
+/// # Errors
+///
+/// If `pman` has no sound archive, if it has no clip `clip`, if the original clip can't be parsed,
+/// or if the WAV file can't be converted.
+pub fn import_wav(pman: &mut PmanFile, clip: usize, wav: &[u8]) -> Result<Option<OverBudget>> {
+    let mut found = None;
+    for (index, file) in pman.files().iter().enumerate() {
+        let zlib = file.to_zlib()?;
+        if SoundArchive::is_tsnd(zlib.as_deref().unwrap_or(file.bytes())) {
+            found = Some((index, zlib));
+            break;
+        }
+    }
+    let (index, zlib) = found.ok_or_else(|| invalid("there is no sound archive"))?;
+    let file = &pman.files()[index];
+    let mut tsnd = SoundArchive::new(zlib.as_deref().unwrap_or(file.bytes()))?;
+
+    let entry = tsnd.banks[INSTRUMENT_BANK]
+        .get_mut(clip)
+        .ok_or_else(|| invalid(format!("there is no clip {clip}")))?;
+    let clip_zlib = pman::decompress(entry)?;
+    let original = AudioClip::new(clip_zlib.as_deref().unwrap_or(entry))?;
+
+    let bytes = wav_to_clip(wav, &original)?.to_bytes();
+    let bytes = if clip_zlib.is_some() {
+        pman::compress(&bytes)?
+    } else {
+        bytes
+    };
+    let warning = (bytes.len() > entry.len()).then_some(OverBudget {
+        clip,
+        size: bytes.len(),
+        budget: entry.len(),
+    });
+    *entry = bytes;
+
+    let bytes = tsnd.to_bytes()?;
+    let bytes = if zlib.is_some() {
+        pman::compress(&bytes)?
+    } else {
+        bytes
+    };
+    pman.files_mut()[index].set_bytes(bytes);
+
+    Ok(warning)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "packfile")]
+    use crate::{error::ErrorKind, export::wav::clip_to_wav};
+
+    #[cfg(feature = "packfile")]
+    const INPUT: &[u8] = include_bytes!("../../.res/packfile.dat");
+    #[cfg(feature = "packfile")]
+    const TSND_ENTRY: usize = 151;
+
+    #[cfg(feature = "packfile")]
+    fn clip(pman: &PmanFile, clip: usize) -> eyre::Result<AudioClip> {
+        let tsnd = SoundArchive::new(pman[TSND_ENTRY].bytes())?;
+        let bytes = pman::decompress(&tsnd.banks[INSTRUMENT_BANK][clip])?
+            .expect("instruments are compressed.");
+
+        Ok(AudioClip::new(&bytes)?)
+    }
+
+    /// A 16-bit PCM WAV file, without a `smpl` chunk.
+    fn wav16(channels: u16, rate: u32, samples: &[i16]) -> Vec<u8> {
+        let data: Vec<_> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0".to_vec();
+
+        wav.extend_from_slice(&PCM_FORMAT.to_le_bytes());
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&rate.to_le_bytes());
+        wav.extend_from_slice(&(rate * u32::from(channels) * 2).to_le_bytes());
+        wav.extend_from_slice(&(channels * 2).to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&u32::try_from(data.len()).unwrap_or(u32::MAX).to_le_bytes());
+        wav.extend_from_slice(&data);
+
+        wav
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn wav_to_clip_round_trip_test() -> eyre::Result<()> {
+        let pman = PmanFile::new(INPUT)?;
+
+        for index in 0..16 {
+            let clip = clip(&pman, index)?;
+            let mut imported = wav_to_clip(&clip_to_wav(&clip), &AudioClip::default())?;
+
+            assert_eq!(imported.samples, clip.samples, "clip {index}");
+            assert_eq!(imported.is_looped(), clip.is_looped(), "clip {index}");
+            if clip.is_looped() {
+                // only whole samples make it into the WAV file.
+                assert_eq!(imported.loop_start, clip.loop_start & !1, "clip {index}");
+                assert_eq!(imported.loop_end / 2, clip.loop_end / 2, "clip {index}");
+            }
+
+            // the header comes from the original clip.
+            imported = wav_to_clip(&clip_to_wav(&clip), &clip)?;
+            assert_eq!(imported.instrument, clip.instrument);
+            assert_eq!((imported.volume, imported.pitch), (clip.volume, clip.pitch));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn wav_to_clip_resample_test() -> eyre::Result<()> {
+        // a stereo square wave at twice the rate of the game; the channels are opposite.
+        let samples: Vec<i16> = (0..400)
+            .flat_map(|i| {
+                if i % 2 == 0 {
+                    [16_384, 0]
+                } else {
+                    [-16_384, 0]
+                }
+            })
+            .collect();
+        let clip = wav_to_clip(&wav16(2, SAMPLE_RATE * 2, &samples), &AudioClip::default())?;
+
+        assert_eq!(clip.samples.len(), 200);
+        assert!(clip.samples.iter().all(|&s| s == 32), "{:?}", clip.samples);
+        assert!(!clip.is_looped());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn import_wav_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(INPUT)?;
+        let original = clip(&pman, 3)?;
+        let short = AudioClip {
+            samples: original.samples[..original.samples.len() / 2].to_vec(),
+            flags: original.flags & !LOOP_FLAG,
+            loop_start: 0,
+            loop_end: 0,
+            ..original.clone()
+        };
+
+        assert_eq!(import_wav(&mut pman, 3, &clip_to_wav(&short))?, None);
+        assert_eq!(clip(&pman, 3)?, short);
+
+        // noise doesn't compress, so it takes more bytes than the original clip.
+        let noise: Vec<i16> = (0..original.samples.len() * 4)
+            .scan(0x2545_F491_u32, |x, _| {
+                // xorshift
+                *x ^= *x << 13;
+                *x ^= *x >> 17;
+                *x ^= *x << 5;
+                let [lo, hi, ..] = x.to_le_bytes();
+                Some(i16::from_le_bytes([lo, hi]))
+            })
+            .collect();
+        let warning = import_wav(&mut pman, 3, &wav16(1, SAMPLE_RATE, &noise))?;
+        assert!(warning.is_some_and(|w| w.clip == 3 && w.size > w.budget));
+
+        // the archive is rebuilt with the new entry.
+        let pman = PmanFile::new(&pman.into_bytes()?)?;
+        assert_eq!(clip(&pman, 3)?.samples.len(), noise.len());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "packfile")]
+    fn import_wav_invalid_test() -> eyre::Result<()> {
+        let mut pman = PmanFile::new(INPUT)?;
+        let wav = wav16(1, SAMPLE_RATE, &[0; 16]);
+
+        for (clip, wav) in [(0, &b"RIFF"[..]), (0, &wav[..30]), (100_000, &wav)] {
+            let err = import_wav(&mut pman, clip, wav).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidImport);
+        }
+
+        let mut float = wav.clone();
+        float[20] = 3;
+        let err = wav_to_clip(&float, &AudioClip::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidImport);
+
+        Ok(())
+    }
+}
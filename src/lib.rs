@@ -1,3 +1,11 @@
 #![warn(clippy::pedantic)]
 
+pub mod error;
+pub mod export;
+#[cfg(test)]
+mod fixture;
 pub mod format;
+pub mod i18n;
+pub mod import;
+#[cfg(test)]
+mod snapshot;
@@ -1,36 +1,1330 @@
-use rashen::format::pman::PmanFile;
+use rashen::{
+    error::Error,
+    export,
+    format::{
+        chunk::Chunk,
+        detect::FileType,
+        doctor::{self, Severity},
+        patch::Patch,
+        pman::{self, EntryTypes, PackOptions, PmanFile},
+    },
+    import::{
+        self,
+        meta::{ContentHash, Meta},
+        overlay::Overlay,
+    },
+    tr,
+};
 use std::{
-    fs::{self, read},
-    io,
-    path::Path,
+    env,
+    fs::{self, read, File},
+    io::{BufWriter, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
 };
 
-fn main() -> eyre::Result<()> {
+fn main() -> miette::Result<ExitCode> {
+    let mut args: Vec<_> = env::args().skip(1).collect();
+    // `--log-file rashen.log` writes everything that happens to `rashen.log` (the spans of the
+    // parsing, with how long they took, and the warnings of the archives), on top of what is shown;
+    // for bug reports about archives that are read wrong.
+    if let Some(index) = args.iter().position(|arg| arg == "--log-file") {
+        let Some(path) = args.get(index + 1) else {
+            miette::bail!("{}", tr!("expected-log-file"));
+        };
+        log_to(Path::new(path))?;
+        args.drain(index..=index + 1);
+    }
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), ?args, "started");
+
+    let code = command(args);
+    match &code {
+        Ok(code) => tracing::info!(?code, "finished"),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+
+    code
+}
+
+/// Logs every event (and the closing of every span) to the file at `path`; it is appended to.
+fn log_to(path: &Path) -> miette::Result<()> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(Error::from)?;
+
+    tracing_subscriber::fmt()
+        .with_writer(std::sync::Mutex::new(file))
+        .with_ansi(false)
+        .with_max_level(tracing::Level::TRACE)
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .init();
+
+    Ok(())
+}
+
+fn command(args: Vec<String>) -> miette::Result<ExitCode> {
+    // every command that writes an archive checks it against the size limits of `pack_options`.
+    // every command that reads one also takes the install folder, the `.sis` installer or the
+    // card image of the game in its place; see `import::install`.
+    // `import-text bank.csv game/packfile.dat --lang de -o packfile.dat` replaces a text bank of
+    // the archive, and writes the new one; see `import::text::import_text`.
+    if args.first().is_some_and(|arg| arg == "import-text") {
+        import_text(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `convert --from wav clip.wav game/packfile.dat --clip 3 -o packfile.dat` replaces an
+    // instrument of the sound archive of the archive, and writes the new one; see
+    // `import::wav::import_wav`.
+    if args.first().is_some_and(|arg| arg == "convert") {
+        convert_from(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `apply-mod mod/ game/packfile.dat` replaces the entries that have a file on `mod/`, and
+    // writes the new archive next to the original one (or to `-o`); see `import::overlay`. Many
+    // mods are merged, from the lowest to the highest priority; `--resolve` lets the last mod win
+    // the entries that more than one of them replace. With `--in-place`, the entries are written
+    // over the original archive instead, if every one of them fits on its slot.
+    if args.first().is_some_and(|arg| arg == "apply-mod") {
+        apply_mod(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `diff a.dat b.dat` lists the entries that changed; with `--emit-patch out.rpatch`, it also
+    // writes a patch that `patch out.rpatch a.dat -o b.dat` applies; see `format::patch`.
+    if args.first().is_some_and(|arg| arg == "diff") {
+        diff(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if args.first().is_some_and(|arg| arg == "patch") {
+        patch(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `mount packfile.dat mnt/` shows the entries as read-only files, until `mnt/` is unmounted;
+    // see `export::mount`.
+    if args.first().is_some_and(|arg| arg == "mount") {
+        mount(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `serve packfile.dat --addr 127.0.0.1:8080` serves an index of the entries, and previews of
+    // them, over HTTP; see `export::serve`.
+    if args.first().is_some_and(|arg| arg == "serve") {
+        serve(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `tree packfile.dat` prints every entry, what it is, and the chunks inside of it; see
+    // `export::tree`.
+    if args.first().is_some_and(|arg| arg == "tree") {
+        tree(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `cat --offset 0xA20 packfile.dat` writes the (decompressed) data of the entry at that offset
+    // to stdout (or to `-o`); see `PmanFile::entry_at_offset`. `--raw` writes it as it is stored.
+    if args.first().is_some_and(|arg| arg == "cat") {
+        cat(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `peek packfile.dat 152` prints a short preview of an entry, that suits its type; see
+    // `export::peek`. Colors are only shown on a terminal, unless `NO_COLOR` is set.
+    if args.first().is_some_and(|arg| arg == "peek") {
+        peek(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `list packfile.dat` prints every entry, with its offset, size and type (see
+    // `PmanFile::metadata`); and the name, the confirmed type and the notes of its annotation, from
+    // the sidecar next to the archive (or `--meta`). See `import::meta`.
+    if args.first().is_some_and(|arg| arg == "list") {
+        list(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `doctor packfile.dat` checks an archive for the common problems (not an archive, truncated,
+    // overlapping entries, `ZL` sizes that don't match), and prints advice for them, the most
+    // urgent first; see `format::doctor`. It fails if any of them is an error.
+    if args.first().is_some_and(|arg| arg == "doctor") {
+        return doctor(&args[1..]);
+    }
+    // `bench packfile.dat` times the parsing, the decompression of every entry and the repacking
+    // of an archive, and prints how fast they are; to report slow disks or CPUs with numbers.
+    // `--runs 10` times them 10 times (5 by default); `--cold` reads the archive from the disk
+    // again before every run, instead of once, before an untimed run that warms everything up.
+    if args.first().is_some_and(|arg| arg == "bench") {
+        bench(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // `schema -o schema/` writes a Kaitai Struct file for every format that the parsers know; see
+    // `format::schema`.
+    if args.first().is_some_and(|arg| arg == "schema") {
+        schema(&args[1..])?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // `--json` prints errors as JSON to stdout, for tools that consume them.
+    let json = args.iter().any(|arg| arg == "--json");
+    // `--deep` also extracts the sections of the entries that have them; see `Chunk::walk`.
+    let deep = args.iter().any(|arg| arg == "--deep");
+    // `--convert` also writes the entries that have a converter in a common format; see
+    // `export::convert`.
+    let convert = args.iter().any(|arg| arg == "--convert");
+    // `--to gltf` converts meshes into `.glb` files, instead of OBJ ones; `--to json` (or
+    // `--to po`) converts text banks into JSON (or PO) files, instead of CSV ones. It can be given
+    // more than once.
+    let mut options = export::Options::default();
+    for format in args
+        .windows(2)
+        .filter(|w| w[0] == "--to")
+        .map(|w| w[1].as_str())
+    {
+        match format {
+            "gltf" | "glb" => options.mesh = export::MeshFormat::Glb,
+            "obj" => options.mesh = export::MeshFormat::Obj,
+            "csv" => options.text = export::text::TextFormat::Csv,
+            "json" => options.text = export::text::TextFormat::Json,
+            "po" => options.text = export::text::TextFormat::Po,
+            _ => {
+                miette::bail!("{}", tr!("unknown-export-format", format = format))
+            }
+        }
+    }
+
+    // `--jobs 4` decompresses the entries on 4 threads (`--jobs 0`, on one per core); see
+    // `PmanFile::par_decompress_all`.
+    let jobs = flag_value(&args, "--jobs")
+        .map(|jobs| {
+            jobs.parse::<usize>()
+                .map_err(|_| miette::miette!("{}", tr!("jobs-not-a-number", jobs = jobs)))
+        })
+        .transpose()?;
+    if jobs.is_some() && cfg!(not(feature = "rayon")) {
+        miette::bail!(
+            "{}",
+            tr!("needs-feature", what = "--jobs", feature = "rayon")
+        );
+    }
+
+    // `--resume` keeps what an extraction that was interrupted already wrote; the entries whose
+    // file has the right size and hash are skipped.
+    let extraction = Extraction {
+        deep,
+        convert: convert.then_some(options),
+        jobs,
+        resume: args.iter().any(|arg| arg == "--resume"),
+    };
+
+    // `extract game1/packfile.dat game2/packfile.dat -o output/` extracts every archive into a
+    // directory of its own, inside of `output/`, and prints a summary of all of them; a pattern
+    // like `dumps/*/packfile.dat` is expanded on shells that don't. An archive that fails doesn't
+    // stop the others.
+    if args.first().is_some_and(|arg| arg == "extract") {
+        return extract_all(&args[1..], extraction);
+    }
+
+    match run(extraction) {
+        Ok(()) => Ok(ExitCode::SUCCESS),
+        Err(err) if json => {
+            println!("{}", err.to_json());
+            Ok(ExitCode::FAILURE)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The value after the flag `name` of `args`, if any.
+fn flag_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.windows(2)
+        .find(|w| w[0] == name)
+        .map(|w| w[1].as_str())
+}
+
+/// The flags that take a value; the argument after any other flag is not part of it.
+const VALUE_FLAGS: &[&str] = &[
+    "--addr",
+    "--clip",
+    "--emit-patch",
+    "--entry",
+    "--entry-budget",
+    "--entry-types",
+    "--from",
+    "--jobs",
+    "--lang",
+    "--log-file",
+    "--max-size",
+    "--meta",
+    "--offset",
+    "--output",
+    "--runs",
+    "--texture",
+    "--to",
+    "-o",
+];
+
+/// The arguments of `args` that are not a flag, or the value of one.
+fn positionals(args: &[String]) -> impl Iterator<Item = &Path> {
+    args.iter()
+        .enumerate()
+        .filter(|(i, arg)| {
+            !arg.starts_with('-') && (*i == 0 || !VALUE_FLAGS.contains(&args[i - 1].as_str()))
+        })
+        .map(|(_, arg)| Path::new(arg))
+}
+
+/// The first positional argument of `args`; the file to import.
+fn import_file(args: &[String]) -> miette::Result<&Path> {
+    positionals(args)
+        .next()
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-import-file")))
+}
+
+/// The second positional argument of `args`; the archive that the file is imported into.
+fn source_packfile(args: &[String]) -> miette::Result<&Path> {
+    positionals(args)
+        .nth(1)
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-source-packfile")))
+}
+
+/// The limits that the new archive is checked against; see `PackOptions`.
+///
+/// - `--max-size 0x400000` limits the size of the whole archive.
+/// - `--entry-budget 72=0x10000` limits the size of an entry; it can be given more than once.
+/// - `--keep-sizes` limits every entry (and the archive) to the size that it has on `original`.
+/// - `--strict` fails, instead of warning, if a limit is exceeded.
+/// - `--reproducible` drops what the original archive had past its last entry, so the same inputs
+///   always give the same archive.
+/// - `--entry-types detected` fills the `type` column of the entry table with the detected type of
+///   every entry; `--entry-types zeros` writes zeros, like the `1.0.6` release. See `EntryTypes`.
+fn pack_options(args: &[String], original: &PmanFile) -> miette::Result<PackOptions> {
+    let size = |value: &str| {
+        let parsed = match value.strip_prefix("0x") {
+            Some(hex) => usize::from_str_radix(hex, 16).ok(),
+            None => value.parse().ok(),
+        };
+
+        parsed.ok_or_else(|| miette::miette!("{}", tr!("not-a-size", value = value)))
+    };
+
+    let mut options = if args.iter().any(|arg| arg == "--keep-sizes") {
+        PackOptions::with_original_sizes(original)
+    } else {
+        PackOptions::default()
+    };
+    if let Some(max) = flag_value(args, "--max-size") {
+        options.max_archive_size = Some(size(max)?);
+    }
+    for budget in args
+        .windows(2)
+        .filter(|w| w[0] == "--entry-budget")
+        .map(|w| w[1].as_str())
+    {
+        let (index, budget) = budget
+            .split_once('=')
+            .and_then(|(index, budget)| Some((index.parse().ok()?, budget)))
+            .ok_or_else(|| miette::miette!("{}", tr!("expected-entry-budget")))?;
+        options.entry_budgets.insert(index, size(budget)?);
+    }
+    options.strict = args.iter().any(|arg| arg == "--strict");
+    options.reproducible = args.iter().any(|arg| arg == "--reproducible");
+    if let Some(entry_types) = flag_value(args, "--entry-types") {
+        options.entry_types = match entry_types {
+            "stored" => EntryTypes::Stored,
+            "zeros" => EntryTypes::Zeros,
+            "detected" => EntryTypes::Detected,
+            _ => miette::bail!("{}", tr!("unknown-entry-types", entry_types = entry_types)),
+        };
+    }
+
+    Ok(options)
+}
+
+/// Reads the archive at `path`; or out of a game install, if that is what `path` is. See
+/// `import::install::locate`.
+fn read_packfile(path: &Path) -> miette::Result<Vec<u8>> {
+    let (bytes, source) = import::install::locate(path)?;
+
+    if source != import::install::Source::Archive {
+        eprintln!("{}", tr!("found-archive", path = path.display()));
+    }
+
+    Ok(bytes)
+}
+
+/// Writes `pman` into `output`, after it is checked against `options`.
+fn write_archive(
+    output: impl AsRef<Path>,
+    mut pman: PmanFile,
+    options: &PackOptions,
+) -> miette::Result<()> {
+    if options.reproducible {
+        pman.clear_trailing_bytes();
+    }
+    for violation in options.validate(&pman)? {
+        eprintln!("{}", tr!("warning", message = violation));
+    }
+    #[cfg(feature = "mmap")]
+    pman.save_mapped(output)?;
+    #[cfg(not(feature = "mmap"))]
+    pman.save(output)?;
+
+    Ok(())
+}
+
+/// Where to write the new archive; the value of `-o` (or `--output`).
+fn output_file(args: &[String]) -> miette::Result<&str> {
+    flag_value(args, "-o")
+        .or_else(|| flag_value(args, "--output"))
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-output")))
+}
+
+fn import_text(args: &[String]) -> miette::Result<()> {
+    let file = import_file(args)?;
+    let language = flag_value(args, "--lang")
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-text-language")))?;
+    let output = output_file(args)?;
+    let extension = file
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let format = export::text::TextFormat::from_extension(extension)
+        .ok_or_else(|| miette::miette!("{}", tr!("unknown-text-format", extension = extension)))?;
+
+    let mut pman = PmanFile::new(&read_packfile(source_packfile(args)?)?)?;
+    let options = pack_options(args, &pman)?;
+    let imported = read(file).map_err(Error::from)?;
+
+    for index in import::text::import_text(&mut pman, language, format, &imported)? {
+        eprintln!("{}", tr!("replaced-entry", index = index));
+    }
+    write_archive(output, pman, &options)?;
+
+    Ok(())
+}
+
+fn convert_from(args: &[String]) -> miette::Result<()> {
+    let from = flag_value(args, "--from")
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-import-format")))?;
+    let file = import_file(args)?;
+    let output = output_file(args)?;
+
+    let mut pman = PmanFile::new(&read_packfile(source_packfile(args)?)?)?;
+    let options = pack_options(args, &pman)?;
+    let imported = read(file).map_err(Error::from)?;
+
+    if from == "wav" {
+        let clip = flag_value(args, "--clip")
+            .and_then(|clip| clip.parse().ok())
+            .ok_or_else(|| miette::miette!("{}", tr!("expected-clip")))?;
+
+        if let Some(warning) = import::wav::import_wav(&mut pman, clip, &imported)? {
+            eprintln!("{}", tr!("warning", message = warning));
+        }
+    } else if let Some(format) = export::palette::PaletteFormat::from_extension(from) {
+        let entry = flag_value(args, "--entry")
+            .and_then(|entry| entry.parse().ok())
+            .ok_or_else(|| miette::miette!("{}", tr!("expected-palette")))?;
+        // `--texture 72` names a texture that uses the palette; it can be given more than once.
+        // `--remap` moves those textures into the new palette.
+        let textures = args
+            .windows(2)
+            .filter(|w| w[0] == "--texture")
+            .map(|w| {
+                w[1].parse()
+                    .map_err(|_| miette::miette!("{}", tr!("expected-texture")))
+            })
+            .collect::<miette::Result<Vec<usize>>>()?;
+        let remap = args.iter().any(|arg| arg == "--remap");
+        let palette = import::palette::decode_palette(format, &imported)?;
+
+        for report in import::palette::import_palette(&mut pman, entry, &palette, &textures, remap)?
+        {
+            eprintln!("{report}");
+        }
+    } else {
+        miette::bail!("{}", tr!("unknown-import-format", format = from));
+    }
+    write_archive(output, pman, &options)?;
+
+    Ok(())
+}
+
+fn apply_mod(args: &[String]) -> miette::Result<()> {
+    let mut paths: Vec<_> = positionals(args).collect();
+    let packfile = paths
+        .pop()
+        .filter(|_| !paths.is_empty())
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-mods")))?;
+    let output = flag_value(args, "-o")
+        .or_else(|| flag_value(args, "--output"))
+        .map_or_else(|| packfile.with_extension("modded.dat"), Into::into);
+    let resolve = args.iter().any(|arg| arg == "--resolve");
+
+    let in_place = args.iter().any(|arg| arg == "--in-place");
+    // only the archive itself can be patched in place.
+    let bytes = if in_place {
+        read(packfile).map_err(Error::from)?
+    } else {
+        read_packfile(packfile)?
+    };
+    let mut pman = PmanFile::new(&bytes)?;
+    let options = pack_options(args, &pman)?;
+    let overlays = paths
+        .iter()
+        .map(|dir| Overlay::from_dir(dir, &pman))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (overlay, conflicts) = Overlay::merge(&overlays, resolve)?;
+
+    for conflict in conflicts {
+        let mods: Vec<_> = conflict
+            .overlays
+            .iter()
+            .map(|&i| format!("`{}`", paths[i].display()))
+            .collect();
+        let winner = mods.last().map_or("", String::as_str);
+
+        let conflict = tr!(
+            "mod-conflict",
+            index = conflict.index,
+            mods = mods.join(", "),
+            winner = winner
+        );
+        eprintln!("{}", tr!("warning", message = conflict));
+    }
+    // `--in-place` writes the new entries straight into the archive, if they fit on the slots of
+    // the ones that they replace (see `pman::patch_in_place`); otherwise, the archive is rewritten
+    // from the first replaced entry onward (see `PmanFile::save_incremental`).
+    let before = pman.clone();
+    if in_place {
+        // the original layout is kept, so the archive depends on more than the inputs.
+        if options.reproducible {
+            miette::bail!("{}", tr!("in-place-reproducible"));
+        }
+        apply_in_place(packfile, &overlay, &mut pman, &options)?;
+
+        return preserve_meta(packfile, &paths, &before, &pman, packfile);
+    }
+    for index in overlay.apply(&mut pman)? {
+        eprintln!("{}", tr!("replaced-entry", index = index));
+    }
+    preserve_meta(packfile, &paths, &before, &pman, &output)?;
+    write_archive(&output, pman, &options)?;
+    eprintln!("{}", tr!("wrote", path = output.display()));
+
+    Ok(())
+}
+
+/// Carries the annotations of the sidecar of `packfile` (and of the ones of the `mods`) over to
+/// the sidecar of `output`, for the entries that the mods replaced; see `Meta::preserve`.
+fn preserve_meta(
+    packfile: &Path,
+    mods: &[&Path],
+    before: &PmanFile,
+    after: &PmanFile,
+    output: &Path,
+) -> miette::Result<()> {
+    let mut meta = Meta::read(Meta::sidecar(packfile))?;
+    for dir in mods {
+        meta.entries
+            .extend(Meta::read(dir.join(import::meta::SIDECAR))?.entries);
+    }
+    if meta.entries.is_empty() {
+        return Ok(());
+    }
+
+    meta.preserve(before, after)?;
+    meta.write(Meta::sidecar(output))?;
+
+    Ok(())
+}
+
+fn apply_in_place(
+    packfile: &Path,
+    overlay: &Overlay,
+    pman: &mut PmanFile,
+    options: &PackOptions,
+) -> miette::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(packfile)
+        .map_err(Error::from)?;
+    let count = pman.files().len();
+
+    // every entry is checked first, so the archive is never left half patched.
+    let mut fits = true;
+    for (&index, bytes) in &overlay.entries {
+        let slot = pman::entry_slot(&mut file, index)?;
+        fits &= bytes.len() <= slot.len() && (index + 1 != count || bytes.len() == slot.len());
+    }
+    overlay.apply(pman)?;
+    for violation in options.validate(pman)? {
+        eprintln!("{}", tr!("warning", message = violation));
+    }
+
+    if !fits {
+        drop(file);
+        let first = pman.dirty_files().first().copied().unwrap_or(count);
+        pman.save_incremental(packfile)?;
+        eprintln!("{}", tr!("rewrote-from", index = first));
+
+        return Ok(());
+    }
+
+    for (&index, bytes) in &overlay.entries {
+        pman::patch_in_place(&mut file, index, bytes)?;
+        eprintln!("{}", tr!("patched-in-place", index = index));
+    }
+
+    Ok(())
+}
+
+fn diff(args: &[String]) -> miette::Result<()> {
+    let mut paths = positionals(args);
+    let (Some(source), Some(target)) = (paths.next(), paths.next()) else {
+        miette::bail!("{}", tr!("expected-diff"));
+    };
+
+    let source = PmanFile::new(&read_packfile(source)?)?;
+    let target = PmanFile::new(&read_packfile(target)?)?;
+    let patch = Patch::diff(&source, &target)?;
+
+    for entry in &patch.entries {
+        println!("{entry}");
+    }
+    if patch.target_count < patch.source_count {
+        let (from, to) = (patch.target_count, patch.source_count);
+        println!("{}", tr!("entries-removed", from = from, to = to));
+    }
+    if let Some(output) = flag_value(args, "--emit-patch") {
+        fs::write(output, patch.to_bytes()?).map_err(Error::from)?;
+    }
+
+    Ok(())
+}
+
+fn patch(args: &[String]) -> miette::Result<()> {
+    let mut paths = positionals(args);
+    let (Some(patch), Some(packfile)) = (paths.next(), paths.next()) else {
+        miette::bail!("{}", tr!("expected-patch"));
+    };
+    let output = flag_value(args, "-o")
+        .or_else(|| flag_value(args, "--output"))
+        .map_or_else(|| packfile.with_extension("patched.dat"), Into::into);
+
+    let patch = Patch::new(&read(patch).map_err(Error::from)?)?;
+    let mut pman = PmanFile::new(&read_packfile(packfile)?)?;
+    let options = pack_options(args, &pman)?;
+
+    for index in patch.apply(&mut pman)? {
+        eprintln!("{}", tr!("patched-entry", index = index));
+    }
+    write_archive(&output, pman, &options)?;
+    eprintln!("{}", tr!("wrote", path = output.display()));
+
+    Ok(())
+}
+
+#[cfg(feature = "fuse")]
+fn mount(args: &[String]) -> miette::Result<()> {
+    let mut paths = positionals(args);
+    let (Some(packfile), Some(mountpoint)) = (paths.next(), paths.next()) else {
+        miette::bail!("{}", tr!("expected-mount"));
+    };
+
+    let pman = PmanFile::new(&read_packfile(packfile)?)?;
+    eprintln!(
+        "{}",
+        tr!(
+            "mounting",
+            archive = packfile.display(),
+            mountpoint = mountpoint.display()
+        )
+    );
+    export::mount::mount(pman, mountpoint)?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "fuse"))]
+fn mount(_: &[String]) -> miette::Result<()> {
+    miette::bail!("{}", tr!("needs-feature", what = "mount", feature = "fuse"))
+}
+
+#[cfg(feature = "serve")]
+fn serve(args: &[String]) -> miette::Result<()> {
+    let packfile = positionals(args)
+        .next()
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-serve")))?;
+    let addr = flag_value(args, "--addr").unwrap_or("127.0.0.1:8080");
+
+    let pman = PmanFile::new(&read_packfile(packfile)?)?;
+    eprintln!(
+        "{}",
+        tr!("serving", archive = packfile.display(), addr = addr)
+    );
+    export::serve::serve(pman, addr)?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "serve"))]
+fn serve(_: &[String]) -> miette::Result<()> {
+    miette::bail!(
+        "{}",
+        tr!("needs-feature", what = "serve", feature = "serve")
+    )
+}
+
+fn tree(args: &[String]) -> miette::Result<()> {
+    let packfile = positionals(args)
+        .next()
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-tree")))?;
+
+    let pman = PmanFile::new(&read_packfile(packfile)?)?;
+    print!(
+        "{}",
+        export::tree::tree(&pman, &packfile.display().to_string())
+    );
+
+    Ok(())
+}
+
+fn cat(args: &[String]) -> miette::Result<()> {
+    let packfile = positionals(args)
+        .next()
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-cat")))?;
+    let offset = flag_value(args, "--offset")
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-offset")))?;
+    // hex, with or without `0x`; like the names of extracted files.
+    let offset = usize::from_str_radix(offset.trim_start_matches("0x"), 16)
+        .map_err(|_| miette::miette!("{}", tr!("not-a-hex-offset", offset = offset)))?;
+
+    let pman = PmanFile::new(&read_packfile(packfile)?)?;
+    let index = pman.entry_at_offset(offset).ok_or_else(|| {
+        miette::miette!("{}", tr!("no-entry-at", offset = format!("0x{offset:08X}")))
+    })?;
+    let file = &pman[index];
+    eprintln!("{}", tr!("entry", index = index));
+
+    let mut writer: Box<dyn Write> =
+        match flag_value(args, "-o").or_else(|| flag_value(args, "--output")) {
+            Some(output) => Box::new(BufWriter::new(File::create(output).map_err(Error::from)?)),
+            None => Box::new(std::io::stdout().lock()),
+        };
+    let compressed = matches!(file.compression(), pman::Compression::Zlib { .. });
+    if compressed && !args.iter().any(|arg| arg == "--raw") {
+        file.decompress_to(&mut writer)?;
+    } else {
+        writer.write_all(file.bytes()).map_err(Error::from)?;
+    }
+    writer.flush().map_err(Error::from)?;
+
+    Ok(())
+}
+
+fn peek(args: &[String]) -> miette::Result<()> {
+    let mut paths = positionals(args);
+    let (Some(packfile), Some(index)) = (paths.next(), paths.next()) else {
+        miette::bail!("{}", tr!("expected-peek"));
+    };
+    let index = index
+        .to_str()
+        .and_then(|index| index.parse().ok())
+        .ok_or_else(|| miette::miette!("{}", tr!("not-an-index", index = index.display())))?;
+
+    let pman = PmanFile::new(&read_packfile(packfile)?)?;
+    let ansi = std::io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let preview = export::peek::peek(&pman, index, ansi)?.ok_or_else(|| {
+        miette::miette!(
+            "{}",
+            tr!("no-entry", index = index, count = pman.files().len())
+        )
+    })?;
+    print!("{preview}");
+
+    Ok(())
+}
+
+fn doctor(args: &[String]) -> miette::Result<ExitCode> {
+    let path = positionals(args)
+        .next()
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-doctor")))?;
+    // what isn't an archive is diagnosed too; so it is read as it is, if it can't be located.
+    let bytes = match import::install::locate(path) {
+        Ok((bytes, _)) => bytes,
+        Err(_) => read(path).map_err(Error::from)?,
+    };
+
+    let findings = doctor::diagnose(&bytes);
+    for finding in &findings {
+        println!("{finding}");
+    }
+
+    Ok(
+        if findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Error)
+        {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        },
+    )
+}
+
+/// The time of every run of a stage of `bench`, and how many bytes it went through.
+struct Timings {
+    stage: &'static str,
+    runs: Vec<Duration>,
+    bytes: usize,
+}
+
+impl Timings {
+    fn new(stage: &'static str) -> Self {
+        Self {
+            stage,
+            runs: Vec::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Times `f`; it returns how many bytes it went through.
+    fn time(&mut self, f: impl FnOnce() -> miette::Result<usize>) -> miette::Result<()> {
+        let start = Instant::now();
+        self.bytes = f()?;
+        self.runs.push(start.elapsed());
+
+        Ok(())
+    }
+
+    fn print(&mut self) {
+        self.runs.sort();
+        let (Some(best), Some(median)) = (self.runs.first(), self.runs.get(self.runs.len() / 2))
+        else {
+            return;
+        };
+        let mib = |bytes: usize| bytes as f64 / f64::from(1 << 20);
+
+        println!(
+            "{}",
+            tr!(
+                "bench-stage",
+                stage = format!("{:<10}", self.stage),
+                best = format!("{:9.1} ms", best.as_secs_f64() * 1000.0),
+                median = format!("{:9.1} ms", median.as_secs_f64() * 1000.0),
+                throughput = format!("{:8.1} MiB/s", mib(self.bytes) / median.as_secs_f64()),
+            )
+        );
+    }
+}
+
+/// The passes of `bench`: whether each one is timed, and whether the archive is read from the disk
+/// again before it. An untimed pass warms everything up first, unless the cache has to stay cold;
+/// then the archive was just read, before the first pass.
+fn bench_passes(runs: usize, cold: bool) -> impl Iterator<Item = (bool, bool)> {
+    (usize::from(cold)..=runs).map(move |run| (run > 0, cold && run > 1))
+}
+
+fn bench(args: &[String]) -> miette::Result<()> {
+    let packfile = positionals(args)
+        .next()
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-bench")))?;
+    let runs = flag_value(args, "--runs")
+        .map(|runs| {
+            runs.parse::<usize>()
+                .ok()
+                .filter(|&runs| runs > 0)
+                .ok_or_else(|| miette::miette!("{}", tr!("runs-not-a-number", runs = runs)))
+        })
+        .transpose()?
+        .unwrap_or(5);
+    let cold = args.iter().any(|arg| arg == "--cold");
+
+    let mut read = Timings::new("read");
+    let mut parse = Timings::new("parse");
+    let mut decompress = Timings::new("decompress");
+    let mut repack = Timings::new("repack");
+
+    let mut bytes = Vec::new();
+    read.time(|| {
+        bytes = import::install::locate(packfile)?.0;
+        Ok(bytes.len())
+    })?;
+    println!(
+        "{}",
+        tr!(
+            "bench-header",
+            path = packfile.display(),
+            size = bytes.len(),
+            runs = runs,
+            cache = if cold {
+                tr!("cache-cold")
+            } else {
+                tr!("cache-warm")
+            },
+        )
+    );
+
+    for (timed, reread) in bench_passes(runs, cold) {
+        if reread {
+            read.time(|| {
+                bytes = import::install::locate(packfile)?.0;
+                Ok(bytes.len())
+            })?;
+        }
+
+        let mut pman = None;
+        let mut zlib = Vec::new();
+        let pass = |timings: &mut Timings, f: &mut dyn FnMut() -> miette::Result<usize>| {
+            if timed {
+                timings.time(f)
+            } else {
+                f().map(drop)
+            }
+        };
+        pass(&mut parse, &mut || {
+            pman = Some(PmanFile::new(&bytes)?);
+            Ok(bytes.len())
+        })?;
+        let mut pman = pman.expect("the archive was just parsed.");
+        pass(&mut decompress, &mut || {
+            zlib.clear();
+            for (index, file) in pman.files().iter().enumerate() {
+                if let Some(data) = file.to_zlib()? {
+                    zlib.push((index, data));
+                }
+            }
+            Ok(zlib.iter().map(|(_, data)| data.len()).sum())
+        })?;
+        pass(&mut repack, &mut || {
+            let data: Vec<_> = zlib.iter().map(|(_, data)| data).collect();
+            for ((index, _), compressed) in zlib.iter().zip(pman::compress_all(&data)?) {
+                pman.files_mut()[*index].set_bytes(compressed);
+            }
+            Ok(pman.clone().into_bytes()?.len())
+        })?;
+    }
+
+    for timings in [&mut read, &mut parse, &mut decompress, &mut repack] {
+        timings.print();
+    }
+
+    Ok(())
+}
+
+fn list(args: &[String]) -> miette::Result<()> {
+    let packfile = positionals(args)
+        .next()
+        .ok_or_else(|| miette::miette!("{}", tr!("expected-list")))?;
+
+    let pman = PmanFile::new(&read_packfile(packfile)?)?;
+    let meta =
+        Meta::read(flag_value(args, "--meta").map_or_else(|| Meta::sidecar(packfile), Into::into))?;
+
+    for (file, metadata) in pman.files().iter().zip(pman.metadata_all()?) {
+        let index = metadata.index;
+        let annotation = meta.entries.get(&metadata.hash);
+        let file_type = annotation
+            .and_then(|annotation| annotation.file_type)
+            .or_else(|| file.detect_type())
+            .map_or("unknown", FileType::extension);
+        let mut line = format!(
+            "{index:03} 0x{:08X} {:>8} {file_type:<8}",
+            metadata.offset, metadata.stored_size
+        );
+
+        if let Some(name) = annotation.and_then(|annotation| annotation.name.as_ref()) {
+            line = format!("{line} {name}");
+        }
+        if let Some(notes) = annotation.and_then(|annotation| annotation.notes.as_ref()) {
+            line = format!("{line}  # {notes}");
+        }
+        println!("{}", line.trim_end());
+    }
+
+    Ok(())
+}
+
+fn schema(args: &[String]) -> miette::Result<()> {
+    let dir = Path::new(
+        flag_value(args, "-o")
+            .or_else(|| flag_value(args, "--output"))
+            .unwrap_or("schema"),
+    );
+    fs::create_dir_all(dir).map_err(Error::from)?;
+
+    for format in rashen::format::schema::formats() {
+        let path = dir.join(format!("{}.ksy", format.id));
+        fs::write(&path, format.to_ksy()).map_err(Error::from)?;
+        eprintln!("{}", tr!("wrote", path = path.display()));
+    }
+
+    Ok(())
+}
+
+/// [`PmanFileData::to_zlib`](pman::PmanFileData::to_zlib) of every entry of `pman`, on `jobs`
+/// threads; `None` if they are not given, so every entry is streamed to its file instead.
+fn decompress_all(
+    pman: &PmanFile,
+    jobs: Option<usize>,
+) -> Result<Option<Vec<Option<Vec<u8>>>>, Error> {
+    #[cfg(feature = "rayon")]
+    if let Some(jobs) = jobs {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()
+            .map_err(|err| Error::Io(std::io::Error::other(err)))?;
+
+        let zlibs = pool.install(|| pman.par_decompress_all());
+
+        return zlibs.into_iter().collect::<Result<_, _>>().map(Some);
+    }
+    #[cfg(not(feature = "rayon"))]
+    let _ = (pman, jobs);
+
+    Ok(None)
+}
+
+fn run(extraction: Extraction) -> Result<(), Error> {
     // FIX(Unavailable): depends on cwd.
-    let bytes = read(".res/packfile.dat")?;
-    // FIX(Unavailable): When an error occurs, the user gets a paywall of bits, which is not that
-    // useful of a error message.
+    let packfile = Path::new(".res/packfile.dat");
+    let bytes = read(packfile)?;
+
+    let extracted = extract(packfile, &bytes, Path::new("output"), extraction)?;
+    if extraction.resume {
+        eprintln!("{}", tr!("resumed", count = extracted.skipped));
+    }
+
+    Ok(())
+}
+
+/// How archives are extracted; from the flags of the command line.
+#[derive(Clone, Copy)]
+struct Extraction {
+    deep: bool,
+    convert: Option<export::Options>,
+    jobs: Option<usize>,
+    resume: bool,
+}
+
+/// What was extracted from an archive; a line of the summary of `extract`.
+struct Extracted {
+    entries: usize,
+    version: pman::PmanVersion,
+    warnings: usize,
+    /// The entries that were already extracted; see `--resume`.
+    skipped: usize,
+}
+
+/// Whether `path` already has the data of `file`, as it is extracted; see `--resume`.
+fn up_to_date(path: &Path, file: &pman::PmanFileData) -> Result<bool, Error> {
+    let Ok(metadata) = fs::metadata(path) else {
+        return Ok(false);
+    };
+    let hash = ContentHash::of_file(file)?;
+
+    Ok(usize::try_from(metadata.len()) == Ok(hash.len) && ContentHash::of(&read(path)?) == hash)
+}
+
+/// Extracts every entry of the archive `bytes` (read from `packfile`) into `output_dir`; it is
+/// emptied first, unless the extraction resumes.
+fn extract(
+    packfile: &Path,
+    bytes: &[u8],
+    output_dir: &Path,
+    extraction: Extraction,
+) -> Result<Extracted, Error> {
+    let Extraction {
+        deep,
+        convert,
+        jobs,
+        resume,
+    } = extraction;
     let pman = PmanFile::new(bytes)?;
 
-    let output_dir = Path::new("output");
-    // the directory might not exists, so ignore the error.
-    _ = fs::remove_dir_all(output_dir);
+    for warning in pman.warnings() {
+        eprintln!("{}", tr!("warning", message = warning));
+    }
+
+    if !resume {
+        // the directory might not exists, so ignore the error.
+        _ = fs::remove_dir_all(output_dir);
+    }
     fs::create_dir_all(output_dir)?;
 
+    let mut skipped = 0;
+    let mut zlibs = decompress_all(&pman, jobs)?.map(Vec::into_iter);
     let size = pman.size_upto_file_data();
-    pman.into_iter().try_fold(size, |offset, file| {
-        let mut path = output_dir.join(format!("{:08X}", offset));
+    pman.files()
+        .iter()
+        .enumerate()
+        .try_fold(size, |offset, (index, file)| {
+            let mut path = output_dir.join(format!("{:08X}", offset));
+            let compressed = matches!(file.compression(), pman::Compression::Zlib { .. });
 
-        if let Some(zlib) = file.to_zlib() {
-            path.set_extension("zlib");
-            fs::write(path, zlib)?;
-        } else {
-            path.set_extension("dat");
-            fs::write(path, file.bytes())?;
+            // the file of the entry is written last; so if it is complete, so are the others.
+            if resume
+                && up_to_date(
+                    &path.with_extension(if compressed { "zlib" } else { "dat" }),
+                    file,
+                )?
+            {
+                zlibs.as_mut().and_then(Iterator::next);
+                skipped += 1;
+                return Ok(offset + file.bytes().len());
+            }
+
+            if let Some(options) = convert {
+                let name = format!("{offset:08X}");
+
+                for converted in export::convert(&pman, index, &name, options)? {
+                    fs::write(output_dir.join(converted.file_name), converted.bytes)?;
+                }
+            }
+
+            if deep {
+                let chunk = Chunk::walk(file.bytes())?;
+
+                if !chunk.children.is_empty() {
+                    write_children(&path, &chunk)?;
+                }
+            }
+
+            match zlibs.as_mut().and_then(Iterator::next) {
+                Some(Some(zlib)) => fs::write(path.with_extension("zlib"), zlib)?,
+                // large entries are never kept in memory as a whole.
+                None if compressed => {
+                    let mut writer = BufWriter::new(File::create(path.with_extension("zlib"))?);
+                    file.decompress_to(&mut writer)?;
+                    writer.flush()?;
+                }
+                _ => {
+                    path.set_extension("dat");
+                    fs::write(path, file.bytes())?;
+                }
+            }
+
+            Ok::<_, Error>(offset + file.bytes().len())
+        })?;
+
+    // the annotations of the extracted entries go along with them; see `import::meta`.
+    let meta = Meta::read(Meta::sidecar(packfile))?.subset(&pman)?;
+    if !meta.entries.is_empty() {
+        meta.write(output_dir.join(import::meta::SIDECAR))?;
+    }
+
+    Ok(Extracted {
+        entries: pman.files().len(),
+        version: pman.version(),
+        warnings: pman.warnings().len(),
+        skipped,
+    })
+}
+
+/// Whether `name` matches `pattern`, where `*` is any run of characters and `?` is any one.
+fn wildcard(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, _) => name.is_empty(),
+        (Some((b'*', rest)), _) => {
+            wildcard(rest, name)
+                || name
+                    .split_first()
+                    .is_some_and(|(_, name)| wildcard(pattern, name))
         }
+        (Some((&p, rest)), Some((&n, name))) => (p == b'?' || p == n) && wildcard(rest, name),
+        (Some(_), None) => false,
+    }
+}
 
-        Ok::<_, io::Error>(offset + file.bytes().len())
-    })?;
+/// The paths that match `pattern`, sorted; `*` and `?` match inside of a component. A pattern
+/// without them is the path itself, whether it exists or not.
+fn expand(pattern: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::new()];
+
+    for component in pattern.components() {
+        let component = component.as_os_str();
+        let name = component.to_string_lossy();
+
+        if !name.contains(['*', '?']) {
+            paths.iter_mut().for_each(|path| path.push(component));
+            continue;
+        }
+        paths = paths
+            .iter()
+            .flat_map(|dir| {
+                let entries = fs::read_dir(if dir.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    dir
+                });
+
+                entries
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .filter(|entry| wildcard(name.as_bytes(), entry.file_name().as_encoded_bytes()))
+                    .map(|entry| dir.join(entry.file_name()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        paths.sort();
+    }
+
+    paths
+}
+
+/// The name of the directory that every archive of `packfiles` is extracted into: its name,
+/// without the extension; or with the names of the directories above it, until no two archives
+/// have the same one (e.g. `game1-packfile` and `game2-packfile`).
+fn output_names(packfiles: &[PathBuf]) -> Vec<String> {
+    let components = |path: &Path| -> Vec<String> {
+        let stem = path.with_extension("");
+
+        stem.components()
+            .rev()
+            .filter_map(|component| match component {
+                std::path::Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect()
+    };
+    let components: Vec<_> = packfiles.iter().map(|path| components(path)).collect();
+    let longest = components.iter().map(Vec::len).max().unwrap_or(0);
+
+    let name = |parts: &[String], depth: usize| -> String {
+        let mut parts: Vec<_> = parts.iter().take(depth).map(String::as_str).collect();
+        parts.reverse();
+        parts.join("-")
+    };
+    let mut depth = 1;
+    while depth < longest {
+        let mut names: Vec<_> = components.iter().map(|parts| name(parts, depth)).collect();
+        names.sort();
+        if names.windows(2).all(|w| w[0] != w[1]) {
+            break;
+        }
+        depth += 1;
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    for parts in &components {
+        // the same archive given twice still gets a directory of its own.
+        let base = name(parts, depth);
+        let mut unique = base.clone();
+        let mut count = 1;
+        while names.contains(&unique) {
+            count += 1;
+            unique = format!("{base}-{count}");
+        }
+        names.push(unique);
+    }
+
+    names
+}
+
+fn extract_all(args: &[String], extraction: Extraction) -> miette::Result<ExitCode> {
+    let packfiles: Vec<_> = positionals(args).flat_map(expand).collect();
+    if packfiles.is_empty() {
+        miette::bail!("{}", tr!("expected-extract"));
+    }
+    let output = Path::new(
+        flag_value(args, "-o")
+            .or_else(|| flag_value(args, "--output"))
+            .unwrap_or("output"),
+    );
+
+    let mut failed = false;
+    let mut summary = Vec::new();
+    for (packfile, name) in packfiles.iter().zip(output_names(&packfiles)) {
+        let output_dir = output.join(&name);
+        eprintln!("{}", tr!("extracting", path = packfile.display()));
+
+        let extracted = import::install::locate(packfile)
+            .and_then(|(bytes, _)| extract(packfile, &bytes, &output_dir, extraction));
+        let line = match extracted {
+            Ok(extracted) => tr!(
+                "extracted",
+                path = packfile.display(),
+                entries = extracted.entries,
+                version = extracted.version,
+                warnings = extracted.warnings,
+                skipped = extracted.skipped,
+                output = output_dir.display(),
+            ),
+            Err(err) => {
+                failed = true;
+                tr!("extract-failed", path = packfile.display(), error = err)
+            }
+        };
+        summary.push(line);
+    }
+
+    println!("{}", tr!("extract-summary", count = packfiles.len()));
+    for line in summary {
+        println!("  {line}");
+    }
+
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// Writes the children of `chunk` inside of `dir`; one file for every leaf, and one directory for
+/// every other chunk.
+fn write_children(dir: &Path, chunk: &Chunk) -> Result<(), Error> {
+    fs::create_dir_all(dir)?;
+
+    for (index, child) in chunk.children.iter().enumerate() {
+        let mut path = dir.join(format!("{index:03}"));
+
+        if child.children.is_empty() {
+            let extension = child.tag_str().map_or("dat", str::trim);
+            path.set_extension(extension.to_ascii_lowercase());
+            fs::write(path, &child.data)?;
+        } else {
+            write_children(&path, child)?;
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positionals_test() {
+        let args = |line: &str| line.split(' ').map(str::to_owned).collect::<Vec<_>>();
+        let paths = |args: &[String]| positionals(args).map(Path::to_owned).collect::<Vec<_>>();
+
+        // a flag without a value doesn't take the argument after it.
+        assert_eq!(
+            paths(&args("--resolve mod/ game/packfile.dat")),
+            [Path::new("mod/"), Path::new("game/packfile.dat")]
+        );
+        assert_eq!(
+            paths(&args("--deep packfile.dat -o output/ --jobs 4")),
+            [Path::new("packfile.dat")]
+        );
+        for flag in VALUE_FLAGS {
+            assert!(paths(&args(&format!("{flag} value"))).is_empty(), "{flag}");
+        }
+    }
+
+    #[test]
+    fn bench_passes_test() {
+        let count = |runs, cold| {
+            let passes: Vec<_> = bench_passes(runs, cold).collect();
+            let timed = passes.iter().filter(|(timed, _)| *timed).count();
+            let rereads = passes.iter().filter(|(_, reread)| *reread).count();
+
+            (timed, passes.len() - timed, rereads)
+        };
+
+        // one untimed pass, to warm up.
+        assert_eq!(count(5, false), (5, 1, 0));
+        // none; every pass but the first reads the archive again.
+        assert_eq!(count(5, true), (5, 0, 4));
+        assert_eq!(count(1, true), (1, 0, 0));
+    }
+}
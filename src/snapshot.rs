@@ -0,0 +1,50 @@
+//! Snapshots of parsed structures, checked in next to the parsers that produce them; a parser
+//! refactor that changes what is read shows up as a diff of them.
+//!
+//! `cargo insta review` (or `INSTA_UPDATE=always cargo test`) updates them after an intended
+//! change.
+
+use std::fmt::Debug;
+
+/// Renders `value` as stable text; its pretty [`Debug`], with runs of the same line folded into one
+/// (e.g. the unused colors of a palette), so snapshots stay short enough to review.
+pub fn render(value: &impl Debug) -> String {
+    let text = format!("{value:#?}");
+    let mut lines: Vec<(&str, usize)> = vec![];
+
+    for line in text.lines() {
+        match lines.last_mut() {
+            Some((last, repeats)) if *last == line => *repeats += 1,
+            _ => lines.push((line, 1)),
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|(line, repeats)| match repeats {
+            1 => line.to_owned(),
+            repeats => format!("{line} (x{repeats})"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Compares the [`render`] of `$value` against the `$name` snapshot of the calling module.
+macro_rules! assert_parsed_snapshot {
+    ($name:expr, $value:expr $(,)?) => {
+        insta::assert_snapshot!($name, $crate::snapshot::render(&$value), stringify!($value))
+    };
+}
+
+pub(crate) use assert_parsed_snapshot;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_test() {
+        assert_eq!(render(&[0, 0, 0, 1]), "[\n    0, (x3)\n    1,\n]");
+        assert_eq!(render(&"text"), "\"text\"");
+    }
+}